@@ -3,7 +3,14 @@ use tracing::{error, info};
 
 use crate::config::AppConfig;
 use crate::database::{DatabasePool, MigrationManager};
+use crate::services::auth_failure_throttle::AuthFailureThrottle;
+use crate::services::rate_limiter::RateLimiter;
+use crate::services::remote_fetch::RemoteFetcher;
+use crate::services::scrub_service::ScrubJob;
+use crate::services::transform_pool::TransformPool;
+use crate::services::transform_presets::TransformPresets;
 use crate::services::TokenService;
+use crate::storage::{create_storage, StorageArea, StorageBackend};
 use crate::utils::AppError;
 
 /// 应用程序全局状态
@@ -17,6 +24,22 @@ pub struct AppState {
     db_pool: Arc<DatabasePool>,
     /// 应用配置
     config: Arc<AppConfig>,
+    /// 原始图片存储后端
+    storage: Arc<dyn StorageBackend>,
+    /// 转换结果缓存存储后端
+    cache_storage: Arc<dyn StorageBackend>,
+    /// 命名转换预设
+    presets: Arc<TransformPresets>,
+    /// 远程图片抓取器
+    fetcher: Arc<RemoteFetcher>,
+    /// 请求频率限制器
+    rate_limiter: Arc<RateLimiter>,
+    /// 认证失败审计事件节流器
+    auth_failure_throttle: Arc<AuthFailureThrottle>,
+    /// 图片转换工作线程池
+    transform_pool: Arc<TransformPool>,
+    /// 存储校验任务
+    scrub_job: Arc<ScrubJob>,
 }
 
 impl AppState {
@@ -24,7 +47,12 @@ impl AppState {
     ///
     /// 这个方法会初始化所有必要的应用资源，包括：
     /// - 加载应用配置
+    /// - 解析转换预设
+    /// - 创建远程图片抓取器
+    /// - 创建请求频率限制器
+    /// - 启动图片转换工作线程池
     /// - 初始化数据库连接池
+    /// - 创建存储后端
     /// - 运行数据库迁移
     /// - 初始化默认管理员账户
     pub async fn new() -> Result<Self, AppError> {
@@ -33,9 +61,41 @@ impl AppState {
         // 获取应用配置
         let config = Arc::new(AppConfig::get().clone());
 
+        // 解析并校验转换预设
+        let presets = Arc::new(TransformPresets::from_config(&config.presets)?);
+        info!("已加载{}个转换预设", config.presets.len());
+
+        // 创建远程图片抓取器
+        let fetcher = Arc::new(RemoteFetcher::from_config(&config.fetch)?);
+
+        // 创建请求频率限制器
+        let rate_limiter = Arc::new(RateLimiter::from_config(&config.rate_limit)?);
+
+        // 创建认证失败审计事件节流器
+        let auth_failure_throttle = Arc::new(AuthFailureThrottle::new(
+            std::time::Duration::from_secs(config.audit.auth_failure_window.as_seconds()),
+        ));
+
+        // 启动图片转换工作线程池
+        let transform_pool = Arc::new(TransformPool::from_config(&config.transform)?);
+        info!(
+            "已启动{}个图片转换工作线程，队列上限{}",
+            transform_pool.workers(),
+            config.transform.queue_size
+        );
+
         // 初始化数据库连接池
         let db_pool = Arc::new(DatabasePool::new().await?);
 
+        // 创建存储后端
+        let storage = create_storage(&config, StorageArea::Uploads)?;
+        let cache_storage = create_storage(&config, StorageArea::Cache)?;
+        info!(
+            "存储后端: 图片={}, 缓存={}",
+            storage.name(),
+            cache_storage.name()
+        );
+
         // 运行数据库迁移
         let connection = db_pool.get_connection();
         MigrationManager::migrate_up(&connection).await?;
@@ -51,7 +111,18 @@ impl AppState {
 
         info!("应用状态初始化完成");
 
-        Ok(Self { db_pool, config })
+        Ok(Self {
+            db_pool,
+            config,
+            storage,
+            cache_storage,
+            presets,
+            fetcher,
+            rate_limiter,
+            auth_failure_throttle,
+            transform_pool,
+            scrub_job: Arc::new(ScrubJob::new()),
+        })
     }
 
     /// 获取数据库连接池
@@ -64,6 +135,46 @@ impl AppState {
         &self.config
     }
 
+    /// 获取原始图片存储后端
+    pub fn storage(&self) -> &dyn StorageBackend {
+        self.storage.as_ref()
+    }
+
+    /// 获取转换结果缓存存储后端
+    pub fn cache_storage(&self) -> Arc<dyn StorageBackend> {
+        self.cache_storage.clone()
+    }
+
+    /// 获取命名转换预设
+    pub fn presets(&self) -> &TransformPresets {
+        self.presets.as_ref()
+    }
+
+    /// 获取远程图片抓取器
+    pub fn fetcher(&self) -> &RemoteFetcher {
+        self.fetcher.as_ref()
+    }
+
+    /// 获取请求频率限制器
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        self.rate_limiter.as_ref()
+    }
+
+    /// 获取认证失败审计事件节流器
+    pub fn auth_failure_throttle(&self) -> &AuthFailureThrottle {
+        self.auth_failure_throttle.as_ref()
+    }
+
+    /// 获取图片转换工作线程池
+    pub fn transform_pool(&self) -> &TransformPool {
+        self.transform_pool.as_ref()
+    }
+
+    /// 获取存储校验任务
+    pub fn scrub_job(&self) -> &ScrubJob {
+        self.scrub_job.as_ref()
+    }
+
     /// 执行健康检查
     ///
     /// 检查所有关键组件的健康状态
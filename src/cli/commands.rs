@@ -0,0 +1,395 @@
+use chrono::Utc;
+use sea_orm::DatabaseConnection;
+use sea_orm_migration::MigratorTrait;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+use super::Command;
+use crate::app_state::AppState;
+use crate::config::AppConfig;
+use crate::database::{DatabasePool, MigrationManager};
+use crate::migrations::Migrator;
+use crate::models::{ApiTokenInfo, CreateTokenPayload, ScrubOptions, TokenRole, UploadOptions};
+use crate::repositories::ImageRepository;
+use crate::services::{CacheService, ImageService, ScrubService, TokenService};
+use crate::storage::UploadStager;
+use crate::utils::{AppError, ByteSize};
+
+/// 导入文件时每次读取的块大小
+const IMPORT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 目录导入结果
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// 成功导入（或已存在）的文件及图片hash
+    pub imported: Vec<(PathBuf, String)>,
+    /// 导入失败的文件及原因
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// 初始化配置并执行命令，不启动HTTP服务
+pub async fn run(config_path: Option<&str>, command: Command) -> Result<(), AppError> {
+    AppConfig::init(config_path)?;
+
+    if command == Command::Migrate {
+        // 只连接数据库执行迁移，不初始化存储等其他资源
+        let db_pool = DatabasePool::new().await?;
+        return migrate(&db_pool.get_connection()).await;
+    }
+
+    let app_state = AppState::new().await?;
+    execute(&app_state, command).await
+}
+
+/// 使用已初始化的应用状态执行命令
+pub async fn execute(app_state: &AppState, command: Command) -> Result<(), AppError> {
+    match command {
+        Command::Serve | Command::Help => {
+            Err(AppError::Internal("该命令需要由程序入口处理".to_string()))
+        }
+        Command::Migrate => migrate(&app_state.db_pool().get_connection()).await,
+        Command::TokenList => list_tokens(app_state).await,
+        Command::TokenCreate {
+            name,
+            role,
+            max_upload_size,
+            max_images,
+            expires,
+        } => {
+            let service = TokenService::new(app_state.db_pool().get_connection());
+            let response = service
+                .create_token(CreateTokenPayload {
+                    name,
+                    role,
+                    max_upload_size: max_upload_size.map(ByteSize::as_bytes),
+                    max_image_count: max_images,
+                    expires_at: expires.map(|expires| {
+                        Utc::now() + chrono::Duration::seconds(expires.as_seconds() as i64)
+                    }),
+                })
+                .await?;
+            println!(
+                "✅ 已创建令牌 #{} ({}, {})",
+                response.token.id,
+                response.token.name,
+                response.token.role.as_str()
+            );
+            print_plaintext(&response.plaintext);
+            Ok(())
+        }
+        Command::TokenRevoke { id } => {
+            let service = TokenService::new(app_state.db_pool().get_connection());
+            let token = service.revoke_token(id).await?;
+            println!("✅ 已禁用令牌 #{} ({})", token.id, token.name);
+            Ok(())
+        }
+        Command::TokenRotate { id } => {
+            let service = TokenService::new(app_state.db_pool().get_connection());
+            let response = service.rotate_token(id).await?;
+            println!(
+                "✅ 已重新生成令牌 #{} ({}) 的明文，旧令牌立即失效",
+                response.token.id, response.token.name
+            );
+            print_plaintext(&response.plaintext);
+            if !response.token.is_active {
+                println!("⚠️ 该令牌已被禁用，当前无法用于认证");
+            }
+            Ok(())
+        }
+        Command::TokenReconcile { dry_run } => {
+            let connection = app_state.db_pool().get_connection();
+            let report = TokenService::new(connection.clone())
+                .reconcile_usage(&ImageRepository::new(connection), dry_run)
+                .await?;
+            for drift in &report.drifted {
+                println!(
+                    "{} #{} {}: {} / {}张 -> {} / {}张",
+                    if dry_run { "⚠️" } else { "✅" },
+                    drift.token_id,
+                    drift.name,
+                    ByteSize::new(drift.recorded_size.max(0) as u64),
+                    drift.recorded_count,
+                    ByteSize::new(drift.actual_size.max(0) as u64),
+                    drift.actual_count
+                );
+            }
+            println!(
+                "检查 {} 个令牌，{} 个令牌的已用配额与图片记录不一致{}",
+                report.tokens_checked,
+                report.drifted.len(),
+                if dry_run { "（未修改）" } else { "，已修正" }
+            );
+            Ok(())
+        }
+        Command::Import {
+            dir,
+            owner,
+            private,
+            recursive,
+        } => {
+            let report = import_directory(app_state, &dir, owner, private, recursive).await?;
+            for (path, hash) in &report.imported {
+                println!("✅ {} -> {}", path.display(), hash);
+            }
+            for (path, error) in &report.failed {
+                println!("❌ {}: {}", path.display(), error);
+            }
+            println!(
+                "导入完成: 成功 {} 个，失败 {} 个",
+                report.imported.len(),
+                report.failed.len()
+            );
+            Ok(())
+        }
+        Command::Verify { checksum, repair } => {
+            let report = ScrubService::scrub(app_state, ScrubOptions { checksum, repair }).await?;
+            for issue in &report.issues {
+                let record = issue.record.as_deref().unwrap_or("-");
+                match (&issue.action, &issue.repair_error) {
+                    (Some(action), _) => println!(
+                        "✅ {} ({}): {}，已修复: {:?}",
+                        issue.storage_key, record, issue.detail, action
+                    ),
+                    (_, Some(error)) => println!(
+                        "❌ {} ({}): {}，修复失败: {}",
+                        issue.storage_key, record, issue.detail, error
+                    ),
+                    _ => println!("❌ {} ({}): {}", issue.storage_key, record, issue.detail),
+                }
+            }
+            println!(
+                "检查了 {} 条图片记录、{} 条缓存记录、{} 个文件，发现 {} 个问题，修复 {} 个",
+                report.images_checked,
+                report.cache_checked,
+                report.files_scanned,
+                report.issues.len(),
+                report.repaired()
+            );
+
+            let unresolved = report.issues.len() - report.repaired();
+            if unresolved == 0 {
+                Ok(())
+            } else {
+                Err(AppError::Internal(format!(
+                    "存储校验发现 {} 个未修复的问题",
+                    unresolved
+                )))
+            }
+        }
+        Command::CachePurge => {
+            let cache_service = CacheService::new(
+                app_state.db_pool().get_connection(),
+                app_state.cache_storage(),
+            )?;
+            let result = cache_service.clear_all().await?;
+            println!(
+                "✅ 已清空转换缓存: 删除 {} 项，释放 {}",
+                result.cleaned_count,
+                ByteSize::new(result.freed_space)
+            );
+            Ok(())
+        }
+        Command::Stats => print_stats(app_state).await,
+    }
+}
+
+/// 执行待执行的数据库迁移
+async fn migrate(connection: &DatabaseConnection) -> Result<(), AppError> {
+    let pending = Migrator::get_pending_migrations(connection)
+        .await
+        .map_err(|e| AppError::Internal(format!("查询待执行的迁移失败: {}", e)))?;
+    if pending.is_empty() {
+        println!("数据库已是最新，无需迁移");
+        return Ok(());
+    }
+
+    for migration in &pending {
+        println!("  {}", migration.name());
+    }
+    MigrationManager::migrate_up(connection).await?;
+    println!("✅ 已执行 {} 个迁移", pending.len());
+    Ok(())
+}
+
+async fn list_tokens(app_state: &AppState) -> Result<(), AppError> {
+    let service = TokenService::new(app_state.db_pool().get_connection());
+    let mut tokens = service.list_tokens().await?;
+    tokens.sort_by_key(|token| token.id);
+
+    for token in tokens {
+        let status = if !token.is_active {
+            "禁用"
+        } else if token
+            .expires_at
+            .is_some_and(|expires_at| expires_at < Utc::now())
+        {
+            "过期"
+        } else {
+            "可用"
+        };
+        let quota = token
+            .max_upload_size
+            .map(|size| ByteSize::new(size.max(0) as u64).to_string())
+            .unwrap_or_else(|| "不限".to_string());
+        let image_quota = token
+            .max_image_count
+            .map(|count| count.to_string())
+            .unwrap_or_else(|| "不限".to_string());
+        println!(
+            "#{} {} [{}, {}] 已用 {} / {}，图片 {} / {}，过期时间: {}，最后使用: {}",
+            token.id,
+            token.name,
+            token.role.as_str(),
+            status,
+            ByteSize::new(token.used_upload_size.max(0) as u64),
+            quota,
+            token.used_image_count,
+            image_quota,
+            format_time(token.expires_at),
+            format_time(token.last_used_at)
+        );
+    }
+    Ok(())
+}
+
+/// 导入目录中的图片，单个文件失败不影响其他文件
+///
+/// 未指定所有者时归属最早创建的可用管理员令牌；以 `.` 开头的文件和目录会被跳过。
+pub async fn import_directory(
+    app_state: &AppState,
+    dir: &Path,
+    owner: Option<i32>,
+    private: bool,
+    recursive: bool,
+) -> Result<ImportReport, AppError> {
+    let token_service = TokenService::new(app_state.db_pool().get_connection());
+    let owner: ApiTokenInfo = match owner {
+        Some(id) => token_service.get_token(id).await?,
+        None => token_service.first_admin().await?.ok_or_else(|| {
+            AppError::BadRequest("没有可用的管理员令牌，请使用 --owner 指定所有者".to_string())
+        })?,
+    };
+    let options = UploadOptions {
+        private,
+        near_duplicate: None,
+    };
+
+    let mut report = ImportReport::default();
+    for path in collect_files(dir, recursive).await? {
+        let original_filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        let result = match stage_file(&path).await {
+            Ok(staged) => {
+                ImageService::save_image(
+                    app_state.db_pool(),
+                    app_state.storage(),
+                    staged,
+                    original_filename,
+                    &owner,
+                    &options,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(image_info) => report.imported.push((path, image_info.hash)),
+            Err(e) => report.failed.push((path, e.to_string())),
+        }
+    }
+    Ok(report)
+}
+
+/// 列出目录中的文件（按路径排序）
+async fn collect_files(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>, AppError> {
+    if !tokio::fs::metadata(dir).await?.is_dir() {
+        return Err(AppError::BadRequest(format!("不是目录: {}", dir.display())));
+    }
+
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                if recursive {
+                    dirs.push(entry.path());
+                }
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// 把本地文件写入上传暂存区，与HTTP上传共用大小限制和哈希计算
+async fn stage_file(path: &Path) -> Result<crate::storage::StagedUpload, AppError> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut stager = UploadStager::new().await?;
+    let mut buffer = vec![0u8; IMPORT_CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        stager.write_chunk(&buffer[..read]).await?;
+    }
+    stager.finish().await
+}
+
+async fn print_stats(app_state: &AppState) -> Result<(), AppError> {
+    let stats = ImageService::get_stats(app_state.db_pool(), None).await?;
+    println!(
+        "图片: {} 张，共 {}",
+        stats.total_count,
+        ByteSize::new(stats.total_size.max(0) as u64)
+    );
+    for stat in &stats.by_type {
+        println!(
+            "  {}: {} 张，{}",
+            stat.mime_type,
+            stat.count,
+            ByteSize::new(stat.total_size.max(0) as u64)
+        );
+    }
+
+    let cache_service = CacheService::new(
+        app_state.db_pool().get_connection(),
+        app_state.cache_storage(),
+    )?;
+    let (entries, size) = cache_service.get_usage().await?;
+    println!("转换缓存: {} 项，共 {}", entries, ByteSize::new(size));
+
+    let tokens = TokenService::new(app_state.db_pool().get_connection())
+        .list_tokens()
+        .await?;
+    let admins = tokens
+        .iter()
+        .filter(|token| token.role == TokenRole::Admin)
+        .count();
+    let active = tokens.iter().filter(|token| token.is_active).count();
+    println!(
+        "令牌: {} 个（管理员 {} 个，可用 {} 个）",
+        tokens.len(),
+        admins,
+        active
+    );
+    Ok(())
+}
+
+fn print_plaintext(plaintext: &str) {
+    println!("🔐 令牌: {}", plaintext);
+    println!("⚠️ 请妥善保存该令牌，之后无法再次查询");
+}
+
+fn format_time(time: Option<chrono::DateTime<Utc>>) -> String {
+    time.map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
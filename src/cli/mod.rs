@@ -0,0 +1,285 @@
+pub mod commands;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::models::TokenRole;
+use crate::utils::{AppError, ByteSize, Duration};
+
+pub use commands::run;
+
+/// 命令行帮助信息
+pub const USAGE: &str = "\
+RIFS 图床服务
+
+用法: rifs [--config <配置文件>] [命令]
+
+命令:
+  serve                          启动HTTP服务（默认）
+  migrate                        执行数据库迁移
+  token list                     列出所有令牌
+  token create <名称> [--role admin|user] [--max-upload-size <大小>] [--max-images <数量>]
+               [--expires <时长>]
+                                 创建令牌并输出明文
+  token revoke <ID>              禁用令牌，已上传的图片保留
+  token rotate <ID>              重新生成令牌明文（找回遗失的管理员令牌）
+  token reconcile [--dry-run]    按图片记录重新计算令牌的已用配额，
+                                 --dry-run 只报告不修改
+  import <目录> [--owner <ID>] [--private] [--recursive]
+                                 导入目录中的图片，默认归属最早创建的管理员令牌
+  verify [--checksum] [--repair] 核对图片、缓存记录与存储中的文件，
+                                 --checksum 同时校验文件内容的 SHA-256，
+                                 --repair 修复发现的问题
+  cache purge                    清空转换缓存
+  stats                          输出图片、缓存和令牌统计
+  help                           显示本帮助
+
+选项:
+  -c, --config <配置文件>        配置文件路径（不含扩展名），默认读取 config.toml
+";
+
+/// 需要取值的选项
+const VALUE_OPTIONS: &[&str] = &[
+    "config",
+    "role",
+    "max-upload-size",
+    "max-images",
+    "expires",
+    "owner",
+];
+
+/// 命令行参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cli {
+    /// 配置文件路径
+    pub config: Option<String>,
+    /// 要执行的命令
+    pub command: Command,
+}
+
+/// 子命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// 启动HTTP服务
+    Serve,
+    /// 显示帮助
+    Help,
+    /// 执行数据库迁移
+    Migrate,
+    /// 列出令牌
+    TokenList,
+    /// 创建令牌
+    TokenCreate {
+        name: String,
+        role: TokenRole,
+        max_upload_size: Option<ByteSize>,
+        max_images: Option<u64>,
+        expires: Option<Duration>,
+    },
+    /// 禁用令牌
+    TokenRevoke { id: i32 },
+    /// 重新生成令牌明文
+    TokenRotate { id: i32 },
+    /// 重新计算令牌的已用配额
+    TokenReconcile { dry_run: bool },
+    /// 导入目录中的图片
+    Import {
+        dir: PathBuf,
+        owner: Option<i32>,
+        private: bool,
+        recursive: bool,
+    },
+    /// 检查存储完整性
+    Verify { checksum: bool, repair: bool },
+    /// 清空转换缓存
+    CachePurge,
+    /// 输出统计信息
+    Stats,
+}
+
+impl Cli {
+    /// 解析命令行参数（不含程序名），参数错误时返回 `AppError::BadRequest`
+    pub fn parse<I, S>(args: I) -> Result<Self, AppError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut args = Args::parse(args.into_iter().map(Into::into))?;
+        let config = args.value("config");
+        if args.flag("help") {
+            return Ok(Self {
+                config,
+                command: Command::Help,
+            });
+        }
+
+        let command = match args.next_positional().as_deref() {
+            None | Some("serve") => Command::Serve,
+            Some("help") => Command::Help,
+            Some("migrate") => Command::Migrate,
+            Some("token") => match args.next_positional().as_deref() {
+                Some("list") => Command::TokenList,
+                Some("create") => Command::TokenCreate {
+                    name: args.required_positional("令牌名称")?,
+                    role: match args.value("role") {
+                        Some(role) => parse_role(&role)?,
+                        None => TokenRole::User,
+                    },
+                    max_upload_size: args
+                        .value("max-upload-size")
+                        .map(|value| parse_option(&value, "--max-upload-size"))
+                        .transpose()?,
+                    max_images: args
+                        .value("max-images")
+                        .map(|value| parse_option(&value, "--max-images"))
+                        .transpose()?,
+                    expires: args
+                        .value("expires")
+                        .map(|value| parse_option(&value, "--expires"))
+                        .transpose()?,
+                },
+                Some("revoke") => Command::TokenRevoke {
+                    id: parse_option(&args.required_positional("令牌ID")?, "令牌ID")?,
+                },
+                Some("rotate") => Command::TokenRotate {
+                    id: parse_option(&args.required_positional("令牌ID")?, "令牌ID")?,
+                },
+                Some("reconcile") => Command::TokenReconcile {
+                    dry_run: args.flag("dry-run"),
+                },
+                Some(other) => return Err(unknown_command(&format!("token {}", other))),
+                None => return Err(AppError::BadRequest("缺少 token 子命令".to_string())),
+            },
+            Some("import") => Command::Import {
+                dir: PathBuf::from(args.required_positional("导入目录")?),
+                owner: args
+                    .value("owner")
+                    .map(|value| parse_option(&value, "--owner"))
+                    .transpose()?,
+                private: args.flag("private"),
+                recursive: args.flag("recursive"),
+            },
+            Some("verify") => Command::Verify {
+                checksum: args.flag("checksum"),
+                repair: args.flag("repair"),
+            },
+            Some("cache") => match args.next_positional().as_deref() {
+                Some("purge") => Command::CachePurge,
+                Some(other) => return Err(unknown_command(&format!("cache {}", other))),
+                None => return Err(AppError::BadRequest("缺少 cache 子命令".to_string())),
+            },
+            Some("stats") => Command::Stats,
+            Some(other) => return Err(unknown_command(other)),
+        };
+
+        args.finish()?;
+        Ok(Self { config, command })
+    }
+}
+
+/// 拆分后的原始参数，解析命令时逐个取出，最后检查是否有多余的参数
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, AppError> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut args = args.peekable();
+
+        while let Some(arg) = args.next() {
+            let name = match arg.as_str() {
+                "-c" => "config".to_string(),
+                "-h" => "help".to_string(),
+                _ => match arg.strip_prefix("--") {
+                    Some(name) => name.to_string(),
+                    None => {
+                        positional.push(arg);
+                        continue;
+                    }
+                },
+            };
+
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (name, None),
+            };
+            let value = if VALUE_OPTIONS.contains(&name.as_str()) {
+                match value.or_else(|| args.next()) {
+                    Some(value) => Some(value),
+                    None => {
+                        return Err(AppError::BadRequest(format!("选项 --{} 需要一个值", name)))
+                    }
+                }
+            } else if value.is_some() {
+                return Err(AppError::BadRequest(format!("选项 --{} 不接受值", name)));
+            } else {
+                None
+            };
+
+            if options.insert(name.clone(), value).is_some() {
+                return Err(AppError::BadRequest(format!("选项 --{} 重复", name)));
+            }
+        }
+
+        positional.reverse();
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    fn next_positional(&mut self) -> Option<String> {
+        self.positional.pop()
+    }
+
+    fn required_positional(&mut self, name: &str) -> Result<String, AppError> {
+        self.next_positional()
+            .ok_or_else(|| AppError::BadRequest(format!("缺少参数: {}", name)))
+    }
+
+    fn value(&mut self, name: &str) -> Option<String> {
+        self.options.remove(name).flatten()
+    }
+
+    fn flag(&mut self, name: &str) -> bool {
+        self.options.remove(name).is_some()
+    }
+
+    fn finish(self) -> Result<(), AppError> {
+        if let Some(name) = self.options.keys().next() {
+            return Err(AppError::BadRequest(format!("未知选项: --{}", name)));
+        }
+        if let Some(arg) = self.positional.last() {
+            return Err(AppError::BadRequest(format!("多余的参数: {}", arg)));
+        }
+        Ok(())
+    }
+}
+
+fn parse_role(value: &str) -> Result<TokenRole, AppError> {
+    match value {
+        "admin" => Ok(TokenRole::Admin),
+        "user" => Ok(TokenRole::User),
+        _ => Err(AppError::BadRequest(format!(
+            "无效的角色: {}，可选 admin 或 user",
+            value
+        ))),
+    }
+}
+
+fn parse_option<T>(value: &str, name: &str) -> Result<T, AppError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| AppError::BadRequest(format!("{} 的值无效: {} ({})", name, value, e)))
+}
+
+fn unknown_command(command: &str) -> AppError {
+    AppError::BadRequest(format!("未知命令: {}", command))
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
 use std::sync::OnceLock;
 
 use crate::utils::{AppError, ByteSize, Duration};
@@ -17,6 +17,21 @@ pub struct AppConfig {
     pub cache: CacheConfig,
     #[serde(default)]
     pub auth: AuthConfig,
+    #[serde(default)]
+    pub transform: TransformConfig,
+    #[serde(default)]
+    pub fetch: FetchConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub scrub: ScrubConfig,
+    #[serde(default)]
+    pub trash: TrashConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    /// 命名转换预设（预设名称 -> 转换参数字符串）
+    #[serde(default)]
+    pub presets: BTreeMap<String, String>,
 }
 
 /// 服务器配置
@@ -35,10 +50,121 @@ pub struct ServerConfig {
 /// 存储配置
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StorageConfig {
-    /// 上传目录
+    /// 上传目录（本地存储根目录，同时存放上传暂存文件）
     pub upload_dir: String,
     /// 最大文件大小
     pub max_file_size: ByteSize,
+    /// 存储后端类型 (local, s3)
+    #[serde(default)]
+    pub backend: StorageBackendKind,
+    /// S3兼容对象存储配置（backend = "s3" 时必填）
+    #[serde(default)]
+    pub s3: Option<S3Config>,
+    /// 上传图片的元数据处理策略 (keep, strip, strip_gps)
+    #[serde(default)]
+    pub metadata_policy: MetadataPolicy,
+    /// 批量上传的请求体大小上限（包括压缩包）
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: ByteSize,
+    /// 批量上传单次最多处理的文件数（包括压缩包内的文件）
+    #[serde(default = "default_max_batch_files")]
+    pub max_batch_files: usize,
+    /// 上传与已有图片视觉相似时的处理策略 (allow, reject, link)
+    #[serde(default)]
+    pub near_duplicate_policy: NearDuplicatePolicy,
+    /// 判定为相似图片的最大感知哈希距离（0-64）
+    #[serde(default = "default_near_duplicate_threshold")]
+    pub near_duplicate_threshold: u32,
+}
+
+fn default_max_batch_size() -> ByteSize {
+    ByteSize::mb(100)
+}
+
+fn default_max_batch_files() -> usize {
+    100
+}
+
+fn default_near_duplicate_threshold() -> u32 {
+    5
+}
+
+/// 相似图片上传策略
+///
+/// 只在同一所有者的图片中比较感知哈希，完全相同的文件始终直接返回已有图片。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NearDuplicatePolicy {
+    /// 照常保存
+    #[default]
+    Allow,
+    /// 拒绝上传
+    Reject,
+    /// 不保存新文件，直接返回最相似的已有图片
+    Link,
+}
+
+/// 图片元数据处理策略
+///
+/// 在计算哈希和写入存储之前执行，ICC 颜色配置文件始终保留。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataPolicy {
+    /// 原样保存
+    #[default]
+    Keep,
+    /// 移除全部 EXIF / XMP / IPTC / 注释等元数据（保留 EXIF 方向信息）
+    Strip,
+    /// 仅移除 GPS 定位信息
+    StripGps,
+}
+
+/// 存储后端类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackendKind {
+    /// 本地文件系统（upload_dir / cache_dir）
+    #[default]
+    Local,
+    /// S3兼容对象存储（AWS S3、MinIO等）
+    S3,
+}
+
+/// S3兼容对象存储配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct S3Config {
+    /// 服务地址，如 "https://s3.amazonaws.com" 或 "http://127.0.0.1:9000"
+    pub endpoint: String,
+    /// 存储桶名称
+    pub bucket: String,
+    /// 区域
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    /// 访问密钥ID
+    pub access_key: String,
+    /// 访问密钥
+    pub secret_key: String,
+    /// 是否使用路径风格访问（MinIO 通常需要开启）
+    #[serde(default = "default_s3_path_style")]
+    pub path_style: bool,
+    /// 对象键前缀，用于多个服务共享同一个存储桶
+    #[serde(default)]
+    pub prefix: String,
+    /// 连接超时和读取超时（两次收到数据之间的最长间隔），不限制整个请求的时长
+    #[serde(default = "default_s3_timeout")]
+    pub timeout: Duration,
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_s3_path_style() -> bool {
+    true
+}
+
+fn default_s3_timeout() -> Duration {
+    Duration::seconds(30)
 }
 
 /// 数据库配置
@@ -104,18 +230,287 @@ pub struct AuthConfig {
     pub token: Option<String>,
     #[serde(default = "default_auth_header_name")]
     pub header_name: String,
+    /// 签名URL使用的HMAC密钥（未配置时无法生成签名URL）
+    #[serde(default)]
+    pub url_signing_secret: Option<String>,
+    /// 签名URL的最长有效期
+    #[serde(default = "default_signed_url_max_age")]
+    pub signed_url_max_age: Duration,
+}
+
+/// 图片转换配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TransformConfig {
+    /// 是否只允许使用预设转换参数（携带有效令牌的请求不受限制）
+    #[serde(default)]
+    pub presets_only: bool,
+    /// 未指定输出格式的请求是否按 Accept 请求头自动选择 AVIF / WebP
+    #[serde(default)]
+    pub auto_format: bool,
+    /// 执行图片转换的工作线程数，0 表示使用CPU核心数
+    #[serde(default)]
+    pub workers: usize,
+    /// 等待工作线程的转换任务上限，队列已满时返回 503
+    #[serde(default = "default_transform_queue_size")]
+    pub queue_size: usize,
+}
+
+fn default_transform_queue_size() -> usize {
+    64
+}
+
+impl Default for TransformConfig {
+    fn default() -> Self {
+        Self {
+            presets_only: false,
+            auto_format: false,
+            workers: 0,
+            queue_size: default_transform_queue_size(),
+        }
+    }
+}
+
+/// 远程图片抓取配置（URL上传）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FetchConfig {
+    /// 抓取整个远程图片的超时时间（包括连接和下载）
+    #[serde(default = "default_fetch_timeout")]
+    pub timeout: Duration,
+    /// 最多跟随的重定向次数
+    #[serde(default = "default_fetch_max_redirects")]
+    pub max_redirects: usize,
+    /// 允许访问的内网地址段（CIDR 或单个IP），默认拒绝所有私有、回环和链路本地地址
+    #[serde(default)]
+    pub allowed_networks: Vec<String>,
+}
+
+fn default_fetch_timeout() -> Duration {
+    Duration::seconds(30)
+}
+
+fn default_fetch_max_redirects() -> usize {
+    3
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            timeout: default_fetch_timeout(),
+            max_redirects: default_fetch_max_redirects(),
+            allowed_networks: Vec::new(),
+        }
+    }
+}
+
+/// 请求频率限制配置
+///
+/// 按令牌桶算法限制，已认证的请求按令牌ID计数，其余请求按客户端IP计数。
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RateLimitConfig {
+    /// 是否启用频率限制
+    #[serde(default)]
+    pub enabled: bool,
+    /// 是否从 X-Forwarded-For 请求头读取客户端IP（仅在可信的反向代理之后开启，取最右侧的地址）
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+    /// 上传请求（/upload、/upload/url、/upload/batch）
+    #[serde(default = "default_upload_rate_limit")]
+    pub upload: RateLimitRule,
+    /// 未命中缓存、需要实际执行的图片转换
+    #[serde(default = "default_transform_rate_limit")]
+    pub transform: RateLimitRule,
+    /// 其他 /api 接口
+    #[serde(default = "default_api_rate_limit")]
+    pub api: RateLimitRule,
+}
+
+/// 单类请求的频率限制规则
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct RateLimitRule {
+    /// 每个时间窗口内补充的请求数
+    pub requests: u32,
+    /// 时间窗口
+    pub period: Duration,
+    /// 允许的突发请求数（桶容量），未配置时等于 requests
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+impl RateLimitRule {
+    /// 桶容量
+    pub fn capacity(&self) -> u32 {
+        self.burst.unwrap_or(self.requests)
+    }
+}
+
+fn default_upload_rate_limit() -> RateLimitRule {
+    RateLimitRule {
+        requests: 60,
+        period: Duration::minutes(1),
+        burst: None,
+    }
+}
+
+fn default_transform_rate_limit() -> RateLimitRule {
+    RateLimitRule {
+        requests: 120,
+        period: Duration::minutes(1),
+        burst: None,
+    }
+}
+
+fn default_api_rate_limit() -> RateLimitRule {
+    RateLimitRule {
+        requests: 600,
+        period: Duration::minutes(1),
+        burst: None,
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            trust_forwarded_for: false,
+            upload: default_upload_rate_limit(),
+            transform: default_transform_rate_limit(),
+            api: default_api_rate_limit(),
+        }
+    }
+}
+
+/// 存储校验配置
+///
+/// 定期核对图片、缓存记录与存储中的文件，也可以通过管理接口或 `rifs verify` 手动执行。
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScrubConfig {
+    /// 是否定期执行存储校验
+    #[serde(default)]
+    pub enabled: bool,
+    /// 执行间隔
+    #[serde(default = "default_scrub_interval")]
+    pub interval: Duration,
+    /// 定期校验时是否读取文件内容校验 SHA-256
+    #[serde(default)]
+    pub checksum: bool,
+    /// 定期校验时是否自动修复发现的问题
+    #[serde(default)]
+    pub repair: bool,
+    /// 最近修改时间在此范围内的文件不视为孤立文件，避免误判正在上传的文件
+    #[serde(default = "default_scrub_orphan_grace")]
+    pub orphan_grace: Duration,
+}
+
+fn default_scrub_interval() -> Duration {
+    Duration::days(1)
+}
+
+fn default_scrub_orphan_grace() -> Duration {
+    Duration::hours(1)
+}
+
+impl Default for ScrubConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: default_scrub_interval(),
+            checksum: false,
+            repair: false,
+            orphan_grace: default_scrub_orphan_grace(),
+        }
+    }
+}
+
+/// 回收站配置
+///
+/// 启用后删除的图片先移入回收站，保留期内可以恢复，过期后由后台任务彻底删除。
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrashConfig {
+    /// 是否启用回收站，关闭后删除图片立即删除文件
+    #[serde(default = "default_trash_enabled")]
+    pub enabled: bool,
+    /// 图片在回收站中的保留时间
+    #[serde(default = "default_trash_retention")]
+    pub retention: Duration,
+    /// 清理过期图片的执行间隔
+    #[serde(default = "default_trash_purge_interval")]
+    pub purge_interval: Duration,
+}
+
+fn default_trash_enabled() -> bool {
+    true
+}
+
+fn default_trash_retention() -> Duration {
+    Duration::days(30)
+}
+
+fn default_trash_purge_interval() -> Duration {
+    Duration::hours(1)
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_trash_enabled(),
+            retention: default_trash_retention(),
+            purge_interval: default_trash_purge_interval(),
+        }
+    }
+}
+
+/// 审计日志配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AuditConfig {
+    /// 审计事件的保留时间，0 表示永久保留
+    #[serde(default = "default_audit_retention")]
+    pub retention: Duration,
+    /// 清理过期事件的执行间隔
+    #[serde(default = "default_audit_purge_interval")]
+    pub purge_interval: Duration,
+    /// 同一客户端IP的认证失败在此时间内只记录一次，其余合并计数，0 表示每次都记录
+    #[serde(default = "default_auth_failure_window")]
+    pub auth_failure_window: Duration,
+}
+
+fn default_audit_retention() -> Duration {
+    Duration::days(90)
+}
+
+fn default_audit_purge_interval() -> Duration {
+    Duration::hours(1)
+}
+
+fn default_auth_failure_window() -> Duration {
+    Duration::minutes(1)
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            retention: default_audit_retention(),
+            purge_interval: default_audit_purge_interval(),
+            auth_failure_window: default_auth_failure_window(),
+        }
+    }
 }
 
 fn default_auth_header_name() -> String {
     "Authorization".to_string()
 }
 
+fn default_signed_url_max_age() -> Duration {
+    Duration::days(7)
+}
+
 impl Default for AuthConfig {
     fn default() -> Self {
         Self {
             enabled: false,
             token: None,
             header_name: default_auth_header_name(),
+            url_signing_secret: None,
+            signed_url_max_age: default_signed_url_max_age(),
         }
     }
 }
@@ -132,6 +527,13 @@ impl Default for AppConfig {
             storage: StorageConfig {
                 upload_dir: "uploads".to_string(),
                 max_file_size: ByteSize::mb(10), // 10MB
+                backend: StorageBackendKind::Local,
+                s3: None,
+                metadata_policy: MetadataPolicy::Keep,
+                max_batch_size: default_max_batch_size(),
+                max_batch_files: default_max_batch_files(),
+                near_duplicate_policy: NearDuplicatePolicy::Allow,
+                near_duplicate_threshold: default_near_duplicate_threshold(),
             },
             database: DatabaseConfig {
                 database_type: "sqlite".to_string(),
@@ -161,6 +563,13 @@ impl Default for AppConfig {
                 space_threshold_percent: 0.8, // 80%使用率时才触发热度清理
             },
             auth: AuthConfig::default(),
+            transform: TransformConfig::default(),
+            fetch: FetchConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            scrub: ScrubConfig::default(),
+            trash: TrashConfig::default(),
+            audit: AuditConfig::default(),
+            presets: BTreeMap::new(),
         }
     }
 }
@@ -210,10 +619,38 @@ request_timeout = "1m"
 # ========================================
 
 [storage]
-# 上传文件存储目录
+# 上传文件存储目录（上传内容会先流式写入其中的 .staging 暂存目录）
 upload_dir = "uploads"
 # 最大文件大小
 max_file_size = "10MB"
+# 存储后端: local（本地目录）, s3（S3兼容对象存储，多副本部署时使用）
+backend = "local"
+# 上传图片的元数据处理: keep（原样保存）, strip（移除全部元数据）, strip_gps（仅移除GPS定位信息）
+# 在计算哈希和保存之前处理，ICC颜色配置文件始终保留
+metadata_policy = "keep"
+# 批量上传的请求体大小上限（包括 zip / tar 压缩包）
+max_batch_size = "100MB"
+# 批量上传单次最多处理的文件数（包括压缩包内的文件）
+max_batch_files = 100
+# 上传与自己已有图片视觉相似（重新编码、缩放后的副本）时的处理:
+# allow（照常保存）, reject（拒绝上传）, link（直接返回已有图片）
+near_duplicate_policy = "allow"
+# 判定为相似图片的最大感知哈希距离（0-64，越小越严格）
+near_duplicate_threshold = 5
+
+# S3兼容对象存储配置（backend = "s3" 时生效）
+# [storage.s3]
+# endpoint = "http://127.0.0.1:9000"
+# bucket = "rifs"
+# region = "us-east-1"
+# access_key = ""
+# secret_key = ""
+# 是否使用路径风格访问（MinIO 需要开启）
+# path_style = true
+# 对象键前缀（可选）
+# prefix = ""
+# 请求超时时间
+# timeout = "30s"
 
 # ========================================
 # 日志配置  
@@ -266,6 +703,119 @@ enabled = false
 token = ""
 # 可选：自定义认证头名称，默认使用 Authorization
 header_name = "Authorization"
+# 签名URL的HMAC密钥（建议设置为复杂随机字符串，多副本部署时需保持一致）
+# 私有图片需要签名URL或认证头才能访问，未配置时无法生成签名URL
+url_signing_secret = ""
+# 签名URL的最长有效期
+signed_url_max_age = "7d"
+
+# ========================================
+# 图片转换配置
+# ========================================
+
+[transform]
+# 是否只允许使用预设转换参数，防止任意尺寸请求滥用
+# 启用认证时携带有效令牌的请求不受限制
+presets_only = false
+# 未指定输出格式的图片请求按浏览器的 Accept 请求头自动输出 AVIF / WebP
+# 也可以只在需要的地址中使用 auto 参数，如 /images/<hash>@w800_auto
+auto_format = false
+# 执行图片解码、缩放和编码的工作线程数，0 表示使用CPU核心数
+workers = 0
+# 等待工作线程的转换任务上限，队列已满时返回 503
+# 相同的并发转换请求只计算一次，不重复占用队列
+queue_size = 64
+
+# ========================================
+# 远程图片抓取配置（POST /upload/url）
+# ========================================
+
+[fetch]
+# 抓取远程图片的总超时时间，文件大小同样受 storage.max_file_size 限制
+timeout = "30s"
+# 最多跟随的重定向次数
+max_redirects = 3
+# 默认拒绝访问私有、回环、链路本地等内网地址，防止通过服务器探测内网
+# 确有需要时可以放行指定的地址段，如 ["10.1.0.0/16", "192.168.1.10"]
+allowed_networks = []
+
+# ========================================
+# 请求频率限制配置
+# ========================================
+
+[rate_limit]
+# 是否启用频率限制，超出限制的请求返回 429 和 Retry-After
+# 携带有效令牌的请求按令牌计数，其余请求按客户端IP计数
+enabled = true
+# 从 X-Forwarded-For 请求头读取客户端IP（取最右侧即反向代理追加的地址）
+# 仅在可信的反向代理之后开启，否则客户端可以伪造地址绕过限制
+trust_forwarded_for = false
+
+# 每类请求按令牌桶限制：每个 period 补充 requests 次，最多允许 burst 次突发请求（默认等于 requests）
+# 上传请求
+[rate_limit.upload]
+requests = 60
+period = "1m"
+
+# 未命中缓存的图片转换（命中缓存和访问原图不受限制）
+[rate_limit.transform]
+requests = 120
+period = "1m"
+
+# 其他 /api 接口
+[rate_limit.api]
+requests = 600
+period = "1m"
+
+# ========================================
+# 存储校验配置
+# ========================================
+
+[scrub]
+# 是否定期核对图片、缓存记录与存储中的文件
+# 检查记录对应的文件是否存在、大小是否一致，以及没有记录的孤立文件
+# 也可以通过 POST /api/system/scrub 或 rifs verify 手动执行
+enabled = false
+# 执行间隔
+interval = "1d"
+# 是否读取文件内容校验 SHA-256，图片较多时耗时较长
+checksum = false
+# 是否自动修复：删除没有文件的记录，为孤立的图片文件创建记录（归属管理员、私有），
+# 损坏的图片文件移入存储中的 .quarantine 目录，有问题的缓存直接删除
+repair = false
+# 最近修改时间在此范围内的文件不视为孤立文件，避免误判正在上传的文件
+orphan_grace = "1h"
+
+# ========================================
+# 回收站配置
+# ========================================
+
+[trash]
+# 是否启用回收站：删除的图片先移入回收站，保留期内可以通过 /api/trash 恢复
+# 关闭后删除图片立即删除文件
+enabled = true
+# 图片在回收站中的保留时间，过期后彻底删除并释放配额
+retention = "30d"
+# 清理过期图片的执行间隔
+purge_interval = "1h"
+
+# ========================================
+# 审计日志配置
+# ========================================
+
+[audit]
+# 审计事件的保留时间，过期后自动删除，0 表示永久保留
+retention = "90d"
+# 清理过期事件的执行间隔
+purge_interval = "1h"
+# 同一客户端IP的认证失败在此时间内只记录一次，其余次数合并到下一条记录中，0 表示每次都记录
+auth_failure_window = "1m"
+
+# 命名转换预设，通过 /images/<hash>@preset:<名称> 访问
+# 启动时解析并校验，参数无效时拒绝启动
+[presets]
+# thumb = "w300_h300_cover_webp_q80"
+# large = "w1200_h1200_webp_q80"
 
 # ========================================
 # 数据库配置
@@ -399,13 +949,13 @@ max_lifetime = "30m"
         format!("{}:{}", self.server.host, self.server.port)
     }
 
-    /// 获取上传目录路径
-    pub fn upload_dir_path(&self) -> PathBuf {
-        PathBuf::from(&self.storage.upload_dir)
-    }
-
     /// 获取缓存控制头
     pub fn cache_control_header(&self) -> String {
         format!("public, max-age={}", self.cache.max_age.as_seconds())
     }
+
+    /// 获取私有图片的缓存控制头，禁止共享缓存存储
+    pub fn private_cache_control_header(&self) -> String {
+        format!("private, max-age={}", self.cache.max_age.as_seconds())
+    }
 }
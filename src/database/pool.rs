@@ -1,4 +1,4 @@
-use sea_orm::{Database, DatabaseConnection};
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend};
 use std::sync::Arc;
 use std::time::Duration as StdDuration;
 use tracing::{error, info};
@@ -142,13 +142,28 @@ impl DatabasePool {
 
     /// 获取连接池统计信息
     pub async fn get_pool_stats(&self) -> PoolStats {
-        // 注意：Sea-ORM 目前不直接暴露连接池统计信息
-        // 这里返回基本配置信息
+        // 从底层 sqlx 连接池读取当前连接数和空闲连接数
+        let (size, idle) = match self.connection.get_database_backend() {
+            DbBackend::Sqlite => {
+                let pool = self.connection.get_sqlite_connection_pool();
+                (pool.size(), pool.num_idle())
+            }
+            DbBackend::Postgres => {
+                let pool = self.connection.get_postgres_connection_pool();
+                (pool.size(), pool.num_idle())
+            }
+            DbBackend::MySql => {
+                let pool = self.connection.get_mysql_connection_pool();
+                (pool.size(), pool.num_idle())
+            }
+        };
+        let idle_connections = idle as u32;
+
         PoolStats {
             max_connections: self.pool_config.max_connections,
             min_connections: self.pool_config.min_connections,
-            active_connections: 0, // 无法直接获取
-            idle_connections: 0,   // 无法直接获取
+            active_connections: size.saturating_sub(idle_connections),
+            idle_connections,
         }
     }
 }
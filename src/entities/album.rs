@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 相册实体模型
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "albums")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+
+    /// 所属的 API Token ID
+    pub owner_token_id: i32,
+
+    /// 相册名称
+    pub name: String,
+
+    /// 相册描述
+    pub description: Option<String>,
+
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+
+    /// 更新时间
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 相册与图片的关联，按 `position` 从小到大排列
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "album_images")]
+pub struct Model {
+    /// 相册ID
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub album_id: i32,
+
+    /// 图片哈希
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub image_hash: String,
+
+    /// 图片在相册中的位置
+    pub position: i64,
+
+    /// 加入相册的时间
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -14,6 +14,8 @@ pub struct Model {
     pub role: String,
     pub max_upload_size: Option<i64>,
     pub used_upload_size: i64,
+    pub max_image_count: Option<i64>,
+    pub used_image_count: i64,
     pub expires_at: Option<DateTime<Utc>>,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
@@ -34,6 +36,8 @@ impl From<Model> for ApiTokenInfo {
             role: TokenRole::from(model.role.as_str()),
             max_upload_size: model.max_upload_size,
             used_upload_size: model.used_upload_size,
+            max_image_count: model.max_image_count,
+            used_image_count: model.used_image_count,
             expires_at: model.expires_at,
             is_active: model.is_active,
             created_at: model.created_at,
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::models::AuditEventInfo;
+
+/// 审计事件实体模型
+///
+/// 记录令牌管理、图片删除、缓存清空等操作以及认证失败，只追加不修改。
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "audit_events")]
+pub struct Model {
+    /// 自增主键
+    #[sea_orm(primary_key)]
+    pub id: i64,
+
+    /// 执行操作的令牌ID，认证失败时为空
+    pub actor_token_id: Option<i32>,
+
+    /// 操作类型
+    pub action: String,
+
+    /// 操作对象（令牌ID、图片hash等）
+    pub target: Option<String>,
+
+    /// 客户端IP
+    pub client_ip: Option<String>,
+
+    /// 操作结果（success / failure）
+    pub outcome: String,
+
+    /// 附加说明，失败时为错误信息
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail: Option<String>,
+
+    /// 发生时间
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for AuditEventInfo {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            actor_token_id: model.actor_token_id,
+            action: model.action,
+            target: model.target,
+            client_ip: model.client_ip,
+            outcome: model.outcome,
+            detail: model.detail,
+            created_at: model.created_at,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 文件内容实体模型
+///
+/// 按内容哈希去重后实际保存在存储后端中的文件，多个图片记录可以引用同一份内容。
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "blobs")]
+pub struct Model {
+    /// 文件内容哈希（主键，不混入所有者ID）
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub hash: String,
+
+    /// 文件大小（字节）
+    pub size: i64,
+
+    /// MIME 类型
+    pub mime_type: String,
+
+    /// 文件扩展名
+    pub extension: String,
+
+    /// 引用该内容的图片记录数量
+    #[sea_orm(default_value = 0)]
+    pub ref_count: i64,
+
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
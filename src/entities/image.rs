@@ -34,6 +34,43 @@ pub struct Model {
 
     /// 所属的 API Token ID
     pub owner_token_id: Option<i32>,
+
+    /// 是否为私有图片（需要签名或认证才能访问）
+    #[sea_orm(default_value = false)]
+    pub is_private: bool,
+
+    /// 感知哈希（64位 dHash 的十六进制表示，无法解码的图片为空）
+    pub phash: Option<String>,
+
+    /// 引用的文件内容哈希（为空时文件按图片哈希存储）
+    pub blob_hash: Option<String>,
+
+    /// 宽度（像素，已按 EXIF 方向旋转）
+    pub width: Option<i32>,
+
+    /// 高度（像素，已按 EXIF 方向旋转）
+    pub height: Option<i32>,
+
+    /// 帧数（静态图片为1）
+    pub frame_count: Option<i32>,
+
+    /// 是否包含透明像素
+    pub has_alpha: Option<bool>,
+
+    /// 颜色类型（如 rgb8、rgba8、l16）
+    pub color_type: Option<String>,
+
+    /// 主色调（#rrggbb）
+    pub dominant_color: Option<String>,
+
+    /// 调色板（逗号分隔的 #rrggbb，按占比从高到低排列）
+    pub palette: Option<String>,
+
+    /// BlurHash 占位字符串
+    pub blurhash: Option<String>,
+
+    /// 移入回收站的时间（为空表示未删除）
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -53,6 +90,22 @@ impl From<Model> for crate::models::ImageInfo {
             access_count: model.access_count,
             original_filename: model.original_filename,
             owner_token_id: model.owner_token_id,
+            is_private: model.is_private,
+            phash: model.phash,
+            blob_hash: model.blob_hash,
+            tags: Vec::new(),
+            width: model.width.map(|width| width as u32),
+            height: model.height.map(|height| height as u32),
+            frame_count: model.frame_count.map(|count| count as u32),
+            has_alpha: model.has_alpha,
+            color_type: model.color_type,
+            dominant_color: model.dominant_color,
+            palette: model
+                .palette
+                .map(|palette| palette.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            blurhash: model.blurhash,
+            deleted_at: model.deleted_at,
         }
     }
 }
@@ -69,6 +122,18 @@ impl From<&crate::models::ImageInfo> for ActiveModel {
             access_count: Set(info.access_count),
             original_filename: Set(info.original_filename.clone()),
             owner_token_id: Set(info.owner_token_id),
+            is_private: Set(info.is_private),
+            phash: Set(info.phash.clone()),
+            blob_hash: Set(info.blob_hash.clone()),
+            width: Set(info.width.map(|width| width as i32)),
+            height: Set(info.height.map(|height| height as i32)),
+            frame_count: Set(info.frame_count.map(|count| count as i32)),
+            has_alpha: Set(info.has_alpha),
+            color_type: Set(info.color_type.clone()),
+            dominant_color: Set(info.dominant_color.clone()),
+            palette: Set((!info.palette.is_empty()).then(|| info.palette.join(","))),
+            blurhash: Set(info.blurhash.clone()),
+            deleted_at: Set(info.deleted_at),
         }
     }
 }
@@ -1,7 +1,17 @@
+pub mod album;
+pub mod album_image;
 pub mod api_token;
+pub mod audit_event;
+pub mod blob;
 pub mod cache;
 pub mod image;
+pub mod image_tag;
 
+pub use album::Entity as Album;
+pub use album_image::Entity as AlbumImage;
 pub use api_token::Entity as ApiToken;
+pub use audit_event::Entity as AuditEvent;
+pub use blob::Entity as Blob;
 pub use cache::Entity as Cache;
 pub use image::Entity as Image;
+pub use image_tag::Entity as ImageTag;
@@ -0,0 +1,169 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use tracing::info;
+
+use crate::app_state::AppState;
+use crate::middleware::verify_token_from_headers;
+use crate::models::{AlbumImagesRequest, CreateAlbumRequest, UpdateAlbumRequest};
+use crate::services::AlbumService;
+use crate::utils::AppError;
+
+/// 列出相册（普通用户只能看到自己的相册）
+pub async fn list_albums(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let album_service = AlbumService::new(app_state.db_pool().get_connection());
+    let albums = album_service.list_albums(&auth_user).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "查询相册列表成功",
+        "data": albums
+    })))
+}
+
+/// 创建相册
+pub async fn create_album(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateAlbumRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("收到创建相册请求: {}", request.name);
+
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let album_service = AlbumService::new(app_state.db_pool().get_connection());
+    let album = album_service.create_album(&auth_user, request).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "相册创建成功",
+        "data": album
+    })))
+}
+
+/// 获取相册信息，相册中的图片通过 `/api/images/query?album_id=` 分页查询
+pub async fn get_album(
+    State(app_state): State<AppState>,
+    Path(album_id): Path<i32>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let album_service = AlbumService::new(app_state.db_pool().get_connection());
+    let album = album_service.get_album(&auth_user, album_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "获取相册信息成功",
+        "data": album
+    })))
+}
+
+/// 修改相册名称或描述
+pub async fn update_album(
+    State(app_state): State<AppState>,
+    Path(album_id): Path<i32>,
+    headers: HeaderMap,
+    Json(request): Json<UpdateAlbumRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("收到修改相册请求: {}", album_id);
+
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let album_service = AlbumService::new(app_state.db_pool().get_connection());
+    let album = album_service
+        .update_album(&auth_user, album_id, request)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "相册已更新",
+        "data": album
+    })))
+}
+
+/// 删除相册（不删除其中的图片）
+pub async fn delete_album(
+    State(app_state): State<AppState>,
+    Path(album_id): Path<i32>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    info!("收到删除相册请求: {}", album_id);
+
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let album_service = AlbumService::new(app_state.db_pool().get_connection());
+    album_service.delete_album(&auth_user, album_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "相册删除成功"
+    })))
+}
+
+/// 向相册中添加图片
+pub async fn add_album_images(
+    State(app_state): State<AppState>,
+    Path(album_id): Path<i32>,
+    headers: HeaderMap,
+    Json(request): Json<AlbumImagesRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!(
+        "收到添加相册图片请求: {} ({}张)",
+        album_id,
+        request.hashes.len()
+    );
+
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let album_service = AlbumService::new(app_state.db_pool().get_connection());
+    let album = album_service
+        .add_images(&auth_user, album_id, &request.hashes)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "图片已添加到相册",
+        "data": album
+    })))
+}
+
+/// 调整相册中图片的顺序
+pub async fn reorder_album_images(
+    State(app_state): State<AppState>,
+    Path(album_id): Path<i32>,
+    headers: HeaderMap,
+    Json(request): Json<AlbumImagesRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let album_service = AlbumService::new(app_state.db_pool().get_connection());
+    let album = album_service
+        .reorder_images(&auth_user, album_id, &request.hashes)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "相册图片顺序已更新",
+        "data": album
+    })))
+}
+
+/// 从相册中移除图片（不删除图片本身）
+pub async fn remove_album_image(
+    State(app_state): State<AppState>,
+    Path((album_id, identifier)): Path<(i32, String)>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let album_service = AlbumService::new(app_state.db_pool().get_connection());
+    album_service
+        .remove_image(&auth_user, album_id, &identifier)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "图片已从相册移除"
+    })))
+}
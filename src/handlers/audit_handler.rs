@@ -0,0 +1,69 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
+
+use crate::app_state::AppState;
+use crate::middleware::verify_token_from_headers;
+use crate::models::{AuditQuery, TokenRole};
+use crate::services::AuditService;
+use crate::utils::AppError;
+
+/// 分页查询审计事件 - 仅管理员
+pub async fn list_audit_events(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    if auth_user.role != TokenRole::Admin {
+        return Err(AppError::Unauthorized(
+            "需要管理员权限访问此资源".to_string(),
+        ));
+    }
+
+    let audit_service = AuditService::new(app_state.db_pool().get_connection());
+    let page_result = audit_service.query(&query).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "查询审计日志成功",
+        "data": {
+            "items": page_result.items,
+            "total": page_result.total,
+            "limit": query.limit.unwrap_or(20),
+            "offset": query.offset.unwrap_or(0)
+        }
+    })))
+}
+
+/// 按条件导出审计事件（JSON Lines）- 仅管理员
+pub async fn export_audit_events(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    if auth_user.role != TokenRole::Admin {
+        return Err(AppError::Unauthorized(
+            "需要管理员权限访问此资源".to_string(),
+        ));
+    }
+
+    let audit_service = AuditService::new(app_state.db_pool().get_connection());
+    let body = Body::from_stream(audit_service.export_json_lines(&query));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson"),
+            (
+                header::CONTENT_DISPOSITION,
+                r#"attachment; filename="audit_events.jsonl""#,
+            ),
+        ],
+        body,
+    ))
+}
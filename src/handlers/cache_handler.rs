@@ -8,8 +8,8 @@ use serde::Serialize;
 use crate::app_state::AppState;
 use crate::handlers::static_files::CACHE_MANAGEMENT_HTML;
 use crate::middleware::verify_token_from_headers;
-use crate::models::{CacheCleanupResult, TokenRole};
-use crate::services::CacheService;
+use crate::models::{AuditAction, CacheCleanupResult, TokenRole};
+use crate::services::{AuditService, CacheService};
 use crate::utils::AppError;
 
 /// 通用API响应结构
@@ -42,7 +42,7 @@ pub async fn get_cache_stats(
     }
 
     let db_connection = state.db_pool().get_connection();
-    let cache_service = CacheService::new(db_connection)?;
+    let cache_service = CacheService::new(db_connection, state.cache_storage())?;
 
     let stats = cache_service.get_stats().await?;
 
@@ -61,7 +61,7 @@ pub async fn decay_heat_scores(
     }
 
     let db_connection = state.db_pool().get_connection();
-    let cache_service = CacheService::new(db_connection)?;
+    let cache_service = CacheService::new(db_connection, state.cache_storage())?;
 
     let updated_count = cache_service.decay_heat_scores().await?;
 
@@ -84,7 +84,7 @@ pub async fn auto_cleanup_cache(
     }
 
     let connection = app_state.db_pool().get_connection();
-    let cache_service = CacheService::new(connection)?;
+    let cache_service = CacheService::new(connection, app_state.cache_storage())?;
 
     let result = cache_service.auto_cleanup().await?;
 
@@ -104,7 +104,7 @@ pub async fn clean_cache(
     }
 
     let connection = app_state.db_pool().get_connection();
-    let cache_service = CacheService::new(connection)?;
+    let cache_service = CacheService::new(connection, app_state.cache_storage())?;
 
     // 解析参数
     // max_age: 前端传递秒数，转换为天数
@@ -127,16 +127,21 @@ pub async fn clear_all_cache(
 ) -> Result<Json<ApiResponse<CacheCleanupResult>>, AppError> {
     // 验证管理员权限
     let auth_user = verify_token_from_headers(&headers, &app_state).await?;
-    if auth_user.role != TokenRole::Admin {
-        return Err(AppError::Unauthorized("需要管理员权限访问此资源".to_string()));
-    }
-
     let connection = app_state.db_pool().get_connection();
-    let cache_service = CacheService::new(connection)?;
+    let result = async {
+        if auth_user.role != TokenRole::Admin {
+            return Err(AppError::Unauthorized("需要管理员权限访问此资源".to_string()));
+        }
 
-    let result = cache_service.clear_all().await?;
+        let cache_service = CacheService::new(connection.clone(), app_state.cache_storage())?;
+        cache_service.clear_all().await
+    }
+    .await;
 
-    Ok(Json(ApiResponse::success("清理完成", Some(result))))
+    AuditService::new(connection)
+        .record_result(&auth_user, AuditAction::CacheClear, None, &result)
+        .await;
+    Ok(Json(ApiResponse::success("清理完成", Some(result?))))
 }
 
 /// 缓存管理面板（返回HTML页面）
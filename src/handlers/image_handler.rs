@@ -1,24 +1,48 @@
 use axum::{
-    extract::{Multipart, Path, Query, State},
-    http::{header, StatusCode},
-    response::IntoResponse,
+    body::{Body, Bytes},
+    extract::{Extension, Multipart, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use std::ops::Range;
+use std::time::Instant;
 use tracing::{error, info, warn};
 
 use crate::app_state::AppState;
 use crate::config::AppConfig;
-use crate::middleware::verify_token_from_headers;
+use crate::middleware::{authenticate_headers, verify_token_from_headers};
 
-use crate::models::{Base64ImageResponse, ImageQuery, ImageTransformParams, UploadResponse};
-use crate::services::{CacheService, ImageService, ImageTransformService};
-use crate::utils::AppError;
+use crate::models::{
+    ApiTokenInfo, AuditAction, Base64ImageResponse, BatchUploadItem, BatchUploadResponse, DeleteImageQuery, ImageInfo,
+    ImageQuery, ImageTagsRequest, ImageTransformParams, RemoteUploadRequest, SignUrlRequest, SignatureQuery,
+    SignedUrl, SimilarImage, SimilarQuery, TokenRole, UploadOptions, UploadResponse,
+    VisibilityRequest,
+};
+use crate::services::blurhash::BlurHash;
+use crate::services::image_format_utils::ImageFormatUtils;
+use crate::services::metrics::Metrics;
+use crate::services::perceptual_hash::PerceptualHash;
+use crate::services::rate_limiter::{RateLimitCategory, RateLimitKey};
+use crate::services::static_image_transform::StaticImageTransform;
+use crate::services::transform_presets::PRESET_PREFIX;
+use crate::services::upload_archive::{extract_archive, ArchiveKind};
+use crate::services::url_signing::{UrlSigner, DEFAULT_SIGNED_URL_TTL};
+use crate::services::{
+    AuditService, CacheService, ImageService, ImageTransformService, TagService, TrashService,
+};
+use crate::storage::{ByteStream, StagedUpload, StorageBackend, UploadStager};
+use crate::utils::conditional::{http_date, if_range_matches, is_not_modified, strong_etag};
+use crate::utils::{parse_range_header, AppError, RangeRequest};
 
 /// 图片上传接口
 pub async fn upload_image(
     State(app_state): State<AppState>,
     headers: axum::http::HeaderMap,
+    Query(options): Query<UploadOptions>,
     mut multipart: Multipart,
 ) -> Result<impl IntoResponse, AppError> {
     info!("收到图片上传请求");
@@ -26,7 +50,7 @@ pub async fn upload_image(
     // 验证token
     let auth_user = verify_token_from_headers(&headers, &app_state).await?;
 
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| {
         error!("解析multipart数据失败: {}", e);
         AppError::BadRequest("无效的multipart数据".to_string())
     })? {
@@ -36,28 +60,41 @@ pub async fn upload_image(
         if name == "file" {
             let original_filename = field.file_name().map(|name| name.to_string());
 
-            // 读取文件数据
-            let data = field.bytes().await.map_err(|e| {
+            // 将文件数据按块写入暂存文件，避免整体读入内存
+            let mut stager = UploadStager::new().await?;
+            while let Some(chunk) = field.chunk().await.map_err(|e| {
                 error!("读取文件数据失败: {}", e);
                 AppError::BadRequest("读取文件数据失败".to_string())
-            })?;
+            })? {
+                stager.write_chunk(&chunk).await?;
+            }
+            let staged = stager.finish().await?;
 
-            if data.is_empty() {
+            if staged.size() == 0 {
                 error!("上传的文件为空");
                 return Err(AppError::InvalidFile);
             }
 
             if let Some(ref filename) = original_filename {
-                info!("开始保存图片: {}字节, 原始文件名: {}", data.len(), filename);
+                info!("开始保存图片: {}字节, 原始文件名: {}", staged.size(), filename);
             } else {
-                info!("开始保存图片: {}字节", data.len());
+                info!("开始保存图片: {}字节", staged.size());
             }
 
             // 保存图片（后端会自动检测真实文件类型）
-            let image_info =
-                ImageService::save_image(app_state.db_pool(), &data, original_filename, &auth_user).await?;
+            let upload_size = staged.size();
+            let image_info = ImageService::save_image(
+                app_state.db_pool(),
+                app_state.storage(),
+                staged,
+                original_filename,
+                &auth_user,
+                &options,
+            )
+            .await?;
 
             info!("图片保存成功: {}", image_info.stored_name());
+            Metrics::get().observe_upload(upload_size);
 
             let response = UploadResponse {
                 success: true,
@@ -73,73 +110,338 @@ pub async fn upload_image(
     Err(AppError::BadRequest("请选择要上传的图片文件".to_string()))
 }
 
+/// 从远程URL上传图片接口
+pub async fn upload_image_from_url(
+    State(app_state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(options): Query<UploadOptions>,
+    Json(request): Json<RemoteUploadRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("收到远程图片上传请求: {}", request.url);
+
+    // 验证token
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+
+    let remote = app_state.fetcher().fetch(&request.url).await?;
+    let original_filename = request.filename.or(remote.filename);
+
+    let upload_size = remote.staged.size();
+    let image_info = ImageService::save_image(
+        app_state.db_pool(),
+        app_state.storage(),
+        remote.staged,
+        original_filename,
+        &auth_user,
+        &options,
+    )
+    .await?;
+
+    info!("远程图片保存成功: {}", image_info.stored_name());
+    Metrics::get().observe_upload(upload_size);
+
+    let response = UploadResponse {
+        success: true,
+        message: "图片上传成功".to_string(),
+        data: Some(image_info),
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// 批量上传接口
+///
+/// 接受多个 `file` / `files` 字段，zip、tar、tar.gz 压缩包会被解开后逐个保存。
+/// 整批大小先一次性计入配额；单个文件失败只体现在对应的结果中，不影响其他文件。
+pub async fn upload_images_batch(
+    State(app_state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(options): Query<UploadOptions>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    info!("收到批量上传请求");
+
+    // 验证token
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let max_files = AppConfig::get().storage.max_batch_files;
+
+    // 按上传顺序记录每个文件，暂存失败的文件直接记为失败
+    let mut entries: Vec<(Option<String>, Result<StagedUpload, AppError>)> = Vec::new();
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| {
+        error!("解析multipart数据失败: {}", e);
+        AppError::BadRequest("无效的multipart数据".to_string())
+    })? {
+        let name = field.name().unwrap_or("").to_string();
+        if name != "file" && name != "files" {
+            continue;
+        }
+        if entries.len() >= max_files {
+            return Err(AppError::BadRequest(format!(
+                "批量上传最多允许 {} 个文件",
+                max_files
+            )));
+        }
+
+        let original_filename = field.file_name().map(|name| name.to_string());
+        let mut stager = UploadStager::new().await?;
+        let mut staged = Ok(());
+        while let Some(chunk) = field.chunk().await.map_err(|e| {
+            error!("读取文件数据失败: {}", e);
+            AppError::BadRequest("读取文件数据失败".to_string())
+        })? {
+            // 单个文件超过大小限制时丢弃剩余数据，继续处理后续文件
+            if staged.is_ok() {
+                staged = stager.write_chunk(&chunk).await;
+            }
+        }
+        let staged = match staged {
+            Ok(()) => stager.finish().await,
+            Err(e) => Err(e),
+        };
+
+        match staged {
+            Ok(staged) => match ArchiveKind::detect(staged.head()) {
+                Some(kind) => {
+                    info!("解开压缩包: {:?} ({:?})", original_filename, kind);
+                    // 单个压缩包无法解开时记为该压缩包失败，只有超过文件数上限时整批失败
+                    match extract_archive(&staged, kind, max_files - entries.len()).await? {
+                        Ok(archive_entries) => entries.extend(
+                            archive_entries
+                                .into_iter()
+                                .map(|entry| (Some(entry.name), entry.staged)),
+                        ),
+                        Err(e) => {
+                            warn!("解开压缩包失败: {:?} - {}", original_filename, e);
+                            entries.push((original_filename, Err(e)));
+                        }
+                    }
+                }
+                None => entries.push((original_filename, Ok(staged))),
+            },
+            Err(e) => entries.push((original_filename, Err(e))),
+        }
+    }
+
+    if entries.is_empty() {
+        error!("未找到有效的文件字段");
+        return Err(AppError::BadRequest("请选择要上传的图片文件".to_string()));
+    }
+
+    // 暂存成功的文件一起保存，结果再按原顺序放回
+    let mut filenames = Vec::with_capacity(entries.len());
+    let mut outcomes = Vec::with_capacity(entries.len());
+    let mut files = Vec::new();
+    for (filename, staged) in entries {
+        match staged {
+            Ok(staged) => {
+                // 压缩包内的路径只保留文件名部分作为原始文件名
+                let original_filename = filename
+                    .as_deref()
+                    .and_then(|name| name.rsplit('/').next())
+                    .map(|name| name.to_string());
+                files.push((staged, original_filename));
+                outcomes.push(None);
+            }
+            Err(e) => outcomes.push(Some(Err(e))),
+        }
+        filenames.push(filename);
+    }
+
+    let sizes: Vec<u64> = files.iter().map(|(staged, _)| staged.size()).collect();
+    let mut saved = ImageService::save_images(
+        app_state.db_pool(),
+        app_state.storage(),
+        files,
+        &auth_user,
+        &options,
+    )
+    .await?
+    .into_iter()
+    .zip(sizes);
+
+    let mut results = Vec::with_capacity(outcomes.len());
+    for (filename, outcome) in filenames.into_iter().zip(outcomes) {
+        let outcome = match outcome {
+            Some(outcome) => outcome,
+            None => {
+                let (result, size) = saved.next().expect("批量保存结果数量不匹配");
+                if result.is_ok() {
+                    Metrics::get().observe_upload(size);
+                }
+                result
+            }
+        };
+        results.push(match outcome {
+            Ok(image_info) => BatchUploadItem {
+                filename,
+                success: true,
+                message: "图片上传成功".to_string(),
+                data: Some(image_info),
+            },
+            Err(e) => {
+                warn!("批量上传中的文件保存失败: {:?} - {}", filename, e);
+                BatchUploadItem {
+                    filename,
+                    success: false,
+                    message: e.to_string(),
+                    data: None,
+                }
+            }
+        });
+    }
+
+    let total = results.len();
+    let succeeded = results.iter().filter(|item| item.success).count();
+    let failed = total - succeeded;
+    info!("批量上传完成: 共{}个文件, 成功{}, 失败{}", total, succeeded, failed);
+
+    let response = BatchUploadResponse {
+        success: failed == 0,
+        message: format!("批量上传完成: 成功{}个, 失败{}个", succeeded, failed),
+        total,
+        succeeded,
+        failed,
+        results,
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
 /// 获取图片接口（通过哈希值，支持格式转换）
 pub async fn get_image(
     State(app_state): State<AppState>,
     Path(identifier): Path<String>,
+    Query(signature): Query<SignatureQuery>,
+    rate_limit_key: Option<Extension<RateLimitKey>>,
+    request_headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
+    let rate_limit_key = rate_limit_key.map(|Extension(key)| key);
     // 解析标识符，检查是否包含转换参数
-    let (hash, transform_params) = if let Some(at_pos) = identifier.find('@') {
-        let hash = &identifier[..at_pos];
-        let params_str = &identifier[at_pos + 1..];
+    let (hash, params_str) = match identifier.split_once('@') {
+        Some((hash, params_str)) => (hash, Some(params_str)),
+        None => (identifier.as_str(), None),
+    };
+
+    // 签名覆盖哈希和原始的转换参数字符串
+    let signed = verify_signature(hash, params_str.unwrap_or(""), &signature)?;
 
+    let config = AppConfig::get();
+    let mut transform_params = if let Some(params_str) = params_str {
         info!("解析转换参数: {}", params_str);
 
-        let params = ImageTransformParams::parse(params_str)
-            .map_err(|e| AppError::BadRequest(format!("转换参数解析失败: {}", e)))?;
+        // 只允许预设时，自定义参数需要携带有效签名或令牌
+        let allow_custom = signed
+            || !config.transform.presets_only
+            || params_str.starts_with(PRESET_PREFIX)
+            || is_authenticated_request(&request_headers, &app_state).await;
 
-        // 检查是否真的需要转换
-        if !params.needs_transform() {
-            info!("转换参数为空，返回原图: {}", hash);
-            (hash, None)
-        } else {
-            // 验证参数合理性
-            ImageTransformService::validate_params(&params)?;
-            (hash, Some(params))
-        }
+        // 解析并验证参数（预设已在启动时验证）
+        Some(app_state.presets().resolve(params_str, allow_custom)?)
     } else {
-        (identifier.as_str(), None)
+        None
     };
 
-    // 读取原始图片文件
-    let image_data = ImageService::read_image_file(app_state.db_pool(), hash).await?;
-
-    // 获取图片信息
+    // 获取图片信息，私有图片需要有效签名或所有者令牌
     let image_info = ImageService::get_image_info(app_state.db_pool(), hash)
         .await?
         .ok_or(AppError::FileNotFound)?;
+    if image_info.is_private && !signed {
+        authorize_private_access(&request_headers, &app_state, &image_info).await?;
+    }
+    ImageService::record_access(app_state.db_pool(), hash).await;
+
+    // BlurHash 输出只返回占位字符串，不做格式协商
+    if let Some(params) = transform_params
+        .as_ref()
+        .filter(|params| params.base64_mode == crate::models::Base64OutputMode::BlurHash)
+    {
+        return blurhash_response(
+            &app_state,
+            &image_info,
+            params,
+            rate_limit_key.as_ref(),
+            &request_headers,
+        )
+        .await;
+    }
 
-    let config = AppConfig::get();
+    // 未指定格式时按 Accept 请求头选择输出格式，协商结果写入参数，ETag 和缓存键随之区分
+    let negotiate_format = match transform_params {
+        Some(ref params) => {
+            params.format.is_none() && (params.auto_format || config.transform.auto_format)
+        }
+        None => config.transform.auto_format,
+    };
+    if negotiate_format {
+        let accept = request_headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("");
+        let params = transform_params.get_or_insert_with(ImageTransformParams::default);
+        params.format = ImageFormatUtils::negotiate_format(accept, &image_info.mime_type, params)
+            .map(str::to_string);
+        params.auto_format = false;
+        info!("协商输出格式: {} -> {:?}", image_info.mime_type, params.format);
+    }
 
-    // 根据是否需要转换决定处理方式
-    let (final_data, final_mime) = if let Some(ref params) = transform_params {
-        let config = AppConfig::get();
+    // 检查是否真的需要转换
+    let transform_params = transform_params.filter(|params| {
+        let needs_transform = params.needs_transform();
+        if !needs_transform {
+            info!("转换参数为空，返回原图: {}", hash);
+        }
+        needs_transform
+    });
 
+    let cache_control = if image_info.is_private {
+        config.private_cache_control_header()
+    } else {
+        config.cache_control_header()
+    };
+
+    // 图片按内容哈希寻址，ETag 由哈希和规范化的转换参数唯一确定
+    let etag = strong_etag(&match transform_params {
+        Some(ref params) => format!("{}@{}", image_info.hash, params.to_normalized_string()),
+        None => image_info.hash.clone(),
+    });
+    let last_modified = http_date(&image_info.created_at);
+
+    if is_not_modified(&request_headers, &etag, &image_info.created_at) {
+        info!("客户端缓存仍然有效: {}", identifier);
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, etag.parse().unwrap());
+        headers.insert(header::LAST_MODIFIED, last_modified.parse().unwrap());
+        headers.insert(header::CACHE_CONTROL, cache_control.parse().unwrap());
+        if negotiate_format {
+            headers.insert(header::VARY, "Accept".parse().unwrap());
+        }
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    // 根据是否需要转换决定处理方式；不需要转换时原图以流的方式返回，final_data 为 None
+    let (final_data, final_mime) = if let Some(ref params) = transform_params {
         // 检查是否启用缓存
         if config.cache.enable_transform_cache {
             let cache_key = CacheService::generate_cache_key(hash, params);
 
             // 尝试从缓存获取
             let connection = app_state.db_pool().get_connection();
-            let cache_service = CacheService::new(connection)?;
-            cache_service.ensure_cache_dir().await?;
+            let cache_service = CacheService::new(connection, app_state.cache_storage())?;
 
             if let Ok(Some(cached)) = cache_service.get_cache(&cache_key).await {
                 info!("缓存命中: {}", cache_key);
+                Metrics::get().observe_cache_lookup(true);
                 let cached_data = cache_service.read_cache(&cached).await?;
-                (cached_data, cached.mime_type)
+                (Some(cached_data), cached.mime_type)
             } else {
-                // 缓存未命中，进行转换
+                // 缓存未命中，读取原图进行转换
                 info!(
                     "缓存未命中，开始图片转换: {} -> {:?}",
                     image_info.mime_type, params
                 );
-                let (transformed_data, transformed_mime) = ImageTransformService::transform_image(
-                    &image_data,
-                    &image_info.mime_type,
-                    params,
-                )
-                .await?;
+                Metrics::get().observe_cache_lookup(false);
+                let (transformed_data, transformed_mime) =
+                    transform_original(&app_state, &image_info, params, rate_limit_key.as_ref())
+                        .await?;
 
                 // 保存到缓存
                 if let Err(e) = cache_service
@@ -149,7 +451,7 @@ pub async fn get_image(
                     warn!("保存缓存失败: {}", e);
                 }
 
-                (transformed_data, transformed_mime)
+                (Some(transformed_data), transformed_mime)
             }
         } else {
             // 缓存未启用，直接转换
@@ -157,13 +459,19 @@ pub async fn get_image(
                 "开始图片转换（缓存未启用）: {} -> {:?}",
                 image_info.mime_type, params
             );
-            ImageTransformService::transform_image(&image_data, &image_info.mime_type, params)
-                .await?
+            let (transformed_data, transformed_mime) =
+                transform_original(&app_state, &image_info, params, rate_limit_key.as_ref())
+                    .await?;
+            (Some(transformed_data), transformed_mime)
         }
     } else {
         // 不需要转换，返回原始数据
-        (image_data, image_info.mime_type.clone())
+        (None, image_info.mime_type.clone())
     };
+    let final_size = final_data
+        .as_ref()
+        .map(|data| data.len() as u64)
+        .unwrap_or(image_info.size);
 
     // 生成文件名（如果进行了转换，使用新的扩展名）
     let filename = if let Some(ref params) = transform_params {
@@ -186,10 +494,13 @@ pub async fn get_image(
     };
 
     let content_disposition = format!(r#"inline; filename="{}""#, filename);
-    let cache_control = config.cache_control_header();
+
+    // base64 输出不支持按区间返回
+    let base64_output = transform_params
+        .as_ref()
+        .is_some_and(|params| params.base64_mode != crate::models::Base64OutputMode::None);
 
     // 构建扩展的响应头，包含图片信息
-    use axum::http::HeaderMap;
     let mut headers = HeaderMap::new();
 
     // 基础响应头
@@ -199,6 +510,14 @@ pub async fn get_image(
         content_disposition.parse().unwrap(),
     );
     headers.insert(header::CACHE_CONTROL, cache_control.parse().unwrap());
+    headers.insert(header::ETAG, etag.parse().unwrap());
+    headers.insert(header::LAST_MODIFIED, last_modified.parse().unwrap());
+    if negotiate_format {
+        headers.insert(header::VARY, "Accept".parse().unwrap());
+    }
+    if !base64_output {
+        headers.insert(header::ACCEPT_RANGES, "bytes".parse().unwrap());
+    }
 
     // 原始图片信息
     headers.insert("x-original-hash", image_info.hash.parse().unwrap());
@@ -234,7 +553,7 @@ pub async fn get_image(
     headers.insert("x-final-mime", final_mime.parse().unwrap());
     headers.insert(
         "x-final-size",
-        final_data.len().to_string().parse().unwrap(),
+        final_size.to_string().parse().unwrap(),
     );
 
     // 如果有转换参数，添加转换信息
@@ -248,7 +567,8 @@ pub async fn get_image(
             crate::models::Base64OutputMode::Raw => {
                 headers.insert("x-output-format", "base64-raw".parse().unwrap());
             }
-            crate::models::Base64OutputMode::None => {}
+            // BlurHash 输出在读取图片前已经返回
+            crate::models::Base64OutputMode::None | crate::models::Base64OutputMode::BlurHash => {}
         }
 
         if let Some(width) = params.width {
@@ -282,8 +602,13 @@ pub async fn get_image(
         let params_summary = identifier.split('@').nth(1).unwrap_or("").to_string();
         headers.insert("x-transform-params", params_summary.parse().unwrap());
 
-        // 检查是否从GIF提取了第一帧
-        if image_info.mime_type == "image/gif" && params.format.is_some() {
+        // 仅在输出为 GIF 的第一帧时标记：指定 frame0，或转换为不支持动画的静图格式
+        let first_frame_only = params.frame == Some(0)
+            || (params.frame.is_none()
+                && params.target_mime_type().is_some_and(|mime| {
+                    !matches!(mime.as_str(), "image/gif" | "image/webp")
+                }));
+        if image_info.mime_type == "image/gif" && first_frame_only {
             headers.insert("x-gif-first-frame", "true".parse().unwrap());
         }
     } else {
@@ -303,6 +628,55 @@ pub async fn get_image(
             .unwrap(),
     );
 
+    // 处理 Range 请求
+    let range_request = match request_headers.get(header::RANGE) {
+        Some(value)
+            if !base64_output
+                && if_range_matches(&request_headers, &etag, &image_info.created_at) =>
+        {
+            value
+                .to_str()
+                .map(|value| parse_range_header(value, final_size))
+                .unwrap_or(RangeRequest::Full)
+        }
+        _ => RangeRequest::Full,
+    };
+    match range_request {
+        RangeRequest::Full => {}
+        RangeRequest::Unsatisfiable => {
+            info!("请求的区间无法满足: {}", identifier);
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::CONTENT_RANGE,
+                format!("bytes */{}", final_size).parse().unwrap(),
+            );
+            headers.insert(header::ETAG, etag.parse().unwrap());
+            return Ok((StatusCode::RANGE_NOT_SATISFIABLE, headers).into_response());
+        }
+        RangeRequest::Partial(ranges) => {
+            return partial_content_response(
+                app_state.storage(),
+                &image_info.storage_key(),
+                final_data.map(Bytes::from),
+                &final_mime,
+                final_size,
+                ranges,
+                headers,
+            )
+            .await;
+        }
+    }
+
+    let final_data = match final_data {
+        Some(data) => data,
+        None => {
+            // 原图直接以流的方式返回
+            let stream = app_state.storage().get_stream(&image_info.storage_key()).await?;
+            headers.insert(header::CONTENT_LENGTH, final_size.into());
+            return Ok((headers, Body::from_stream(stream)).into_response());
+        }
+    };
+
     // 检查是否需要返回base64格式
     if let Some(ref params) = transform_params {
         match params.base64_mode {
@@ -334,7 +708,7 @@ pub async fn get_image(
                 )
                     .into_response());
             }
-            crate::models::Base64OutputMode::None => {
+            crate::models::Base64OutputMode::None | crate::models::Base64OutputMode::BlurHash => {
                 // 不需要base64处理，继续正常流程
             }
         }
@@ -343,14 +717,233 @@ pub async fn get_image(
     Ok((headers, final_data).into_response())
 }
 
+/// 返回图片的 BlurHash 占位字符串
+///
+/// 只有 BlurHash 参数时直接返回上传时保存的值，带有其他转换参数时按转换结果重新计算。
+async fn blurhash_response(
+    app_state: &AppState,
+    image_info: &ImageInfo,
+    params: &ImageTransformParams,
+    rate_limit_key: Option<&RateLimitKey>,
+    request_headers: &HeaderMap,
+) -> Result<Response, AppError> {
+    let config = AppConfig::get();
+    let cache_control = if image_info.is_private {
+        config.private_cache_control_header()
+    } else {
+        config.cache_control_header()
+    };
+    let etag = strong_etag(&format!(
+        "{}@{}",
+        image_info.hash,
+        params.to_normalized_string()
+    ));
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ETAG, etag.parse().unwrap());
+    headers.insert(header::CACHE_CONTROL, cache_control.parse().unwrap());
+    if is_not_modified(request_headers, &etag, &image_info.created_at) {
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    let blurhash = match image_info.blurhash {
+        Some(ref blurhash) if !params.needs_transform() => blurhash.clone(),
+        _ => {
+            let data = if params.needs_transform() {
+                transform_original(app_state, image_info, params, rate_limit_key)
+                    .await?
+                    .0
+            } else {
+                app_state.storage().get(&image_info.storage_key()).await?
+            };
+            let img = StaticImageTransform::load_image_with_color_info(&data)?;
+            BlurHash::encode(&img)
+        }
+    };
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        "text/plain; charset=utf-8".parse().unwrap(),
+    );
+    headers.insert("x-output-format", "blurhash".parse().unwrap());
+    Ok((headers, blurhash).into_response())
+}
+
+/// 读取原图并在转换工作线程池中执行转换，记录转换耗时
+///
+/// 每次未命中缓存的转换请求都计入请求方的转换频率限制；
+/// 相同图片和参数的并发请求只转换一次，共享同一个结果。
+async fn transform_original(
+    app_state: &AppState,
+    image_info: &ImageInfo,
+    params: &ImageTransformParams,
+    rate_limit_key: Option<&RateLimitKey>,
+) -> Result<(Vec<u8>, String), AppError> {
+    if let Some(key) = rate_limit_key {
+        app_state
+            .rate_limiter()
+            .check(RateLimitCategory::Transform, key)?;
+    }
+
+    let cache_key = CacheService::generate_cache_key(&image_info.hash, params);
+    let task_state = app_state.clone();
+    let image_info = image_info.clone();
+    let params = params.clone();
+    let task = async move {
+        let image_data = task_state.storage().get(&image_info.storage_key()).await?;
+
+        let start = Instant::now();
+        let source_mime = image_info.mime_type.clone();
+        let (transformed_data, transformed_mime) = task_state
+            .transform_pool()
+            .execute(move || {
+                ImageTransformService::transform_image_blocking(&image_data, &source_mime, &params)
+            })
+            .await?;
+        Metrics::get().observe_transform(
+            &image_info.mime_type,
+            &transformed_mime,
+            start.elapsed(),
+        );
+
+        Ok((transformed_data, transformed_mime))
+    };
+
+    app_state
+        .transform_pool()
+        .deduplicate(&cache_key, task)
+        .await
+}
+
+/// 校验签名URL参数，未携带签名时返回 false
+fn verify_signature(hash: &str, transform: &str, query: &SignatureQuery) -> Result<bool, AppError> {
+    let (Some(expires), Some(signature)) = (query.expires, query.signature.as_deref()) else {
+        return Ok(false);
+    };
+
+    let signer = UrlSigner::from_config(&AppConfig::get().auth)?;
+    signer.verify(hash, transform, expires, signature, Utc::now().timestamp())?;
+    Ok(true)
+}
+
+/// 私有图片只允许所有者或管理员通过认证头访问
+async fn authorize_private_access(
+    headers: &HeaderMap,
+    app_state: &AppState,
+    image_info: &ImageInfo,
+) -> Result<(), AppError> {
+    let auth_user = verify_token_from_headers(headers, app_state).await?;
+    ensure_owner(&auth_user, image_info, "无权限访问此私有图片")
+}
+
+/// 检查令牌是否为图片所有者或管理员
+fn ensure_owner(
+    auth_user: &ApiTokenInfo,
+    image_info: &ImageInfo,
+    message: &str,
+) -> Result<(), AppError> {
+    if auth_user.role != TokenRole::Admin && image_info.owner_token_id != Some(auth_user.id) {
+        return Err(AppError::Forbidden(message.to_string()));
+    }
+    Ok(())
+}
+
+/// 请求是否携带有效令牌（认证未启用时无法区分调用方，视为未认证）
+async fn is_authenticated_request(headers: &HeaderMap, app_state: &AppState) -> bool {
+    AppConfig::get().auth.enabled && authenticate_headers(headers, app_state).await.is_ok()
+}
+
+/// 构建 206 部分内容响应
+///
+/// `data` 为 `None` 时按区间从存储后端流式读取原图，否则直接截取内存中的转换结果。
+/// 多个区间使用 `multipart/byteranges` 返回。
+async fn partial_content_response(
+    storage: &dyn StorageBackend,
+    storage_key: &str,
+    data: Option<Bytes>,
+    mime: &str,
+    total: u64,
+    ranges: Vec<Range<u64>>,
+    mut headers: HeaderMap,
+) -> Result<Response, AppError> {
+    async fn range_stream(
+        storage: &dyn StorageBackend,
+        storage_key: &str,
+        data: Option<&Bytes>,
+        range: Range<u64>,
+    ) -> Result<ByteStream, AppError> {
+        match data {
+            Some(data) => {
+                let chunk = data.slice(range.start as usize..range.end as usize);
+                Ok(stream::once(async move { Ok(chunk) }).boxed())
+            }
+            None => storage.get_range_stream(storage_key, range).await,
+        }
+    }
+
+    if let [range] = ranges.as_slice() {
+        let range = range.clone();
+        headers.insert(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", range.start, range.end - 1, total)
+                .parse()
+                .unwrap(),
+        );
+        headers.insert(header::CONTENT_LENGTH, (range.end - range.start).into());
+        let body = range_stream(storage, storage_key, data.as_ref(), range).await?;
+        return Ok((StatusCode::PARTIAL_CONTENT, headers, Body::from_stream(body)).into_response());
+    }
+
+    let boundary = format!("rifs-{:016x}", rand::random::<u64>());
+    let mut parts: Vec<ByteStream> = Vec::with_capacity(ranges.len() * 2 + 1);
+    let mut content_length = 0u64;
+
+    for range in ranges {
+        let part_header = format!(
+            "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+            boundary,
+            mime,
+            range.start,
+            range.end - 1,
+            total
+        );
+        content_length += part_header.len() as u64 + (range.end - range.start);
+        parts.push(stream::once(async move { Ok(Bytes::from(part_header)) }).boxed());
+        parts.push(range_stream(storage, storage_key, data.as_ref(), range).await?);
+    }
+
+    let closing = format!("\r\n--{}--\r\n", boundary);
+    content_length += closing.len() as u64;
+    parts.push(stream::once(async move { Ok(Bytes::from(closing)) }).boxed());
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        format!("multipart/byteranges; boundary={}", boundary)
+            .parse()
+            .unwrap(),
+    );
+    headers.insert(header::CONTENT_LENGTH, content_length.into());
+    let body = Body::from_stream(stream::iter(parts).flatten());
+    Ok((StatusCode::PARTIAL_CONTENT, headers, body).into_response())
+}
+
 /// 获取图片信息接口（通过哈希值）
 pub async fn get_image_info(
     State(app_state): State<AppState>,
     Path(identifier): Path<String>,
+    Query(signature): Query<SignatureQuery>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
-    let image_info = ImageService::get_image_info(app_state.db_pool(), &identifier)
+    let signed = verify_signature(&identifier, "", &signature)?;
+    let mut image_info = ImageService::get_image_info(app_state.db_pool(), &identifier)
         .await?
         .ok_or(AppError::FileNotFound)?;
+    if image_info.is_private && !signed {
+        authorize_private_access(&headers, &app_state, &image_info).await?;
+    }
+
+    let tag_service = TagService::new(app_state.db_pool().get_connection());
+    image_info.tags = tag_service.get_tags(&image_info.hash).await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
@@ -452,9 +1045,12 @@ pub async fn get_stats(
 }
 
 /// 删除图片接口（通过哈希值）
+///
+/// 启用回收站时图片移入回收站，`permanent=true` 时直接彻底删除。
 pub async fn delete_image(
     State(app_state): State<AppState>,
     Path(identifier): Path<String>,
+    Query(query): Query<DeleteImageQuery>,
     headers: axum::http::HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
     info!("收到删除图片请求: {}", identifier);
@@ -462,45 +1058,211 @@ pub async fn delete_image(
     // 验证token
     let auth_user = verify_token_from_headers(&headers, &app_state).await?;
 
-    // 获取图片信息以检查权限
+    let trashed = !query.permanent && AppConfig::get().trash.enabled;
+    let result = async {
+        // 获取图片信息以检查权限
+        let image_info = ImageService::get_image_info(app_state.db_pool(), &identifier)
+            .await?
+            .ok_or(AppError::FileNotFound)?;
+
+        // 检查权限：管理员可以删除任何图片，普通用户只能删除自己上传的图片
+        if auth_user.role != crate::models::TokenRole::Admin
+            && image_info.owner_token_id != Some(auth_user.id) {
+            return Err(AppError::Unauthorized("无权限删除此图片".to_string()));
+        }
+
+        // 相关缓存随图片一起删除
+        TrashService::delete(&app_state, &image_info, query.permanent).await
+    }
+    .await;
+
+    let action = if trashed { AuditAction::ImageDelete } else { AuditAction::ImagePurge };
+    AuditService::new(app_state.db_pool().get_connection())
+        .record_result(&auth_user, action, Some(identifier.clone()), &result)
+        .await;
+    let cache_count = result?;
+
+    info!("图片删除成功: {}", identifier);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": if trashed { "图片已移入回收站" } else { "图片删除成功" },
+        "trashed": trashed,
+        "cache_cleaned": cache_count
+    })))
+}
+
+/// 修改图片可见性接口（仅图片所有者或管理员）
+pub async fn set_image_visibility(
+    State(app_state): State<AppState>,
+    Path(identifier): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<VisibilityRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!(
+        "收到修改图片可见性请求: {} -> {}",
+        identifier, request.is_private
+    );
+
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
     let image_info = ImageService::get_image_info(app_state.db_pool(), &identifier)
         .await?
         .ok_or(AppError::FileNotFound)?;
+    ensure_owner(&auth_user, &image_info, "无权限修改此图片")?;
 
-    // 检查权限：管理员可以删除任何图片，普通用户只能删除自己上传的图片
-    if auth_user.role != crate::models::TokenRole::Admin 
-        && image_info.owner_token_id != Some(auth_user.id) {
-        return Err(AppError::Unauthorized("无权限删除此图片".to_string()));
-    }
+    ImageService::set_visibility(app_state.db_pool(), &identifier, request.is_private).await?;
 
-    ImageService::delete_image(app_state.db_pool(), &identifier).await?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "图片可见性已更新",
+        "data": {
+            "hash": image_info.hash,
+            "is_private": request.is_private
+        }
+    })))
+}
 
-    let config = AppConfig::get();
-    let mut cache_count = 0;
-
-    // 如果启用了缓存，同时删除相关缓存
-    if config.cache.enable_transform_cache {
-        let connection = app_state.db_pool().get_connection();
-        let cache_service = CacheService::new(connection)?;
-
-        match cache_service.remove_by_original_hash(&identifier).await {
-            Ok(count) => {
-                cache_count = count;
-                if count > 0 {
-                    info!("删除图片{}的{}个相关缓存", identifier, count);
-                }
-            }
-            Err(e) => {
-                warn!("删除图片缓存失败: {}", e);
-            }
+/// 查找相似图片接口（仅图片所有者或管理员）
+///
+/// 在该图片所有者的图片中按感知哈希距离查找视觉相似的图片，不包含图片本身。
+pub async fn find_similar_images(
+    State(app_state): State<AppState>,
+    Path(identifier): Path<String>,
+    Query(query): Query<SimilarQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    info!("收到查找相似图片请求: {}", identifier);
+
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let image_info = ImageService::get_image_info(app_state.db_pool(), &identifier)
+        .await?
+        .ok_or(AppError::FileNotFound)?;
+    ensure_owner(&auth_user, &image_info, "无权限查看此图片")?;
+
+    let phash = image_info
+        .phash
+        .as_deref()
+        .and_then(PerceptualHash::parse)
+        .ok_or_else(|| AppError::BadRequest("该图片没有感知哈希，无法查找相似图片".to_string()))?;
+
+    let max_distance = query
+        .max_distance
+        .unwrap_or(AppConfig::get().storage.near_duplicate_threshold)
+        .min(64);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let similar: Vec<SimilarImage> = ImageService::find_similar(
+        app_state.db_pool(),
+        image_info.owner_token_id,
+        phash,
+        max_distance,
+    )
+    .await?
+    .into_iter()
+    .filter(|similar| similar.image.hash != image_info.hash)
+    .take(limit)
+    .collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("找到{}张相似图片", similar.len()),
+        "data": similar
+    })))
+}
+
+/// 设置图片标签接口（仅图片所有者或管理员），替换图片原有的全部标签
+pub async fn set_image_tags(
+    State(app_state): State<AppState>,
+    Path(identifier): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<ImageTagsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("收到设置图片标签请求: {} -> {:?}", identifier, request.tags);
+
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let image_info = ImageService::get_image_info(app_state.db_pool(), &identifier)
+        .await?
+        .ok_or(AppError::FileNotFound)?;
+    ensure_owner(&auth_user, &image_info, "无权限修改此图片")?;
+
+    let tag_service = TagService::new(app_state.db_pool().get_connection());
+    let tags = tag_service.set_tags(&image_info.hash, &request.tags).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "图片标签已更新",
+        "data": {
+            "hash": image_info.hash,
+            "tags": tags
         }
+    })))
+}
+
+/// 标签列表接口，返回每个标签的图片数量（普通用户只统计自己的图片）
+pub async fn list_tags(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let owner_token_id = (auth_user.role != TokenRole::Admin).then_some(auth_user.id);
+
+    let tag_service = TagService::new(app_state.db_pool().get_connection());
+    let tags = tag_service.list_tags(owner_token_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "查询标签列表成功",
+        "data": tags
+    })))
+}
+
+/// 生成签名URL接口（仅图片所有者或管理员）
+pub async fn sign_image_url(
+    State(app_state): State<AppState>,
+    Path(identifier): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<SignUrlRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("收到生成签名URL请求: {}", identifier);
+
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let image_info = ImageService::get_image_info(app_state.db_pool(), &identifier)
+        .await?
+        .ok_or(AppError::FileNotFound)?;
+    ensure_owner(&auth_user, &image_info, "无权限为此图片生成签名URL")?;
+
+    let config = AppConfig::get();
+    let signer = UrlSigner::from_config(&config.auth)?;
+
+    let max_age = config.auth.signed_url_max_age.as_seconds();
+    let expires_in = request.expires_in.unwrap_or(DEFAULT_SIGNED_URL_TTL);
+    if expires_in == 0 || expires_in > max_age {
+        return Err(AppError::BadRequest(format!(
+            "有效期必须在1-{}秒之间",
+            max_age
+        )));
     }
 
-    info!("图片删除成功: {}", identifier);
+    // 提前校验转换参数，避免生成无法使用的链接
+    let transform = request.transform.as_deref().unwrap_or("").trim();
+    if !transform.is_empty() {
+        app_state.presets().resolve(transform, true)?;
+    }
+
+    let expires = Utc::now().timestamp() + expires_in as i64;
+    let signed_url = SignedUrl {
+        url: signer.signed_path(&image_info.hash, transform, expires),
+        expires_at: DateTime::from_timestamp(expires, 0).unwrap_or_default(),
+    };
+
+    info!(
+        "签名URL生成成功: {}, 过期时间: {}",
+        identifier, signed_url.expires_at
+    );
 
     Ok(Json(serde_json::json!({
         "success": true,
-        "message": "图片删除成功",
-        "cache_cleaned": cache_count
+        "message": "生成签名URL成功",
+        "data": signed_url
     })))
 }
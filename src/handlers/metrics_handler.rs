@@ -0,0 +1,28 @@
+use axum::{extract::State, http::header, response::IntoResponse};
+use tracing::warn;
+
+use crate::app_state::AppState;
+use crate::services::metrics::Metrics;
+use crate::services::CacheService;
+use crate::utils::AppError;
+
+/// Prometheus 指标接口
+pub async fn metrics(State(app_state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let metrics = Metrics::get();
+
+    // 状态类指标在抓取时刷新
+    let cache_service = CacheService::new(
+        app_state.db_pool().get_connection(),
+        app_state.cache_storage(),
+    )?;
+    match cache_service.get_usage().await {
+        Ok((entries, size)) => metrics.set_cache_usage(entries, size),
+        Err(e) => warn!("获取缓存占用失败: {}", e),
+    }
+    metrics.set_pool_stats(&app_state.db_pool().get_pool_stats().await);
+
+    Ok((
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics.render(),
+    ))
+}
@@ -1,10 +1,20 @@
+pub mod album_handler;
+pub mod audit_handler;
 pub mod auth_handler;
 pub mod cache_handler;
 pub mod health_handler;
 pub mod image_handler;
+pub mod metrics_handler;
+pub mod scrub_handler;
 pub mod static_files;
 pub mod token_handler;
+pub mod trash_handler;
 
+pub use album_handler::{
+    add_album_images, create_album, delete_album, get_album, list_albums, remove_album_image,
+    reorder_album_images, update_album,
+};
+pub use audit_handler::{export_audit_events, list_audit_events};
 pub use auth_handler::{get_auth_config, verify_token};
 pub use cache_handler::{
     auto_cleanup_cache, cache_management_dashboard, clean_cache, clear_all_cache, decay_heat_scores,
@@ -12,8 +22,14 @@ pub use cache_handler::{
 };
 pub use health_handler::{get_system_stats, health_check_detailed};
 pub use image_handler::{
-    delete_image, get_image, get_image_info, get_stats, query_images_get, query_images_post,
-    upload_image,
+    delete_image, find_similar_images, get_image, get_image_info, get_stats, list_tags,
+    query_images_get, query_images_post, set_image_tags, set_image_visibility, sign_image_url,
+    upload_image, upload_image_from_url, upload_images_batch,
 };
+pub use metrics_handler::metrics;
+pub use scrub_handler::{get_scrub_status, start_scrub};
 pub use static_files::{api_docs, gallery_page, login_page, serve_static, user_management_page};
-pub use token_handler::{create_token, delete_token, get_token, list_tokens};
+pub use token_handler::{
+    create_token, delete_token, get_token, list_tokens, reconcile_token_usage,
+};
+pub use trash_handler::{empty_trash, list_trash, purge_image, restore_image};
@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use tracing::info;
+
+use crate::app_state::AppState;
+use crate::middleware::verify_token_from_headers;
+use crate::models::{ScrubOptions, TokenRole};
+use crate::services::scrub_service::ScrubJob;
+use crate::utils::AppError;
+
+/// 在后台启动存储校验 - 仅管理员
+///
+/// 校验可能耗时较长，立即返回 202，结果通过 GET /api/system/scrub 查询。
+pub async fn start_scrub(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(options): Query<ScrubOptions>,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    if auth_user.role != TokenRole::Admin {
+        return Err(AppError::Unauthorized(
+            "需要管理员权限访问此资源".to_string(),
+        ));
+    }
+
+    info!("管理员 {} 启动存储校验: {:?}", auth_user.name, options);
+    ScrubJob::spawn(&app_state, options)?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "success": true,
+            "message": "存储校验已开始",
+            "data": app_state.scrub_job().status()
+        })),
+    ))
+}
+
+/// 查询存储校验状态和最近一次的报告 - 仅管理员
+pub async fn get_scrub_status(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    if auth_user.role != TokenRole::Admin {
+        return Err(AppError::Unauthorized(
+            "需要管理员权限访问此资源".to_string(),
+        ));
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "查询存储校验状态成功",
+        "data": app_state.scrub_job().status()
+    })))
+}
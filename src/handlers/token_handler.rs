@@ -9,8 +9,9 @@ use tracing::info;
 
 use crate::app_state::AppState;
 use crate::middleware::verify_token_from_headers;
-use crate::models::{CreateTokenPayload, TokenQuery, TokenRole};
-use crate::services::TokenService;
+use crate::models::{AuditAction, CreateTokenPayload, QuotaReconcileQuery, TokenQuery, TokenRole};
+use crate::repositories::ImageRepository;
+use crate::services::{AuditService, TokenService};
 use crate::utils::AppError;
 
 #[derive(Deserialize)]
@@ -19,6 +20,8 @@ pub struct CreateTokenRequest {
     pub role: String,
     #[serde(default)]
     pub max_upload_size: Option<u64>,
+    #[serde(default)]
+    pub max_image_count: Option<u64>,
     pub expires_at: Option<String>,
 }
 
@@ -66,31 +69,39 @@ pub async fn create_token(
 ) -> Result<impl IntoResponse, AppError> {
     // 验证token并检查管理员权限
     let auth_user = verify_token_from_headers(&headers, &app_state).await?;
-    
-    // 检查是否为管理员
-    if auth_user.role != crate::models::TokenRole::Admin {
-        return Err(AppError::Unauthorized("需要管理员权限访问此资源".to_string()));
-    }
     let connection = app_state.db_pool().get_connection();
-    let token_service = TokenService::new(connection);
-    
-    let expires_at = if let Some(s) = payload.expires_at {
-        DateTime::parse_from_rfc3339(&s)
-            .ok()
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-    } else {
-        None
-    };
-    
-    let create_payload = CreateTokenPayload {
-        name: payload.name,
-        role: TokenRole::from(payload.role.as_str()),
-        max_upload_size: payload.max_upload_size,
-        expires_at,
-    };
-    
-    let response = token_service.create_token(create_payload).await?;
-    Ok(Json(response))
+    let result = async {
+        // 检查是否为管理员
+        if auth_user.role != crate::models::TokenRole::Admin {
+            return Err(AppError::Unauthorized("需要管理员权限访问此资源".to_string()));
+        }
+        let token_service = TokenService::new(connection.clone());
+
+        let expires_at = if let Some(s) = payload.expires_at {
+            DateTime::parse_from_rfc3339(&s)
+                .ok()
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+        } else {
+            None
+        };
+
+        let create_payload = CreateTokenPayload {
+            name: payload.name,
+            role: TokenRole::from(payload.role.as_str()),
+            max_upload_size: payload.max_upload_size,
+            max_image_count: payload.max_image_count,
+            expires_at,
+        };
+
+        token_service.create_token(create_payload).await
+    }
+    .await;
+
+    let target = result.as_ref().ok().map(|response| response.token.id.to_string());
+    AuditService::new(connection)
+        .record_result(&auth_user, AuditAction::TokenCreate, target, &result)
+        .await;
+    Ok(Json(result?))
 }
 
 /// 删除Token - 仅管理员
@@ -101,13 +112,26 @@ pub async fn delete_token(
 ) -> Result<impl IntoResponse, AppError> {
     // 验证token并检查管理员权限
     let auth_user = verify_token_from_headers(&headers, &app_state).await?;
-    
-    // 检查是否为管理员
-    if auth_user.role != crate::models::TokenRole::Admin {
-        return Err(AppError::Unauthorized("需要管理员权限访问此资源".to_string()));
+    let connection = app_state.db_pool().get_connection();
+    let result = async {
+        // 检查是否为管理员
+        if auth_user.role != crate::models::TokenRole::Admin {
+            return Err(AppError::Unauthorized("需要管理员权限访问此资源".to_string()));
+        }
+        let token_service = TokenService::new(connection.clone());
+        token_service.delete_token_with_data(&app_state, token_id).await
     }
-    let token_service = TokenService::new(app_state.db_pool().get_connection());
-    token_service.delete_token_with_data(app_state.db_pool(), token_id).await?;
+    .await;
+
+    AuditService::new(connection)
+        .record_result(
+            &auth_user,
+            AuditAction::TokenDelete,
+            Some(token_id.to_string()),
+            &result,
+        )
+        .await;
+    result?;
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
@@ -129,3 +153,35 @@ pub async fn get_token(
     let token = token_service.get_token(token_id).await?;
     Ok(Json(token))
 }
+
+/// 按图片记录重新计算所有令牌的已用配额 - 仅管理员
+pub async fn reconcile_token_usage(
+    State(app_state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(query): Query<QuotaReconcileQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    // 验证token并检查管理员权限
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+
+    // 检查是否为管理员
+    if auth_user.role != crate::models::TokenRole::Admin {
+        return Err(AppError::Unauthorized("需要管理员权限访问此资源".to_string()));
+    }
+    let connection = app_state.db_pool().get_connection();
+    let token_service = TokenService::new(connection.clone());
+    let report = token_service
+        .reconcile_usage(&ImageRepository::new(connection), query.dry_run)
+        .await?;
+
+    info!(
+        "{} 重新计算配额: 检查{}个令牌，{}个不一致",
+        auth_user.name,
+        report.tokens_checked,
+        report.drifted.len()
+    );
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": if report.dry_run { "配额检查完成" } else { "配额已重新计算" },
+        "data": report
+    })))
+}
@@ -0,0 +1,132 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use tracing::info;
+
+use crate::app_state::AppState;
+use crate::config::AppConfig;
+use crate::middleware::verify_token_from_headers;
+use crate::models::{ApiTokenInfo, AuditAction, ImageInfo, ImageQuery, TokenRole};
+use crate::services::{AuditService, ImageService, TrashService};
+use crate::utils::AppError;
+
+/// 查询回收站中的图片（普通用户只能查看自己的图片）
+pub async fn list_trash(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(mut query): Query<ImageQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    if auth_user.role != TokenRole::Admin {
+        query.owner_token_id = Some(auth_user.id);
+    }
+    query.trashed = Some(true);
+
+    let (images, total) = ImageService::query_images(app_state.db_pool(), &query).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "查询回收站成功",
+        "data": {
+            "items": images,
+            "total": total,
+            "limit": query.limit.unwrap_or(20),
+            "offset": query.offset.unwrap_or(0),
+            "retention_seconds": AppConfig::get().trash.retention.as_seconds()
+        }
+    })))
+}
+
+/// 从回收站恢复图片（仅图片所有者或管理员）
+pub async fn restore_image(
+    State(app_state): State<AppState>,
+    Path(identifier): Path<String>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    info!("收到恢复图片请求: {}", identifier);
+
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let image_info = find_trashed(&app_state, &auth_user, &identifier).await?;
+    let image_info = TrashService::restore(&app_state, &image_info).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "图片已恢复",
+        "data": image_info
+    })))
+}
+
+/// 彻底删除回收站中的图片（仅图片所有者或管理员）
+pub async fn purge_image(
+    State(app_state): State<AppState>,
+    Path(identifier): Path<String>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    info!("收到彻底删除图片请求: {}", identifier);
+
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let result = async {
+        let image_info = find_trashed(&app_state, &auth_user, &identifier).await?;
+        TrashService::purge(&app_state, &image_info).await
+    }
+    .await;
+
+    AuditService::new(app_state.db_pool().get_connection())
+        .record_result(&auth_user, AuditAction::ImagePurge, Some(identifier), &result)
+        .await;
+    let cache_count = result?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "图片已彻底删除",
+        "cache_cleaned": cache_count
+    })))
+}
+
+/// 清空回收站（普通用户只清空自己的图片，管理员清空全部）
+pub async fn empty_trash(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_user = verify_token_from_headers(&headers, &app_state).await?;
+    let owner_token_id = (auth_user.role != TokenRole::Admin).then_some(auth_user.id);
+
+    info!("{} 清空回收站", auth_user.name);
+    let result = TrashService::empty(&app_state, owner_token_id).await;
+
+    AuditService::new(app_state.db_pool().get_connection())
+        .record_result(
+            &auth_user,
+            AuditAction::TrashEmpty,
+            owner_token_id.map(|id| id.to_string()),
+            &result,
+        )
+        .await;
+    let purged = result?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("已彻底删除{}张图片", purged),
+        "data": {
+            "purged": purged
+        }
+    })))
+}
+
+/// 查询回收站中的图片并检查权限
+async fn find_trashed(
+    app_state: &AppState,
+    auth_user: &ApiTokenInfo,
+    identifier: &str,
+) -> Result<ImageInfo, AppError> {
+    let image_info = TrashService::get_trashed(app_state, identifier)
+        .await?
+        .ok_or(AppError::FileNotFound)?;
+    if auth_user.role != TokenRole::Admin && image_info.owner_token_id != Some(auth_user.id) {
+        return Err(AppError::Forbidden("无权限操作此图片".to_string()));
+    }
+    Ok(image_info)
+}
@@ -1,4 +1,5 @@
 pub mod app_state;
+pub mod cli;
 pub mod config;
 pub mod database;
 pub mod entities;
@@ -11,4 +12,5 @@ pub mod repositories;
 pub mod routes;
 pub mod server;
 pub mod services;
+pub mod storage;
 pub mod utils;
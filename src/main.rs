@@ -1,4 +1,5 @@
 mod app_state;
+mod cli;
 mod config;
 mod database;
 mod entities;
@@ -11,14 +12,41 @@ mod repositories;
 mod routes;
 mod server;
 mod services;
+mod storage;
 mod utils;
 
+use cli::{Cli, Command, USAGE};
 use server::run_server;
+use utils::AppError;
 
 #[tokio::main]
 async fn main() {
-    if let Err(e) = run_server().await {
-        eprintln!("服务器启动失败: {}", e);
-        std::process::exit(1);
+    let cli = match Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            // 参数错误只输出具体原因，不带 "请求格式错误" 前缀
+            let message = match e {
+                AppError::BadRequest(message) => message,
+                other => other.to_string(),
+            };
+            eprintln!("❌ {}\n\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match cli.command {
+        Command::Help => print!("{}", USAGE),
+        Command::Serve => {
+            if let Err(e) = run_server(cli.config.as_deref()).await {
+                eprintln!("服务器启动失败: {}", e);
+                std::process::exit(1);
+            }
+        }
+        command => {
+            if let Err(e) = cli::run(cli.config.as_deref(), command).await {
+                eprintln!("❌ {}", e);
+                std::process::exit(1);
+            }
+        }
     }
 }
@@ -5,12 +5,45 @@ use axum::{
 use tracing::warn;
 
 use crate::{app_state::AppState, config::AppConfig, models::{ApiTokenInfo, TokenRole}, utils::AppError};
-use crate::services::TokenService;
+use crate::models::{AuditAction, AuditOutcome};
+use crate::middleware::current_client_ip;
+use crate::services::{AuditService, TokenService};
 
-/// 从请求头中验证token并返回用户信息（公共方法）
+/// 从请求头中验证token并返回用户信息（公共方法），认证失败时记录审计事件
 pub async fn verify_token_from_headers(
     headers: &axum::http::HeaderMap,
     app_state: &AppState,
+) -> Result<ApiTokenInfo, AppError> {
+    let result = authenticate_headers(headers, app_state).await;
+    if let Err(AppError::Unauthorized(reason)) = &result {
+        // 同一客户端的重复失败合并记录，避免审计日志被大量请求刷满
+        if let Some(suppressed) = app_state
+            .auth_failure_throttle()
+            .admit(current_client_ip())
+        {
+            let detail = if suppressed > 0 {
+                format!("{}（此前另有{}次认证失败未单独记录）", reason, suppressed)
+            } else {
+                reason.clone()
+            };
+            AuditService::new(app_state.db_pool().get_connection())
+                .record(
+                    None,
+                    AuditAction::AuthFailure,
+                    None,
+                    AuditOutcome::Failure,
+                    Some(detail),
+                )
+                .await;
+        }
+    }
+    result
+}
+
+/// 从请求头中验证token，不记录审计事件（用于令牌可有可无的场景）
+pub async fn authenticate_headers(
+    headers: &axum::http::HeaderMap,
+    app_state: &AppState,
 ) -> Result<ApiTokenInfo, AppError> {
     use axum::http::{header, HeaderName};
     use chrono::Utc;
@@ -27,6 +60,8 @@ pub async fn verify_token_from_headers(
             role: TokenRole::Admin,
             max_upload_size: None,
             used_upload_size: 0,
+            max_image_count: None,
+            used_image_count: 0,
             expires_at: None,
             is_active: true,
             created_at: Utc::now(),
@@ -200,6 +235,8 @@ where
                         role: TokenRole::Admin,
                         max_upload_size: None,
                         used_upload_size: 0,
+                        max_image_count: None,
+                        used_image_count: 0,
                         expires_at: None,
                         is_active: true,
                         created_at: Utc::now(),
@@ -247,6 +284,8 @@ where
                 role: TokenRole::Admin,
                 max_upload_size: None,
                 used_upload_size: 0,
+                max_image_count: None,
+                used_image_count: 0,
                 expires_at: None,
                 is_active: true,
                 created_at: chrono::Utc::now(),
@@ -288,6 +327,8 @@ where
                         role: TokenRole::Admin,
                         max_upload_size: None,
                         used_upload_size: 0,
+                        max_image_count: None,
+                        used_image_count: 0,
                         expires_at: None,
                         is_active: true,
                         created_at: chrono::Utc::now(),
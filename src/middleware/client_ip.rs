@@ -0,0 +1,45 @@
+use axum::{extract::ConnectInfo, http::HeaderMap, middleware::Next, response::Response};
+use std::net::{IpAddr, SocketAddr};
+
+use crate::config::AppConfig;
+
+tokio::task_local! {
+    static CLIENT_IP: Option<IpAddr>;
+}
+
+/// 客户端IP中间件
+///
+/// 从 `ConnectInfo` 取得客户端地址，在处理请求期间保存在任务局部变量中，
+/// 审计等只拿到请求头的代码通过 [`current_client_ip`] 读取。
+pub async fn capture_client_ip(
+    request: axum::http::Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    let remote_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let client_ip = resolve_client_ip(request.headers(), remote_ip);
+    CLIENT_IP.scope(client_ip, next.run(request)).await
+}
+
+/// 当前请求的客户端IP，不在请求处理过程中或地址未知时为空
+pub fn current_client_ip() -> Option<IpAddr> {
+    CLIENT_IP.try_with(|ip| *ip).ok().flatten()
+}
+
+/// 确定客户端IP：配置信任反向代理时优先使用 X-Forwarded-For，否则使用连接地址
+pub fn resolve_client_ip(headers: &HeaderMap, remote_ip: Option<IpAddr>) -> Option<IpAddr> {
+    // 取 X-Forwarded-For 最右侧的地址，即反向代理看到的客户端地址，左侧的内容可被客户端伪造
+    let forwarded = if AppConfig::get().rate_limit.trust_forwarded_for {
+        headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+    } else {
+        None
+    };
+
+    forwarded.or(remote_ip).map(|ip| ip.to_canonical())
+}
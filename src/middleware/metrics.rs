@@ -0,0 +1,25 @@
+use axum::{extract::MatchedPath, middleware::Next, response::Response};
+
+use crate::services::metrics::{Metrics, UNMATCHED_ROUTE};
+
+/// 请求指标中间件，按路由模板而非实际路径统计，避免图片哈希等参数产生大量标签
+pub async fn track_metrics(request: axum::http::Request<axum::body::Body>, next: Next) -> Response {
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+    let start = std::time::Instant::now();
+    let response = next.run(request).await;
+
+    Metrics::get().observe_request(
+        method.as_str(),
+        &route,
+        response.status().as_u16(),
+        start.elapsed(),
+    );
+
+    response
+}
@@ -1,7 +1,15 @@
 pub mod auth;
+pub mod client_ip;
 pub mod logging;
+pub mod metrics;
+pub mod rate_limit;
 pub mod timeout;
 
-pub use auth::{AdminGuard, AuthGuard, AuthenticatedUser, verify_token_from_headers};
+pub use auth::{
+    authenticate_headers, verify_token_from_headers, AdminGuard, AuthGuard, AuthenticatedUser,
+};
+pub use client_ip::{capture_client_ip, current_client_ip, resolve_client_ip};
 pub use logging::log_requests;
+pub use metrics::track_metrics;
+pub use rate_limit::rate_limit;
 pub use timeout::request_timeout;
@@ -0,0 +1,71 @@
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::{IpAddr, SocketAddr};
+
+use crate::app_state::AppState;
+use crate::middleware::{authenticate_headers, resolve_client_ip};
+use crate::services::rate_limiter::{RateLimitCategory, RateLimitKey};
+
+/// 请求频率限制中间件
+///
+/// 上传和 /api 接口在进入处理器前扣减令牌桶；图片转换只在缓存未命中时由处理器扣减，
+/// 这里只把计数对象放入请求扩展。
+pub async fn rate_limit(
+    State(app_state): State<AppState>,
+    mut request: axum::http::Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    let limiter = app_state.rate_limiter();
+    if !limiter.is_enabled() {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path();
+    let category = if request.method() == Method::POST && path.starts_with("/upload") {
+        Some(RateLimitCategory::Upload)
+    } else if path.starts_with("/api/") {
+        Some(RateLimitCategory::Api)
+    } else {
+        None
+    };
+    let may_transform = path.starts_with("/images/") && path.contains('@');
+    if category.is_none() && !may_transform {
+        return next.run(request).await;
+    }
+
+    let remote_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let key = client_key(request.headers(), remote_ip, &app_state).await;
+    if let Some(category) = category {
+        if let Err(e) = limiter.check(category, &key) {
+            return e.into_response();
+        }
+    }
+
+    request.extensions_mut().insert(key);
+    next.run(request).await
+}
+
+/// 确定请求的计数对象：有效令牌优先，否则使用客户端IP
+async fn client_key(
+    headers: &HeaderMap,
+    remote_ip: Option<IpAddr>,
+    app_state: &AppState,
+) -> RateLimitKey {
+    if app_state.config().auth.enabled {
+        // 只用于确定计数对象，认证失败由处理器负责记录
+        if let Ok(token) = authenticate_headers(headers, app_state).await {
+            return RateLimitKey::Token(token.id);
+        }
+    }
+
+    resolve_client_ip(headers, remote_ip)
+        .map(RateLimitKey::Ip)
+        .unwrap_or(RateLimitKey::Unknown)
+}
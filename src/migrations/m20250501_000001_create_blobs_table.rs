@@ -0,0 +1,104 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建文件内容表，按内容哈希在所有用户之间共享存储
+        manager
+            .create_table(
+                Table::create()
+                    .table(Blobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Blobs::Hash)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Blobs::Size).big_integer().not_null())
+                    .col(ColumnDef::new(Blobs::MimeType).string().not_null())
+                    .col(ColumnDef::new(Blobs::Extension).string().not_null())
+                    .col(
+                        ColumnDef::new(Blobs::RefCount)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Blobs::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 图片记录引用的文件内容，旧记录为空并继续使用按图片哈希存储的文件
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Images::Table)
+                    .add_column(ColumnDef::new(Images::BlobHash).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_images_blob_hash")
+                    .table(Images::Table)
+                    .col(Images::BlobHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_images_blob_hash")
+                    .table(Images::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Images::Table)
+                    .drop_column(Images::BlobHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Blobs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Blobs {
+    Table,
+    Hash,
+    Size,
+    MimeType,
+    Extension,
+    RefCount,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Images {
+    Table,
+    BlobHash,
+}
@@ -0,0 +1,160 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 相册
+        manager
+            .create_table(
+                Table::create()
+                    .table(Albums::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Albums::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Albums::OwnerTokenId).integer().not_null())
+                    .col(ColumnDef::new(Albums::Name).string().not_null())
+                    .col(ColumnDef::new(Albums::Description).text().null())
+                    .col(
+                        ColumnDef::new(Albums::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Albums::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_albums_owner_token_id")
+                    .table(Albums::Table)
+                    .col(Albums::OwnerTokenId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // 相册中的图片及其顺序
+        manager
+            .create_table(
+                Table::create()
+                    .table(AlbumImages::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(AlbumImages::AlbumId).integer().not_null())
+                    .col(ColumnDef::new(AlbumImages::ImageHash).string().not_null())
+                    .col(
+                        ColumnDef::new(AlbumImages::Position)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AlbumImages::AddedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(AlbumImages::AlbumId)
+                            .col(AlbumImages::ImageHash),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_album_images_image_hash")
+                    .table(AlbumImages::Table)
+                    .col(AlbumImages::ImageHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        // 图片标签
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImageTags::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ImageTags::ImageHash).string().not_null())
+                    .col(ColumnDef::new(ImageTags::Tag).string().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(ImageTags::ImageHash)
+                            .col(ImageTags::Tag),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_image_tags_tag")
+                    .table(ImageTags::Table)
+                    .col(ImageTags::Tag)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ImageTags::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AlbumImages::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Albums::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Albums {
+    Table,
+    Id,
+    OwnerTokenId,
+    Name,
+    Description,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum AlbumImages {
+    Table,
+    AlbumId,
+    ImageHash,
+    Position,
+    AddedAt,
+}
+
+#[derive(DeriveIden)]
+enum ImageTags {
+    Table,
+    ImageHash,
+    Tag,
+}
@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能添加一列
+        let columns = [
+            ColumnDef::new(Images::Width).integer().null().to_owned(),
+            ColumnDef::new(Images::Height).integer().null().to_owned(),
+            ColumnDef::new(Images::FrameCount)
+                .integer()
+                .null()
+                .to_owned(),
+            ColumnDef::new(Images::HasAlpha).boolean().null().to_owned(),
+            ColumnDef::new(Images::ColorType)
+                .string_len(16)
+                .null()
+                .to_owned(),
+            ColumnDef::new(Images::DominantColor)
+                .string_len(7)
+                .null()
+                .to_owned(),
+            ColumnDef::new(Images::Palette).string().null().to_owned(),
+        ];
+
+        for mut column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Images::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Images::Width,
+            Images::Height,
+            Images::FrameCount,
+            Images::HasAlpha,
+            Images::ColorType,
+            Images::DominantColor,
+            Images::Palette,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Images::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Images {
+    Table,
+    Width,
+    Height,
+    FrameCount,
+    HasAlpha,
+    ColorType,
+    DominantColor,
+    Palette,
+}
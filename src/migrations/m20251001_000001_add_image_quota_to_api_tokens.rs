@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能添加一列
+        let columns = [
+            ColumnDef::new(ApiTokens::MaxImageCount)
+                .big_integer()
+                .null()
+                .to_owned(),
+            ColumnDef::new(ApiTokens::UsedImageCount)
+                .big_integer()
+                .not_null()
+                .default(0)
+                .to_owned(),
+        ];
+
+        for mut column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ApiTokens::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // 按已有的图片记录（包括回收站中的图片）初始化图片数量
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE api_tokens SET used_image_count = \
+                 (SELECT COUNT(*) FROM images WHERE images.owner_token_id = api_tokens.id)",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [ApiTokens::MaxImageCount, ApiTokens::UsedImageCount] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ApiTokens::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ApiTokens {
+    Table,
+    MaxImageCount,
+    UsedImageCount,
+}
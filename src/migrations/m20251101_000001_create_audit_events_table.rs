@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建审计事件表，记录管理操作、破坏性操作和认证失败
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditEvents::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditEvents::ActorTokenId).integer().null())
+                    .col(ColumnDef::new(AuditEvents::Action).string().not_null())
+                    .col(ColumnDef::new(AuditEvents::Target).string().null())
+                    .col(ColumnDef::new(AuditEvents::ClientIp).string().null())
+                    .col(ColumnDef::new(AuditEvents::Outcome).string().not_null())
+                    .col(ColumnDef::new(AuditEvents::Detail).text().null())
+                    .col(
+                        ColumnDef::new(AuditEvents::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        for (name, column) in [
+            ("idx_audit_events_created_at", AuditEvents::CreatedAt),
+            ("idx_audit_events_actor_token_id", AuditEvents::ActorTokenId),
+            ("idx_audit_events_action", AuditEvents::Action),
+        ] {
+            manager
+                .create_index(
+                    Index::create()
+                        .if_not_exists()
+                        .name(name)
+                        .table(AuditEvents::Table)
+                        .col(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditEvents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditEvents {
+    Table,
+    Id,
+    ActorTokenId,
+    Action,
+    Target,
+    ClientIp,
+    Outcome,
+    Detail,
+    CreatedAt,
+}
@@ -5,6 +5,15 @@ mod m20241201_000001_create_cache_table;
 mod m20250101_000001_add_original_filename;
 mod m20250201_000001_create_api_tokens_table;
 mod m20250201_000002_add_owner_to_images;
+mod m20250301_000001_add_visibility_to_images;
+mod m20250401_000001_add_phash_to_images;
+mod m20250501_000001_create_blobs_table;
+mod m20250601_000001_create_albums_and_tags;
+mod m20250701_000001_add_image_properties;
+mod m20250801_000001_add_blurhash_to_images;
+mod m20250901_000001_add_deleted_at_to_images;
+mod m20251001_000001_add_image_quota_to_api_tokens;
+mod m20251101_000001_create_audit_events_table;
 
 pub struct Migrator;
 
@@ -17,6 +26,15 @@ impl MigratorTrait for Migrator {
             Box::new(m20250101_000001_add_original_filename::Migration),
             Box::new(m20250201_000001_create_api_tokens_table::Migration),
             Box::new(m20250201_000002_add_owner_to_images::Migration),
+            Box::new(m20250301_000001_add_visibility_to_images::Migration),
+            Box::new(m20250401_000001_add_phash_to_images::Migration),
+            Box::new(m20250501_000001_create_blobs_table::Migration),
+            Box::new(m20250601_000001_create_albums_and_tags::Migration),
+            Box::new(m20250701_000001_add_image_properties::Migration),
+            Box::new(m20250801_000001_add_blurhash_to_images::Migration),
+            Box::new(m20250901_000001_add_deleted_at_to_images::Migration),
+            Box::new(m20251001_000001_add_image_quota_to_api_tokens::Migration),
+            Box::new(m20251101_000001_create_audit_events_table::Migration),
         ]
     }
 }
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::config::NearDuplicatePolicy;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum TokenRole {
@@ -40,6 +42,8 @@ pub struct ApiTokenInfo {
     pub role: TokenRole,
     pub max_upload_size: Option<i64>,
     pub used_upload_size: i64,
+    pub max_image_count: Option<i64>,
+    pub used_image_count: i64,
     pub expires_at: Option<DateTime<Utc>>,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
@@ -54,9 +58,44 @@ pub struct CreateTokenPayload {
     #[serde(default)]
     pub role: TokenRole,
     pub max_upload_size: Option<u64>,
+    #[serde(default)]
+    pub max_image_count: Option<u64>,
     pub expires_at: Option<DateTime<Utc>>,
 }
 
+/// 记录的已用配额与图片记录不一致的令牌
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaDrift {
+    pub token_id: i32,
+    pub name: String,
+    /// 记录的已用字节数
+    pub recorded_size: i64,
+    /// 按图片记录计算的字节数
+    pub actual_size: i64,
+    /// 记录的图片数量
+    pub recorded_count: i64,
+    /// 按图片记录计算的图片数量
+    pub actual_count: i64,
+}
+
+/// 重新计算配额的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaReconcileReport {
+    /// 为 true 时只检查，不修改记录
+    pub dry_run: bool,
+    /// 检查的令牌数量
+    pub tokens_checked: usize,
+    /// 不一致的令牌
+    pub drifted: Vec<QuotaDrift>,
+}
+
+/// 重新计算配额的查询参数
+#[derive(Debug, Deserialize, Default)]
+pub struct QuotaReconcileQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
 /// 创建 Token 响应
 #[derive(Debug, Serialize)]
 pub struct CreateTokenResponse {
@@ -86,6 +125,45 @@ pub struct ImageInfo {
     /// 所属的 Token ID
     #[serde(skip_serializing_if = "Option::is_none")]
     pub owner_token_id: Option<i32>,
+    /// 是否为私有图片
+    #[serde(default)]
+    pub is_private: bool,
+    /// 感知哈希，用于查找相似图片
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phash: Option<String>,
+    /// 引用的文件内容哈希，不同用户上传的相同文件共享同一份存储
+    #[serde(default, skip_serializing)]
+    pub blob_hash: Option<String>,
+    /// 标签（仅在查询图片信息和图片列表时填充）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 宽度（像素，已按 EXIF 方向旋转）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    /// 高度（像素，已按 EXIF 方向旋转）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// 帧数（静态图片为1）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_count: Option<u32>,
+    /// 是否包含透明像素
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_alpha: Option<bool>,
+    /// 颜色类型（如 rgb8、rgba8、l16）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_type: Option<String>,
+    /// 主色调（#rrggbb）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dominant_color: Option<String>,
+    /// 调色板（#rrggbb，按占比从高到低排列）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub palette: Vec<String>,
+    /// BlurHash 占位字符串，图片加载前用于显示模糊预览
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blurhash: Option<String>,
+    /// 移入回收站的时间，未删除的图片为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl ImageInfo {
@@ -93,6 +171,33 @@ impl ImageInfo {
     pub fn stored_name(&self) -> String {
         format!("{}.{}", self.hash, self.extension)
     }
+
+    /// 获取在存储后端中的对象键（`ab/cd/<hash>.<ext>`）
+    ///
+    /// 引用了共享文件内容的图片使用内容哈希，旧记录使用图片自身的哈希。
+    pub fn storage_key(&self) -> String {
+        let hash = self.blob_hash.as_deref().unwrap_or(&self.hash);
+        crate::storage::sharded_key(hash, &format!("{}.{}", hash, self.extension))
+    }
+}
+
+/// 相似图片
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarImage {
+    /// 与查询图片的感知哈希距离（0-64，越小越相似）
+    pub distance: u32,
+    /// 图片信息
+    #[serde(flatten)]
+    pub image: ImageInfo,
+}
+
+/// 相似图片查询参数
+#[derive(Debug, Deserialize, Default)]
+pub struct SimilarQuery {
+    /// 最大感知哈希距离，默认使用 `storage.near_duplicate_threshold`
+    pub max_distance: Option<u32>,
+    /// 最多返回的图片数量
+    pub limit: Option<usize>,
 }
 
 /// 图片查询参数
@@ -118,11 +223,136 @@ pub struct ImageQuery {
     pub end_time: Option<DateTime<Utc>>,
     /// 搜索关键词（文件名）
     pub search: Option<String>,
+    /// 最小宽度
+    pub min_width: Option<u32>,
+    /// 最大宽度
+    pub max_width: Option<u32>,
+    /// 最小高度
+    pub min_height: Option<u32>,
+    /// 最大高度
+    pub max_height: Option<u32>,
+    /// 方向过滤
+    pub orientation: Option<ImageOrientation>,
+    /// 相册过滤，未指定排序字段时按相册中的顺序排列
+    pub album_id: Option<i32>,
+    /// 必须同时带有的标签（逗号分隔或数组）
+    #[serde(default, deserialize_with = "deserialize_tag_list")]
+    pub tags: Option<Vec<String>>,
+    /// 至少带有其中一个的标签（逗号分隔或数组）
+    #[serde(default, deserialize_with = "deserialize_tag_list")]
+    pub any_tags: Option<Vec<String>>,
+    /// 不能带有的标签（逗号分隔或数组）
+    #[serde(default, deserialize_with = "deserialize_tag_list")]
+    pub exclude_tags: Option<Vec<String>>,
+    /// 为 true 时只查询回收站中的图片，默认只查询未删除的图片
+    pub trashed: Option<bool>,
     /// 所属的 Token 过滤（内部使用）
     #[serde(skip_serializing, skip_deserializing)]
     pub owner_token_id: Option<i32>,
 }
 
+/// 图片方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageOrientation {
+    /// 横向（宽大于高）
+    Landscape,
+    /// 纵向（高大于宽）
+    Portrait,
+    /// 正方形
+    Square,
+}
+
+/// 解析标签列表，URL查询参数中使用逗号分隔，JSON中也可以直接传数组
+fn deserialize_tag_list<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum TagList {
+        List(Vec<String>),
+        Joined(String),
+    }
+
+    let tags = match Option::<TagList>::deserialize(deserializer)? {
+        Some(TagList::List(tags)) => tags,
+        Some(TagList::Joined(joined)) => joined.split(',').map(str::to_string).collect(),
+        None => return Ok(None),
+    };
+    let tags: Vec<String> = tags
+        .iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    Ok((!tags.is_empty()).then_some(tags))
+}
+
+/// 相册信息
+#[derive(Debug, Clone, Serialize)]
+pub struct AlbumInfo {
+    /// 相册ID
+    pub id: i32,
+    /// 所属的 Token ID
+    pub owner_token_id: i32,
+    /// 相册名称
+    pub name: String,
+    /// 相册描述
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 相册中的图片数量
+    pub image_count: u64,
+    /// 封面（相册中第一张图片的哈希）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<String>,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+    /// 更新时间
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 创建相册请求
+#[derive(Debug, Deserialize)]
+pub struct CreateAlbumRequest {
+    /// 相册名称
+    pub name: String,
+    /// 相册描述
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// 修改相册请求，未提供的字段保持不变，描述为空字符串时清除
+#[derive(Debug, Deserialize, Default)]
+pub struct UpdateAlbumRequest {
+    /// 相册名称
+    pub name: Option<String>,
+    /// 相册描述
+    pub description: Option<String>,
+}
+
+/// 相册图片请求
+#[derive(Debug, Deserialize)]
+pub struct AlbumImagesRequest {
+    /// 图片哈希列表，添加时依次追加到相册末尾，排序时按列表顺序排列
+    pub hashes: Vec<String>,
+}
+
+/// 设置图片标签请求
+#[derive(Debug, Deserialize)]
+pub struct ImageTagsRequest {
+    /// 新的标签列表，替换图片原有的全部标签
+    pub tags: Vec<String>,
+}
+
+/// 标签使用统计
+#[derive(Debug, Serialize)]
+pub struct TagCount {
+    /// 标签
+    pub tag: String,
+    /// 带有该标签的图片数量
+    pub count: u64,
+}
+
 /// Token查询参数
 #[derive(Debug, Deserialize, Clone)]
 pub struct TokenQuery {
@@ -184,6 +414,101 @@ pub struct UploadResponse {
     pub data: Option<ImageInfo>,
 }
 
+/// 批量上传中单个文件的结果
+#[derive(Debug, Serialize)]
+pub struct BatchUploadItem {
+    /// 文件名（压缩包内为文件路径）
+    pub filename: Option<String>,
+    /// 是否保存成功
+    pub success: bool,
+    /// 结果消息
+    pub message: String,
+    /// 图片信息（成功时）
+    pub data: Option<ImageInfo>,
+}
+
+/// 批量上传响应结构体
+#[derive(Debug, Serialize)]
+pub struct BatchUploadResponse {
+    /// 是否全部上传成功
+    pub success: bool,
+    /// 响应消息
+    pub message: String,
+    /// 文件总数
+    pub total: usize,
+    /// 成功数量
+    pub succeeded: usize,
+    /// 失败数量
+    pub failed: usize,
+    /// 每个文件的结果，顺序与上传顺序一致
+    pub results: Vec<BatchUploadItem>,
+}
+
+/// 上传选项（URL查询参数）
+#[derive(Debug, Deserialize, Default)]
+pub struct UploadOptions {
+    /// 是否设为私有图片
+    #[serde(default)]
+    pub private: bool,
+    /// 本次上传的相似图片处理策略，未指定时使用 `storage.near_duplicate_policy`
+    #[serde(default)]
+    pub near_duplicate: Option<NearDuplicatePolicy>,
+}
+
+/// 从远程URL上传请求
+#[derive(Debug, Deserialize)]
+pub struct RemoteUploadRequest {
+    /// 图片地址（http / https）
+    pub url: String,
+    /// 原始文件名，未提供时取URL路径中的文件名
+    #[serde(default)]
+    pub filename: Option<String>,
+}
+
+/// 删除图片的查询参数
+#[derive(Debug, Deserialize, Default)]
+pub struct DeleteImageQuery {
+    /// 跳过回收站直接彻底删除
+    #[serde(default)]
+    pub permanent: bool,
+}
+
+/// 修改图片可见性请求
+#[derive(Debug, Deserialize)]
+pub struct VisibilityRequest {
+    /// 是否设为私有图片
+    pub is_private: bool,
+}
+
+/// 生成签名URL请求
+#[derive(Debug, Deserialize, Default)]
+pub struct SignUrlRequest {
+    /// 转换参数（与图片地址中 `@` 之后的写法一致）
+    #[serde(default)]
+    pub transform: Option<String>,
+    /// 有效期（秒），默认1小时
+    #[serde(default)]
+    pub expires_in: Option<u64>,
+}
+
+/// 签名URL
+#[derive(Debug, Serialize)]
+pub struct SignedUrl {
+    /// 带签名参数的图片路径
+    pub url: String,
+    /// 过期时间
+    pub expires_at: DateTime<Utc>,
+}
+
+/// 签名URL的查询参数
+#[derive(Debug, Deserialize, Default)]
+pub struct SignatureQuery {
+    /// 过期时间（Unix 秒）
+    pub expires: Option<i64>,
+    /// URL安全的 Base64 签名
+    pub signature: Option<String>,
+}
+
 /// 错误响应结构体
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
@@ -196,14 +521,34 @@ pub struct ErrorResponse {
 }
 
 /// 图片转换参数
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ImageTransformParams {
     /// 目标宽度
     pub width: Option<u32>,
     /// 目标高度
     pub height: Option<u32>,
+    /// 同时指定宽高时的适配模式
+    pub fit: FitMode,
+    /// 裁剪或留白时的对齐方式
+    pub gravity: Gravity,
+    /// 缩放前先裁剪的矩形区域
+    pub crop: Option<CropRect>,
+    /// 是否允许放大小图
+    pub upscale: bool,
+    /// 顺时针旋转角度（1-359）
+    pub rotate: Option<u16>,
+    /// 是否水平翻转
+    pub flip_horizontal: bool,
+    /// 是否垂直翻转
+    pub flip_vertical: bool,
+    /// 是否移除输出图片的元数据（保留ICC配置文件）
+    pub strip_metadata: bool,
+    /// 只输出动图中指定索引的单帧（从0开始）
+    pub frame: Option<u32>,
     /// 目标格式
     pub format: Option<String>,
+    /// 按 Accept 请求头自动选择目标格式（未指定格式时生效）
+    pub auto_format: bool,
     /// 图片质量 (1-100)
     pub quality: Option<u8>,
     /// 是否去除透明通道
@@ -215,14 +560,17 @@ pub struct ImageTransformParams {
 }
 
 /// Base64输出模式
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum Base64OutputMode {
     /// 不输出base64
+    #[default]
     None,
     /// 输出包含完整信息的JSON结构体
     Structured,
     /// 只输出纯base64字符串
     Raw,
+    /// 只输出 BlurHash 占位字符串
+    BlurHash,
 }
 
 /// 背景色选项
@@ -233,32 +581,159 @@ pub enum BackgroundColor {
     Custom(u8, u8, u8), // RGB
 }
 
+impl BackgroundColor {
+    /// RGB 分量
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match self {
+            BackgroundColor::White => (255, 255, 255),
+            BackgroundColor::Black => (0, 0, 0),
+            BackgroundColor::Custom(r, g, b) => (*r, *g, *b),
+        }
+    }
+}
+
+/// 同时指定宽高时的适配模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FitMode {
+    /// 等比缩放到框内（默认）
+    #[default]
+    Inside,
+    /// 等比缩放到覆盖整个框
+    Outside,
+    /// 等比缩放覆盖整个框，并按对齐方式裁剪为目标尺寸
+    Cover,
+    /// 等比缩放到框内，并按对齐方式留白补齐为目标尺寸
+    Contain,
+    /// 忽略宽高比拉伸到目标尺寸
+    Fill,
+}
+
+impl FitMode {
+    /// 从参数解析适配模式
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "inside" => Some(Self::Inside),
+            "outside" => Some(Self::Outside),
+            "cover" => Some(Self::Cover),
+            "contain" => Some(Self::Contain),
+            "fill" => Some(Self::Fill),
+            _ => None,
+        }
+    }
+
+    /// 参数名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Inside => "inside",
+            Self::Outside => "outside",
+            Self::Cover => "cover",
+            Self::Contain => "contain",
+            Self::Fill => "fill",
+        }
+    }
+}
+
+/// 裁剪或留白时的对齐方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Gravity {
+    #[default]
+    Center,
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+    /// 按图像信息熵选择细节最丰富的区域
+    Smart,
+}
+
+impl Gravity {
+    /// 从参数（去掉 `g` 前缀）解析对齐方式
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "c" | "center" => Some(Self::Center),
+            "n" | "north" => Some(Self::North),
+            "s" | "south" => Some(Self::South),
+            "e" | "east" => Some(Self::East),
+            "w" | "west" => Some(Self::West),
+            "ne" | "northeast" => Some(Self::NorthEast),
+            "nw" | "northwest" => Some(Self::NorthWest),
+            "se" | "southeast" => Some(Self::SouthEast),
+            "sw" | "southwest" => Some(Self::SouthWest),
+            "smart" | "entropy" => Some(Self::Smart),
+            _ => None,
+        }
+    }
+
+    /// 参数名称（不含 `g` 前缀）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Center => "c",
+            Self::North => "n",
+            Self::South => "s",
+            Self::East => "e",
+            Self::West => "w",
+            Self::NorthEast => "ne",
+            Self::NorthWest => "nw",
+            Self::SouthEast => "se",
+            Self::SouthWest => "sw",
+            Self::Smart => "smart",
+        }
+    }
+
+    /// 水平和垂直方向的对齐比例（0.0 为左/上，1.0 为右/下）
+    pub fn alignment(&self) -> (f32, f32) {
+        match self {
+            Self::Center | Self::Smart => (0.5, 0.5),
+            Self::North => (0.5, 0.0),
+            Self::South => (0.5, 1.0),
+            Self::East => (1.0, 0.5),
+            Self::West => (0.0, 0.5),
+            Self::NorthEast => (1.0, 0.0),
+            Self::NorthWest => (0.0, 0.0),
+            Self::SouthEast => (1.0, 1.0),
+            Self::SouthWest => (0.0, 1.0),
+        }
+    }
+}
+
+/// 裁剪矩形，参数格式为 `crop{宽}x{高}+{x}+{y}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CropRect {
+    /// 解析 `{宽}x{高}+{x}+{y}`，省略偏移时从左上角开始
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split('+');
+        let (width, height) = parts.next()?.split_once('x')?;
+        let x = parts.next().map_or(Some(0), |x| x.parse().ok())?;
+        let y = parts.next().map_or(Some(0), |y| y.parse().ok())?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            x,
+            y,
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+        })
+    }
+}
+
 impl ImageTransformParams {
     /// 从URL参数字符串解析转换参数
-    /// 格式: w1200_h1200_jpeg_naw_q80
+    /// 格式: w1200_h1200_cover_gsmart_jpeg_naw_q80
     pub fn parse(params_str: &str) -> Result<Self, String> {
-        // 如果参数字符串为空，返回空的转换参数
-        if params_str.trim().is_empty() {
-            return Ok(Self {
-                width: None,
-                height: None,
-                format: None,
-                quality: None,
-                no_alpha: false,
-                background_color: None,
-                base64_mode: Base64OutputMode::None,
-            });
-        }
-
-        let mut params = ImageTransformParams {
-            width: None,
-            height: None,
-            format: None,
-            quality: None,
-            no_alpha: false,
-            background_color: None,
-            base64_mode: Base64OutputMode::None,
-        };
+        let mut params = ImageTransformParams::default();
 
         for param in params_str.split('_') {
             if param.is_empty() {
@@ -269,6 +744,49 @@ impl ImageTransformParams {
             if Self::is_valid_format(param) {
                 // 图片格式
                 params.format = Some(param.to_lowercase());
+            } else if let Some(fit) = FitMode::from_token(param) {
+                // 适配模式
+                params.fit = fit;
+            } else if param == "auto" {
+                // 按 Accept 请求头自动选择格式
+                params.auto_format = true;
+            } else if param == "up" || param == "upscale" {
+                // 允许放大
+                params.upscale = true;
+            } else if param == "fh" {
+                // 水平翻转
+                params.flip_horizontal = true;
+            } else if param == "fv" {
+                // 垂直翻转
+                params.flip_vertical = true;
+            } else if param == "strip" {
+                // 移除元数据
+                params.strip_metadata = true;
+            } else if let Some(index_str) = param.strip_prefix("frame") {
+                // 动图单帧 frame0 / frame3
+                params.frame = Some(
+                    index_str
+                        .parse::<u32>()
+                        .map_err(|_| format!("无效的帧参数: {}", param))?,
+                );
+            } else if let Some(angle_str) = param.strip_prefix('r') {
+                // 顺时针旋转角度 r90 / r-45
+                let angle = angle_str
+                    .parse::<i32>()
+                    .map_err(|_| format!("无效的旋转参数: {}", param))?;
+                params.rotate = match angle.rem_euclid(360) {
+                    0 => None,
+                    angle => Some(angle as u16),
+                };
+            } else if let Some(crop_str) = param.strip_prefix("crop") {
+                // 裁剪矩形 crop300x200+10+20
+                params.crop =
+                    Some(CropRect::parse(crop_str).ok_or_else(|| {
+                        format!("无效的裁剪参数: {}，格式应为crop宽x高+x+y", param)
+                    })?);
+            } else if let Some(gravity) = param.strip_prefix('g').and_then(Gravity::from_token) {
+                // 对齐方式
+                params.gravity = gravity;
             } else if let Some(bg_part) = param.strip_prefix("na") {
                 // 去除透明通道及背景色设置
                 params.no_alpha = true;
@@ -313,6 +831,9 @@ impl ImageTransformParams {
             } else if param == "base64raw" || param == "b64raw" {
                 // base64纯文本输出参数
                 params.base64_mode = Base64OutputMode::Raw;
+            } else if param == "blurhash" || param == "bh" {
+                // BlurHash 占位字符串输出参数
+                params.base64_mode = Base64OutputMode::BlurHash;
             }
         }
 
@@ -330,8 +851,19 @@ impl ImageTransformParams {
     /// 检查是否需要进行转换
     /// base64参数不影响是否需要转换，它只控制输出格式
     pub fn needs_transform(&self) -> bool {
+        self.needs_reencode() || self.strip_metadata
+    }
+
+    /// 检查是否需要解码并重新编码像素数据
+    /// 仅移除元数据时直接改写图片容器，不需要重新编码
+    pub fn needs_reencode(&self) -> bool {
         self.width.is_some()
             || self.height.is_some()
+            || self.crop.is_some()
+            || self.rotate.is_some()
+            || self.flip_horizontal
+            || self.flip_vertical
+            || self.frame.is_some()
             || self.format.is_some()
             || self.quality.is_some()
             || self.no_alpha
@@ -351,8 +883,50 @@ impl ImageTransformParams {
             parts.push(format!("h{}", height));
         }
 
+        // 默认值不写入，保证旧的缓存键保持不变
+        if self.fit != FitMode::Inside {
+            parts.push(self.fit.as_str().to_string());
+        }
+
+        if self.gravity != Gravity::Center {
+            parts.push(format!("g{}", self.gravity.as_str()));
+        }
+
+        if let Some(crop) = self.crop {
+            parts.push(format!(
+                "crop{}x{}+{}+{}",
+                crop.width, crop.height, crop.x, crop.y
+            ));
+        }
+
+        if self.upscale {
+            parts.push("up".to_string());
+        }
+
+        if let Some(angle) = self.rotate {
+            parts.push(format!("r{}", angle));
+        }
+
+        if self.flip_horizontal {
+            parts.push("fh".to_string());
+        }
+
+        if self.flip_vertical {
+            parts.push("fv".to_string());
+        }
+
+        if self.strip_metadata {
+            parts.push("strip".to_string());
+        }
+
+        if let Some(frame) = self.frame {
+            parts.push(format!("frame{}", frame));
+        }
+
         if let Some(ref format) = self.format {
             parts.push(format.clone());
+        } else if self.auto_format {
+            parts.push("auto".to_string());
         }
 
         if let Some(quality) = self.quality {
@@ -373,6 +947,7 @@ impl ImageTransformParams {
         match self.base64_mode {
             Base64OutputMode::Structured => parts.push("base64".to_string()),
             Base64OutputMode::Raw => parts.push("base64raw".to_string()),
+            Base64OutputMode::BlurHash => parts.push("blurhash".to_string()),
             Base64OutputMode::None => {}
         }
 
@@ -424,7 +999,7 @@ pub struct CacheInfo {
     pub original_hash: String,
     /// 转换参数字符串
     pub transform_params: String,
-    /// 缓存文件路径
+    /// 缓存文件在存储后端中的对象键
     pub file_path: String,
     /// 缓存文件大小（字节）
     pub file_size: u64,
@@ -485,6 +1060,112 @@ pub struct CacheCleanupResult {
     pub duration_ms: u64,
 }
 
+/// 存储校验选项
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScrubOptions {
+    /// 读取文件内容校验 SHA-256（较慢）
+    #[serde(default)]
+    pub checksum: bool,
+    /// 修复发现的问题
+    #[serde(default)]
+    pub repair: bool,
+}
+
+/// 存储校验的区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrubArea {
+    /// 原始上传图片
+    Uploads,
+    /// 转换结果缓存
+    Cache,
+}
+
+/// 存储问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrubIssueKind {
+    /// 记录对应的文件不存在
+    MissingFile,
+    /// 文件没有对应的记录
+    OrphanFile,
+    /// 文件内容与记录的哈希不一致
+    ChecksumMismatch,
+    /// 文件大小与记录不一致
+    SizeMismatch,
+}
+
+/// 已执行的修复操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrubAction {
+    /// 删除了没有文件的记录
+    DeletedRecord,
+    /// 删除了文件（及其记录），仅用于可重新生成的缓存
+    DeletedFile,
+    /// 为孤立文件创建了图片记录
+    AdoptedFile,
+    /// 文件已移入隔离区，引用它的记录已删除
+    QuarantinedFile,
+}
+
+/// 单个存储问题
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrubIssue {
+    /// 所在区域
+    pub area: ScrubArea,
+    /// 问题类型
+    pub kind: ScrubIssueKind,
+    /// 文件的存储键
+    pub storage_key: String,
+    /// 相关记录（图片hash或缓存键），孤立文件为空
+    pub record: Option<String>,
+    /// 问题描述
+    pub detail: String,
+    /// 已执行的修复，未修复时为空
+    pub action: Option<ScrubAction>,
+    /// 修复失败的原因
+    pub repair_error: Option<String>,
+}
+
+/// 存储校验报告
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrubReport {
+    /// 本次校验的选项
+    pub options: ScrubOptions,
+    /// 开始时间
+    pub started_at: DateTime<Utc>,
+    /// 耗时（毫秒）
+    pub duration_ms: u64,
+    /// 检查的图片记录数
+    pub images_checked: u64,
+    /// 检查的缓存记录数
+    pub cache_checked: u64,
+    /// 扫描的存储文件数
+    pub files_scanned: u64,
+    /// 发现的问题
+    pub issues: Vec<ScrubIssue>,
+}
+
+impl ScrubReport {
+    /// 已修复的问题数
+    pub fn repaired(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.action.is_some())
+            .count()
+    }
+}
+
+/// 存储校验任务状态
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrubStatus {
+    /// 是否正在执行
+    pub running: bool,
+    /// 最近一次完成的校验报告
+    pub last_report: Option<ScrubReport>,
+}
+
 /// Base64图片响应结构体
 #[derive(Debug, Serialize)]
 pub struct Base64ImageResponse {
@@ -501,3 +1182,94 @@ pub struct Base64ImageResponse {
     /// 原图信息
     pub original: ImageInfo,
 }
+
+/// 审计操作类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// 创建令牌
+    TokenCreate,
+    /// 删除令牌
+    TokenDelete,
+    /// 删除图片（移入回收站或直接删除）
+    ImageDelete,
+    /// 彻底删除回收站中的图片
+    ImagePurge,
+    /// 清空回收站
+    TrashEmpty,
+    /// 清空转换缓存
+    CacheClear,
+    /// 认证失败
+    AuthFailure,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::TokenCreate => "token_create",
+            AuditAction::TokenDelete => "token_delete",
+            AuditAction::ImageDelete => "image_delete",
+            AuditAction::ImagePurge => "image_purge",
+            AuditAction::TrashEmpty => "trash_empty",
+            AuditAction::CacheClear => "cache_clear",
+            AuditAction::AuthFailure => "auth_failure",
+        }
+    }
+}
+
+/// 审计操作结果
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Failure => "failure",
+        }
+    }
+}
+
+/// 审计事件
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEventInfo {
+    pub id: i64,
+    /// 执行操作的令牌ID，认证失败时为空
+    pub actor_token_id: Option<i32>,
+    /// 操作类型
+    pub action: String,
+    /// 操作对象（令牌ID、图片hash等）
+    pub target: Option<String>,
+    /// 客户端IP
+    pub client_ip: Option<String>,
+    /// 操作结果
+    pub outcome: String,
+    /// 附加说明，失败时为错误信息
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 审计事件查询参数
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct AuditQuery {
+    /// 分页大小
+    pub limit: Option<u64>,
+    /// 偏移量
+    pub offset: Option<u64>,
+    /// 按操作令牌过滤
+    pub actor_token_id: Option<i32>,
+    /// 按操作类型过滤
+    pub action: Option<AuditAction>,
+    /// 按操作对象过滤
+    pub target: Option<String>,
+    /// 按操作结果过滤
+    pub outcome: Option<AuditOutcome>,
+    /// 起始时间（包含）
+    pub since: Option<DateTime<Utc>>,
+    /// 截止时间（不包含）
+    pub until: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,260 @@
+use chrono::Utc;
+use sea_orm::sea_query::{Expr, Query, SelectStatement};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use std::sync::Arc;
+
+use crate::entities::{album, album_image, image, Album, AlbumImage, Image};
+use crate::repositories::{BaseRepository, Repository};
+use crate::utils::AppError;
+
+/// 相册仓储
+pub struct AlbumRepository {
+    base: BaseRepository,
+}
+
+impl AlbumRepository {
+    pub fn new(connection: Arc<DatabaseConnection>) -> Self {
+        Self {
+            base: BaseRepository::new(connection),
+        }
+    }
+
+    fn conn(&self) -> Arc<DatabaseConnection> {
+        self.base.get_connection()
+    }
+
+    pub async fn insert(&self, active_model: album::ActiveModel) -> Result<album::Model, AppError> {
+        active_model
+            .insert(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("创建相册失败: {}", e)))
+    }
+
+    pub async fn update(&self, active_model: album::ActiveModel) -> Result<album::Model, AppError> {
+        active_model
+            .update(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("更新相册失败: {}", e)))
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<Option<album::Model>, AppError> {
+        Album::find_by_id(id)
+            .one(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("查询相册失败: {}", e)))
+    }
+
+    /// 查询相册列表（所有者为空时查询全部），按创建时间倒序
+    pub async fn list(&self, owner_token_id: Option<i32>) -> Result<Vec<album::Model>, AppError> {
+        let mut select = Album::find();
+        if let Some(owner_token_id) = owner_token_id {
+            select = select.filter(album::Column::OwnerTokenId.eq(owner_token_id));
+        }
+
+        select
+            .order_by_desc(album::Column::CreatedAt)
+            .order_by_desc(album::Column::Id)
+            .all(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("查询相册失败: {}", e)))
+    }
+
+    /// 删除相册及其中的图片关联（不删除图片本身）
+    pub async fn delete_by_id(&self, id: i32) -> Result<bool, AppError> {
+        let txn = self
+            .conn()
+            .begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("开启事务失败: {}", e)))?;
+
+        AlbumImage::delete_many()
+            .filter(album_image::Column::AlbumId.eq(id))
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("删除相册图片失败: {}", e)))?;
+        let result = Album::delete_by_id(id)
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("删除相册失败: {}", e)))?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("提交事务失败: {}", e)))?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// 查询所有者的全部相册ID
+    pub async fn find_ids_by_owner(&self, owner_token_id: i32) -> Result<Vec<i32>, AppError> {
+        Album::find()
+            .select_only()
+            .column(album::Column::Id)
+            .filter(album::Column::OwnerTokenId.eq(owner_token_id))
+            .into_tuple()
+            .all(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("查询相册失败: {}", e)))
+    }
+
+    /// 相册中的图片数量（不包含回收站中的图片）
+    pub async fn count_images(&self, album_id: i32) -> Result<u64, AppError> {
+        AlbumImage::find()
+            .filter(album_image::Column::AlbumId.eq(album_id))
+            .filter(album_image::Column::ImageHash.in_subquery(Self::live_images()))
+            .count(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("统计相册图片失败: {}", e)))
+    }
+
+    /// 按顺序查询相册中的图片哈希
+    pub async fn find_image_hashes(
+        &self,
+        album_id: i32,
+        limit: Option<u64>,
+    ) -> Result<Vec<String>, AppError> {
+        AlbumImage::find()
+            .select_only()
+            .column(album_image::Column::ImageHash)
+            .filter(album_image::Column::AlbumId.eq(album_id))
+            .order_by_asc(album_image::Column::Position)
+            .order_by_asc(album_image::Column::AddedAt)
+            .limit(limit)
+            .into_tuple()
+            .all(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("查询相册图片失败: {}", e)))
+    }
+
+    /// 相册封面，即按顺序排在最前面且不在回收站中的图片
+    pub async fn find_cover(&self, album_id: i32) -> Result<Option<String>, AppError> {
+        AlbumImage::find()
+            .select_only()
+            .column(album_image::Column::ImageHash)
+            .filter(album_image::Column::AlbumId.eq(album_id))
+            .filter(album_image::Column::ImageHash.in_subquery(Self::live_images()))
+            .order_by_asc(album_image::Column::Position)
+            .order_by_asc(album_image::Column::AddedAt)
+            .into_tuple()
+            .one(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("查询相册图片失败: {}", e)))
+    }
+
+    /// 未移入回收站的图片哈希子查询
+    fn live_images() -> SelectStatement {
+        Query::select()
+            .column(image::Column::Hash)
+            .from(Image)
+            .and_where(image::Column::DeletedAt.is_null())
+            .to_owned()
+    }
+
+    /// 依次把图片追加到相册末尾，已在相册中的图片保持原位置，返回新加入的数量
+    pub async fn append_images(&self, album_id: i32, hashes: &[String]) -> Result<u64, AppError> {
+        let txn = self
+            .conn()
+            .begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("开启事务失败: {}", e)))?;
+
+        let existing: Vec<String> = AlbumImage::find()
+            .select_only()
+            .column(album_image::Column::ImageHash)
+            .filter(album_image::Column::AlbumId.eq(album_id))
+            .into_tuple()
+            .all(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("查询相册图片失败: {}", e)))?;
+        let last_position: Option<i64> = AlbumImage::find()
+            .select_only()
+            .column_as(album_image::Column::Position.max(), "position")
+            .filter(album_image::Column::AlbumId.eq(album_id))
+            .into_tuple()
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("查询相册图片失败: {}", e)))?
+            .flatten();
+
+        let mut position = last_position.map_or(0, |position| position + 1);
+        let mut added = 0;
+        let now = Utc::now();
+        for hash in hashes {
+            if existing.contains(hash) {
+                continue;
+            }
+            album_image::ActiveModel {
+                album_id: Set(album_id),
+                image_hash: Set(hash.clone()),
+                position: Set(position),
+                added_at: Set(now),
+            }
+            .insert(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("添加相册图片失败: {}", e)))?;
+            position += 1;
+            added += 1;
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("提交事务失败: {}", e)))?;
+        Ok(added)
+    }
+
+    /// 按给定顺序重新编排相册中的全部图片
+    pub async fn set_positions(&self, album_id: i32, hashes: &[String]) -> Result<(), AppError> {
+        let txn = self
+            .conn()
+            .begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("开启事务失败: {}", e)))?;
+
+        for (position, hash) in hashes.iter().enumerate() {
+            AlbumImage::update_many()
+                .col_expr(album_image::Column::Position, Expr::value(position as i64))
+                .filter(album_image::Column::AlbumId.eq(album_id))
+                .filter(album_image::Column::ImageHash.eq(hash.as_str()))
+                .exec(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("更新相册图片顺序失败: {}", e)))?;
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("提交事务失败: {}", e)))
+    }
+
+    /// 从相册中移除图片
+    pub async fn remove_image(&self, album_id: i32, hash: &str) -> Result<bool, AppError> {
+        let result = AlbumImage::delete_many()
+            .filter(album_image::Column::AlbumId.eq(album_id))
+            .filter(album_image::Column::ImageHash.eq(hash))
+            .exec(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("移除相册图片失败: {}", e)))?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// 把图片从所有相册中移除（图片被删除时使用）
+    pub async fn remove_image_from_all(&self, hash: &str) -> Result<u64, AppError> {
+        let result = AlbumImage::delete_many()
+            .filter(album_image::Column::ImageHash.eq(hash))
+            .exec(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("移除相册图片失败: {}", e)))?;
+        Ok(result.rows_affected)
+    }
+
+    /// 更新相册的修改时间
+    pub async fn touch(&self, id: i32) -> Result<(), AppError> {
+        Album::update_many()
+            .col_expr(album::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(album::Column::Id.eq(id))
+            .exec(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("更新相册失败: {}", e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder,
+};
+use std::sync::Arc;
+
+use crate::entities::{audit_event, AuditEvent};
+use crate::models::{AuditEventInfo, AuditQuery};
+use crate::repositories::{BaseRepository, PageResult, Repository};
+use crate::utils::AppError;
+
+/// 审计事件仓储
+pub struct AuditRepository {
+    base: BaseRepository,
+}
+
+impl AuditRepository {
+    pub fn new(connection: Arc<DatabaseConnection>) -> Self {
+        Self {
+            base: BaseRepository::new(connection),
+        }
+    }
+
+    fn conn(&self) -> Arc<DatabaseConnection> {
+        self.base.get_connection()
+    }
+
+    pub async fn insert(
+        &self,
+        active_model: audit_event::ActiveModel,
+    ) -> Result<audit_event::Model, AppError> {
+        active_model
+            .insert(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("写入审计事件失败: {}", e)))
+    }
+
+    /// 删除指定时间之前的审计事件，返回删除的数量
+    pub async fn delete_before(&self, cutoff: DateTime<Utc>) -> Result<u64, AppError> {
+        let result = AuditEvent::delete_many()
+            .filter(audit_event::Column::CreatedAt.lt(cutoff))
+            .exec(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("删除过期审计事件失败: {}", e)))?;
+        Ok(result.rows_affected)
+    }
+
+    /// 按条件分页查询审计事件，按时间倒序
+    pub async fn find_by_query(
+        &self,
+        query: &AuditQuery,
+    ) -> Result<PageResult<AuditEventInfo>, AppError> {
+        let mut condition = Condition::all();
+        if let Some(actor_token_id) = query.actor_token_id {
+            condition = condition.add(audit_event::Column::ActorTokenId.eq(actor_token_id));
+        }
+        if let Some(action) = query.action {
+            condition = condition.add(audit_event::Column::Action.eq(action.as_str()));
+        }
+        if let Some(target) = &query.target {
+            condition = condition.add(audit_event::Column::Target.eq(target.as_str()));
+        }
+        if let Some(outcome) = query.outcome {
+            condition = condition.add(audit_event::Column::Outcome.eq(outcome.as_str()));
+        }
+        if let Some(since) = query.since {
+            condition = condition.add(audit_event::Column::CreatedAt.gte(since));
+        }
+        if let Some(until) = query.until {
+            condition = condition.add(audit_event::Column::CreatedAt.lt(until));
+        }
+
+        let limit = query.limit.unwrap_or(20).max(1);
+        let offset = query.offset.unwrap_or(0);
+
+        let connection = self.conn();
+        let paginator = AuditEvent::find()
+            .filter(condition)
+            .order_by_desc(audit_event::Column::CreatedAt)
+            .order_by_desc(audit_event::Column::Id)
+            .paginate(&*connection, limit);
+        let total = paginator
+            .num_items()
+            .await
+            .map_err(|e| AppError::Internal(format!("查询审计事件总数失败: {}", e)))?;
+        let models = paginator
+            .fetch_page(offset / limit)
+            .await
+            .map_err(|e| AppError::Internal(format!("查询审计事件失败: {}", e)))?;
+
+        Ok(PageResult {
+            items: models.into_iter().map(AuditEventInfo::from).collect(),
+            total,
+        })
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::Utc;
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, TransactionTrait};
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::entities::{blob, Blob};
+use crate::repositories::{BaseRepository, Repository};
+use crate::utils::AppError;
+
+/// 文件内容仓储
+///
+/// 引用计数的增减都在数据库中原子完成，并发上传或删除相同内容时不会丢失计数，
+/// 也不会删除刚被重新引用的文件。
+pub struct BlobRepository {
+    base: BaseRepository,
+}
+
+impl BlobRepository {
+    pub fn new(connection: Arc<DatabaseConnection>) -> Self {
+        Self {
+            base: BaseRepository::new(connection),
+        }
+    }
+
+    fn conn(&self) -> Arc<DatabaseConnection> {
+        self.base.get_connection()
+    }
+
+    pub async fn find_by_hash(&self, hash: &str) -> Result<Option<blob::Model>, AppError> {
+        Blob::find_by_id(hash.to_string())
+            .one(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("查询文件内容失败: {}", e)))
+    }
+
+    /// 增加一次引用，内容不存在时创建记录
+    ///
+    /// 返回增加后的引用计数，为1表示这是新的内容，需要写入存储后端。
+    pub async fn acquire(
+        &self,
+        hash: &str,
+        size: u64,
+        mime_type: &str,
+        extension: &str,
+    ) -> Result<i64, AppError> {
+        let active_model = blob::ActiveModel {
+            hash: Set(hash.to_string()),
+            size: Set(size as i64),
+            mime_type: Set(mime_type.to_string()),
+            extension: Set(extension.to_string()),
+            ref_count: Set(1),
+            created_at: Set(Utc::now()),
+        };
+
+        Blob::insert(active_model)
+            .on_conflict(
+                OnConflict::column(blob::Column::Hash)
+                    .value(
+                        blob::Column::RefCount,
+                        Expr::col(blob::Column::RefCount).add(1),
+                    )
+                    .to_owned(),
+            )
+            .exec_without_returning(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("保存文件内容失败: {}", e)))?;
+
+        let model = self
+            .find_by_hash(hash)
+            .await?
+            .ok_or_else(|| AppError::Internal(format!("文件内容记录丢失: {}", hash)))?;
+        Ok(model.ref_count)
+    }
+
+    /// 减少一次引用，计数归零时先调用 `remove_file` 删除存储中的文件，再删除记录
+    ///
+    /// 整个过程在一个事务中完成，减少计数时获得的行锁（SQLite 为写锁）一直持有到事务结束。
+    /// 并发上传相同内容的 [`Self::acquire`] 会等待事务结束后重新创建记录并写入文件，
+    /// 不会出现新的引用指向已被删除的文件。删除文件失败时回滚，计数保持不变。
+    /// 返回 true 表示最后一个引用已释放。
+    pub async fn release<F, Fut>(&self, hash: &str, remove_file: F) -> Result<bool, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(), AppError>>,
+    {
+        let txn = self
+            .conn()
+            .begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("开启事务失败: {}", e)))?;
+
+        Blob::update_many()
+            .col_expr(
+                blob::Column::RefCount,
+                Expr::col(blob::Column::RefCount).sub(1),
+            )
+            .filter(blob::Column::Hash.eq(hash))
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("更新文件内容引用失败: {}", e)))?;
+
+        let released = Blob::find_by_id(hash.to_string())
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("查询文件内容失败: {}", e)))?
+            .is_some_and(|model| model.ref_count <= 0);
+
+        if released {
+            remove_file().await?;
+            Blob::delete_by_id(hash.to_string())
+                .exec(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("删除文件内容失败: {}", e)))?;
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("提交事务失败: {}", e)))?;
+        Ok(released)
+    }
+}
@@ -1,6 +1,8 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use sea_orm::{entity::prelude::*, ActiveModelTrait, EntityTrait, QueryOrder};
+use sea_orm::{
+    entity::prelude::*, ActiveModelTrait, DbBackend, EntityTrait, QueryOrder, Statement,
+};
 use std::sync::Arc;
 use tracing::{debug, error, info};
 
@@ -30,6 +32,9 @@ pub trait CacheRepositoryTrait: Repository {
     /// 获取缓存统计信息
     async fn get_stats(&self) -> Result<CacheStats, AppError>;
 
+    /// 获取缓存项数量和总大小（字节）
+    async fn get_usage(&self) -> Result<(u64, u64), AppError>;
+
     /// 清理所有缓存
     async fn clear_all(&self) -> Result<u64, AppError>;
 
@@ -283,6 +288,41 @@ impl CacheRepositoryTrait for CacheRepository {
         })
     }
 
+    async fn get_usage(&self) -> Result<(u64, u64), AppError> {
+        debug!("获取缓存占用");
+
+        let connection = self.get_connection();
+        let db_backend = connection.get_database_backend();
+        // SUM 在 PostgreSQL/MySQL 中返回 NUMERIC/DECIMAL，统一转换为整数
+        let integer_type = match db_backend {
+            DbBackend::MySql => "SIGNED",
+            _ => "BIGINT",
+        };
+
+        let row = connection
+            .query_one(Statement::from_string(
+                db_backend,
+                format!(
+                    "SELECT COUNT(*) AS total_count, CAST(COALESCE(SUM(file_size), 0) AS {}) AS total_size FROM cache",
+                    integer_type
+                ),
+            ))
+            .await
+            .map_err(|e| AppError::Internal(format!("查询缓存占用失败: {}", e)))?;
+
+        let Some(row) = row else {
+            return Ok((0, 0));
+        };
+        let total_count: i64 = row
+            .try_get("", "total_count")
+            .map_err(|e| AppError::Internal(format!("读取缓存数量失败: {}", e)))?;
+        let total_size: i64 = row
+            .try_get("", "total_size")
+            .map_err(|e| AppError::Internal(format!("读取缓存大小失败: {}", e)))?;
+
+        Ok((total_count.max(0) as u64, total_size.max(0) as u64))
+    }
+
     async fn clear_all(&self) -> Result<u64, AppError> {
         debug!("清理所有缓存");
 
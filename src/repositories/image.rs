@@ -1,14 +1,16 @@
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
-    PaginatorTrait, QueryFilter, QueryOrder, Statement,
+    sea_query::{Expr, Query as SeaQuery, SelectStatement, SimpleExpr},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbBackend,
+    EntityTrait,
+    Order, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Statement,
 };
 use std::sync::Arc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use crate::entities::{image, Image};
-use crate::models::{ImageInfo, ImageQuery, ImageStats, TimeStat, TypeStat};
+use crate::entities::{album_image, image, image_tag, AlbumImage, Image, ImageTag};
+use crate::models::{ImageInfo, ImageOrientation, ImageQuery, ImageStats, TimeStat, TypeStat};
 use crate::repositories::{BaseRepository, PageResult, Repository};
 use crate::utils::AppError;
 
@@ -27,6 +29,9 @@ pub trait ImageRepositoryTrait: Repository {
     /// 更新图片访问信息
     async fn update_access(&self, hash: &str) -> Result<bool, AppError>;
 
+    /// 更新图片可见性
+    async fn update_visibility(&self, hash: &str, is_private: bool) -> Result<bool, AppError>;
+
     /// 删除图片记录
     async fn delete_by_hash(&self, hash: &str) -> Result<bool, AppError>;
 
@@ -35,6 +40,63 @@ pub trait ImageRepositoryTrait: Repository {
 
     /// 根据Token查询所有图片
     async fn find_by_owner(&self, owner_token_id: i32) -> Result<Vec<ImageInfo>, AppError>;
+
+    /// 查询所有者名下带有感知哈希的图片（所有者为空时查询无主图片）
+    async fn find_with_phash(&self, owner_token_id: Option<i32>) -> Result<Vec<ImageInfo>, AppError>;
+
+    /// 按hash顺序分批读取图片，`after` 为上一批最后一张图片的hash，用于遍历全部图片
+    async fn find_batch_after(&self, after: Option<&str>, limit: u64) -> Result<Vec<ImageInfo>, AppError>;
+
+    /// 按所有者统计图片占用的字节数和数量（包括回收站中的图片），返回 (所有者ID, 字节数, 数量)
+    async fn usage_by_owner(&self) -> Result<Vec<(i32, i64, i64)>, AppError> {
+        let connection = self.get_connection();
+        let db_backend = connection.get_database_backend();
+        // SUM 在 PostgreSQL/MySQL 中返回 NUMERIC/DECIMAL，统一转换为整数
+        let integer_type = match db_backend {
+            DbBackend::MySql => "SIGNED",
+            _ => "BIGINT",
+        };
+
+        let rows = connection
+            .query_all(Statement::from_string(
+                db_backend,
+                format!(
+                    "SELECT owner_token_id, CAST(COALESCE(SUM(size), 0) AS {}) AS total_size, COUNT(*) AS image_count FROM images WHERE owner_token_id IS NOT NULL GROUP BY owner_token_id",
+                    integer_type
+                ),
+            ))
+            .await
+            .map_err(|e| AppError::Internal(format!("统计图片占用失败: {}", e)))?;
+
+        let mut usage = Vec::with_capacity(rows.len());
+        for row in rows {
+            let owner_token_id: i32 = match row.try_get("", "owner_token_id") {
+                Ok(owner_token_id) => owner_token_id,
+                Err(e) => {
+                    warn!("读取图片所有者失败，跳过该行: {}", e);
+                    continue;
+                }
+            };
+            let total_size: i64 = row
+                .try_get("", "total_size")
+                .map_err(|e| AppError::Internal(format!("读取图片占用大小失败: {}", e)))?;
+            let image_count: i64 = row
+                .try_get("", "image_count")
+                .map_err(|e| AppError::Internal(format!("读取图片数量失败: {}", e)))?;
+            usage.push((owner_token_id, total_size, image_count));
+        }
+        Ok(usage)
+    }
+
+    async fn update_deleted_at(&self, hash: &str, deleted_at: Option<DateTime<Utc>>) -> Result<bool, AppError>;
+
+    /// 查询在给定时间之前移入回收站的图片（所有者为空时查询全部），按删除时间排序
+    async fn find_trashed_before(
+        &self,
+        before: DateTime<Utc>,
+        owner_token_id: Option<i32>,
+        limit: u64,
+    ) -> Result<Vec<ImageInfo>, AppError>;
 }
 
 /// 图片仓储实现
@@ -54,6 +116,13 @@ impl ImageRepository {
     fn build_query_condition(&self, query: &ImageQuery) -> Condition {
         let mut condition = Condition::all();
 
+        // 默认不包含回收站中的图片
+        condition = condition.add(if query.trashed == Some(true) {
+            image::Column::DeletedAt.is_not_null()
+        } else {
+            image::Column::DeletedAt.is_null()
+        });
+
         if let Some(mime_type) = &query.mime_type {
             condition = condition.add(image::Column::MimeType.eq(mime_type));
         }
@@ -85,18 +154,114 @@ impl ImageRepository {
             condition = condition.add(image::Column::OwnerTokenId.eq(owner_id));
         }
 
+        // 尺寸过滤，没有尺寸信息的旧图片不会匹配
+        // 尺寸列是 i32，超出范围的参数截断为 i32::MAX，不会回绕成负数
+        let dimension = |value: u32| i32::try_from(value).unwrap_or(i32::MAX);
+
+        if let Some(min_width) = query.min_width {
+            condition = condition.add(image::Column::Width.gte(dimension(min_width)));
+        }
+
+        if let Some(max_width) = query.max_width {
+            condition = condition.add(image::Column::Width.lte(dimension(max_width)));
+        }
+
+        if let Some(min_height) = query.min_height {
+            condition = condition.add(image::Column::Height.gte(dimension(min_height)));
+        }
+
+        if let Some(max_height) = query.max_height {
+            condition = condition.add(image::Column::Height.lte(dimension(max_height)));
+        }
+
+        if let Some(orientation) = query.orientation {
+            let width = Expr::col((Image, image::Column::Width));
+            let height = Expr::col((Image, image::Column::Height));
+            condition = condition.add(match orientation {
+                ImageOrientation::Landscape => width.gt(height),
+                ImageOrientation::Portrait => width.lt(height),
+                ImageOrientation::Square => width.eq(height),
+            });
+        }
+
+        if let Some(album_id) = query.album_id {
+            let album_images = SeaQuery::select()
+                .column(album_image::Column::ImageHash)
+                .from(AlbumImage)
+                .and_where(album_image::Column::AlbumId.eq(album_id))
+                .to_owned();
+            condition = condition.add(image::Column::Hash.in_subquery(album_images));
+        }
+
+        // 每个标签单独作为一个条件，图片需要同时带有全部标签
+        for tag in query.tags.iter().flatten() {
+            let tagged = Self::tagged_images([tag]);
+            condition = condition.add(image::Column::Hash.in_subquery(tagged));
+        }
+
+        if let Some(any_tags) = &query.any_tags {
+            let tagged = Self::tagged_images(any_tags);
+            condition = condition.add(image::Column::Hash.in_subquery(tagged));
+        }
+
+        if let Some(exclude_tags) = &query.exclude_tags {
+            let tagged = Self::tagged_images(exclude_tags);
+            condition = condition.add(image::Column::Hash.not_in_subquery(tagged));
+        }
+
         condition
     }
 
+    /// 带有任意一个给定标签的图片哈希子查询
+    fn tagged_images<'a>(tags: impl IntoIterator<Item = &'a String>) -> SelectStatement {
+        SeaQuery::select()
+            .column(image_tag::Column::ImageHash)
+            .from(ImageTag)
+            .and_where(image_tag::Column::Tag.is_in(tags.into_iter().map(String::as_str)))
+            .to_owned()
+    }
+
     /// 应用排序
     fn apply_ordering(
         &self,
         select: sea_orm::Select<Image>,
         query: &ImageQuery,
     ) -> sea_orm::Select<Image> {
-        let order_by = query.order_by.as_deref().unwrap_or("created_at");
-        let order_dir = query.order_dir.as_deref().unwrap_or("DESC");
+        // 按相册过滤时默认使用相册中的顺序
+        let default_order = if query.album_id.is_some() { "position" } else { "created_at" };
+        let order_by = query.order_by.as_deref().unwrap_or(default_order);
+        let default_dir = if order_by == "position" { "ASC" } else { "DESC" };
+        let order_dir = query.order_dir.as_deref().unwrap_or(default_dir);
+
+        match (order_by, query.album_id) {
+            ("position", Some(album_id)) => {
+                let position = SeaQuery::select()
+                    .column(album_image::Column::Position)
+                    .from(AlbumImage)
+                    .and_where(album_image::Column::AlbumId.eq(album_id))
+                    .and_where(
+                        Expr::col((AlbumImage, album_image::Column::ImageHash))
+                            .equals((Image, image::Column::Hash)),
+                    )
+                    .to_owned();
+                let position =
+                    SimpleExpr::SubQuery(None, Box::new(position.into_sub_query_statement()));
+                if order_dir.to_uppercase() == "ASC" {
+                    select.order_by(position, Order::Asc)
+                } else {
+                    select.order_by(position, Order::Desc)
+                }
+            }
+            _ => Self::order_by_column(select, order_by, order_dir),
+        }
+    }
 
+    /// 按图片表中的字段排序
+    fn order_by_column(
+        select: sea_orm::Select<Image>,
+        order_by: &str,
+        order_dir: &str,
+    ) -> sea_orm::Select<Image> {
         match order_by {
             "hash" => {
                 if order_dir.to_uppercase() == "ASC" {
@@ -119,6 +284,20 @@ impl ImageRepository {
                     select.order_by_desc(image::Column::CreatedAt)
                 }
             }
+            "width" => {
+                if order_dir.to_uppercase() == "ASC" {
+                    select.order_by_asc(image::Column::Width)
+                } else {
+                    select.order_by_desc(image::Column::Width)
+                }
+            }
+            "height" => {
+                if order_dir.to_uppercase() == "ASC" {
+                    select.order_by_asc(image::Column::Height)
+                } else {
+                    select.order_by_desc(image::Column::Height)
+                }
+            }
             "access_count" => {
                 if order_dir.to_uppercase() == "ASC" {
                     select.order_by_asc(image::Column::AccessCount)
@@ -247,6 +426,20 @@ impl ImageRepositoryTrait for ImageRepository {
         }
     }
 
+    async fn update_visibility(&self, hash: &str, is_private: bool) -> Result<bool, AppError> {
+        debug!("更新图片可见性: {} -> {}", hash, is_private);
+
+        let connection = self.get_connection();
+        let result = Image::update_many()
+            .col_expr(image::Column::IsPrivate, Expr::value(is_private))
+            .filter(image::Column::Hash.eq(hash))
+            .exec(&*connection)
+            .await
+            .map_err(|e| AppError::Internal(format!("更新图片可见性失败: {}", e)))?;
+
+        Ok(result.rows_affected > 0)
+    }
+
     async fn delete_by_hash(&self, hash: &str) -> Result<bool, AppError> {
         debug!("删除图片记录: {}", hash);
 
@@ -270,10 +463,11 @@ impl ImageRepositoryTrait for ImageRepository {
 
         let connection = self.get_connection();
         let db_backend = connection.get_database_backend();
+        // 回收站中的图片不计入统计
         let where_clause = if owner_token_id.is_some() {
-            " WHERE owner_token_id = ?"
+            " WHERE deleted_at IS NULL AND owner_token_id = ?"
         } else {
-            ""
+            " WHERE deleted_at IS NULL"
         };
         let params = owner_token_id
             .map(|id| vec![sea_orm::Value::Int(Some(id))])
@@ -362,4 +556,99 @@ impl ImageRepositoryTrait for ImageRepository {
 
         Ok(records.into_iter().map(|model| model.into()).collect())
     }
+
+    async fn find_with_phash(&self, owner_token_id: Option<i32>) -> Result<Vec<ImageInfo>, AppError> {
+        let owner_condition = match owner_token_id {
+            Some(owner_token_id) => image::Column::OwnerTokenId.eq(owner_token_id),
+            None => image::Column::OwnerTokenId.is_null(),
+        };
+
+        let connection = self.get_connection();
+        let records = Image::find()
+            .filter(owner_condition)
+            .filter(image::Column::Phash.is_not_null())
+            .filter(image::Column::DeletedAt.is_null())
+            .all(&*connection)
+            .await
+            .map_err(|e| AppError::Internal(format!("查询图片失败: {}", e)))?;
+
+        Ok(records.into_iter().map(|model| model.into()).collect())
+    }
+
+    async fn find_batch_after(&self, after: Option<&str>, limit: u64) -> Result<Vec<ImageInfo>, AppError> {
+        let mut select = Image::find();
+        if let Some(after) = after {
+            select = select.filter(image::Column::Hash.gt(after));
+        }
+
+        let connection = self.get_connection();
+        let records = select
+            .order_by_asc(image::Column::Hash)
+            .limit(limit)
+            .all(&*connection)
+            .await
+            .map_err(|e| AppError::Internal(format!("查询图片失败: {}", e)))?;
+
+        Ok(records.into_iter().map(|model| model.into()).collect())
+    }
+
+    async fn usage_by_owner(&self) -> Result<Vec<(i32, i64, i64)>, AppError> {
+        let connection = self.get_connection();
+        let rows = connection
+            .query_all(Statement::from_string(
+                connection.get_database_backend(),
+                "SELECT owner_token_id, COALESCE(SUM(size), 0) as total_size, COUNT(*) as image_count FROM images WHERE owner_token_id IS NOT NULL GROUP BY owner_token_id",
+            ))
+            .await
+            .map_err(|e| AppError::Internal(format!("统计图片占用失败: {}", e)))?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some((
+                    row.try_get("", "owner_token_id").ok()?,
+                    row.try_get("", "total_size").unwrap_or(0i64),
+                    row.try_get("", "image_count").unwrap_or(0i64),
+                ))
+            })
+            .collect())
+    }
+
+    async fn update_deleted_at(&self, hash: &str, deleted_at: Option<DateTime<Utc>>) -> Result<bool, AppError> {
+        debug!("更新图片删除时间: {} -> {:?}", hash, deleted_at);
+
+        let connection = self.get_connection();
+        let result = Image::update_many()
+            .col_expr(image::Column::DeletedAt, Expr::value(deleted_at))
+            .filter(image::Column::Hash.eq(hash))
+            .exec(&*connection)
+            .await
+            .map_err(|e| AppError::Internal(format!("更新图片删除时间失败: {}", e)))?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn find_trashed_before(
+        &self,
+        before: DateTime<Utc>,
+        owner_token_id: Option<i32>,
+        limit: u64,
+    ) -> Result<Vec<ImageInfo>, AppError> {
+        let mut select = Image::find()
+            .filter(image::Column::DeletedAt.is_not_null())
+            .filter(image::Column::DeletedAt.lte(before));
+        if let Some(owner_token_id) = owner_token_id {
+            select = select.filter(image::Column::OwnerTokenId.eq(owner_token_id));
+        }
+
+        let connection = self.get_connection();
+        let records = select
+            .order_by_asc(image::Column::DeletedAt)
+            .limit(limit)
+            .all(&*connection)
+            .await
+            .map_err(|e| AppError::Internal(format!("查询回收站图片失败: {}", e)))?;
+
+        Ok(records.into_iter().map(|model| model.into()).collect())
+    }
 }
@@ -1,9 +1,17 @@
+pub mod album;
+pub mod audit;
 pub mod base;
+pub mod blob;
 pub mod cache;
 pub mod image;
+pub mod tag;
 pub mod token;
 
+pub use album::*;
+pub use audit::*;
 pub use base::*;
+pub use blob::*;
 pub use cache::*;
 pub use image::*;
+pub use tag::*;
 pub use token::*;
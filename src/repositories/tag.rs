@@ -0,0 +1,135 @@
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    TransactionTrait,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::entities::{image, image_tag, Image, ImageTag};
+use crate::repositories::{BaseRepository, Repository};
+use crate::utils::AppError;
+
+/// 图片标签仓储
+pub struct TagRepository {
+    base: BaseRepository,
+}
+
+impl TagRepository {
+    pub fn new(connection: Arc<DatabaseConnection>) -> Self {
+        Self {
+            base: BaseRepository::new(connection),
+        }
+    }
+
+    fn conn(&self) -> Arc<DatabaseConnection> {
+        self.base.get_connection()
+    }
+
+    /// 查询图片的标签，按字母顺序排列
+    pub async fn find_by_image(&self, hash: &str) -> Result<Vec<String>, AppError> {
+        ImageTag::find()
+            .select_only()
+            .column(image_tag::Column::Tag)
+            .filter(image_tag::Column::ImageHash.eq(hash))
+            .order_by_asc(image_tag::Column::Tag)
+            .into_tuple()
+            .all(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("查询图片标签失败: {}", e)))
+    }
+
+    /// 批量查询多张图片的标签
+    pub async fn find_by_images(
+        &self,
+        hashes: &[String],
+    ) -> Result<HashMap<String, Vec<String>>, AppError> {
+        if hashes.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let records = ImageTag::find()
+            .filter(image_tag::Column::ImageHash.is_in(hashes.iter().map(String::as_str)))
+            .order_by_asc(image_tag::Column::Tag)
+            .all(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("查询图片标签失败: {}", e)))?;
+
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        for record in records {
+            tags.entry(record.image_hash).or_default().push(record.tag);
+        }
+        Ok(tags)
+    }
+
+    /// 替换图片的全部标签
+    pub async fn replace(&self, hash: &str, tags: &[String]) -> Result<(), AppError> {
+        let txn = self
+            .conn()
+            .begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("开启事务失败: {}", e)))?;
+
+        ImageTag::delete_many()
+            .filter(image_tag::Column::ImageHash.eq(hash))
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("删除图片标签失败: {}", e)))?;
+
+        if !tags.is_empty() {
+            let models = tags.iter().map(|tag| image_tag::ActiveModel {
+                image_hash: Set(hash.to_string()),
+                tag: Set(tag.clone()),
+            });
+            ImageTag::insert_many(models)
+                .exec_without_returning(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("保存图片标签失败: {}", e)))?;
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("提交事务失败: {}", e)))
+    }
+
+    /// 删除图片的全部标签（图片被删除时使用）
+    pub async fn delete_by_image(&self, hash: &str) -> Result<u64, AppError> {
+        let result = ImageTag::delete_many()
+            .filter(image_tag::Column::ImageHash.eq(hash))
+            .exec(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("删除图片标签失败: {}", e)))?;
+        Ok(result.rows_affected)
+    }
+
+    /// 统计各标签的图片数量（所有者为空时统计全部图片），按数量从多到少排序
+    pub async fn count_by_tag(
+        &self,
+        owner_token_id: Option<i32>,
+    ) -> Result<Vec<(String, i64)>, AppError> {
+        let mut select = ImageTag::find()
+            .select_only()
+            .column(image_tag::Column::Tag)
+            .column_as(image_tag::Column::ImageHash.count(), "count");
+
+        // 回收站中的图片不计入标签数量
+        let mut images = Query::select()
+            .column(image::Column::Hash)
+            .from(Image)
+            .and_where(image::Column::DeletedAt.is_null())
+            .to_owned();
+        if let Some(owner_token_id) = owner_token_id {
+            images.and_where(image::Column::OwnerTokenId.eq(owner_token_id));
+        }
+        select = select.filter(image_tag::Column::ImageHash.in_subquery(images));
+
+        select
+            .group_by(image_tag::Column::Tag)
+            .order_by_desc(Expr::col(image_tag::Column::ImageHash).count())
+            .order_by_asc(image_tag::Column::Tag)
+            .into_tuple()
+            .all(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("统计标签失败: {}", e)))
+    }
+}
@@ -1,8 +1,8 @@
 use async_trait::async_trait;
 use chrono::Utc;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
-    TransactionTrait,
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    Set, TransactionTrait,
 };
 use std::sync::Arc;
 
@@ -151,6 +151,38 @@ impl TokenRepository {
             .map_err(|e| AppError::Internal(format!("统计管理员数量失败: {}", e)))
     }
 
+    /// 统计未禁用的管理员令牌数量
+    pub async fn count_active_admins(&self) -> Result<i64, AppError> {
+        ApiToken::find()
+            .filter(api_token::Column::Role.eq("admin"))
+            .filter(api_token::Column::IsActive.eq(true))
+            .count(&*self.conn())
+            .await
+            .map(|count| count as i64)
+            .map_err(|e| AppError::Internal(format!("统计管理员数量失败: {}", e)))
+    }
+
+    /// 查询最早创建的未禁用管理员令牌
+    pub async fn find_first_admin(&self) -> Result<Option<api_token::Model>, AppError> {
+        ApiToken::find()
+            .filter(api_token::Column::Role.eq("admin"))
+            .filter(api_token::Column::IsActive.eq(true))
+            .order_by_asc(api_token::Column::Id)
+            .one(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("查询Token失败: {}", e)))
+    }
+
+    pub async fn update(
+        &self,
+        active_model: api_token::ActiveModel,
+    ) -> Result<api_token::Model, AppError> {
+        active_model
+            .update(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("更新Token失败: {}", e)))
+    }
+
     pub async fn count_all(&self) -> Result<i64, AppError> {
         ApiToken::find()
             .count(&*self.conn())
@@ -173,7 +205,10 @@ impl TokenRepository {
         Ok(())
     }
 
-    pub async fn adjust_usage(&self, token_id: i32, delta: i64) -> Result<(), AppError> {
+    /// 调整已用配额，`bytes` 和 `images` 分别为字节数和图片数量的变化量
+    ///
+    /// 增加时检查是否超过配额；减少时令牌已被删除则无需处理。
+    pub async fn adjust_usage(&self, token_id: i32, bytes: i64, images: i64) -> Result<(), AppError> {
         // ID为0的token是临时用户，不需要检查存储配额
         if token_id == 0 {
             return Ok(());
@@ -185,7 +220,7 @@ impl TokenRepository {
             .await
             .map_err(|e| AppError::Internal(format!("开启事务失败: {}", e)))?;
 
-        let mut model = match ApiToken::find_by_id(token_id)
+        let model = match ApiToken::find_by_id(token_id)
             .one(&txn)
             .await
             .map_err(|e| AppError::Internal(format!("查询Token失败: {}", e)))?
@@ -195,26 +230,39 @@ impl TokenRepository {
                 txn.rollback()
                     .await
                     .ok();
+                if bytes <= 0 && images <= 0 {
+                    return Ok(());
+                }
                 return Err(AppError::BadRequest("Token不存在".to_string()));
             }
         };
 
-        if delta > 0 {
+        if bytes > 0 {
             if let Some(limit) = model.max_upload_size {
-                if model.used_upload_size + delta > limit {
+                if model.used_upload_size + bytes > limit {
                     txn.rollback().await.ok();
                     return Err(AppError::BadRequest("已超过上传配额".to_string()));
                 }
             }
         }
 
-        model.used_upload_size += delta;
-        if model.used_upload_size < 0 {
-            model.used_upload_size = 0;
+        if images > 0 {
+            if let Some(limit) = model.max_image_count {
+                if model.used_image_count + images > limit {
+                    txn.rollback().await.ok();
+                    return Err(AppError::BadRequest("已超过图片数量配额".to_string()));
+                }
+            }
         }
-        model.updated_at = Utc::now();
 
+        let used_upload_size = (model.used_upload_size + bytes).max(0);
+        let used_image_count = (model.used_image_count + images).max(0);
+
+        // 直接由 Model 转换的 ActiveModel 字段均为未修改状态，需要显式设置才会写入
         let mut active: api_token::ActiveModel = model.into();
+        active.used_upload_size = Set(used_upload_size);
+        active.used_image_count = Set(used_image_count);
+        active.updated_at = Set(Utc::now());
         active
             .update(&txn)
             .await
@@ -224,6 +272,19 @@ impl TokenRepository {
             .await
             .map_err(|e| AppError::Internal(format!("提交事务失败: {}", e)))
     }
+
+    /// 直接设置已用配额（用于按图片记录重新计算）
+    pub async fn set_usage(&self, token_id: i32, bytes: i64, images: i64) -> Result<(), AppError> {
+        ApiToken::update_many()
+            .col_expr(api_token::Column::UsedUploadSize, Expr::value(bytes))
+            .col_expr(api_token::Column::UsedImageCount, Expr::value(images))
+            .col_expr(api_token::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(api_token::Column::Id.eq(token_id))
+            .exec(&*self.conn())
+            .await
+            .map_err(|e| AppError::Internal(format!("更新Token失败: {}", e)))?;
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -2,7 +2,7 @@ use axum::{
     extract::DefaultBodyLimit,
     http::Method,
     middleware,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 use tower_http::cors::{Any, CorsLayer};
@@ -10,13 +10,19 @@ use tower_http::cors::{Any, CorsLayer};
 use crate::app_state::AppState;
 use crate::config::AppConfig;
 use crate::handlers::{
-    api_docs, auto_cleanup_cache, cache_management_dashboard, clean_cache, clear_all_cache, create_token,
-    decay_heat_scores, delete_image, delete_token, gallery_page, get_auth_config,
-    get_cache_stats, get_image, get_image_info, get_stats, get_system_stats,
-    get_token, health_check_detailed, list_tokens, login_page, query_images_get,
-    query_images_post, serve_static, upload_image, user_management_page, verify_token,
+    add_album_images, api_docs, auto_cleanup_cache, cache_management_dashboard, clean_cache,
+    clear_all_cache, create_album, export_audit_events, create_token, decay_heat_scores, delete_album, delete_image,
+    delete_token, empty_trash, find_similar_images, gallery_page, get_album, get_auth_config,
+    get_cache_stats, get_image, get_image_info, get_scrub_status, get_stats, get_system_stats,
+    get_token, health_check_detailed,
+    list_albums, list_audit_events, list_tags, list_tokens, list_trash, login_page, metrics, purge_image,
+    query_images_get, query_images_post, reconcile_token_usage, remove_album_image, reorder_album_images, restore_image,
+    serve_static, set_image_tags, set_image_visibility, sign_image_url, start_scrub, update_album,
+    upload_image, upload_image_from_url, upload_images_batch, user_management_page, verify_token,
+};
+use crate::middleware::{
+    capture_client_ip, log_requests, rate_limit, request_timeout, track_metrics,
 };
-use crate::middleware::{log_requests, request_timeout};
 
 /// 创建应用路由
 pub fn create_routes(app_state: AppState, config: &AppConfig) -> Router {
@@ -37,14 +43,30 @@ pub fn create_routes(app_state: AppState, config: &AppConfig) -> Router {
         // Token管理接口
         .route("/api/tokens/list", get(list_tokens))
         .route("/api/tokens/create", post(create_token))
+        .route("/api/tokens/reconcile", post(reconcile_token_usage))
         .route("/api/tokens/{id}", get(get_token).delete(delete_token))
         // 健康检查
         .route("/health", get(health_check_detailed))
         .route("/health/detailed", get(health_check_detailed))
+        // Prometheus 指标
+        .route("/metrics", get(metrics))
         // 系统管理接口
         .route("/api/system/stats", get(get_system_stats))
+        .route("/api/system/scrub", get(get_scrub_status).post(start_scrub))
+        // 审计日志
+        .route("/api/audit", get(list_audit_events))
+        .route("/api/audit/export", get(export_audit_events))
         // 图片上传
         .route("/upload", post(upload_image))
+        // 从远程URL上传
+        .route("/upload/url", post(upload_image_from_url))
+        // 批量上传，使用单独的请求体大小限制
+        .route(
+            "/upload/batch",
+            post(upload_images_batch).layer(DefaultBodyLimit::max(
+                config.storage.max_batch_size.as_bytes() as usize,
+            )),
+        )
         // 获取图片 - 直接返回图片数据
         .route("/images/{filename}", get(get_image))
         // 获取图片信息 - 返回JSON格式的图片元数据
@@ -58,6 +80,31 @@ pub fn create_routes(app_state: AppState, config: &AppConfig) -> Router {
         .route("/api/stats", get(get_stats))
         // 删除图片
         .route("/api/images/{filename}", delete(delete_image))
+        // 回收站
+        .route("/api/trash", get(list_trash).delete(empty_trash))
+        .route("/api/trash/{filename}", delete(purge_image))
+        .route("/api/trash/{filename}/restore", post(restore_image))
+        // 修改图片可见性
+        .route("/api/images/{filename}/visibility", post(set_image_visibility))
+        // 查找相似图片
+        .route("/api/images/{filename}/similar", get(find_similar_images))
+        // 生成签名URL
+        .route("/api/images/{filename}/sign", post(sign_image_url))
+        // 设置图片标签
+        .route("/api/images/{filename}/tags", put(set_image_tags))
+        // 标签列表
+        .route("/api/tags", get(list_tags))
+        // 相册管理
+        .route("/api/albums", get(list_albums).post(create_album))
+        .route(
+            "/api/albums/{id}",
+            get(get_album).put(update_album).delete(delete_album),
+        )
+        .route(
+            "/api/albums/{id}/images",
+            post(add_album_images).put(reorder_album_images),
+        )
+        .route("/api/albums/{id}/images/{filename}", delete(remove_album_image))
         // 缓存管理接口（简化版）
         .route("/api/cache/stats", get(get_cache_stats))
         .route("/api/cache/clean", post(clean_cache))
@@ -71,18 +118,27 @@ pub fn create_routes(app_state: AppState, config: &AppConfig) -> Router {
         .layer(DefaultBodyLimit::max(
             config.storage.max_file_size.as_bytes() as usize,
         ))
+        // 记录客户端IP，供审计日志使用
+        .layer(middleware::from_fn(capture_client_ip))
+        // 添加请求频率限制中间件
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit,
+        ))
         // 添加请求超时中间件
         .layer(middleware::from_fn_with_state(
             app_state.clone(),
             request_timeout,
         ))
+        // 添加请求指标中间件
+        .layer(middleware::from_fn(track_metrics))
         // 添加自定义请求日志中间件
         .layer(middleware::from_fn(log_requests));
 
     // 添加CORS中间件（如果启用）
     if config.server.enable_cors {
         let cors = CorsLayer::new()
-            .allow_methods([Method::GET, Method::POST, Method::DELETE])
+            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
             .allow_headers(Any)
             .allow_origin(Any);
 
@@ -5,9 +5,9 @@ use tracing::{error, info};
 use crate::app_state::AppState;
 use crate::config::AppConfig;
 use crate::logging;
+use crate::models::ScrubOptions;
 use crate::routes::create_routes;
 use crate::services;
-use crate::utils;
 
 /// 启动缓存清理任务
 pub fn start_cache_cleanup_task(app_state: AppState, config: &AppConfig) -> Option<JoinHandle<()>> {
@@ -24,13 +24,9 @@ pub fn start_cache_cleanup_task(app_state: AppState, config: &AppConfig) -> Opti
             tokio::select! {
                 _ = interval.tick() => {
                     let db_connection = app_state_for_cleanup.db_pool().get_connection();
-                    match services::cache_service::CacheService::new(db_connection) {
+                    let cache_storage = app_state_for_cleanup.cache_storage();
+                    match services::cache_service::CacheService::new(db_connection, cache_storage) {
                         Ok(cache_service) => {
-                            if let Err(e) = cache_service.ensure_cache_dir().await {
-                                error!("创建缓存目录失败: {}", e);
-                                continue;
-                            }
-
                             match cache_service.auto_cleanup().await {
                                 Ok(result) => {
                                     if result.cleaned_count > 0 || !result.applied_policies.is_empty() {
@@ -56,17 +52,116 @@ pub fn start_cache_cleanup_task(app_state: AppState, config: &AppConfig) -> Opti
     }))
 }
 
+/// 启动定期存储校验任务
+pub fn start_scrub_task(app_state: AppState, config: &AppConfig) -> Option<JoinHandle<()>> {
+    if !config.scrub.enabled {
+        return None;
+    }
+
+    let period = std::time::Duration::from_secs(config.scrub.interval.as_seconds());
+    let options = ScrubOptions {
+        checksum: config.scrub.checksum,
+        repair: config.scrub.repair,
+    };
+
+    Some(tokio::spawn(async move {
+        // 启动后等待一个周期再执行，避免每次重启都完整扫描一遍存储
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = app_state.scrub_job().run(&app_state, options).await {
+                        error!("定期存储校验失败: {}", e);
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    break;
+                }
+            }
+        }
+    }))
+}
+
+/// 启动回收站清理任务，定期彻底删除超过保留期的图片
+pub fn start_trash_purge_task(app_state: AppState, config: &AppConfig) -> Option<JoinHandle<()>> {
+    if !config.trash.enabled {
+        return None;
+    }
+
+    let period = std::time::Duration::from_secs(config.trash.purge_interval.as_seconds());
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = services::TrashService::purge_expired(&app_state).await {
+                        error!("清理回收站失败: {}", e);
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    break;
+                }
+            }
+        }
+    }))
+}
+
+/// 启动审计日志清理任务，定期删除超过保留期的审计事件
+pub fn start_audit_purge_task(app_state: AppState, config: &AppConfig) -> Option<JoinHandle<()>> {
+    if config.audit.retention.as_seconds() == 0 {
+        return None;
+    }
+
+    let period = std::time::Duration::from_secs(config.audit.purge_interval.as_seconds());
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let audit_service = services::AuditService::new(app_state.db_pool().get_connection());
+                    if let Err(e) = audit_service.purge_expired().await {
+                        error!("清理审计日志失败: {}", e);
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    break;
+                }
+            }
+        }
+    }))
+}
+
 /// 打印API接口信息
 pub fn print_api_info() {
     info!("API接口:");
     info!("  API文档:  GET      /");
     info!("  健康检查: GET      /health");
+    info!("  监控指标: GET      /metrics");
     info!("  上传图片: POST     /upload");
+    info!("  批量上传: POST     /upload/batch");
+    info!("  URL上传:  POST     /upload/url");
     info!("  获取图片: GET      /images/<filename>");
     info!("  图片信息: GET      /images/<filename>/info");
     info!("  查询列表: GET/POST /api/images/query");
     info!("  统计信息: GET      /api/stats");
+    info!("  存储校验: GET/POST /api/system/scrub");
+    info!("  配额校正: POST     /api/tokens/reconcile");
+    info!("  审计日志: GET      /api/audit");
+    info!("  导出审计: GET      /api/audit/export");
     info!("  删除图片: DEL      /api/images/<filename>");
+    info!("  回收站:   GET/DEL  /api/trash");
+    info!("  恢复图片: POST     /api/trash/<filename>/restore");
+    info!("  彻底删除: DEL      /api/trash/<filename>");
+    info!("  图片可见: POST     /api/images/<filename>/visibility");
+    info!("  签名链接: POST     /api/images/<filename>/sign");
+    info!("  相似图片: GET      /api/images/<filename>/similar");
+    info!("  图片标签: PUT      /api/images/<filename>/tags");
+    info!("  标签列表: GET      /api/tags");
+    info!("  相册管理: GET/POST /api/albums");
+    info!("  相册详情: GET/PUT/DEL /api/albums/<id>");
+    info!("  相册图片: POST/PUT /api/albums/<id>/images");
     info!("  缓存管理: GET      /cache/management");
     info!("  缓存统计: GET      /api/cache/stats");
     info!("  缓存清理: POST     /api/cache/cleanup/auto");
@@ -75,9 +170,11 @@ pub fn print_api_info() {
 }
 
 /// 运行服务器
-pub async fn run_server() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// `config_path` 为配置文件路径，未指定时读取默认的 config.toml。
+pub async fn run_server(config_path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
     // 初始化配置
-    if let Err(e) = AppConfig::init(None) {
+    if let Err(e) = AppConfig::init(config_path) {
         eprintln!("配置初始化失败: {}", e);
         std::process::exit(1);
     }
@@ -90,12 +187,6 @@ pub async fn run_server() -> Result<(), Box<dyn std::error::Error>> {
     info!("启动图床服务...");
     info!("使用配置: {:#?}", config);
 
-    // 确保上传目录存在
-    if let Err(e) = utils::ensure_upload_dir().await {
-        eprintln!("创建上传目录失败: {}", e);
-        std::process::exit(1);
-    }
-
     // 初始化应用状态
     let app_state = match AppState::new().await {
         Ok(state) => {
@@ -111,6 +202,15 @@ pub async fn run_server() -> Result<(), Box<dyn std::error::Error>> {
     // 启动缓存自动清理任务
     let cleanup_task = start_cache_cleanup_task(app_state.clone(), config);
 
+    // 启动定期存储校验任务
+    let scrub_task = start_scrub_task(app_state.clone(), config);
+
+    // 启动回收站清理任务
+    let trash_task = start_trash_purge_task(app_state.clone(), config);
+
+    // 启动审计日志清理任务
+    let audit_task = start_audit_purge_task(app_state.clone(), config);
+
     // 创建路由
     let app = create_routes(app_state, config);
 
@@ -141,10 +241,19 @@ pub async fn run_server() -> Result<(), Box<dyn std::error::Error>> {
         error!("服务器运行错误: {}", e);
     }
 
-    // 停止缓存清理任务
+    // 停止后台任务
     if let Some(task) = cleanup_task {
         task.abort();
     }
+    if let Some(task) = scrub_task {
+        task.abort();
+    }
+    if let Some(task) = trash_task {
+        task.abort();
+    }
+    if let Some(task) = audit_task {
+        task.abort();
+    }
 
     Ok(())
 }
@@ -0,0 +1,236 @@
+use chrono::Utc;
+use sea_orm::{DatabaseConnection, Set};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::entities::album;
+use crate::models::{AlbumInfo, ApiTokenInfo, CreateAlbumRequest, TokenRole, UpdateAlbumRequest};
+use crate::repositories::{AlbumRepository, ImageRepository, ImageRepositoryTrait};
+use crate::utils::AppError;
+
+/// 相册名称的最大长度（字符）
+const MAX_ALBUM_NAME_LENGTH: usize = 100;
+
+/// 相册业务逻辑
+///
+/// 相册属于创建它的令牌，只能包含同一令牌上传的图片；管理员可以管理所有相册。
+pub struct AlbumService {
+    repo: AlbumRepository,
+    image_repo: ImageRepository,
+}
+
+impl AlbumService {
+    pub fn new(connection: Arc<DatabaseConnection>) -> Self {
+        Self {
+            repo: AlbumRepository::new(connection.clone()),
+            image_repo: ImageRepository::new(connection),
+        }
+    }
+
+    /// 创建相册
+    pub async fn create_album(
+        &self,
+        owner: &ApiTokenInfo,
+        request: CreateAlbumRequest,
+    ) -> Result<AlbumInfo, AppError> {
+        let now = Utc::now();
+        let model = self
+            .repo
+            .insert(album::ActiveModel {
+                owner_token_id: Set(owner.id),
+                name: Set(Self::validate_name(&request.name)?),
+                description: Set(Self::normalize_description(request.description)),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            })
+            .await?;
+
+        info!("创建相册: {} ({})", model.name, model.id);
+        self.to_info(model).await
+    }
+
+    /// 列出相册，管理员可以看到全部相册
+    pub async fn list_albums(&self, user: &ApiTokenInfo) -> Result<Vec<AlbumInfo>, AppError> {
+        let owner_token_id = (user.role != TokenRole::Admin).then_some(user.id);
+        let mut albums = Vec::new();
+        for model in self.repo.list(owner_token_id).await? {
+            albums.push(self.to_info(model).await?);
+        }
+        Ok(albums)
+    }
+
+    /// 获取相册信息
+    pub async fn get_album(&self, user: &ApiTokenInfo, id: i32) -> Result<AlbumInfo, AppError> {
+        let model = self.find_accessible(user, id).await?;
+        self.to_info(model).await
+    }
+
+    /// 修改相册名称或描述
+    pub async fn update_album(
+        &self,
+        user: &ApiTokenInfo,
+        id: i32,
+        request: UpdateAlbumRequest,
+    ) -> Result<AlbumInfo, AppError> {
+        let model = self.find_accessible(user, id).await?;
+
+        let mut active: album::ActiveModel = model.into();
+        if let Some(name) = request.name {
+            active.name = Set(Self::validate_name(&name)?);
+        }
+        if let Some(description) = request.description {
+            active.description = Set(Self::normalize_description(Some(description)));
+        }
+        active.updated_at = Set(Utc::now());
+
+        let model = self.repo.update(active).await?;
+        self.to_info(model).await
+    }
+
+    /// 删除相册，相册中的图片不受影响
+    pub async fn delete_album(&self, user: &ApiTokenInfo, id: i32) -> Result<(), AppError> {
+        self.find_accessible(user, id).await?;
+        self.repo.delete_by_id(id).await?;
+        info!("删除相册: {}", id);
+        Ok(())
+    }
+
+    /// 删除令牌名下的全部相册
+    pub async fn delete_albums_by_owner(&self, owner_token_id: i32) -> Result<usize, AppError> {
+        let ids = self.repo.find_ids_by_owner(owner_token_id).await?;
+        for id in &ids {
+            self.repo.delete_by_id(*id).await?;
+        }
+        Ok(ids.len())
+    }
+
+    /// 把图片依次追加到相册末尾，已在相册中的图片保持原位置
+    pub async fn add_images(
+        &self,
+        user: &ApiTokenInfo,
+        id: i32,
+        hashes: &[String],
+    ) -> Result<AlbumInfo, AppError> {
+        let model = self.find_accessible(user, id).await?;
+        if hashes.is_empty() {
+            return Err(AppError::BadRequest("图片列表不能为空".to_string()));
+        }
+
+        for hash in hashes {
+            let image = self.image_repo.find_by_hash(hash).await?;
+            let image = image.filter(|image| image.deleted_at.is_none());
+            if image.and_then(|image| image.owner_token_id) != Some(model.owner_token_id) {
+                return Err(AppError::BadRequest(format!(
+                    "图片不存在或不属于相册所有者: {}",
+                    hash
+                )));
+            }
+        }
+
+        let added = self.repo.append_images(id, hashes).await?;
+        self.repo.touch(id).await?;
+        info!("相册 {} 新增 {} 张图片", id, added);
+        self.get_album(user, id).await
+    }
+
+    /// 从相册中移除图片
+    pub async fn remove_image(
+        &self,
+        user: &ApiTokenInfo,
+        id: i32,
+        hash: &str,
+    ) -> Result<(), AppError> {
+        self.find_accessible(user, id).await?;
+        if !self.repo.remove_image(id, hash).await? {
+            return Err(AppError::NotFound("相册中没有该图片".to_string()));
+        }
+        self.repo.touch(id).await
+    }
+
+    /// 调整相册中图片的顺序
+    ///
+    /// 列出的图片按给定顺序排在最前面，未列出的图片保持原有的相对顺序排在其后。
+    pub async fn reorder_images(
+        &self,
+        user: &ApiTokenInfo,
+        id: i32,
+        hashes: &[String],
+    ) -> Result<AlbumInfo, AppError> {
+        self.find_accessible(user, id).await?;
+
+        let existing = self.repo.find_image_hashes(id, None).await?;
+        let mut listed = HashSet::new();
+        for hash in hashes {
+            if !existing.contains(hash) {
+                return Err(AppError::BadRequest(format!("相册中没有该图片: {}", hash)));
+            }
+            if !listed.insert(hash) {
+                return Err(AppError::BadRequest(format!("图片重复出现: {}", hash)));
+            }
+        }
+
+        let order: Vec<String> = hashes
+            .iter()
+            .chain(existing.iter().filter(|hash| !listed.contains(hash)))
+            .cloned()
+            .collect();
+        self.repo.set_positions(id, &order).await?;
+        self.repo.touch(id).await?;
+        self.get_album(user, id).await
+    }
+
+    /// 查询相册并检查访问权限
+    async fn find_accessible(
+        &self,
+        user: &ApiTokenInfo,
+        id: i32,
+    ) -> Result<album::Model, AppError> {
+        let model = self
+            .repo
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("相册不存在".to_string()))?;
+        if user.role != TokenRole::Admin && model.owner_token_id != user.id {
+            return Err(AppError::Forbidden("无权限访问此相册".to_string()));
+        }
+        Ok(model)
+    }
+
+    async fn to_info(&self, model: album::Model) -> Result<AlbumInfo, AppError> {
+        let image_count = self.repo.count_images(model.id).await?;
+        let cover = self.repo.find_cover(model.id).await?;
+
+        Ok(AlbumInfo {
+            id: model.id,
+            owner_token_id: model.owner_token_id,
+            name: model.name,
+            description: model.description,
+            image_count,
+            cover,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        })
+    }
+
+    fn validate_name(name: &str) -> Result<String, AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::BadRequest("相册名称不能为空".to_string()));
+        }
+        if name.chars().count() > MAX_ALBUM_NAME_LENGTH {
+            return Err(AppError::BadRequest(format!(
+                "相册名称不能超过 {} 个字符",
+                MAX_ALBUM_NAME_LENGTH
+            )));
+        }
+        Ok(name.to_string())
+    }
+
+    fn normalize_description(description: Option<String>) -> Option<String> {
+        description
+            .map(|description| description.trim().to_string())
+            .filter(|description| !description.is_empty())
+    }
+}
@@ -0,0 +1,323 @@
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use std::io::Cursor;
+use tracing::{error, info};
+
+use crate::models::ImageTransformParams;
+use crate::utils::AppError;
+
+/// 动图解码或转换后允许的最大像素总数（所有帧合计），防止小文件解码或放大出超大数据
+const MAX_ANIMATION_PIXELS: u64 = 64 * 1024 * 1024;
+
+/// GIF 编码时的量化速度（1-30，越小质量越好、越慢）
+const GIF_QUANTIZE_SPEED: i32 = 10;
+
+/// 动图中的一帧，保存合成后的完整画布
+pub struct AnimationFrame {
+    /// 完整画布像素
+    pub image: RgbaImage,
+    /// 显示时长（毫秒）
+    pub delay_ms: u32,
+}
+
+/// 解码后的动图
+pub struct Animation {
+    /// 所有帧，尺寸一致
+    pub frames: Vec<AnimationFrame>,
+    /// 播放次数，0 表示无限循环
+    pub loop_count: u32,
+}
+
+/// 动图转换服务
+///
+/// GIF 和 WebP 动图先解码为完整画布序列，逐帧转换后再重新编码，保留每帧时长和循环次数。
+pub struct AnimatedImageTransform;
+
+impl AnimatedImageTransform {
+    /// 解码动图的所有帧
+    pub fn decode(data: &[u8], mime_type: &str) -> Result<Animation, AppError> {
+        match mime_type {
+            "image/gif" => Self::decode_gif(data, usize::MAX),
+            "image/webp" => Self::decode_webp(data),
+            _ => Err(AppError::BadRequest(format!(
+                "不支持的动图格式: {}",
+                mime_type
+            ))),
+        }
+    }
+
+    /// 提取指定索引的单帧（从0开始）
+    pub fn load_frame(data: &[u8], mime_type: &str, index: u32) -> Result<DynamicImage, AppError> {
+        let index = index as usize;
+        let mut animation = match mime_type {
+            // GIF 逐帧合成，只需解码到目标帧
+            "image/gif" => Self::decode_gif(data, index.saturating_add(1))?,
+            _ => Self::decode(data, mime_type)?,
+        };
+
+        if index >= animation.frames.len() {
+            return Err(AppError::BadRequest(format!(
+                "帧索引超出范围: {}，动图共{}帧",
+                index,
+                animation.frames.len()
+            )));
+        }
+
+        info!("提取动图第{}帧", index);
+        Ok(DynamicImage::ImageRgba8(
+            animation.frames.swap_remove(index).image,
+        ))
+    }
+
+    /// 编码为 GIF 或 WebP 动图
+    pub fn encode(
+        animation: Animation,
+        format: ImageFormat,
+        params: &ImageTransformParams,
+    ) -> Result<Vec<u8>, AppError> {
+        if animation.frames.is_empty() {
+            return Err(AppError::BadRequest("动图不包含任何帧".to_string()));
+        }
+
+        match format {
+            ImageFormat::Gif => Self::encode_gif(animation),
+            ImageFormat::WebP => Self::encode_webp(animation, params),
+            _ => Err(AppError::BadRequest(format!(
+                "目标格式不支持动图: {:?}",
+                format
+            ))),
+        }
+    }
+
+    /// 解码 GIF，按处置方式合成每一帧的完整画布，最多解码 `max_frames` 帧
+    fn decode_gif(data: &[u8], max_frames: usize) -> Result<Animation, AppError> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+
+        let mut reader = options.read_info(Cursor::new(data)).map_err(|e| {
+            error!("GIF解析失败: {}", e);
+            AppError::BadRequest("无法解析GIF文件".to_string())
+        })?;
+
+        let (width, height) = (reader.width() as u32, reader.height() as u32);
+        let mut canvas = RgbaImage::new(width, height);
+        let mut frames = Vec::new();
+
+        while frames.len() < max_frames {
+            let Some(frame) = reader.read_next_frame().map_err(|e| {
+                error!("读取GIF帧失败: {}", e);
+                AppError::BadRequest("无法读取GIF帧".to_string())
+            })?
+            else {
+                break;
+            };
+
+            Self::check_pixel_budget(frames.len() + 1, width, height)?;
+
+            let restore = (frame.dispose == gif::DisposalMethod::Previous).then(|| canvas.clone());
+            let (left, top) = (frame.left as u32, frame.top as u32);
+            let frame_width = frame.width as u32;
+
+            // 透明像素保留下层画布内容
+            for (index, pixel) in frame.buffer.chunks_exact(4).enumerate() {
+                if pixel[3] == 0 {
+                    continue;
+                }
+                let x = left + index as u32 % frame_width;
+                let y = top + index as u32 / frame_width;
+                if x < width && y < height {
+                    canvas.put_pixel(x, y, Rgba([pixel[0], pixel[1], pixel[2], pixel[3]]));
+                }
+            }
+
+            frames.push(AnimationFrame {
+                image: canvas.clone(),
+                delay_ms: frame.delay as u32 * 10,
+            });
+
+            match frame.dispose {
+                gif::DisposalMethod::Background => {
+                    let right = (left + frame_width).min(width);
+                    let bottom = (top + frame.height as u32).min(height);
+                    for y in top.min(bottom)..bottom {
+                        for x in left.min(right)..right {
+                            canvas.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+                        }
+                    }
+                }
+                gif::DisposalMethod::Previous => {
+                    if let Some(previous) = restore {
+                        canvas = previous;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // 循环扩展通常位于第一帧之前，读取帧之后才能确定
+        let loop_count = match reader.repeat() {
+            gif::Repeat::Infinite => 0,
+            // NETSCAPE 扩展中的次数为额外重复次数
+            gif::Repeat::Finite(count) => count as u32 + 1,
+        };
+
+        info!(
+            "GIF解码完成: {}x{}, {}帧, 循环次数: {}",
+            width,
+            height,
+            frames.len(),
+            loop_count
+        );
+        Ok(Animation { frames, loop_count })
+    }
+
+    /// 解码 WebP 动图，libwebp 已合成完整画布
+    fn decode_webp(data: &[u8]) -> Result<Animation, AppError> {
+        let decoded = webp::AnimDecoder::new(data).decode().map_err(|e| {
+            error!("WebP动图解析失败: {}", e);
+            AppError::BadRequest("无法解析WebP动图".to_string())
+        })?;
+
+        let mut frames = Vec::with_capacity(decoded.len());
+        let mut previous_timestamp = 0;
+        for frame in (&decoded).into_iter() {
+            let (width, height) = (frame.width(), frame.height());
+            Self::check_pixel_budget(frames.len() + 1, width, height)?;
+
+            let pixels = frame.get_image().to_vec();
+            let image = if frame.get_layout().is_alpha() {
+                RgbaImage::from_raw(width, height, pixels)
+            } else {
+                image::RgbImage::from_raw(width, height, pixels)
+                    .map(|rgb| DynamicImage::ImageRgb8(rgb).to_rgba8())
+            }
+            .ok_or_else(|| AppError::Internal("构建WebP帧失败".to_string()))?;
+
+            // 解码器返回的是每帧的结束时间
+            let timestamp = frame.get_time_ms();
+            frames.push(AnimationFrame {
+                image,
+                delay_ms: timestamp.saturating_sub(previous_timestamp).max(0) as u32,
+            });
+            previous_timestamp = timestamp;
+        }
+
+        info!(
+            "WebP动图解码完成: {}帧, 循环次数: {}",
+            frames.len(),
+            decoded.loop_count
+        );
+        Ok(Animation {
+            frames,
+            loop_count: decoded.loop_count,
+        })
+    }
+
+    fn encode_gif(animation: Animation) -> Result<Vec<u8>, AppError> {
+        let (width, height) = animation.frames[0].image.dimensions();
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(AppError::BadRequest("GIF尺寸不能超过65535像素".to_string()));
+        };
+
+        let encode_error = |e: gif::EncodingError| {
+            error!("GIF动图编码失败: {}", e);
+            AppError::Internal("GIF动图编码失败".to_string())
+        };
+
+        let frame_count = animation.frames.len();
+        let mut output = Vec::new();
+        {
+            let mut encoder =
+                gif::Encoder::new(&mut output, width, height, &[]).map_err(encode_error)?;
+            match animation.loop_count {
+                0 => encoder
+                    .set_repeat(gif::Repeat::Infinite)
+                    .map_err(encode_error)?,
+                // 只播放一次时不写入循环扩展
+                1 => {}
+                count => encoder
+                    .set_repeat(gif::Repeat::Finite((count - 1).min(u16::MAX as u32) as u16))
+                    .map_err(encode_error)?,
+            }
+
+            for frame in animation.frames {
+                let mut pixels = frame.image.into_raw();
+                let mut gif_frame =
+                    gif::Frame::from_rgba_speed(width, height, &mut pixels, GIF_QUANTIZE_SPEED);
+                // GIF 时长单位为 10 毫秒
+                gif_frame.delay = ((frame.delay_ms + 5) / 10).min(u16::MAX as u32) as u16;
+                // 每帧都是完整画布，显示下一帧前清除，避免透明区域露出上一帧
+                gif_frame.dispose = gif::DisposalMethod::Background;
+                encoder.write_frame(&gif_frame).map_err(encode_error)?;
+            }
+        }
+
+        info!(
+            "GIF动图编码完成: {}x{}, {}帧, {}字节",
+            width,
+            height,
+            frame_count,
+            output.len()
+        );
+        Ok(output)
+    }
+
+    fn encode_webp(
+        animation: Animation,
+        params: &ImageTransformParams,
+    ) -> Result<Vec<u8>, AppError> {
+        let (width, height) = animation.frames[0].image.dimensions();
+        let quality = params.quality.unwrap_or(85);
+
+        let mut config = webp::WebPConfig::new()
+            .map_err(|_| AppError::Internal("WebP编码配置初始化失败".to_string()))?;
+        // 与静图一致，高质量使用无损编码
+        config.lossless = i32::from(quality >= 95);
+        config.quality = quality as f32;
+
+        let mut encoder = webp::AnimEncoder::new(width, height, &config);
+        encoder.set_loop_count(animation.loop_count.min(i32::MAX as u32) as i32);
+
+        // 编码器以结束调用的时间戳 0 计算最后一帧时长，因此时间戳从负的总时长开始递增到 0
+        let total: i64 = animation
+            .frames
+            .iter()
+            .map(|frame| frame.delay_ms as i64)
+            .sum();
+        let mut timestamp = -total.min(i32::MAX as i64);
+        for frame in &animation.frames {
+            encoder.add_frame(webp::AnimFrame::from_rgba(
+                frame.image.as_raw(),
+                width,
+                height,
+                timestamp as i32,
+            ));
+            timestamp += frame.delay_ms as i64;
+        }
+
+        let encoded = encoder.try_encode().map_err(|e| {
+            error!("WebP动图编码失败: {:?}", e);
+            AppError::Internal("WebP动图编码失败".to_string())
+        })?;
+
+        info!(
+            "WebP动图编码完成: {}x{}, {}帧, {}字节",
+            width,
+            height,
+            animation.frames.len(),
+            encoded.len()
+        );
+        Ok(encoded.to_vec())
+    }
+
+    /// 检查所有帧合计的像素数是否超出预算，解码输入和转换输出共用同一上限
+    pub fn check_pixel_budget(frame_count: usize, width: u32, height: u32) -> Result<(), AppError> {
+        let pixels = frame_count as u64 * width as u64 * height as u64;
+        if pixels > MAX_ANIMATION_PIXELS {
+            return Err(AppError::BadRequest(format!(
+                "动图过大: {}帧 {}x{}",
+                frame_count, width, height
+            )));
+        }
+        Ok(())
+    }
+}
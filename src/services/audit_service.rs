@@ -0,0 +1,132 @@
+use chrono::Utc;
+use futures_util::{stream, Stream};
+use sea_orm::{ActiveValue::Set, DatabaseConnection};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+use crate::entities::audit_event;
+use crate::middleware::current_client_ip;
+use crate::models::{ApiTokenInfo, AuditAction, AuditEventInfo, AuditOutcome, AuditQuery};
+use crate::repositories::{AuditRepository, PageResult};
+use crate::utils::AppError;
+
+/// 导出审计事件时每批读取的数量
+const EXPORT_BATCH_SIZE: u64 = 500;
+
+/// 审计日志服务
+///
+/// 事件在请求处理过程中写入，客户端IP取自当前请求；写入失败只记录日志，不影响操作本身。
+pub struct AuditService {
+    repo: AuditRepository,
+}
+
+impl AuditService {
+    pub fn new(connection: Arc<DatabaseConnection>) -> Self {
+        Self {
+            repo: AuditRepository::new(connection),
+        }
+    }
+
+    /// 记录一条审计事件
+    pub async fn record(
+        &self,
+        actor_token_id: Option<i32>,
+        action: AuditAction,
+        target: Option<String>,
+        outcome: AuditOutcome,
+        detail: Option<String>,
+    ) {
+        let active_model = audit_event::ActiveModel {
+            actor_token_id: Set(actor_token_id),
+            action: Set(action.as_str().to_string()),
+            target: Set(target),
+            client_ip: Set(current_client_ip().map(|ip| ip.to_string())),
+            outcome: Set(outcome.as_str().to_string()),
+            detail: Set(detail),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        };
+
+        if let Err(e) = self.repo.insert(active_model).await {
+            warn!("记录审计事件 {} 失败: {}", action.as_str(), e);
+        }
+    }
+
+    /// 按操作结果记录审计事件，失败时附带错误信息
+    pub async fn record_result<T>(
+        &self,
+        actor: &ApiTokenInfo,
+        action: AuditAction,
+        target: Option<String>,
+        result: &Result<T, AppError>,
+    ) {
+        let (outcome, detail) = match result {
+            Ok(_) => (AuditOutcome::Success, None),
+            Err(e) => (AuditOutcome::Failure, Some(e.to_string())),
+        };
+        self.record(Some(actor.id), action, target, outcome, detail)
+            .await;
+    }
+
+    /// 删除超过保留期的审计事件，返回删除的数量；保留期为 0 时不删除
+    pub async fn purge_expired(&self) -> Result<u64, AppError> {
+        let retention = AppConfig::get().audit.retention.as_seconds();
+        if retention == 0 {
+            return Ok(0);
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::seconds(retention as i64);
+        let purged = self.repo.delete_before(cutoff).await?;
+        if purged > 0 {
+            info!("已清理{}条过期审计事件", purged);
+        }
+        Ok(purged)
+    }
+
+    /// 分页查询审计事件
+    pub async fn query(&self, query: &AuditQuery) -> Result<PageResult<AuditEventInfo>, AppError> {
+        self.repo.find_by_query(query).await
+    }
+
+    /// 按条件导出全部审计事件（忽略分页参数），每行一个 JSON 对象
+    ///
+    /// 按批查询并逐批输出，不会把全部事件读入内存。
+    pub fn export_json_lines(
+        self,
+        query: &AuditQuery,
+    ) -> impl Stream<Item = Result<String, AppError>> + Send + 'static {
+        // 固定截止时间，导出过程中新写入的事件不会打乱分页
+        let query = AuditQuery {
+            limit: Some(EXPORT_BATCH_SIZE),
+            offset: Some(0),
+            until: query.until.or_else(|| Some(Utc::now())),
+            ..query.clone()
+        };
+
+        stream::try_unfold((self, Some(query)), |(service, query)| async move {
+            let Some(mut query) = query else {
+                return Ok(None);
+            };
+
+            let page = service.repo.find_by_query(&query).await?;
+            let fetched = page.items.len() as u64;
+            let mut lines = String::new();
+            for event in &page.items {
+                let line = serde_json::to_string(event)
+                    .map_err(|e| AppError::Internal(format!("序列化审计事件失败: {}", e)))?;
+                lines.push_str(&line);
+                lines.push('\n');
+            }
+
+            let offset = query.offset.unwrap_or(0) + fetched;
+            let next = if fetched < EXPORT_BATCH_SIZE || offset >= page.total {
+                None
+            } else {
+                query.offset = Some(offset);
+                Some(query)
+            };
+            Ok(Some((lines, (service, next))))
+        })
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 记录的客户端数量超过此值时清理已经结束的时间窗口
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// 单个客户端当前的时间窗口
+struct FailureWindow {
+    started: Instant,
+    suppressed: u64,
+}
+
+/// 认证失败审计事件节流器
+///
+/// 同一客户端IP在时间窗口内只记录第一次认证失败，其余失败只计数，
+/// 合并到窗口结束后的下一条记录中。状态保存在进程内存中，多副本部署时每个副本分别计数。
+pub struct AuthFailureThrottle {
+    window: Duration,
+    clients: Mutex<HashMap<Option<IpAddr>, FailureWindow>>,
+}
+
+impl AuthFailureThrottle {
+    /// 创建节流器，时间窗口为 0 时每次认证失败都记录
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 登记一次认证失败
+    ///
+    /// 需要记录时返回上一个时间窗口内被合并的失败次数，窗口内的重复失败返回 None。
+    pub fn admit(&self, client_ip: Option<IpAddr>) -> Option<u64> {
+        if self.window.is_zero() {
+            return Some(0);
+        }
+
+        let now = Instant::now();
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+
+        if clients.len() >= PRUNE_THRESHOLD {
+            clients.retain(|_, client| now.duration_since(client.started) < self.window);
+        }
+
+        match clients.get_mut(&client_ip) {
+            Some(client) if now.duration_since(client.started) < self.window => {
+                client.suppressed += 1;
+                None
+            }
+            Some(client) => {
+                let suppressed = client.suppressed;
+                *client = FailureWindow {
+                    started: now,
+                    suppressed: 0,
+                };
+                Some(suppressed)
+            }
+            None => {
+                clients.insert(
+                    client_ip,
+                    FailureWindow {
+                        started: now,
+                        suppressed: 0,
+                    },
+                );
+                Some(0)
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use image::{DynamicImage, GenericImageView};
+use std::f32::consts::PI;
+
+/// Base83 编码使用的字符
+const BASE83_CHARS: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// 计算前把图片缩小到的边长，占位图只需要低频信息
+const SAMPLE_SIZE: u32 = 32;
+
+/// 长边和短边方向的分量数
+const LONG_SIDE_COMPONENTS: u32 = 4;
+const SHORT_SIDE_COMPONENTS: u32 = 3;
+
+/// BlurHash 占位字符串
+///
+/// 把图片表示为少量余弦分量并用 Base83 编码成二三十个字符，
+/// 前端在原图加载完成前解码为模糊预览，详见 <https://blurha.sh>。
+pub struct BlurHash;
+
+impl BlurHash {
+    /// 计算已解码图片（动图为第一帧）的 BlurHash，透明像素按原色计算
+    pub fn encode(img: &DynamicImage) -> String {
+        let (width, height) = img.dimensions();
+        let (components_x, components_y) = if width >= height {
+            (LONG_SIDE_COMPONENTS, SHORT_SIDE_COMPONENTS)
+        } else {
+            (SHORT_SIDE_COMPONENTS, LONG_SIDE_COMPONENTS)
+        };
+
+        let sample = img.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).to_rgb8();
+        let (width, height) = sample.dimensions();
+        let pixels: Vec<[f32; 3]> = sample
+            .pixels()
+            .map(|pixel| pixel.0.map(Self::srgb_to_linear))
+            .collect();
+
+        let mut factors = Vec::with_capacity((components_x * components_y) as usize);
+        for j in 0..components_y {
+            for i in 0..components_x {
+                let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+                let mut factor = [0.0f32; 3];
+                for y in 0..height {
+                    let basis_y = (PI * j as f32 * y as f32 / height as f32).cos();
+                    for x in 0..width {
+                        let basis = basis_y * (PI * i as f32 * x as f32 / width as f32).cos();
+                        let pixel = pixels[(y * width + x) as usize];
+                        for channel in 0..3 {
+                            factor[channel] += basis * pixel[channel];
+                        }
+                    }
+                }
+                let scale = normalisation / (width * height) as f32;
+                factors.push(factor.map(|value| value * scale));
+            }
+        }
+
+        let mut hash = String::new();
+        Self::push_base83(&mut hash, (components_x - 1) + (components_y - 1) * 9, 1);
+
+        let (dc, ac) = factors.split_first().expect("至少有一个分量");
+        let maximum_value = if ac.is_empty() {
+            Self::push_base83(&mut hash, 0, 1);
+            1.0
+        } else {
+            let actual_maximum = ac
+                .iter()
+                .flat_map(|factor| factor.iter())
+                .fold(0.0f32, |max, value| max.max(value.abs()));
+            let quantised_maximum = (actual_maximum * 166.0 - 0.5).floor().clamp(0.0, 82.0) as u32;
+            Self::push_base83(&mut hash, quantised_maximum, 1);
+            (quantised_maximum + 1) as f32 / 166.0
+        };
+
+        let [r, g, b] = dc.map(Self::linear_to_srgb);
+        Self::push_base83(&mut hash, (r << 16) | (g << 8) | b, 4);
+
+        for factor in ac {
+            let [r, g, b] = factor.map(|value| {
+                let quantised = (Self::sign_pow(value / maximum_value, 0.5) * 9.0 + 9.5).floor();
+                quantised.clamp(0.0, 18.0) as u32
+            });
+            Self::push_base83(&mut hash, r * 19 * 19 + g * 19 + b, 2);
+        }
+
+        hash
+    }
+
+    fn push_base83(hash: &mut String, value: u32, length: u32) {
+        for i in 1..=length {
+            let digit = (value / 83u32.pow(length - i)) % 83;
+            hash.push(BASE83_CHARS[digit as usize] as char);
+        }
+    }
+
+    fn srgb_to_linear(value: u8) -> f32 {
+        let value = value as f32 / 255.0;
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    fn linear_to_srgb(value: f32) -> u32 {
+        let value = value.clamp(0.0, 1.0);
+        if value <= 0.003_130_8 {
+            (value * 12.92 * 255.0 + 0.5) as u32
+        } else {
+            ((1.055 * value.powf(1.0 / 2.4) - 0.055) * 255.0 + 0.5) as u32
+        }
+    }
+
+    fn sign_pow(value: f32, exp: f32) -> f32 {
+        value.abs().powf(exp).copysign(value)
+    }
+}
@@ -1,43 +1,38 @@
 use chrono::Utc;
 use sea_orm::DatabaseConnection;
 use sha2::{Digest, Sha256};
-use std::path::Path;
 use std::sync::Arc;
-use tokio::fs;
 use tracing::{error, info, warn};
 
 use crate::config::AppConfig;
 use crate::models::{CacheCleanupResult, CacheInfo, CacheStats, ImageTransformParams};
 use crate::repositories::{CacheRepository, CacheRepositoryTrait};
+use crate::storage::{sharded_key, StorageBackend};
 use crate::utils::AppError;
 
 /// 缓存服务
 pub struct CacheService {
     cache_repo: CacheRepository,
+    storage: Arc<dyn StorageBackend>,
     cache_dir: String,
 }
 
 impl CacheService {
     /// 创建新的缓存服务实例
-    pub fn new(connection: Arc<DatabaseConnection>) -> Result<Self, AppError> {
+    pub fn new(
+        connection: Arc<DatabaseConnection>,
+        storage: Arc<dyn StorageBackend>,
+    ) -> Result<Self, AppError> {
         let config = AppConfig::get();
         let cache_dir = config.cache.cache_dir.clone();
 
         Ok(Self {
             cache_repo: CacheRepository::new(connection),
+            storage,
             cache_dir,
         })
     }
 
-    /// 异步初始化缓存目录
-    pub async fn ensure_cache_dir(&self) -> Result<(), AppError> {
-        // 确保缓存目录存在
-        tokio::fs::create_dir_all(&self.cache_dir)
-            .await
-            .map_err(|e| AppError::Internal(format!("创建缓存目录失败: {}", e)))?;
-        Ok(())
-    }
-
     /// 生成缓存键
     /// 使用原始hash和标准化的转换参数生成一致的缓存键
     pub fn generate_cache_key(
@@ -52,12 +47,8 @@ impl CacheService {
         format!("{:x}", hasher.finalize())
     }
 
-    /// 获取缓存文件路径
-    fn get_cache_file_path(&self, cache_key: &str, mime_type: &str) -> String {
-        // 基于缓存键的前几位创建子目录以避免单个目录文件过多
-        let prefix = &cache_key[..2];
-        let middle = &cache_key[2..4];
-
+    /// 获取缓存文件的存储键
+    fn get_cache_storage_key(cache_key: &str, mime_type: &str) -> String {
         let ext = match mime_type {
             "image/jpeg" => "jpg",
             "image/png" => "png",
@@ -67,17 +58,27 @@ impl CacheService {
             _ => "cache",
         };
 
-        format!(
-            "{}/{}/{}/{}.{}",
-            self.cache_dir, prefix, middle, cache_key, ext
-        )
+        // 基于缓存键的前几位创建子目录以避免单个目录文件过多
+        sharded_key(cache_key, &format!("{}.{}", cache_key, ext))
+    }
+
+    /// 将缓存记录中的路径转换为存储键
+    ///
+    /// 旧版本记录的是包含缓存目录的完整路径（如 `cache/ab/cd/xx.webp`），
+    /// 这里去掉目录前缀以兼容历史数据。
+    pub fn storage_key_of<'a>(&self, cache_info: &'a CacheInfo) -> &'a str {
+        let dir_prefix = format!("{}/", self.cache_dir.trim_end_matches('/'));
+        cache_info
+            .file_path
+            .strip_prefix(&dir_prefix)
+            .unwrap_or(&cache_info.file_path)
     }
 
     /// 检查缓存是否存在
     pub async fn get_cache(&self, cache_key: &str) -> Result<Option<CacheInfo>, AppError> {
         if let Some(cache_info) = self.cache_repo.find_by_key(cache_key).await? {
             // 检查文件是否实际存在
-            if tokio::fs::metadata(&cache_info.file_path).await.is_ok() {
+            if self.storage.exists(self.storage_key_of(&cache_info)).await? {
                 Ok(Some(cache_info))
             } else {
                 // 文件不存在，清理数据库记录
@@ -92,7 +93,9 @@ impl CacheService {
 
     /// 读取缓存文件内容
     pub async fn read_cache(&self, cache_info: &CacheInfo) -> Result<Vec<u8>, AppError> {
-        let data = fs::read(&cache_info.file_path)
+        let data = self
+            .storage
+            .get(self.storage_key_of(cache_info))
             .await
             .map_err(|e| AppError::Internal(format!("读取缓存文件失败: {}", e)))?;
 
@@ -118,18 +121,12 @@ impl CacheService {
         }
 
         let cache_key = Self::generate_cache_key(original_hash, transform_params);
-        let file_path = self.get_cache_file_path(&cache_key, mime_type);
+        let file_path = Self::get_cache_storage_key(&cache_key, mime_type);
         let normalized_params_str = transform_params.to_normalized_string();
 
-        // 确保缓存子目录存在
-        if let Some(parent) = Path::new(&file_path).parent() {
-            fs::create_dir_all(parent)
-                .await
-                .map_err(|e| AppError::Internal(format!("创建缓存目录失败: {}", e)))?;
-        }
-
         // 写入缓存文件
-        fs::write(&file_path, data)
+        self.storage
+            .put(&file_path, data)
             .await
             .map_err(|e| AppError::Internal(format!("写入缓存文件失败: {}", e)))?;
 
@@ -341,7 +338,7 @@ impl CacheService {
 
         for cache_info in candidates {
             // 删除文件
-            if let Err(e) = fs::remove_file(&cache_info.file_path).await {
+            if let Err(e) = self.storage.delete(self.storage_key_of(&cache_info)).await {
                 warn!("删除缓存文件失败: {} - {}", cache_info.file_path, e);
             } else {
                 freed_space += cache_info.file_size;
@@ -439,14 +436,18 @@ impl CacheService {
         // 删除所有数据库记录
         let deleted_count = self.cache_repo.clear_all().await?;
 
-        // 删除缓存目录
-        if let Err(e) = fs::remove_dir_all(&self.cache_dir).await {
-            warn!("删除缓存目录失败: {} - {}", self.cache_dir, e);
-        }
-
-        // 重新创建缓存目录
-        if let Err(e) = fs::create_dir_all(&self.cache_dir).await {
-            error!("重新创建缓存目录失败: {} - {}", self.cache_dir, e);
+        // 删除所有缓存文件
+        match self.storage.list("").await {
+            Ok(objects) => {
+                for object in objects {
+                    if let Err(e) = self.storage.delete(&object.key).await {
+                        warn!("删除缓存文件失败: {} - {}", object.key, e);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("列举缓存文件失败: {}", e);
+            }
         }
 
         Ok(CacheCleanupResult {
@@ -462,6 +463,23 @@ impl CacheService {
         self.cache_repo.get_stats().await
     }
 
+    /// 获取缓存项数量和总大小（字节）
+    pub async fn get_usage(&self) -> Result<(u64, u64), AppError> {
+        self.cache_repo.get_usage().await
+    }
+
+    /// 列出所有缓存记录
+    pub async fn list_all(&self) -> Result<Vec<CacheInfo>, AppError> {
+        self.cache_repo.list_all_caches().await
+    }
+
+    /// 删除单个缓存的文件和记录
+    pub async fn remove_entry(&self, cache_info: &CacheInfo) -> Result<(), AppError> {
+        self.storage.delete(self.storage_key_of(cache_info)).await?;
+        self.cache_repo.delete_by_key(&cache_info.cache_key).await?;
+        Ok(())
+    }
+
     /// 根据原始哈希删除相关缓存
     pub async fn remove_by_original_hash(&self, original_hash: &str) -> Result<u64, AppError> {
         self.cache_repo.delete_by_original_hash(original_hash).await
@@ -21,6 +21,68 @@ impl ImageFormatUtils {
         }
     }
 
+    /// 统计图片的帧数，只解析容器结构而不解码像素（静态图片和无法解析时为1）
+    pub fn frame_count(mime_type: &str, data: &[u8]) -> u32 {
+        let count = match mime_type {
+            "image/gif" => Self::gif_frame_count(data),
+            "image/webp" => Self::webp_frame_count(data),
+            "image/png" => Self::apng_frame_count(data),
+            _ => None,
+        };
+        count.unwrap_or(1).max(1)
+    }
+
+    /// 跳过 LZW 解码统计 GIF 帧数
+    fn gif_frame_count(data: &[u8]) -> Option<u32> {
+        let mut options = gif::DecodeOptions::new();
+        options.skip_frame_decoding(true);
+        let mut reader = options.read_info(std::io::Cursor::new(data)).ok()?;
+
+        let mut count = 0;
+        while let Ok(Some(_)) = reader.read_next_frame() {
+            count += 1;
+        }
+        Some(count)
+    }
+
+    /// 统计 WebP 中的 ANMF 动画帧块
+    fn webp_frame_count(data: &[u8]) -> Option<u32> {
+        if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+            return None;
+        }
+
+        let mut count = 0;
+        let mut offset = 12;
+        while offset + 8 <= data.len() {
+            let size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().ok()?) as usize;
+            if &data[offset..offset + 4] == b"ANMF" {
+                count += 1;
+            }
+            // 块大小为奇数时有一个填充字节
+            offset = offset.checked_add(8 + size + (size & 1))?;
+        }
+        Some(count)
+    }
+
+    /// 读取 APNG 的 acTL 块中声明的帧数
+    fn apng_frame_count(data: &[u8]) -> Option<u32> {
+        let mut offset = 8;
+        while offset + 8 <= data.len() {
+            let length = u32::from_be_bytes(data[offset..offset + 4].try_into().ok()?) as usize;
+            match &data[offset + 4..offset + 8] {
+                b"acTL" if offset + 12 <= data.len() => {
+                    let frames = data[offset + 8..offset + 12].try_into().ok()?;
+                    return Some(u32::from_be_bytes(frames));
+                }
+                // acTL 必须出现在图像数据之前
+                b"IDAT" => return None,
+                _ => {}
+            }
+            offset = offset.checked_add(12 + length)?;
+        }
+        None
+    }
+
     /// 检测GIF是否为动画（包含多帧）
     fn is_gif_animated(data: &[u8]) -> bool {
         // 使用gif库解析检测
@@ -117,6 +179,71 @@ impl ImageFormatUtils {
         }
     }
 
+    /// 按 Accept 请求头协商输出格式，返回 None 表示保持原格式
+    ///
+    /// 只认可明确列出的 `image/avif` / `image/webp`（`image/*` 和 `*/*` 不代表支持新格式），
+    /// 按 q 值选择权重最高的格式，权重相同时优先 AVIF；客户端对原格式的权重不低于候选格式时保持原样。
+    pub fn negotiate_format(
+        accept: &str,
+        original_mime: &str,
+        params: &ImageTransformParams,
+    ) -> Option<&'static str> {
+        let candidates: &[(&'static str, &str)] = match original_mime {
+            // AVIF 暂不支持质量参数，指定质量时只协商 WebP
+            "image/jpeg" | "image/png" if params.quality.is_none() => {
+                &[("avif", "image/avif"), ("webp", "image/webp")]
+            }
+            "image/jpeg" | "image/png" => &[("webp", "image/webp")],
+            // 动图只协商为 WebP，保留动画
+            "image/gif" | "image/webp" => &[("webp", "image/webp")],
+            _ => &[],
+        };
+
+        let mut best: Option<(&'static str, &str, f32)> = None;
+        for &(format, mime) in candidates {
+            let Some(q) = Self::accept_quality(accept, mime) else {
+                continue;
+            };
+            if q > 0.0 && best.is_none_or(|(_, _, best_q)| q > best_q) {
+                best = Some((format, mime, q));
+            }
+        }
+
+        best.filter(|(_, mime, q)| {
+            *mime != original_mime
+                && Self::accept_quality(accept, original_mime).is_none_or(|original| original < *q)
+        })
+        .map(|(format, _, _)| format)
+    }
+
+    /// Accept 请求头中明确列出的MIME类型的权重（q 值），未列出时返回 None
+    ///
+    /// 缺省或无法解析的 q 值按 1 处理，q=0 表示拒绝。
+    fn accept_quality(accept: &str, mime_type: &str) -> Option<f32> {
+        accept
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let media_type = parts.next().unwrap_or("").trim();
+                if !media_type.eq_ignore_ascii_case(mime_type) {
+                    return None;
+                }
+
+                let q = parts
+                    .filter_map(|param| {
+                        let (name, value) = param.split_once('=')?;
+                        name.trim()
+                            .eq_ignore_ascii_case("q")
+                            .then(|| value.trim().parse::<f32>().ok())
+                            .flatten()
+                    })
+                    .next()
+                    .unwrap_or(1.0);
+                Some(q.clamp(0.0, 1.0))
+            })
+            .reduce(f32::max)
+    }
+
     /// 验证格式兼容性
     pub fn validate_format_compatibility(
         format: &ImageFormat,
@@ -151,6 +278,13 @@ impl ImageFormatUtils {
             }
         }
 
+        // 检查裁剪区域
+        if let Some(crop) = params.crop {
+            if crop.width == 0 || crop.height == 0 {
+                return Err(AppError::BadRequest("裁剪区域的宽高必须大于0".to_string()));
+            }
+        }
+
         // 检查质量参数
         if let Some(quality) = params.quality {
             if quality == 0 || quality > 100 {
@@ -0,0 +1,566 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use exif::experimental::Writer as ExifWriter;
+use image::{ImageDecoder, ImageFormat, ImageReader};
+use img_parts::jpeg::{markers, Jpeg, JpegSegment};
+use img_parts::png::{Png, PngChunk};
+use img_parts::riff::{RiffChunk, RiffContent};
+use img_parts::webp::{
+    WebP, CHUNK_ALPH, CHUNK_EXIF, CHUNK_ICCP, CHUNK_VP8L, CHUNK_VP8X, CHUNK_XMP,
+};
+use img_parts::ImageICC;
+use std::io::{Cursor, Write};
+use tracing::{info, warn};
+
+use crate::config::MetadataPolicy;
+use crate::utils::AppError;
+
+/// JPEG APP1 / WebP EXIF 块中的 EXIF 标识
+const EXIF_PREFIX: &[u8] = b"Exif\0\0";
+/// JPEG APP1 中的 XMP 标识
+const XMP_PREFIX: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// JPEG APP1 中扩展 XMP 的标识（XMP 超过单个段大小时使用）
+const XMP_EXTENSION_PREFIX: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+/// JPEG APP2 中的 ICC 标识
+const ICC_PREFIX: &[u8] = b"ICC_PROFILE\0";
+/// 单个 APP2 段可容纳的 ICC 数据长度（65535 - 2字节长度 - 14字节标识和序号）
+const ICC_SEGMENT_MAX_SIZE: usize = 65519;
+
+/// PNG 元数据块
+const PNG_EXIF: [u8; 4] = *b"eXIf";
+const PNG_TIME: [u8; 4] = *b"tIME";
+const PNG_TEXT_CHUNKS: [[u8; 4]; 3] = [*b"tEXt", *b"zTXt", *b"iTXt"];
+/// PNG 中保存 XMP 的文本块关键字
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+/// ImageMagick 等工具以十六进制文本保存原始 EXIF/IPTC 的关键字前缀
+const PNG_RAW_PROFILE_KEYWORD: &[u8] = b"Raw profile type";
+
+/// WebP VP8X 标志位
+const VP8X_ICC_FLAG: u8 = 0x20;
+const VP8X_ALPHA_FLAG: u8 = 0x10;
+const VP8X_EXIF_FLAG: u8 = 0x08;
+const VP8X_XMP_FLAG: u8 = 0x04;
+
+/// 图片元数据工具
+///
+/// 直接改写 JPEG / PNG / WebP 的容器结构，不重新编码像素数据。
+pub struct ImageMetadata;
+
+impl ImageMetadata {
+    /// 检查是否支持处理该格式的元数据
+    pub fn supports(mime_type: &str) -> bool {
+        matches!(mime_type, "image/jpeg" | "image/png" | "image/webp")
+    }
+
+    /// 按策略移除元数据，ICC 颜色配置文件和 EXIF 方向信息始终保留
+    ///
+    /// 返回 `None` 表示无需修改（策略为 keep、格式不支持或没有需要移除的内容）。
+    pub fn strip(
+        data: &[u8],
+        mime_type: &str,
+        policy: MetadataPolicy,
+    ) -> Result<Option<Vec<u8>>, AppError> {
+        let chunks = Self::strip_chunks(Bytes::copy_from_slice(data), mime_type, policy)?;
+        Ok(chunks.map(|chunks| chunks.concat()))
+    }
+
+    /// 按策略移除元数据并把结果按块写入 `output`，不在内存中拼接完整的副本
+    ///
+    /// 返回 false 表示无需修改，此时不写入任何内容。
+    pub fn strip_to(
+        data: Bytes,
+        mime_type: &str,
+        policy: MetadataPolicy,
+        output: &mut dyn Write,
+    ) -> Result<bool, AppError> {
+        let Some(chunks) = Self::strip_chunks(data, mime_type, policy)? else {
+            return Ok(false);
+        };
+        for chunk in chunks {
+            output.write_all(&chunk)?;
+        }
+        Ok(true)
+    }
+
+    /// 移除元数据后的内容块，未改动的部分直接引用原数据
+    fn strip_chunks(
+        data: Bytes,
+        mime_type: &str,
+        policy: MetadataPolicy,
+    ) -> Result<Option<Vec<Bytes>>, AppError> {
+        if policy == MetadataPolicy::Keep || !Self::supports(mime_type) {
+            return Ok(None);
+        }
+
+        let chunks = match mime_type {
+            "image/jpeg" => Self::strip_jpeg(data.clone(), policy),
+            "image/png" => Self::strip_png(data.clone(), policy),
+            _ => Self::strip_webp(data.clone(), policy),
+        }
+        .map_err(|e| {
+            warn!("解析图片元数据失败: {} - {}", mime_type, e);
+            AppError::BadRequest("无法解析图片元数据".to_string())
+        })?;
+
+        if same_content(&chunks, &data) {
+            return Ok(None);
+        }
+
+        info!(
+            "已移除图片元数据: {:?}, {}字节 -> {}字节",
+            policy,
+            data.len(),
+            chunks.iter().map(Bytes::len).sum::<usize>()
+        );
+        Ok(Some(chunks))
+    }
+
+    /// 读取图片中的 RGB ICC 配置文件
+    ///
+    /// 解码后的像素统一为 RGB，其他颜色空间（如 CMYK）的配置文件不再适用。
+    pub fn rgb_icc_profile(data: &[u8]) -> Option<Vec<u8>> {
+        let mut decoder = ImageReader::new(Cursor::new(data))
+            .with_guessed_format()
+            .ok()?
+            .into_decoder()
+            .ok()?;
+        let profile = decoder.icc_profile().ok().flatten()?;
+        (profile.get(16..20) == Some(b"RGB ".as_slice())).then_some(profile)
+    }
+
+    /// 将 ICC 配置文件写入已编码的图片，不支持的格式原样返回
+    pub fn embed_icc_profile(data: Vec<u8>, format: ImageFormat, profile: Vec<u8>) -> Vec<u8> {
+        let input = Bytes::from(data);
+        let profile = Bytes::from(profile);
+
+        let result = match format {
+            ImageFormat::Jpeg => Jpeg::from_bytes(input.clone()).map(|mut jpeg| {
+                set_jpeg_icc(&mut jpeg, profile);
+                jpeg.encoder().bytes()
+            }),
+            ImageFormat::Png => Png::from_bytes(input.clone()).map(|mut png| {
+                png.set_icc_profile(Some(profile));
+                png.encoder().bytes()
+            }),
+            ImageFormat::WebP => WebP::from_bytes(input.clone()).map(|mut webp| {
+                set_webp_icc(&mut webp, profile);
+                webp.encoder().bytes()
+            }),
+            _ => return input.to_vec(),
+        };
+
+        match result {
+            Ok(output) => output.to_vec(),
+            Err(e) => {
+                warn!("写入ICC配置文件失败: {:?} - {}", format, e);
+                input.to_vec()
+            }
+        }
+    }
+
+    fn strip_jpeg(data: Bytes, policy: MetadataPolicy) -> img_parts::Result<Vec<Bytes>> {
+        let mut jpeg = Jpeg::from_bytes(data)?;
+        let drop_xmp = policy == MetadataPolicy::Strip
+            || jpeg
+                .segments()
+                .iter()
+                .any(|segment| is_jpeg_xmp(segment) && contains_gps(segment.contents()));
+
+        let segments = std::mem::take(jpeg.segments_mut());
+        for segment in segments {
+            let marker = segment.marker();
+            let contents = segment.contents().clone();
+
+            if marker == markers::APP1 && contents.starts_with(EXIF_PREFIX) {
+                if let Some(exif) = rewrite_exif(&contents[EXIF_PREFIX.len()..], policy) {
+                    jpeg.segments_mut().push(JpegSegment::new_with_contents(
+                        markers::APP1,
+                        prefixed(EXIF_PREFIX, &exif),
+                    ));
+                }
+            } else if is_jpeg_xmp(&segment) {
+                if !drop_xmp {
+                    jpeg.segments_mut().push(segment);
+                }
+            } else if marker == markers::APP2 && !contents.starts_with(ICC_PREFIX) {
+                // 多图格式（MPF）索引，附属图片位于 EOI 之后，会一并移除
+            } else if marker == markers::SOS {
+                jpeg.segments_mut().push(truncate_after_eoi(segment));
+            } else if policy == MetadataPolicy::Strip && is_jpeg_metadata_marker(marker) {
+                // APP0（JFIF）和 APP14（Adobe 颜色变换）影响解码，予以保留
+            } else {
+                jpeg.segments_mut().push(segment);
+            }
+        }
+
+        Ok(jpeg.encoder().collect())
+    }
+
+    fn strip_png(data: Bytes, policy: MetadataPolicy) -> img_parts::Result<Vec<Bytes>> {
+        let mut png = Png::from_bytes(data)?;
+        let drop_xmp = policy == MetadataPolicy::Strip
+            || png.chunks().iter().any(|chunk| {
+                PNG_TEXT_CHUNKS.contains(&chunk.kind())
+                    && png_text_keyword(chunk) == PNG_XMP_KEYWORD
+                    && png_text_may_contain_gps(chunk)
+            });
+
+        let chunks = std::mem::take(png.chunks_mut());
+        for chunk in chunks {
+            let kind = chunk.kind();
+
+            if kind == PNG_EXIF {
+                if let Some(exif) = rewrite_exif(chunk.contents(), policy) {
+                    png.chunks_mut()
+                        .push(PngChunk::new(PNG_EXIF, Bytes::from(exif)));
+                }
+                continue;
+            }
+
+            let keep = if PNG_TEXT_CHUNKS.contains(&kind) {
+                let keyword = png_text_keyword(&chunk);
+                match policy {
+                    MetadataPolicy::StripGps if keyword == PNG_XMP_KEYWORD => !drop_xmp,
+                    // 十六进制文本形式的原始 EXIF 无法逐项改写，整体移除
+                    MetadataPolicy::StripGps => !keyword.starts_with(PNG_RAW_PROFILE_KEYWORD),
+                    _ => false,
+                }
+            } else {
+                !(policy == MetadataPolicy::Strip && kind == PNG_TIME)
+            };
+
+            if keep {
+                png.chunks_mut().push(chunk);
+            }
+        }
+
+        Ok(png.encoder().collect())
+    }
+
+    fn strip_webp(data: Bytes, policy: MetadataPolicy) -> img_parts::Result<Vec<Bytes>> {
+        let mut webp = WebP::from_bytes(data)?;
+
+        let xmp_has_gps = webp
+            .chunk_by_id(CHUNK_XMP)
+            .and_then(|chunk| chunk.content().data())
+            .is_some_and(|data| contains_gps(data));
+        if policy == MetadataPolicy::Strip || xmp_has_gps {
+            webp.remove_chunks_by_id(CHUNK_XMP);
+        }
+
+        let exif = webp
+            .chunk_by_id(CHUNK_EXIF)
+            .and_then(|chunk| chunk.content().data())
+            .cloned();
+        if let Some(exif) = exif {
+            // 规范要求 EXIF 块直接保存 TIFF 数据，但部分软件会写入 "Exif\0\0" 前缀
+            let prefix = if exif.starts_with(EXIF_PREFIX) {
+                EXIF_PREFIX
+            } else {
+                &[]
+            };
+            let rewritten = rewrite_exif(&exif[prefix.len()..], policy);
+
+            webp.remove_chunks_by_id(CHUNK_EXIF);
+            if let Some(rewritten) = rewritten {
+                webp.chunks_mut().push(RiffChunk::new(
+                    CHUNK_EXIF,
+                    RiffContent::Data(prefixed(prefix, &rewritten)),
+                ));
+            }
+        }
+
+        update_webp_flags(&mut webp);
+        Ok(webp.encoder().collect())
+    }
+}
+
+/// 内容块依次拼接后是否与原数据完全相同
+fn same_content(chunks: &[Bytes], data: &[u8]) -> bool {
+    let mut rest = data;
+    for chunk in chunks {
+        match rest.strip_prefix(chunk.as_ref()) {
+            Some(remaining) => rest = remaining,
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+/// 检查数据中是否出现 GPS 字样（用于判断 XMP 是否包含定位信息）
+fn contains_gps(data: &[u8]) -> bool {
+    data.windows(3).any(|window| window == b"GPS")
+}
+
+fn prefixed(prefix: &[u8], data: &[u8]) -> Bytes {
+    let mut output = BytesMut::with_capacity(prefix.len() + data.len());
+    output.put_slice(prefix);
+    output.put_slice(data);
+    output.freeze()
+}
+
+/// 按策略改写 TIFF 格式的 EXIF 数据，返回 `None` 表示整体移除
+fn rewrite_exif(tiff: &[u8], policy: MetadataPolicy) -> Option<Vec<u8>> {
+    match policy {
+        MetadataPolicy::StripGps => remove_gps(tiff),
+        MetadataPolicy::Strip => orientation_only(tiff),
+        MetadataPolicy::Keep => Some(tiff.to_vec()),
+    }
+}
+
+/// 仅保留 EXIF 中的方向信息
+///
+/// 像素数据未重新编码，丢失 Orientation 会导致手机照片显示方向错误。
+/// 没有方向信息或无需旋转时返回 `None`。
+fn orientation_only(tiff: &[u8]) -> Option<Vec<u8>> {
+    let exif = exif::Reader::new().read_raw(tiff.to_vec()).ok()?;
+    let field = exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?;
+    if field.value.get_uint(0).is_none_or(|value| value == 1) {
+        return None;
+    }
+
+    let mut writer = ExifWriter::new();
+    writer.push_field(field);
+    let mut output = Cursor::new(Vec::new());
+    match writer.write(&mut output, exif.little_endian()) {
+        Ok(()) => Some(output.into_inner()),
+        Err(e) => {
+            warn!("重建EXIF方向信息失败，将整体移除: {}", e);
+            None
+        }
+    }
+}
+
+/// 从 TIFF 格式的 EXIF 数据中移除 GPS IFD
+///
+/// 没有 GPS 信息时原样返回；无法解析或重建时返回 `None`，由调用方整体移除 EXIF。
+fn remove_gps(tiff: &[u8]) -> Option<Vec<u8>> {
+    let exif = exif::Reader::new().read_raw(tiff.to_vec()).ok()?;
+    let has_gps = exif.fields().any(|field| {
+        field.tag.context() == exif::Context::Gps || field.tag == exif::Tag::GPSInfoIFDPointer
+    });
+    if !has_gps {
+        return Some(tiff.to_vec());
+    }
+
+    // 缩略图数据需要单独交给写入器
+    let thumbnail = exif
+        .get_field(exif::Tag::JPEGInterchangeFormat, exif::In::THUMBNAIL)
+        .and_then(|field| field.value.get_uint(0))
+        .zip(
+            exif.get_field(exif::Tag::JPEGInterchangeFormatLength, exif::In::THUMBNAIL)
+                .and_then(|field| field.value.get_uint(0)),
+        )
+        .and_then(|(offset, length)| {
+            exif.buf()
+                .get(offset as usize..offset as usize + length as usize)
+        });
+
+    let mut writer = ExifWriter::new();
+    for field in exif
+        .fields()
+        .filter(|field| field.tag.context() != exif::Context::Gps)
+    {
+        writer.push_field(field);
+    }
+    if let Some(thumbnail) = thumbnail {
+        writer.set_jpeg(thumbnail, exif::In::THUMBNAIL);
+    }
+
+    let mut output = Cursor::new(Vec::new());
+    match writer.write(&mut output, exif.little_endian()) {
+        Ok(()) => Some(output.into_inner()),
+        Err(e) => {
+            warn!("重建EXIF失败，将整体移除: {}", e);
+            None
+        }
+    }
+}
+
+fn is_jpeg_xmp(segment: &JpegSegment) -> bool {
+    segment.marker() == markers::APP1
+        && (segment.contents().starts_with(XMP_PREFIX)
+            || segment.contents().starts_with(XMP_EXTENSION_PREFIX))
+}
+
+/// 移除全部元数据时需要删除的 JPEG 段
+fn is_jpeg_metadata_marker(marker: u8) -> bool {
+    matches!(
+        marker,
+        markers::APP1 | markers::APP3..=markers::APP13 | markers::APP15 | markers::COM
+    )
+}
+
+/// 截断 EOI 之后的附加数据（多图格式的附属图片、厂商私有数据等）
+fn truncate_after_eoi(segment: JpegSegment) -> JpegSegment {
+    let marker = segment.marker();
+    let contents = segment.contents().clone();
+    let header_len = 4 + contents.len();
+    let encoded = segment.encoder().bytes();
+    let entropy = encoded.slice(header_len..);
+
+    match jpeg_eoi_end(&entropy) {
+        Some(end) if end < entropy.len() => {
+            JpegSegment::new_with_entropy(marker, contents, entropy.slice(..end))
+        }
+        _ => JpegSegment::new_with_entropy(marker, contents, entropy),
+    }
+}
+
+/// 在扫描数据中查找 EOI 标记，返回其后的位置
+fn jpeg_eoi_end(data: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while i + 1 < data.len() {
+        if data[i] != markers::P {
+            i += 1;
+            continue;
+        }
+
+        match data[i + 1] {
+            // 字节填充、填充字节和复位标记属于扫描数据
+            markers::Z | markers::P | markers::RST0..=markers::RST7 => i += 1,
+            markers::EOI => return Some(i + 2),
+            _ => {
+                // 渐进式 JPEG 多次扫描之间的带长度段（DHT、SOS 等）
+                let length = u16::from_be_bytes([*data.get(i + 2)?, *data.get(i + 3)?]);
+                i += 2 + length as usize;
+            }
+        }
+    }
+    None
+}
+
+fn png_text_keyword(chunk: &PngChunk) -> &[u8] {
+    let contents = chunk.contents();
+    let end = contents
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(contents.len());
+    &contents[..end]
+}
+
+/// PNG 文本块是否可能包含 GPS 信息（压缩的文本无法检查，按包含处理）
+fn png_text_may_contain_gps(chunk: &PngChunk) -> bool {
+    let contents = chunk.contents();
+    let compressed = match &chunk.kind() {
+        b"zTXt" => true,
+        b"iTXt" => contents
+            .get(png_text_keyword(chunk).len() + 1)
+            .is_some_and(|&flag| flag != 0),
+        _ => false,
+    };
+    compressed || contains_gps(contents)
+}
+
+/// 写入 JPEG ICC 配置文件，按 APP2 段大小分片
+fn set_jpeg_icc(jpeg: &mut Jpeg, profile: Bytes) {
+    let count = profile.len().div_ceil(ICC_SEGMENT_MAX_SIZE);
+    let Ok(count) = u8::try_from(count) else {
+        warn!("ICC配置文件过大，跳过写入: {}字节", profile.len());
+        return;
+    };
+
+    jpeg.segments_mut().retain(|segment| {
+        !(segment.marker() == markers::APP2 && segment.contents().starts_with(ICC_PREFIX))
+    });
+
+    // 放在 APP0 / APP1 等应用段之后
+    let position = jpeg
+        .segments()
+        .iter()
+        .position(|segment| !(markers::APP0..=markers::APP15).contains(&segment.marker()))
+        .unwrap_or(jpeg.segments().len());
+
+    for (index, chunk) in profile.chunks(ICC_SEGMENT_MAX_SIZE).enumerate() {
+        let mut contents = BytesMut::with_capacity(ICC_PREFIX.len() + 2 + chunk.len());
+        contents.put_slice(ICC_PREFIX);
+        contents.put_u8(index as u8 + 1);
+        contents.put_u8(count);
+        contents.put_slice(chunk);
+
+        jpeg.segments_mut().insert(
+            position + index,
+            JpegSegment::new_with_contents(markers::APP2, contents.freeze()),
+        );
+    }
+}
+
+/// 写入 WebP ICC 配置文件，简单格式会先转换为带 VP8X 头的扩展格式
+fn set_webp_icc(webp: &mut WebP, profile: Bytes) {
+    webp.remove_chunks_by_id(CHUNK_ICCP);
+
+    if !webp.has_chunk(CHUNK_VP8X) {
+        let Some((width, height)) = webp.dimensions() else {
+            return;
+        };
+        let mut header = BytesMut::with_capacity(10);
+        header.put_bytes(0, 4);
+        header.put_slice(&(width - 1).to_le_bytes()[..3]);
+        header.put_slice(&(height - 1).to_le_bytes()[..3]);
+        webp.chunks_mut().insert(
+            0,
+            RiffChunk::new(CHUNK_VP8X, RiffContent::Data(header.freeze())),
+        );
+    }
+
+    let position = webp
+        .chunks()
+        .iter()
+        .position(|chunk| chunk.id() == CHUNK_VP8X)
+        .map_or(0, |position| position + 1);
+    webp.chunks_mut().insert(
+        position,
+        RiffChunk::new(CHUNK_ICCP, RiffContent::Data(profile)),
+    );
+
+    update_webp_flags(webp);
+}
+
+/// 根据实际存在的块更新 VP8X 中的 ICC / 透明 / EXIF / XMP 标志位
+fn update_webp_flags(webp: &mut WebP) {
+    let mut flags = 0;
+    if webp.has_chunk(CHUNK_ICCP) {
+        flags |= VP8X_ICC_FLAG;
+    }
+    if webp_has_alpha(webp) {
+        flags |= VP8X_ALPHA_FLAG;
+    }
+    if webp.has_chunk(CHUNK_EXIF) {
+        flags |= VP8X_EXIF_FLAG;
+    }
+    if webp.has_chunk(CHUNK_XMP) {
+        flags |= VP8X_XMP_FLAG;
+    }
+    let mask = VP8X_ICC_FLAG | VP8X_ALPHA_FLAG | VP8X_EXIF_FLAG | VP8X_XMP_FLAG;
+
+    let Some(vp8x) = webp
+        .chunks_mut()
+        .iter_mut()
+        .find(|chunk| chunk.id() == CHUNK_VP8X)
+    else {
+        return;
+    };
+    let Some(data) = vp8x.content().data() else {
+        return;
+    };
+    let mut header = BytesMut::from(data.as_ref());
+    let Some(first) = header.first_mut() else {
+        return;
+    };
+    // 动画帧中的透明信息由帧本身描述，保留原有透明标志
+    *first = (*first & !mask) | flags | (*first & VP8X_ALPHA_FLAG);
+    *vp8x = RiffChunk::new(CHUNK_VP8X, RiffContent::Data(header.freeze()));
+}
+
+/// 检查 WebP 是否带透明通道（有损格式的 ALPH 块或无损格式头中的标志位）
+fn webp_has_alpha(webp: &WebP) -> bool {
+    if webp.has_chunk(CHUNK_ALPH) {
+        return true;
+    }
+    webp.chunk_by_id(CHUNK_VP8L)
+        .and_then(|chunk| chunk.content().data())
+        .and_then(|data| data.get(1..5))
+        .is_some_and(|header| {
+            let bits = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+            (bits >> 28) & 1 == 1
+        })
+}
@@ -0,0 +1,123 @@
+use image::{ColorType, DynamicImage, GenericImageView};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::image_format_utils::ImageFormatUtils;
+
+/// 调色板中的最大颜色数
+const MAX_PALETTE_COLORS: usize = 5;
+
+/// 统计颜色时把图片缩小到的边长
+const PALETTE_SAMPLE_SIZE: u32 = 64;
+
+/// 调色板中两种颜色之间的最小距离（RGB 欧氏距离的平方）
+const MIN_PALETTE_DISTANCE: u32 = 48 * 48;
+
+/// 上传时提取并保存的图片属性
+///
+/// 客户端无需下载和解码原图即可排版网格、显示占位色。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageProperties {
+    /// 宽度（已按 EXIF 方向旋转）
+    pub width: u32,
+    /// 高度（已按 EXIF 方向旋转）
+    pub height: u32,
+    /// 帧数（静态图片为1）
+    pub frame_count: u32,
+    /// 是否包含透明像素
+    pub has_alpha: bool,
+    /// 颜色类型（如 rgb8、rgba8、l16）
+    pub color_type: String,
+    /// 按占比从高到低排列的主要颜色（#rrggbb），第一个为主色调，全透明的图片为空
+    pub palette: Vec<String>,
+}
+
+impl ImageProperties {
+    /// 从已解码的图片（动图为第一帧）中提取属性，`data` 用于统计动图帧数
+    pub fn analyze(img: &DynamicImage, data: &[u8], mime_type: &str) -> Self {
+        let (width, height) = img.dimensions();
+        Self {
+            width,
+            height,
+            frame_count: ImageFormatUtils::frame_count(mime_type, data),
+            has_alpha: Self::has_transparency(img),
+            color_type: Self::color_type_name(img.color()).to_string(),
+            palette: Self::palette(img),
+        }
+    }
+
+    /// 颜色类型的名称
+    pub fn color_type_name(color: ColorType) -> &'static str {
+        match color {
+            ColorType::L8 => "l8",
+            ColorType::La8 => "la8",
+            ColorType::Rgb8 => "rgb8",
+            ColorType::Rgba8 => "rgba8",
+            ColorType::L16 => "l16",
+            ColorType::La16 => "la16",
+            ColorType::Rgb16 => "rgb16",
+            ColorType::Rgba16 => "rgba16",
+            ColorType::Rgb32F => "rgb32f",
+            ColorType::Rgba32F => "rgba32f",
+            _ => "unknown",
+        }
+    }
+
+    /// 带 alpha 通道且至少有一个像素不完全不透明
+    fn has_transparency(img: &DynamicImage) -> bool {
+        img.color().has_alpha() && img.pixels().any(|(_, _, pixel)| pixel[3] < u8::MAX)
+    }
+
+    /// 提取主要颜色
+    ///
+    /// 缩小后按每通道高4位分桶统计不透明像素，按像素数从多到少取各桶的平均色，
+    /// 跳过与已选颜色过于接近的颜色。
+    fn palette(img: &DynamicImage) -> Vec<String> {
+        let sample = img
+            .thumbnail(PALETTE_SAMPLE_SIZE, PALETTE_SAMPLE_SIZE)
+            .to_rgba8();
+
+        let mut buckets: HashMap<u16, (u32, [u32; 3])> = HashMap::new();
+        for pixel in sample.pixels() {
+            let [r, g, b, a] = pixel.0;
+            if a < 128 {
+                continue;
+            }
+            let key = (u16::from(r >> 4) << 8) | (u16::from(g >> 4) << 4) | u16::from(b >> 4);
+            let (count, sum) = buckets.entry(key).or_default();
+            *count += 1;
+            sum[0] += u32::from(r);
+            sum[1] += u32::from(g);
+            sum[2] += u32::from(b);
+        }
+
+        let mut buckets: Vec<(u32, [u32; 3])> = buckets.into_values().collect();
+        buckets.sort_by_key(|(count, _)| Reverse(*count));
+
+        let mut colors: Vec<[u8; 3]> = Vec::new();
+        for (count, sum) in buckets {
+            let color = sum.map(|channel| (channel / count) as u8);
+            if colors
+                .iter()
+                .all(|chosen| Self::distance(chosen, &color) >= MIN_PALETTE_DISTANCE)
+            {
+                colors.push(color);
+                if colors.len() == MAX_PALETTE_COLORS {
+                    break;
+                }
+            }
+        }
+
+        colors
+            .into_iter()
+            .map(|[r, g, b]| format!("#{:02x}{:02x}{:02x}", r, g, b))
+            .collect()
+    }
+
+    fn distance(a: &[u8; 3], b: &[u8; 3]) -> u32 {
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| u32::from(x.abs_diff(*y)).pow(2))
+            .sum()
+    }
+}
@@ -1,73 +1,180 @@
+use bytes::Bytes;
 use chrono::Utc;
+use sea_orm::DatabaseConnection;
 use std::io::ErrorKind;
+use std::sync::Arc;
 
-use sha2::{Digest, Sha256};
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
-use tracing::warn;
+use tracing::{info, warn};
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, MetadataPolicy, NearDuplicatePolicy};
 use crate::database::DatabasePool;
-use crate::models::{ApiTokenInfo, ImageInfo, ImageQuery, ImageStats, TokenRole};
-use crate::repositories::{ImageRepository, ImageRepositoryTrait};
-use crate::utils::{
-    detect_file_type, ensure_image_dir, ensure_upload_dir, get_extension_from_mime, get_file_path,
-    get_upload_dir, validate_file_size, AppError,
+use crate::models::{
+    ApiTokenInfo, ImageInfo, ImageQuery, ImageStats, SimilarImage, TokenRole, UploadOptions,
+};
+use crate::repositories::{AlbumRepository, BlobRepository, ImageRepository, ImageRepositoryTrait};
+use crate::storage::{sharded_key, StagedUpload, StorageBackend};
+use crate::utils::{detect_file_type, get_extension_from_mime, validate_file_size, AppError};
+use super::{
+    blurhash::BlurHash, cache_service::CacheService, image_metadata::ImageMetadata,
+    image_properties::ImageProperties, perceptual_hash::PerceptualHash, static_image_transform::StaticImageTransform,
+    tag_service::TagService, token_service::TokenService,
 };
-use super::{cache_service::CacheService, token_service::TokenService};
 
 /// 图片服务结构体
 pub struct ImageService;
 
 impl ImageService {
-    /// 计算文件哈希值
-    fn calculate_file_hash(data: &[u8], owner_token_id: Option<i32>) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(data);
-        if let Some(owner_id) = owner_token_id {
-            hasher.update(owner_id.to_le_bytes());
-        }
-        format!("{:x}", hasher.finalize())
-    }
-
-    /// 保存上传的图片文件
+    /// 保存上传的图片文件（已暂存到临时文件）
     pub async fn save_image(
         pool: &DatabasePool,
-        data: &[u8],
+        storage: &dyn StorageBackend,
+        staged: StagedUpload,
         original_filename: Option<String>,
         owner: &ApiTokenInfo,
+        options: &UploadOptions,
     ) -> Result<ImageInfo, AppError> {
+        let (image_info, _) =
+            Self::store_image(pool, storage, staged, original_filename, owner, options, true)
+                .await?;
+        Ok(image_info)
+    }
+
+    /// 批量保存图片
+    ///
+    /// 整批文件的大小和数量先一次性从配额中预留，超出配额时整批拒绝；之后逐个保存，
+    /// 单个文件失败不影响其他文件，未实际占用的配额（失败、重复文件等）最后统一退还。
+    pub async fn save_images(
+        pool: &DatabasePool,
+        storage: &dyn StorageBackend,
+        files: Vec<(StagedUpload, Option<String>)>,
+        owner: &ApiTokenInfo,
+        options: &UploadOptions,
+    ) -> Result<Vec<Result<ImageInfo, AppError>>, AppError> {
+        let reserved: u64 = files.iter().map(|(staged, _)| staged.size()).sum();
+        let token_service = TokenService::new(pool.get_connection());
+        let reserved_count = files.len() as i64;
+        token_service
+            .reserve_storage(owner.id, reserved as i64, reserved_count)
+            .await?;
+
+        let mut used = 0;
+        let mut created = 0;
+        let mut results = Vec::with_capacity(files.len());
+        for (staged, original_filename) in files {
+            let result =
+                Self::store_image(pool, storage, staged, original_filename, owner, options, false)
+                    .await;
+            results.push(result.map(|(image_info, charged)| {
+                if charged > 0 {
+                    used += charged;
+                    created += 1;
+                }
+                image_info
+            }));
+        }
+
+        if let Err(e) = token_service
+            .release_storage(
+                owner.id,
+                reserved.saturating_sub(used) as i64,
+                reserved_count - created,
+            )
+            .await
+        {
+            warn!("退还批量上传未使用的配额失败: {}", e);
+        }
+
+        Ok(results)
+    }
+
+    /// 保存单个图片，返回图片信息和实际占用的配额（已存在的文件为0）
+    ///
+    /// `reserve` 为 false 时调用方已经预留了配额。
+    async fn store_image(
+        pool: &DatabasePool,
+        storage: &dyn StorageBackend,
+        mut staged: StagedUpload,
+        original_filename: Option<String>,
+        owner: &ApiTokenInfo,
+        options: &UploadOptions,
+        reserve: bool,
+    ) -> Result<(ImageInfo, u64), AppError> {
         // 验证文件是否为空
-        if data.is_empty() {
+        if staged.size() == 0 {
             return Err(AppError::InvalidFile);
         }
 
         // 验证文件大小
-        validate_file_size(data.len() as u64)?;
+        validate_file_size(staged.size())?;
 
         // 基于文件内容检测真实的MIME类型（安全）
-        let mime_type = detect_file_type(data)?;
+        let mime_type = detect_file_type(staged.head())?;
+
+        // 按配置移除元数据，需在计算哈希之前完成
+        let metadata_policy = AppConfig::get().storage.metadata_policy;
+        if metadata_policy != MetadataPolicy::Keep && ImageMetadata::supports(&mime_type) {
+            // 改写结果直接写入新的暂存文件，不在内存中同时保留改写前后的两份内容
+            let strip_mime = mime_type.clone();
+            staged = staged
+                .rewrite(move |data, output| {
+                    ImageMetadata::strip_to(Bytes::from(data), &strip_mime, metadata_policy, output)
+                })
+                .await?;
+        }
 
+        // 文件哈希混入所有者ID，不同用户上传相同文件时互不影响
         let owner_token_id = Some(owner.id);
-        let file_hash = Self::calculate_file_hash(data, owner_token_id);
+        let file_hash = staged.hash_with(&owner.id.to_le_bytes());
 
         // 检查是否已存在相同文件
         let connection = pool.get_connection();
         let image_repo = ImageRepository::new(connection.clone());
-        if let Some(existing_image) = image_repo.find_by_hash(&file_hash).await? {
-            return Ok(existing_image);
+        if let Some(mut existing_image) = image_repo.find_by_hash(&file_hash).await? {
+            // 回收站中的相同图片直接恢复，配额在彻底删除前一直占用，无需重新预留
+            if existing_image.deleted_at.take().is_some() {
+                image_repo.update_deleted_at(&file_hash, None).await?;
+                info!("重新上传回收站中的图片，已恢复: {}", file_hash);
+            }
+            return Ok((existing_image, 0));
         }
 
-        // 确保基础上传目录存在
-        ensure_upload_dir().await?;
+        let (phash, properties, blurhash) = Self::analyze_staged(&staged, &mime_type).await?;
+
+        // 按配置处理与已有图片视觉相似的上传
+        let storage_config = &AppConfig::get().storage;
+        if let Some(phash) = phash {
+            let policy = options
+                .near_duplicate
+                .unwrap_or(storage_config.near_duplicate_policy);
+            if policy != NearDuplicatePolicy::Allow {
+                let threshold = storage_config.near_duplicate_threshold;
+                let similar = Self::find_similar(pool, owner_token_id, phash, threshold).await?;
+                if let Some(nearest) = similar.into_iter().next() {
+                    if policy == NearDuplicatePolicy::Reject {
+                        return Err(AppError::BadRequest(format!(
+                            "已存在相似图片: {}",
+                            nearest.image.hash
+                        )));
+                    }
+                    info!(
+                        "上传的图片与已有图片相似，直接返回已有图片: {} (距离 {})",
+                        nearest.image.hash, nearest.distance
+                    );
+                    return Ok((nearest.image, 0));
+                }
+            }
+        }
 
         // 根据真实MIME类型生成文件扩展名
         let extension = get_extension_from_mime(&mime_type)?;
 
+        // 文件内容按不混入所有者的哈希存储，所有用户共享同一份文件
+        let blob_hash = staged.hash_with(&[]);
+
         // 创建图片信息
         let image_info = ImageInfo {
             hash: file_hash.clone(),
-            size: data.len() as u64,
+            size: staged.size(),
             mime_type,
             created_at: Utc::now(),
             last_accessed: None,
@@ -75,40 +182,203 @@ impl ImageService {
             access_count: 0,
             original_filename,
             owner_token_id,
+            is_private: options.private,
+            phash: phash.map(PerceptualHash::to_hex),
+            blob_hash: Some(blob_hash.clone()),
+            tags: Vec::new(),
+            width: properties.as_ref().map(|p| p.width),
+            height: properties.as_ref().map(|p| p.height),
+            frame_count: properties.as_ref().map(|p| p.frame_count),
+            has_alpha: properties.as_ref().map(|p| p.has_alpha),
+            color_type: properties.as_ref().map(|p| p.color_type.clone()),
+            dominant_color: properties.as_ref().and_then(|p| p.palette.first().cloned()),
+            palette: properties.map(|p| p.palette).unwrap_or_default(),
+            blurhash,
+            deleted_at: None,
         };
 
-        let relative_path = format!(
-            "{}/{}/{}",
-            &file_hash[0..2],
-            &file_hash[2..4],
-            image_info.stored_name()
-        );
+        let storage_key = image_info.storage_key();
 
-        let reserve_amount = data.len() as i64;
+        let reserve_amount = staged.size() as i64;
         let token_service = TokenService::new(connection.clone());
-        token_service.reserve_storage(owner.id, reserve_amount).await?;
+        if reserve {
+            token_service.reserve_storage(owner.id, reserve_amount, 1).await?;
+        }
 
         let result = async {
-            ensure_image_dir(std::path::Path::new(&relative_path)).await?;
-            let file_path = get_file_path(std::path::Path::new(&relative_path));
+            let blob_repo = BlobRepository::new(connection.clone());
+            let ref_count = blob_repo
+                .acquire(&blob_hash, staged.size(), &image_info.mime_type, &image_info.extension)
+                .await?;
+
+            // 已有其他图片引用该内容时无需再次写入，除非文件已丢失
+            if ref_count == 1 || !storage.exists(&storage_key).await? {
+                if let Err(e) = storage.put_file(&storage_key, staged.path()).await {
+                    let _ = Self::release_file(&connection, storage, &image_info).await;
+                    return Err(e);
+                }
+            } else {
+                info!("复用已有的文件内容: {} (引用 {} 次)", blob_hash, ref_count);
+            }
+
+            if let Err(e) = image_repo.insert(&image_info).await {
+                let _ = Self::release_file(&connection, storage, &image_info).await;
+                return Err(e);
+            }
+            Ok::<_, AppError>(image_info)
+        }
+        .await;
+
+        if reserve && result.is_err() {
+            let _ = token_service.release_storage(owner.id, reserve_amount, 1).await;
+        }
 
-            let mut file = File::create(&file_path).await?;
-            file.write_all(data).await?;
-            file.sync_all().await?;
+        result.map(|image_info| (image_info, reserve_amount as u64))
+    }
 
-            image_repo.insert(&image_info).await?;
-            Ok::<_, AppError>(image_info)
+    /// 在阻塞线程中分析暂存文件，文件内容只在分析期间读入内存
+    async fn analyze_staged(
+        staged: &StagedUpload,
+        mime_type: &str,
+    ) -> Result<(Option<u64>, Option<ImageProperties>, Option<String>), AppError> {
+        let path = staged.path().to_path_buf();
+        let mime_type = mime_type.to_string();
+        tokio::task::spawn_blocking(move || {
+            std::fs::read(&path).map(|data| Self::analyze_image(&data, &mime_type))
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("分析图片失败: {}", e)))?
+        .map_err(AppError::from)
+    }
+
+    /// 解码一次，同时提取感知哈希、图片属性和占位图；无法解码的图片（如 SVG）不提取
+    fn analyze_image(
+        data: &[u8],
+        mime_type: &str,
+    ) -> (Option<u64>, Option<ImageProperties>, Option<String>) {
+        match StaticImageTransform::load_image_with_color_info(data) {
+            Ok(img) => (
+                Some(PerceptualHash::from_image(&img)),
+                Some(ImageProperties::analyze(&img, data, mime_type)),
+                Some(BlurHash::encode(&img)),
+            ),
+            Err(_) => (None, None, None),
+        }
+    }
+
+    /// 为存储中已有但没有图片记录的文件创建记录（存储校验修复孤立文件时使用）
+    ///
+    /// `staged` 为从存储中读出的文件，其 SHA-256 必须与存储键中的文件名一致；文件保持原样，
+    /// 不移除元数据，也不做相似图片检查。新记录为私有图片，文件大小计入所有者的已用配额。
+    pub async fn adopt_file(
+        pool: &DatabasePool,
+        storage_key: &str,
+        staged: &StagedUpload,
+        owner: &ApiTokenInfo,
+    ) -> Result<ImageInfo, AppError> {
+        let blob_hash = staged.hash_with(&[]);
+        let mime_type = detect_file_type(staged.head())?;
+        let extension = get_extension_from_mime(&mime_type)?;
+        let expected_key = sharded_key(&blob_hash, &format!("{}.{}", blob_hash, extension));
+        if expected_key != storage_key {
+            return Err(AppError::BadRequest(format!(
+                "文件内容与存储键不一致: {}",
+                storage_key
+            )));
+        }
+
+        let file_hash = staged.hash_with(&owner.id.to_le_bytes());
+
+        let connection = pool.get_connection();
+        let image_repo = ImageRepository::new(connection.clone());
+        if let Some(existing_image) = image_repo.find_by_hash(&file_hash).await? {
+            return Ok(existing_image);
+        }
+
+        let (phash, properties, blurhash) = Self::analyze_staged(staged, &mime_type).await?;
+        let image_info = ImageInfo {
+            hash: file_hash,
+            size: staged.size(),
+            mime_type,
+            created_at: Utc::now(),
+            last_accessed: None,
+            extension,
+            access_count: 0,
+            original_filename: None,
+            owner_token_id: Some(owner.id),
+            is_private: true,
+            phash: phash.map(PerceptualHash::to_hex),
+            blob_hash: Some(blob_hash.clone()),
+            tags: Vec::new(),
+            width: properties.as_ref().map(|p| p.width),
+            height: properties.as_ref().map(|p| p.height),
+            frame_count: properties.as_ref().map(|p| p.frame_count),
+            has_alpha: properties.as_ref().map(|p| p.has_alpha),
+            color_type: properties.as_ref().map(|p| p.color_type.clone()),
+            dominant_color: properties.as_ref().and_then(|p| p.palette.first().cloned()),
+            palette: properties.map(|p| p.palette).unwrap_or_default(),
+            blurhash,
+            deleted_at: None,
+        };
+
+        let token_service = TokenService::new(connection.clone());
+        token_service.reserve_storage(owner.id, image_info.size as i64, 1).await?;
+
+        // 文件已经在存储中，只增加内容引用；失败时只回退引用，不能删除文件
+        let blob_repo = BlobRepository::new(connection);
+        let result = async {
+            blob_repo
+                .acquire(&blob_hash, image_info.size, &image_info.mime_type, &image_info.extension)
+                .await?;
+            if let Err(e) = image_repo.insert(&image_info).await {
+                let _ = blob_repo
+                    .release(&blob_hash, || std::future::ready(Ok(())))
+                    .await;
+                return Err(e);
+            }
+            Ok(())
         }
         .await;
 
-        if result.is_err() {
-            let _ = token_service.release_storage(owner.id, reserve_amount).await;
+        if let Err(e) = result {
+            let _ = token_service.release_storage(owner.id, image_info.size as i64, 1).await;
+            return Err(e);
         }
 
-        result
+        info!("已为孤立文件创建图片记录: {} -> {}", storage_key, image_info.hash);
+        Ok(image_info)
     }
 
-    /// 根据哈希值获取图片信息
+    /// 在所有者的图片中查找与感知哈希相似的图片，按距离从近到远排序
+    pub async fn find_similar(
+        pool: &DatabasePool,
+        owner_token_id: Option<i32>,
+        phash: u64,
+        max_distance: u32,
+    ) -> Result<Vec<SimilarImage>, AppError> {
+        let connection = pool.get_connection();
+        let image_repo = ImageRepository::new(connection);
+
+        let mut similar: Vec<SimilarImage> = image_repo
+            .find_with_phash(owner_token_id)
+            .await?
+            .into_iter()
+            .filter_map(|image| {
+                let other = image.phash.as_deref().and_then(PerceptualHash::parse)?;
+                let distance = PerceptualHash::distance(phash, other);
+                (distance <= max_distance).then_some(SimilarImage { distance, image })
+            })
+            .collect();
+        similar.sort_by(|a, b| {
+            a.distance
+                .cmp(&b.distance)
+                .then(b.image.created_at.cmp(&a.image.created_at))
+        });
+
+        Ok(similar)
+    }
+
+    /// 根据哈希值获取图片信息（不包含回收站中的图片）
     pub async fn get_image_info(
         pool: &DatabasePool,
         identifier: &str,
@@ -122,73 +392,101 @@ impl ImageService {
         let image_repo = ImageRepository::new(connection);
 
         // 从数据库查询
-        image_repo.find_by_hash(identifier).await
-    }
-
-    /// 读取图片文件内容
-    pub async fn read_image_file(
-        pool: &DatabasePool,
-        identifier: &str,
-    ) -> Result<Vec<u8>, AppError> {
-        // 获取图片信息
-        let image_info = Self::get_image_info(pool, identifier)
+        Ok(image_repo
+            .find_by_hash(identifier)
             .await?
-            .ok_or(AppError::FileNotFound)?;
+            .filter(|image| image.deleted_at.is_none()))
+    }
 
-        // 更新访问信息
+    /// 记录一次图片访问
+    pub async fn record_access(pool: &DatabasePool, identifier: &str) {
         let connection = pool.get_connection();
         let image_repo = ImageRepository::new(connection);
         let _ = image_repo.update_access(identifier).await;
+    }
 
-        // 构建文件路径
-        let stored_name = image_info.stored_name();
-        let relative_path = format!(
-            "{}/{}/{}",
-            &image_info.hash[0..2],
-            &image_info.hash[2..4],
-            stored_name
-        );
-        let file_path = get_upload_dir().join(&relative_path);
-
-        let data = tokio::fs::read(&file_path).await?;
-        Ok(data)
+    /// 修改图片可见性
+    pub async fn set_visibility(
+        pool: &DatabasePool,
+        identifier: &str,
+        is_private: bool,
+    ) -> Result<(), AppError> {
+        let connection = pool.get_connection();
+        let image_repo = ImageRepository::new(connection);
+        if !image_repo.update_visibility(identifier, is_private).await? {
+            return Err(AppError::FileNotFound);
+        }
+        Ok(())
     }
 
-    /// 删除图片文件
-    pub async fn delete_image(pool: &DatabasePool, identifier: &str) -> Result<(), AppError> {
+    /// 立即删除图片记录和文件（包括回收站中的图片）
+    pub async fn delete_image(
+        pool: &DatabasePool,
+        storage: &dyn StorageBackend,
+        identifier: &str,
+    ) -> Result<(), AppError> {
         // 获取图片信息
-        let image_info = Self::get_image_info(pool, identifier)
+        let connection = pool.get_connection();
+        let image_repo = ImageRepository::new(connection.clone());
+        let image_info = image_repo
+            .find_by_hash(identifier)
             .await?
             .ok_or(AppError::FileNotFound)?;
 
         // 从数据库删除记录
-        let connection = pool.get_connection();
-        let image_repo = ImageRepository::new(connection);
         image_repo.delete_by_hash(identifier).await?;
 
-        // 删除文件
-        let stored_name = image_info.stored_name();
-        let relative_path = format!(
-            "{}/{}/{}",
-            &image_info.hash[0..2],
-            &image_info.hash[2..4],
-            stored_name
-        );
-        let file_path = get_upload_dir().join(&relative_path);
-
-        tokio::fs::remove_file(&file_path).await?;
-
+        // 移出所有相册并删除标签
+        AlbumRepository::new(connection.clone())
+            .remove_image_from_all(identifier)
+            .await?;
+        TagService::new(connection.clone()).delete_tags(identifier).await?;
+
+        // 释放文件内容，没有其他图片引用时删除文件
+        Self::release_file(&connection, storage, &image_info).await?;
+
+        // 退还所有者的配额；所有者令牌可能已被删除，退还失败不影响删除结果
+        if let Some(owner_token_id) = image_info.owner_token_id {
+            if let Err(e) = TokenService::new(connection)
+                .release_storage(owner_token_id, image_info.size as i64, 1)
+                .await
+            {
+                warn!("退还图片 {} 占用的配额失败: {}", image_info.hash, e);
+            }
+        }
         Ok(())
     }
 
+    /// 释放图片对文件内容的引用，最后一个引用释放后删除存储后端中的文件
+    async fn release_file(
+        connection: &Arc<DatabaseConnection>,
+        storage: &dyn StorageBackend,
+        image_info: &ImageInfo,
+    ) -> Result<(), AppError> {
+        let storage_key = image_info.storage_key();
+        match &image_info.blob_hash {
+            Some(blob_hash) => {
+                let blob_repo = BlobRepository::new(connection.clone());
+                blob_repo
+                    .release(blob_hash, || storage.delete(&storage_key))
+                    .await?;
+                Ok(())
+            }
+            None => storage.delete(&storage_key).await,
+        }
+    }
+
     /// 查询图片列表
     pub async fn query_images(
         pool: &DatabasePool,
         query: &ImageQuery,
     ) -> Result<(Vec<ImageInfo>, u64), AppError> {
         let connection = pool.get_connection();
-        let image_repo = ImageRepository::new(connection);
-        let page_result = image_repo.find_by_query(query).await?;
+        let image_repo = ImageRepository::new(connection.clone());
+        let mut page_result = image_repo.find_by_query(query).await?;
+        TagService::new(connection)
+            .attach_tags(&mut page_result.items)
+            .await?;
         Ok((page_result.items, page_result.total))
     }
 
@@ -1,15 +1,20 @@
+use image::{DynamicImage, ImageFormat};
 use tracing::info;
 
-use super::{image_format_utils::ImageFormatUtils, static_image_transform::StaticImageTransform};
-use crate::models::ImageTransformParams;
+use super::{
+    animated_image_transform::AnimatedImageTransform, image_format_utils::ImageFormatUtils,
+    image_metadata::ImageMetadata, static_image_transform::StaticImageTransform,
+};
+use crate::config::MetadataPolicy;
+use crate::models::{Gravity, ImageTransformParams};
 use crate::utils::AppError;
 
 /// 图片转换服务 - 支持所有image库编解码器
 pub struct ImageTransformService;
 
 impl ImageTransformService {
-    /// 高级图片转换 - 使用专用编码器和优化参数
-    pub async fn transform_image(
+    /// 同步执行图片转换，解码、缩放和编码都在调用线程上完成
+    pub fn transform_image_blocking(
         image_data: &[u8],
         original_mime: &str,
         params: &ImageTransformParams,
@@ -21,44 +26,16 @@ impl ImageTransformService {
 
         info!("开始高级图片转换: {:?}", params);
 
-        // 检测是否为动图格式（需要实际解析数据）
-        let is_animated_format = ImageFormatUtils::is_animated_format(original_mime, image_data);
-
-        // 如果是动图格式且用户没有要求格式转换，直接返回原图
-        if is_animated_format && params.format.is_none() {
-            info!(
-                "检测到多帧动图且无格式转换要求，直接返回原图: {}",
-                original_mime
-            );
-            return Ok((image_data.to_vec(), original_mime.to_string()));
+        // 仅移除元数据时直接改写图片容器，不重新编码
+        if !params.needs_reencode() {
+            return Ok((
+                Self::strip_metadata(image_data, original_mime)?,
+                original_mime.to_string(),
+            ));
         }
 
-        // 如果是动图但用户明确要求转换格式，提取第一帧进行静图转换
-        if is_animated_format && params.format.is_some() {
-            info!(
-                "检测到多帧动图但用户要求格式转换，将提取第一帧: {} -> {:?}",
-                original_mime, params.format
-            );
-        }
-
-        // 加载静图（如果是动图但要求转换，提取第一帧）
-        let mut img = if original_mime == "image/gif" {
-            // GIF格式使用专门的第一帧提取函数，无论单帧还是多帧
-            info!("使用GIF第一帧提取器");
-            StaticImageTransform::load_gif_first_frame(image_data)?
-        } else if original_mime == "image/webp" && is_animated_format {
-            // 多帧WebP要求转换时，也提取第一帧（暂时使用通用加载器）
-            info!("多帧WebP转换，使用通用加载器提取第一帧");
-            StaticImageTransform::load_image_with_color_info(image_data)?
-        } else {
-            // 其他格式使用通用加载器
-            StaticImageTransform::load_image_with_color_info(image_data)?
-        };
-
-        // 调整尺寸（使用高质量重采样）
-        if params.width.is_some() || params.height.is_some() {
-            img = StaticImageTransform::resize_image_hq(img, params.width, params.height)?;
-        }
+        // 检测是否为动图格式（需要实际解析数据）
+        let is_animated_format = ImageFormatUtils::is_animated_format(original_mime, image_data);
 
         // 确定目标格式
         let target_format =
@@ -67,12 +44,6 @@ impl ImageTransformService {
             .target_mime_type()
             .unwrap_or_else(|| original_mime.to_string());
 
-        // 处理透明通道
-        if params.no_alpha || ImageFormatUtils::format_requires_no_alpha(&target_format) {
-            img =
-                StaticImageTransform::remove_alpha_channel_advanced(img, &params.background_color)?;
-        }
-
         // 验证格式编码能力和参数兼容性
         ImageFormatUtils::validate_format_compatibility(
             &target_format,
@@ -80,10 +51,65 @@ impl ImageTransformService {
             params.format.is_some(),
         )?;
 
+        // 动图输出为 GIF / WebP 时逐帧转换，保留时长和循环次数
+        if is_animated_format
+            && params.frame.is_none()
+            && matches!(target_format, ImageFormat::Gif | ImageFormat::WebP)
+        {
+            let encoded_data =
+                Self::transform_animation(image_data, original_mime, target_format, params)?;
+
+            info!(
+                "动图转换完成: {} -> {}, 原始大小: {}字节, 转换后: {}字节",
+                original_mime,
+                target_mime,
+                image_data.len(),
+                encoded_data.len()
+            );
+            return Ok((encoded_data, target_mime));
+        }
+
+        // 加载静图（指定帧时提取该帧，动图转为静图格式时提取第一帧）
+        let img = if let Some(index) = params.frame {
+            if is_animated_format {
+                AnimatedImageTransform::load_frame(image_data, original_mime, index)?
+            } else if index == 0 {
+                StaticImageTransform::load_image_with_color_info(image_data)?
+            } else {
+                return Err(AppError::BadRequest(format!(
+                    "帧索引超出范围: {}，静态图片只有1帧",
+                    index
+                )));
+            }
+        } else if original_mime == "image/gif" {
+            // GIF格式使用专门的第一帧提取函数，无论单帧还是多帧
+            info!("使用GIF第一帧提取器");
+            StaticImageTransform::load_gif_first_frame(image_data)?
+        } else {
+            if is_animated_format {
+                info!(
+                    "多帧动图转换为静图格式，提取第一帧: {} -> {:?}",
+                    original_mime, target_format
+                );
+            }
+            StaticImageTransform::load_image_with_color_info(image_data)?
+        };
+
+        let img = Self::apply_pixel_transforms(img, target_format, params)?;
+
+        // 重新编码不会保留 EXIF / XMP，但需要带上原图的 ICC 配置文件以保证颜色一致
+        let icc_profile = if img.color().has_color() {
+            ImageMetadata::rgb_icc_profile(image_data)
+        } else {
+            None
+        };
+
         // 使用专用编码器编码静图
-        let encoded_data =
-            StaticImageTransform::encode_with_specialized_encoder(img, target_format, params)
-                .await?;
+        let mut encoded_data =
+            StaticImageTransform::encode_with_specialized_encoder(img, target_format, params)?;
+        if let Some(profile) = icc_profile {
+            encoded_data = ImageMetadata::embed_icc_profile(encoded_data, target_format, profile);
+        }
 
         info!(
             "高级图片转换完成: {} -> {}, 原始大小: {}字节, 转换后: {}字节",
@@ -96,6 +122,97 @@ impl ImageTransformService {
         Ok((encoded_data, target_mime))
     }
 
+    /// 逐帧应用转换参数并重新编码为动图
+    fn transform_animation(
+        image_data: &[u8],
+        original_mime: &str,
+        target_format: ImageFormat,
+        params: &ImageTransformParams,
+    ) -> Result<Vec<u8>, AppError> {
+        let mut animation = AnimatedImageTransform::decode(image_data, original_mime)?;
+        info!(
+            "逐帧转换动图: {}帧 -> {:?}",
+            animation.frames.len(),
+            target_format
+        );
+
+        // 智能裁剪按帧计算会导致画面抖动，动图统一居中
+        let mut frame_params = params.clone();
+        if frame_params.gravity == Gravity::Smart {
+            frame_params.gravity = Gravity::Center;
+        }
+
+        // 所有帧画布尺寸一致，先按输出尺寸检查像素预算，避免放大后逐帧占用大量内存
+        if let Some(frame) = animation.frames.first() {
+            let (width, height) = frame.image.dimensions();
+            let (width, height) =
+                StaticImageTransform::output_dimensions(width, height, &frame_params);
+            AnimatedImageTransform::check_pixel_budget(animation.frames.len(), width, height)?;
+        }
+
+        for frame in &mut animation.frames {
+            let image = std::mem::take(&mut frame.image);
+            frame.image = Self::apply_pixel_transforms(
+                DynamicImage::ImageRgba8(image),
+                target_format,
+                &frame_params,
+            )?
+            .to_rgba8();
+        }
+
+        let encoded_data = AnimatedImageTransform::encode(animation, target_format, params)?;
+
+        // GIF 不支持 ICC 配置文件，WebP 动图与静图一样保留原图的颜色配置
+        Ok(match ImageMetadata::rgb_icc_profile(image_data) {
+            Some(profile) => ImageMetadata::embed_icc_profile(encoded_data, target_format, profile),
+            None => encoded_data,
+        })
+    }
+
+    /// 按固定顺序应用裁剪、旋转、翻转、缩放和透明通道处理
+    fn apply_pixel_transforms(
+        mut img: DynamicImage,
+        target_format: ImageFormat,
+        params: &ImageTransformParams,
+    ) -> Result<DynamicImage, AppError> {
+        // 先按矩形裁剪（坐标基于摆正后的原图）
+        if let Some(ref crop) = params.crop {
+            img = StaticImageTransform::crop_region(img, crop)?;
+        }
+
+        // 旋转和翻转
+        if let Some(angle) = params.rotate {
+            img = StaticImageTransform::rotate(img, angle, &params.background_color);
+        }
+        if params.flip_horizontal {
+            img = img.fliph();
+        }
+        if params.flip_vertical {
+            img = img.flipv();
+        }
+
+        // 调整尺寸（使用高质量重采样）
+        if params.width.is_some() || params.height.is_some() {
+            img = StaticImageTransform::resize_with_params(img, params)?;
+        }
+
+        // 处理透明通道
+        if params.no_alpha || ImageFormatUtils::format_requires_no_alpha(&target_format) {
+            img =
+                StaticImageTransform::remove_alpha_channel_advanced(img, &params.background_color)?;
+        }
+
+        Ok(img)
+    }
+
+    /// 移除全部元数据，不支持的格式原样返回
+    fn strip_metadata(image_data: &[u8], mime_type: &str) -> Result<Vec<u8>, AppError> {
+        Ok(
+            ImageMetadata::strip(image_data, mime_type, MetadataPolicy::Strip)?
+                .unwrap_or_else(|| image_data.to_vec()),
+        )
+    }
+
     /// 验证转换参数
     pub fn validate_params(params: &ImageTransformParams) -> Result<(), AppError> {
         ImageFormatUtils::validate_params(params)
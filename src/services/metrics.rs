@@ -0,0 +1,179 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::error;
+
+use crate::database::PoolStats;
+
+/// 未匹配到路由的请求使用的路由标签，避免任意路径撑大标签基数
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// 图片转换耗时的直方图分桶（秒）
+const TRANSFORM_BUCKETS: [f64; 11] = [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// Prometheus 指标
+///
+/// 计数器和直方图在请求处理过程中累加；缓存占用和连接池等状态类指标在抓取时刷新。
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    uploads: IntCounter,
+    upload_bytes: IntCounter,
+    transform_duration: HistogramVec,
+    transform_cache_requests: IntCounterVec,
+    transform_cache_entries: IntGauge,
+    transform_cache_size: IntGauge,
+    transform_queue_depth: IntGauge,
+    transform_rejected: IntCounter,
+    db_pool_connections: IntGaugeVec,
+}
+
+impl Metrics {
+    /// 获取全局指标实例
+    pub fn get() -> &'static Metrics {
+        METRICS.get_or_init(|| Self::new().expect("注册Prometheus指标失败"))
+    }
+
+    fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some("rifs".to_string()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP请求总数"),
+            &["method", "route", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP请求处理耗时（秒）"),
+            &["method", "route", "status"],
+        )?;
+        let uploads = IntCounter::new("uploads_total", "成功上传的图片数")?;
+        let upload_bytes = IntCounter::new("upload_bytes_total", "成功上传的图片总字节数")?;
+        let transform_duration = HistogramVec::new(
+            HistogramOpts::new("transform_duration_seconds", "图片转换耗时（秒）")
+                .buckets(TRANSFORM_BUCKETS.to_vec()),
+            &["source", "target"],
+        )?;
+        let transform_cache_requests = IntCounterVec::new(
+            Opts::new("transform_cache_requests_total", "转换缓存查询次数"),
+            &["result"],
+        )?;
+        let transform_cache_entries = IntGauge::new("transform_cache_entries", "转换缓存项数量")?;
+        let transform_cache_size =
+            IntGauge::new("transform_cache_size_bytes", "转换缓存总大小（字节）")?;
+        let transform_queue_depth =
+            IntGauge::new("transform_queue_depth", "等待工作线程的图片转换任务数")?;
+        let transform_rejected =
+            IntCounter::new("transform_rejected_total", "因转换队列已满被拒绝的请求数")?;
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "数据库连接池连接数"),
+            &["state"],
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(uploads.clone()))?;
+        registry.register(Box::new(upload_bytes.clone()))?;
+        registry.register(Box::new(transform_duration.clone()))?;
+        registry.register(Box::new(transform_cache_requests.clone()))?;
+        registry.register(Box::new(transform_cache_entries.clone()))?;
+        registry.register(Box::new(transform_cache_size.clone()))?;
+        registry.register(Box::new(transform_queue_depth.clone()))?;
+        registry.register(Box::new(transform_rejected.clone()))?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            uploads,
+            upload_bytes,
+            transform_duration,
+            transform_cache_requests,
+            transform_cache_entries,
+            transform_cache_size,
+            transform_queue_depth,
+            transform_rejected,
+            db_pool_connections,
+        })
+    }
+
+    /// 记录一次HTTP请求
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, latency: Duration) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_request_duration
+            .with_label_values(&labels)
+            .observe(latency.as_secs_f64());
+    }
+
+    /// 记录一次成功上传
+    pub fn observe_upload(&self, size: u64) {
+        self.uploads.inc();
+        self.upload_bytes.inc_by(size);
+    }
+
+    /// 记录一次图片转换，格式使用MIME类型
+    pub fn observe_transform(&self, source_mime: &str, target_mime: &str, elapsed: Duration) {
+        self.transform_duration
+            .with_label_values(&[format_label(source_mime), format_label(target_mime)])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// 记录一次转换缓存查询结果
+    pub fn observe_cache_lookup(&self, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        self.transform_cache_requests
+            .with_label_values(&[result])
+            .inc();
+    }
+
+    /// 更新转换缓存占用
+    pub fn set_cache_usage(&self, entries: u64, size: u64) {
+        self.transform_cache_entries.set(entries as i64);
+        self.transform_cache_size.set(size as i64);
+    }
+
+    /// 更新等待执行的转换任务数
+    pub fn set_transform_queue_depth(&self, depth: usize) {
+        self.transform_queue_depth.set(depth as i64);
+    }
+
+    /// 记录一次因转换队列已满被拒绝的请求
+    pub fn observe_transform_rejected(&self) {
+        self.transform_rejected.inc();
+    }
+
+    /// 更新数据库连接池状态
+    pub fn set_pool_stats(&self, stats: &PoolStats) {
+        for (state, value) in [
+            ("active", stats.active_connections),
+            ("idle", stats.idle_connections),
+            ("max", stats.max_connections),
+            ("min", stats.min_connections),
+        ] {
+            self.db_pool_connections
+                .with_label_values(&[state])
+                .set(value as i64);
+        }
+    }
+
+    /// 以 Prometheus 文本格式导出所有指标
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            error!("导出Prometheus指标失败: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// 将MIME类型转换为格式标签，如 `image/jpeg` -> `jpeg`
+fn format_label(mime_type: &str) -> &str {
+    mime_type.strip_prefix("image/").unwrap_or(mime_type)
+}
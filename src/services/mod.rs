@@ -1,11 +1,34 @@
+pub mod album_service;
+pub mod animated_image_transform;
+pub mod audit_service;
+pub mod auth_failure_throttle;
+pub mod blurhash;
 pub mod cache_service;
 pub mod image_format_utils;
+pub mod image_metadata;
+pub mod image_properties;
 pub mod image_service;
 pub mod image_transform_service;
+pub mod metrics;
+pub mod perceptual_hash;
+pub mod rate_limiter;
+pub mod remote_fetch;
+pub mod scrub_service;
 pub mod static_image_transform;
+pub mod tag_service;
 pub mod token_service;
+pub mod trash_service;
+pub mod transform_pool;
+pub mod transform_presets;
+pub mod upload_archive;
+pub mod url_signing;
 
+pub use album_service::AlbumService;
+pub use audit_service::AuditService;
 pub use cache_service::CacheService;
 pub use image_service::ImageService;
 pub use image_transform_service::ImageTransformService;
+pub use scrub_service::ScrubService;
+pub use tag_service::TagService;
 pub use token_service::TokenService;
+pub use trash_service::TrashService;
@@ -0,0 +1,38 @@
+use image::DynamicImage;
+
+/// 感知哈希（dHash）
+///
+/// 把图片缩小为 9x8 的灰度图，比较每行相邻像素的亮度得到 64 位哈希。
+/// 重新编码、缩放或轻微调色后的副本哈希基本不变，两个哈希的汉明距离越小越相似。
+pub struct PerceptualHash;
+
+impl PerceptualHash {
+    /// 计算已解码图片的感知哈希
+    pub fn from_image(img: &DynamicImage) -> u64 {
+        let gray = img.thumbnail_exact(9, 8).to_luma8();
+        let mut hash = 0u64;
+        for y in 0..8 {
+            for x in 0..8 {
+                let left = gray.get_pixel(x, y)[0];
+                let right = gray.get_pixel(x + 1, y)[0];
+                hash = (hash << 1) | u64::from(left > right);
+            }
+        }
+        hash
+    }
+
+    /// 两个哈希的汉明距离（0-64）
+    pub fn distance(a: u64, b: u64) -> u32 {
+        (a ^ b).count_ones()
+    }
+
+    /// 转换为数据库中存储的16位十六进制字符串
+    pub fn to_hex(hash: u64) -> String {
+        format!("{:016x}", hash)
+    }
+
+    /// 解析十六进制字符串形式的哈希
+    pub fn parse(value: &str) -> Option<u64> {
+        u64::from_str_radix(value, 16).ok()
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::warn;
+
+use crate::config::{RateLimitConfig, RateLimitRule};
+use crate::utils::AppError;
+
+/// 桶数量超过该值时清理已经补满的桶，避免大量一次性客户端占用内存
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// 受频率限制的请求类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitCategory {
+    /// 上传请求
+    Upload,
+    /// 未命中缓存的图片转换
+    Transform,
+    /// 其他 API 请求
+    Api,
+}
+
+impl RateLimitCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Upload => "upload",
+            Self::Transform => "transform",
+            Self::Api => "api",
+        }
+    }
+}
+
+/// 计数对象
+///
+/// 已认证的请求按令牌ID计数，其余按客户端IP计数；无法获取客户端IP的请求共用同一个桶。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    Token(i32),
+    Ip(IpAddr),
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// 令牌桶规则（每秒补充的令牌数和桶容量）
+#[derive(Debug, Clone, Copy)]
+struct BucketRule {
+    rate: f64,
+    capacity: f64,
+}
+
+impl BucketRule {
+    fn from_config(name: &str, rule: &RateLimitRule) -> Result<Self, AppError> {
+        let period = rule.period.as_seconds();
+        if rule.requests == 0 || period == 0 || rule.capacity() == 0 {
+            return Err(AppError::Internal(format!(
+                "无效的频率限制配置: rate_limit.{} 的 requests、period 和 burst 必须大于0",
+                name
+            )));
+        }
+
+        Ok(Self {
+            rate: rule.requests as f64 / period as f64,
+            capacity: rule.capacity() as f64,
+        })
+    }
+
+    /// 按经过的时间补充令牌
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.capacity);
+        bucket.updated = now;
+    }
+}
+
+/// 请求频率限制器
+///
+/// 令牌桶保存在进程内存中，多副本部署时每个副本分别计数。
+pub struct RateLimiter {
+    enabled: bool,
+    upload: BucketRule,
+    transform: BucketRule,
+    api: BucketRule,
+    buckets: Mutex<HashMap<(RateLimitCategory, RateLimitKey), Bucket>>,
+}
+
+impl RateLimiter {
+    /// 根据配置创建限制器，规则无效时返回错误
+    pub fn from_config(config: &RateLimitConfig) -> Result<Self, AppError> {
+        Ok(Self {
+            enabled: config.enabled,
+            upload: BucketRule::from_config("upload", &config.upload)?,
+            transform: BucketRule::from_config("transform", &config.transform)?,
+            api: BucketRule::from_config("api", &config.api)?,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 是否启用频率限制
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 扣减一次请求，超出限制时返回带有重试等待秒数的错误
+    pub fn check(&self, category: RateLimitCategory, key: &RateLimitKey) -> Result<(), AppError> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let rule = self.rule(category);
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|(category, _), bucket| {
+                let rule = self.rule(*category);
+                rule.refill(bucket, now);
+                bucket.tokens < rule.capacity
+            });
+        }
+
+        let bucket = buckets.entry((category, key.clone())).or_insert(Bucket {
+            tokens: rule.capacity,
+            updated: now,
+        });
+        rule.refill(bucket, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let retry_after = ((1.0 - bucket.tokens) / rule.rate).ceil().max(1.0) as u64;
+        warn!(
+            "请求频率超出限制: {} {:?}，{} 秒后重试",
+            category.as_str(),
+            key,
+            retry_after
+        );
+        Err(AppError::TooManyRequests { retry_after })
+    }
+
+    fn rule(&self, category: RateLimitCategory) -> BucketRule {
+        match category {
+            RateLimitCategory::Upload => self.upload,
+            RateLimitCategory::Transform => self.transform,
+            RateLimitCategory::Api => self.api,
+        }
+    }
+}
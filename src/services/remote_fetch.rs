@@ -0,0 +1,270 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::FetchConfig;
+use crate::storage::{StagedUpload, UploadStager};
+use crate::utils::{validate_file_size, AppError};
+
+/// 地址段（CIDR），也可以是单个IP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    /// 解析 `10.0.0.0/8`、`::1/128` 或单个IP
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::Internal(format!("无效的地址段: {}", value));
+        let (addr, prefix) = match value.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value.trim(), None),
+        };
+
+        let addr: IpAddr = addr.parse().map_err(|_| invalid())?;
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse().map_err(|_| invalid())?,
+            None => max_prefix,
+        };
+        if prefix > max_prefix {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            addr: addr.to_canonical(),
+            prefix,
+        })
+    }
+
+    /// 判断地址是否属于该地址段
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// 判断地址是否为可以公开访问的地址
+///
+/// 私有、回环、链路本地、运营商级NAT、组播、文档示例和保留地址均视为内网地址。
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => is_public_ipv6(ip),
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 0.0.0.0/8
+        || a == 0
+        // 100.64.0.0/10 运营商级NAT
+        || (a == 100 && (b & 0xc0) == 64)
+        // 192.0.0.0/24 IETF协议分配
+        || (a == 192 && b == 0 && c == 0)
+        // 198.18.0.0/15 基准测试
+        || (a == 198 && (b & 0xfe) == 18)
+        // 240.0.0.0/4 保留
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    // 64:ff9b::/96 NAT64 按内嵌的IPv4地址判断
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [.., a, b, c, d] = ip.octets();
+        return is_public_ipv4(Ipv4Addr::new(a, b, c, d));
+    }
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // fc00::/7 唯一本地地址
+        || (segments[0] & 0xfe00) == 0xfc00
+        // fe80::/10 链路本地，fec0::/10 已废弃的站点本地
+        || (segments[0] & 0xffc0) == 0xfe80
+        || (segments[0] & 0xffc0) == 0xfec0
+        // 2001:db8::/32 文档示例
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+}
+
+/// 目标地址访问策略
+#[derive(Debug, Default)]
+struct AddressPolicy {
+    allowed_networks: Vec<IpNetwork>,
+}
+
+impl AddressPolicy {
+    fn permits(&self, ip: IpAddr) -> bool {
+        is_public_ip(ip) || self.allowed_networks.iter().any(|net| net.contains(ip))
+    }
+
+    /// 检查URL的协议和IP形式的主机名；域名在解析时由 [`GuardedResolver`] 检查
+    fn check_url(&self, url: &Url) -> Result<(), String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("不支持的协议: {}", url.scheme()));
+        }
+
+        let host = url.host_str().ok_or_else(|| "URL缺少主机名".to_string())?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            if !self.permits(ip) {
+                return Err(format!("不允许访问内网地址: {}", ip));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 过滤内网地址的DNS解析器
+///
+/// 在连接前检查解析结果，避免域名指向内网地址（包括DNS重绑定）绕过URL检查。
+struct GuardedResolver {
+    policy: Arc<AddressPolicy>,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.policy.clone();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| policy.permits(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} 解析到不允许访问的内网地址", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// 抓取到的远程图片
+pub struct RemoteImage {
+    /// 已暂存的图片内容
+    pub staged: StagedUpload,
+    /// 从最终URL路径中取出的文件名
+    pub filename: Option<String>,
+}
+
+/// 远程图片抓取器
+///
+/// 限制协议、重定向次数、超时和文件大小，默认拒绝访问内网地址，防止SSRF。
+pub struct RemoteFetcher {
+    client: reqwest::Client,
+    policy: Arc<AddressPolicy>,
+}
+
+impl RemoteFetcher {
+    /// 根据配置创建抓取器，地址段配置无效时返回错误
+    pub fn from_config(config: &FetchConfig) -> Result<Self, AppError> {
+        let allowed_networks = config
+            .allowed_networks
+            .iter()
+            .map(|network| IpNetwork::parse(network))
+            .collect::<Result<Vec<_>, _>>()?;
+        let policy = Arc::new(AddressPolicy { allowed_networks });
+
+        let max_redirects = config.max_redirects;
+        let redirect_policy = policy.clone();
+        let redirect = Policy::custom(move |attempt| {
+            if attempt.previous().len() > max_redirects {
+                attempt.error(format!("重定向次数超过 {} 次", max_redirects))
+            } else if let Err(e) = redirect_policy.check_url(attempt.url()) {
+                attempt.error(e)
+            } else {
+                attempt.follow()
+            }
+        });
+
+        let timeout = Duration::from_secs(config.timeout.as_seconds());
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .connect_timeout(timeout)
+            .redirect(redirect)
+            // 代理会绕过地址检查
+            .no_proxy()
+            .dns_resolver(Arc::new(GuardedResolver {
+                policy: policy.clone(),
+            }))
+            .user_agent(concat!("rifs/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| AppError::Internal(format!("创建HTTP客户端失败: {}", e)))?;
+
+        Ok(Self { client, policy })
+    }
+
+    /// 下载远程图片到暂存文件，大小受 `storage.max_file_size` 限制
+    pub async fn fetch(&self, url: &str) -> Result<RemoteImage, AppError> {
+        let url = Url::parse(url).map_err(|_| AppError::BadRequest("无效的URL".to_string()))?;
+        self.policy.check_url(&url).map_err(AppError::BadRequest)?;
+
+        info!("开始抓取远程图片: {}", url);
+        let mut response = self.client.get(url).send().await.map_err(fetch_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AppError::BadRequest(format!(
+                "远程服务器返回状态码 {}",
+                status.as_u16()
+            )));
+        }
+
+        // 声明的长度超出限制时不必下载
+        if let Some(length) = response.content_length() {
+            validate_file_size(length)?;
+        }
+
+        let filename = response
+            .url()
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string());
+
+        let mut stager = UploadStager::new().await?;
+        while let Some(chunk) = response.chunk().await.map_err(fetch_error)? {
+            stager.write_chunk(&chunk).await?;
+        }
+        let staged = stager.finish().await?;
+
+        Ok(RemoteImage { staged, filename })
+    }
+}
+
+/// 转换请求错误，附带完整的错误链以便定位原因
+fn fetch_error(e: reqwest::Error) -> AppError {
+    if e.is_timeout() {
+        return AppError::BadRequest("获取远程图片超时".to_string());
+    }
+
+    let mut message = e.to_string();
+    let mut source = std::error::Error::source(&e);
+    while let Some(inner) = source {
+        message = format!("{}: {}", message, inner);
+        source = inner.source();
+    }
+    warn!("获取远程图片失败: {}", message);
+    AppError::BadRequest(format!("获取远程图片失败: {}", message))
+}
@@ -0,0 +1,494 @@
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::app_state::AppState;
+use crate::models::{
+    ApiTokenInfo, ImageInfo, ScrubAction, ScrubArea, ScrubIssue, ScrubIssueKind, ScrubOptions,
+    ScrubReport, ScrubStatus,
+};
+use crate::repositories::{ImageRepository, ImageRepositoryTrait};
+use crate::services::{CacheService, ImageService, TokenService};
+use crate::storage::{ObjectMeta, StagedUpload, StorageBackend, UploadStager};
+use crate::utils::AppError;
+
+/// 隔离区前缀，损坏的文件移到这里而不是直接删除，便于人工检查
+pub const QUARANTINE_PREFIX: &str = ".quarantine/";
+
+/// 遍历图片记录时每批读取的数量
+const IMAGE_BATCH_SIZE: u64 = 500;
+
+/// 存储校验任务
+///
+/// 同一时间只允许一次校验，定期任务和管理接口共用；保留最近一次的报告供查询。
+#[derive(Default)]
+pub struct ScrubJob {
+    running: Arc<AtomicBool>,
+    last_report: Mutex<Option<ScrubReport>>,
+}
+
+impl ScrubJob {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 当前状态和最近一次的报告
+    pub fn status(&self) -> ScrubStatus {
+        ScrubStatus {
+            running: self.running.load(Ordering::SeqCst),
+            last_report: self
+                .last_report
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        }
+    }
+
+    /// 执行校验并等待完成，已有校验在执行时返回 [`AppError::ServiceUnavailable`]
+    pub async fn run(
+        &self,
+        app_state: &AppState,
+        options: ScrubOptions,
+    ) -> Result<ScrubReport, AppError> {
+        let _guard = self.begin()?;
+        let report = ScrubService::scrub(app_state, options).await?;
+        self.record(report.clone());
+        Ok(report)
+    }
+
+    /// 在后台执行校验，立即返回；结果通过 [`ScrubJob::status`] 查询
+    pub fn spawn(app_state: &AppState, options: ScrubOptions) -> Result<(), AppError> {
+        let guard = app_state.scrub_job().begin()?;
+        let app_state = app_state.clone();
+        tokio::spawn(async move {
+            let _guard = guard;
+            match ScrubService::scrub(&app_state, options).await {
+                Ok(report) => app_state.scrub_job().record(report),
+                Err(e) => error!("存储校验失败: {}", e),
+            }
+        });
+        Ok(())
+    }
+
+    fn begin(&self) -> Result<RunningGuard, AppError> {
+        if self
+            .running
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(AppError::ServiceUnavailable(
+                "存储校验正在进行中".to_string(),
+            ));
+        }
+        Ok(RunningGuard(self.running.clone()))
+    }
+
+    fn record(&self, report: ScrubReport) {
+        *self.last_report.lock().unwrap_or_else(|e| e.into_inner()) = Some(report);
+    }
+}
+
+/// 校验结束（包括失败和panic）时清除执行标记
+struct RunningGuard(Arc<AtomicBool>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// 存储校验服务
+///
+/// 核对图片记录与上传目录、缓存记录与缓存目录中的文件：
+/// 记录对应的文件不存在、文件大小不一致、内容哈希不一致，以及没有记录的孤立文件。
+/// 修复时删除没有文件的记录，为内容完好的孤立图片文件创建记录，
+/// 损坏的图片文件移入隔离区；缓存可以重新生成，有问题时直接删除。
+pub struct ScrubService;
+
+impl ScrubService {
+    /// 执行一次完整的校验
+    pub async fn scrub(
+        app_state: &AppState,
+        options: ScrubOptions,
+    ) -> Result<ScrubReport, AppError> {
+        let started_at = Utc::now();
+        let start_time = Instant::now();
+        let orphan_grace = app_state.config().scrub.orphan_grace.as_seconds() as i64;
+        info!(
+            "开始存储校验: 校验内容={}, 修复={}",
+            options.checksum, options.repair
+        );
+
+        let mut scrubber = Scrubber {
+            app_state,
+            options,
+            orphan_cutoff: started_at - chrono::Duration::seconds(orphan_grace),
+            report: ScrubReport {
+                options,
+                started_at,
+                duration_ms: 0,
+                images_checked: 0,
+                cache_checked: 0,
+                files_scanned: 0,
+                issues: Vec::new(),
+            },
+        };
+        scrubber.scrub_uploads().await?;
+        scrubber.scrub_cache().await?;
+
+        let mut report = scrubber.report;
+        report.duration_ms = start_time.elapsed().as_millis() as u64;
+        info!(
+            "存储校验完成: 图片记录{}条，缓存记录{}条，文件{}个，发现{}个问题，修复{}个，耗时{}ms",
+            report.images_checked,
+            report.cache_checked,
+            report.files_scanned,
+            report.issues.len(),
+            report.repaired(),
+            report.duration_ms
+        );
+        Ok(report)
+    }
+}
+
+/// 一次校验的执行状态
+struct Scrubber<'a> {
+    app_state: &'a AppState,
+    options: ScrubOptions,
+    /// 修改时间晚于此时刻的文件可能正在上传，不视为孤立文件
+    orphan_cutoff: DateTime<Utc>,
+    report: ScrubReport,
+}
+
+impl Scrubber<'_> {
+    async fn scrub_uploads(&mut self) -> Result<(), AppError> {
+        let storage = self.app_state.storage();
+        let image_repo = ImageRepository::new(self.app_state.db_pool().get_connection());
+        let mut referenced = HashSet::new();
+        // 同一文件可能被多条记录引用，只检查一次
+        let mut verdicts: HashMap<String, Option<(ScrubIssueKind, String)>> = HashMap::new();
+        let mut quarantined = HashSet::new();
+        let mut after: Option<String> = None;
+
+        loop {
+            let batch = image_repo
+                .find_batch_after(after.as_deref(), IMAGE_BATCH_SIZE)
+                .await?;
+            let Some(last) = batch.last() else {
+                break;
+            };
+            after = Some(last.hash.clone());
+
+            for image in batch {
+                self.report.images_checked += 1;
+                let storage_key = image.storage_key();
+                referenced.insert(storage_key.clone());
+
+                let verdict = match verdicts.get(&storage_key) {
+                    Some(verdict) => verdict.clone(),
+                    None => {
+                        let verdict = self.check_image_file(storage, &image, &storage_key).await?;
+                        verdicts.insert(storage_key.clone(), verdict.clone());
+                        verdict
+                    }
+                };
+                let Some((kind, detail)) = verdict else {
+                    continue;
+                };
+
+                let repair = if self.options.repair {
+                    Some(
+                        self.repair_image(storage, &image, &storage_key, kind, &mut quarantined)
+                            .await,
+                    )
+                } else {
+                    None
+                };
+                self.push_issue(
+                    ScrubArea::Uploads,
+                    kind,
+                    storage_key,
+                    Some(image.hash),
+                    detail,
+                    repair,
+                );
+            }
+        }
+
+        let mut adopt_owner = None;
+        for object in storage.list("").await? {
+            if object.key.starts_with(QUARANTINE_PREFIX) {
+                continue;
+            }
+            self.report.files_scanned += 1;
+            if referenced.contains(&object.key) || !self.is_settled(&object) {
+                continue;
+            }
+
+            let repair = if self.options.repair {
+                Some(
+                    self.repair_orphan_upload(storage, &object.key, &mut adopt_owner)
+                        .await,
+                )
+            } else {
+                None
+            };
+            self.push_issue(
+                ScrubArea::Uploads,
+                ScrubIssueKind::OrphanFile,
+                object.key,
+                None,
+                "没有对应的图片记录".to_string(),
+                repair,
+            );
+        }
+        Ok(())
+    }
+
+    async fn scrub_cache(&mut self) -> Result<(), AppError> {
+        let storage = self.app_state.cache_storage();
+        let cache_service =
+            CacheService::new(self.app_state.db_pool().get_connection(), storage.clone())?;
+        let mut referenced = HashSet::new();
+
+        // 缓存记录不保存内容哈希，只检查文件是否存在和大小
+        for entry in cache_service.list_all().await? {
+            self.report.cache_checked += 1;
+            let storage_key = cache_service.storage_key_of(&entry).to_string();
+            referenced.insert(storage_key.clone());
+
+            let (kind, detail) = match storage.stat(&storage_key).await? {
+                None => (ScrubIssueKind::MissingFile, "文件不存在".to_string()),
+                Some(meta) if meta.size != entry.file_size => (
+                    ScrubIssueKind::SizeMismatch,
+                    size_mismatch(entry.file_size, meta.size),
+                ),
+                Some(_) => continue,
+            };
+
+            let repair = if self.options.repair {
+                let action = match kind {
+                    ScrubIssueKind::MissingFile => ScrubAction::DeletedRecord,
+                    _ => ScrubAction::DeletedFile,
+                };
+                Some(cache_service.remove_entry(&entry).await.map(|_| action))
+            } else {
+                None
+            };
+            self.push_issue(
+                ScrubArea::Cache,
+                kind,
+                storage_key,
+                Some(entry.cache_key),
+                detail,
+                repair,
+            );
+        }
+
+        for object in storage.list("").await? {
+            if object.key.starts_with(QUARANTINE_PREFIX) {
+                continue;
+            }
+            self.report.files_scanned += 1;
+            if referenced.contains(&object.key) || !self.is_settled(&object) {
+                continue;
+            }
+
+            let repair = if self.options.repair {
+                Some(
+                    storage
+                        .delete(&object.key)
+                        .await
+                        .map(|_| ScrubAction::DeletedFile),
+                )
+            } else {
+                None
+            };
+            self.push_issue(
+                ScrubArea::Cache,
+                ScrubIssueKind::OrphanFile,
+                object.key,
+                None,
+                "没有对应的缓存记录".to_string(),
+                repair,
+            );
+        }
+        Ok(())
+    }
+
+    /// 检查图片记录对应的文件，返回发现的问题
+    async fn check_image_file(
+        &self,
+        storage: &dyn StorageBackend,
+        image: &ImageInfo,
+        storage_key: &str,
+    ) -> Result<Option<(ScrubIssueKind, String)>, AppError> {
+        let meta = match storage.stat(storage_key).await? {
+            Some(meta) => meta,
+            None => {
+                return Ok(Some((
+                    ScrubIssueKind::MissingFile,
+                    "文件不存在".to_string(),
+                )))
+            }
+        };
+        if meta.size != image.size {
+            return Ok(Some((
+                ScrubIssueKind::SizeMismatch,
+                size_mismatch(image.size, meta.size),
+            )));
+        }
+
+        // 早期上传的图片没有内容哈希，不做比对
+        let Some(blob_hash) = image.blob_hash.as_deref().filter(|_| self.options.checksum) else {
+            return Ok(None);
+        };
+        let actual = hash_object(storage, storage_key).await?;
+        if actual == blob_hash {
+            Ok(None)
+        } else {
+            Ok(Some((
+                ScrubIssueKind::ChecksumMismatch,
+                format!("内容校验失败: 记录 {}，实际 {}", blob_hash, actual),
+            )))
+        }
+    }
+
+    /// 修复图片记录：文件不存在时删除记录，文件损坏时移入隔离区后删除引用它的记录
+    async fn repair_image(
+        &self,
+        storage: &dyn StorageBackend,
+        image: &ImageInfo,
+        storage_key: &str,
+        kind: ScrubIssueKind,
+        quarantined: &mut HashSet<String>,
+    ) -> Result<ScrubAction, AppError> {
+        let action = if kind == ScrubIssueKind::MissingFile {
+            ScrubAction::DeletedRecord
+        } else {
+            if !quarantined.contains(storage_key) {
+                let staged =
+                    UploadStager::stage_stream(storage.get_stream(storage_key).await?).await?;
+                quarantine(storage, storage_key, &staged).await?;
+                quarantined.insert(storage_key.to_string());
+            }
+            ScrubAction::QuarantinedFile
+        };
+
+        let pool = self.app_state.db_pool();
+        ImageService::delete_image(pool, storage, &image.hash).await?;
+        let cache_service =
+            CacheService::new(pool.get_connection(), self.app_state.cache_storage())?;
+        cache_service.remove_by_original_hash(&image.hash).await?;
+        Ok(action)
+    }
+
+    /// 修复孤立的图片文件：内容与存储键一致时归入最早创建的管理员令牌，否则移入隔离区
+    async fn repair_orphan_upload(
+        &self,
+        storage: &dyn StorageBackend,
+        storage_key: &str,
+        adopt_owner: &mut Option<ApiTokenInfo>,
+    ) -> Result<ScrubAction, AppError> {
+        let staged = UploadStager::stage_stream(storage.get_stream(storage_key).await?).await?;
+        if !content_matches_key(storage_key, &staged.hash_with(&[])) {
+            quarantine(storage, storage_key, &staged).await?;
+            return Ok(ScrubAction::QuarantinedFile);
+        }
+
+        if adopt_owner.is_none() {
+            let token_service = TokenService::new(self.app_state.db_pool().get_connection());
+            *adopt_owner = token_service.first_admin().await?;
+        }
+        let owner = adopt_owner.as_ref().ok_or_else(|| {
+            AppError::Internal("没有可用的管理员令牌，无法为孤立文件创建记录".to_string())
+        })?;
+        ImageService::adopt_file(self.app_state.db_pool(), storage_key, &staged, owner).await?;
+        Ok(ScrubAction::AdoptedFile)
+    }
+
+    /// 文件是否已经写入足够久，不再可能是正在进行的上传
+    fn is_settled(&self, object: &ObjectMeta) -> bool {
+        object
+            .last_modified
+            .is_none_or(|modified| modified <= self.orphan_cutoff)
+    }
+
+    fn push_issue(
+        &mut self,
+        area: ScrubArea,
+        kind: ScrubIssueKind,
+        storage_key: String,
+        record: Option<String>,
+        detail: String,
+        repair: Option<Result<ScrubAction, AppError>>,
+    ) {
+        let (action, repair_error) = match repair {
+            None => (None, None),
+            Some(Ok(action)) => (Some(action), None),
+            Some(Err(e)) => (None, Some(e.to_string())),
+        };
+        match (&action, &repair_error) {
+            (Some(action), _) => info!(
+                "存储问题已修复: {} ({}) -> {:?}",
+                storage_key, detail, action
+            ),
+            (_, Some(e)) => error!("修复存储问题失败: {} ({}) - {}", storage_key, detail, e),
+            _ => warn!("发现存储问题: {} ({})", storage_key, detail),
+        }
+
+        self.report.issues.push(ScrubIssue {
+            area,
+            kind,
+            storage_key,
+            record,
+            detail,
+            action,
+            repair_error,
+        });
+    }
+}
+
+/// 把文件移入隔离区
+async fn quarantine(
+    storage: &dyn StorageBackend,
+    storage_key: &str,
+    staged: &StagedUpload,
+) -> Result<(), AppError> {
+    storage
+        .put_file(
+            &format!("{}{}", QUARANTINE_PREFIX, storage_key),
+            staged.path(),
+        )
+        .await?;
+    storage.delete(storage_key).await
+}
+
+/// 以流的方式读取对象并计算内容的 SHA-256
+async fn hash_object(storage: &dyn StorageBackend, storage_key: &str) -> Result<String, AppError> {
+    let mut stream = storage.get_stream(storage_key).await?;
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = stream.next().await {
+        hasher.update(&chunk?);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 图片文件以内容的 SHA-256 命名，检查文件名与内容哈希是否一致
+fn content_matches_key(storage_key: &str, content_hash: &str) -> bool {
+    let file_name = storage_key.rsplit('/').next().unwrap_or(storage_key);
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    stem == content_hash
+}
+
+fn size_mismatch(expected: u64, actual: u64) -> String {
+    format!(
+        "文件大小不一致: 记录 {} 字节，实际 {} 字节",
+        expected, actual
+    )
+}
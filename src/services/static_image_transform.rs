@@ -4,21 +4,22 @@ use image::{
         png::{CompressionType as PngCompression, FilterType as PngFilter, PngEncoder},
     },
     imageops::FilterType,
-    DynamicImage, GenericImageView, ImageFormat,
+    metadata::Orientation,
+    DynamicImage, GenericImageView, ImageFormat, Rgba, RgbaImage,
 };
 use std::io::Cursor;
 use tracing::{error, info, warn};
 
-use crate::models::{BackgroundColor, ImageTransformParams};
+use crate::models::{BackgroundColor, CropRect, FitMode, Gravity, ImageTransformParams};
 use crate::utils::AppError;
 
 /// 静图转换服务
 pub struct StaticImageTransform;
 
 impl StaticImageTransform {
-    /// 加载图片并获取颜色信息
+    /// 加载图片并获取颜色信息，同时按 EXIF 方向信息自动摆正
     pub fn load_image_with_color_info(data: &[u8]) -> Result<DynamicImage, AppError> {
-        let img = image::load_from_memory(data).map_err(|e| {
+        let mut img = image::load_from_memory(data).map_err(|e| {
             error!("图片加载失败: {}", e);
             AppError::InvalidFile
         })?;
@@ -30,9 +31,102 @@ impl StaticImageTransform {
             width, height, color_type
         );
 
+        // 手机拍摄的照片通常以传感器方向存储，需要按 EXIF Orientation 旋转
+        let orientation = Self::read_exif_orientation(data);
+        if orientation != Orientation::NoTransforms {
+            info!("按EXIF方向信息调整图片: {:?}", orientation);
+            img.apply_orientation(orientation);
+        }
+
         Ok(img)
     }
 
+    /// 读取 EXIF 中的方向信息（支持 JPEG、WebP、AVIF 等容器）
+    pub fn read_exif_orientation(data: &[u8]) -> Orientation {
+        let Ok(exif) = exif::Reader::new().read_from_container(&mut Cursor::new(data)) else {
+            return Orientation::NoTransforms;
+        };
+
+        exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0))
+            .and_then(|value| u8::try_from(value).ok())
+            .and_then(Orientation::from_exif)
+            .unwrap_or(Orientation::NoTransforms)
+    }
+
+    /// 顺时针旋转指定角度
+    ///
+    /// 90/180/270 度无损旋转；其他角度使用双线性插值，画布扩展为旋转后的外接矩形，
+    /// 空白区域使用背景色填充（未指定时透明）。
+    pub fn rotate(
+        img: DynamicImage,
+        angle: u16,
+        background: &Option<BackgroundColor>,
+    ) -> DynamicImage {
+        match angle % 360 {
+            0 => return img,
+            90 => return img.rotate90(),
+            180 => return img.rotate180(),
+            270 => return img.rotate270(),
+            _ => {}
+        }
+
+        let source = img.to_rgba8();
+        let (width, height) = source.dimensions();
+        let radians = (angle as f64).to_radians();
+        let (sin, cos) = radians.sin_cos();
+
+        let (new_width, new_height) = Self::rotated_dimensions(width, height, angle);
+        let fill = match background {
+            Some(color) => {
+                let (r, g, b) = color.rgb();
+                Rgba([r, g, b, 255])
+            }
+            None => Rgba([0, 0, 0, 0]),
+        };
+
+        let source_center = (width as f64 / 2.0, height as f64 / 2.0);
+        let target_center = (new_width as f64 / 2.0, new_height as f64 / 2.0);
+        let sample = |x: i64, y: i64| -> [f64; 4] {
+            if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+                fill.0.map(f64::from)
+            } else {
+                source.get_pixel(x as u32, y as u32).0.map(f64::from)
+            }
+        };
+
+        let rotated = RgbaImage::from_fn(new_width, new_height, |x, y| {
+            // 目标像素中心逆向旋转回原图坐标
+            let dx = x as f64 + 0.5 - target_center.0;
+            let dy = y as f64 + 0.5 - target_center.1;
+            let sx = dx * cos + dy * sin + source_center.0 - 0.5;
+            let sy = -dx * sin + dy * cos + source_center.1 - 0.5;
+
+            let (x0, y0) = (sx.floor(), sy.floor());
+            let (fx, fy) = (sx - x0, sy - y0);
+            let (x0, y0) = (x0 as i64, y0 as i64);
+
+            let top_left = sample(x0, y0);
+            let top_right = sample(x0 + 1, y0);
+            let bottom_left = sample(x0, y0 + 1);
+            let bottom_right = sample(x0 + 1, y0 + 1);
+
+            let mut pixel = [0u8; 4];
+            for channel in 0..4 {
+                let top = top_left[channel] * (1.0 - fx) + top_right[channel] * fx;
+                let bottom = bottom_left[channel] * (1.0 - fx) + bottom_right[channel] * fx;
+                pixel[channel] = (top * (1.0 - fy) + bottom * fy).round().clamp(0.0, 255.0) as u8;
+            }
+            Rgba(pixel)
+        });
+
+        info!(
+            "任意角度旋转: {}度, {}x{} -> {}x{}",
+            angle, width, height, new_width, new_height
+        );
+        DynamicImage::ImageRgba8(rotated)
+    }
+
     /// 从GIF提取第一帧作为静态图片
     pub fn load_gif_first_frame(data: &[u8]) -> Result<DynamicImage, AppError> {
         let mut decoder = gif::DecodeOptions::new();
@@ -109,11 +203,321 @@ impl StaticImageTransform {
         }
     }
 
-    /// 高质量图片缩放
+    /// 按矩形裁剪，超出图片的部分自动截断
+    pub fn crop_region(img: DynamicImage, rect: &CropRect) -> Result<DynamicImage, AppError> {
+        let (current_width, current_height) = img.dimensions();
+        if rect.x >= current_width || rect.y >= current_height {
+            return Err(AppError::BadRequest(format!(
+                "裁剪区域超出图片范围: 图片尺寸 {}x{}",
+                current_width, current_height
+            )));
+        }
+
+        let width = rect.width.min(current_width - rect.x);
+        let height = rect.height.min(current_height - rect.y);
+        info!(
+            "裁剪区域: {}x{}+{}+{} (原图 {}x{})",
+            width, height, rect.x, rect.y, current_width, current_height
+        );
+
+        Ok(img.crop_imm(rect.x, rect.y, width, height))
+    }
+
+    /// 按转换参数中的宽高、适配模式和对齐方式调整尺寸
+    pub fn resize_with_params(
+        img: DynamicImage,
+        params: &ImageTransformParams,
+    ) -> Result<DynamicImage, AppError> {
+        let (Some(width), Some(height)) = (params.width, params.height) else {
+            // 只指定一边时所有模式都等价于等比缩放
+            return Self::resize_image_hq(img, params.width, params.height, params.upscale);
+        };
+
+        match params.fit {
+            FitMode::Inside => {
+                Self::resize_image_hq(img, Some(width), Some(height), params.upscale)
+            }
+            FitMode::Outside => {
+                let (current_width, current_height) = img.dimensions();
+                let ratio = (width as f32 / current_width as f32)
+                    .max(height as f32 / current_height as f32);
+                let ratio = Self::limit_ratio(ratio, params.upscale);
+                Ok(Self::scale_to(
+                    img,
+                    Self::scale_dimension(current_width, ratio),
+                    Self::scale_dimension(current_height, ratio),
+                ))
+            }
+            FitMode::Fill => {
+                let (current_width, current_height) = img.dimensions();
+                let (target_width, target_height) = if params.upscale {
+                    (width, height)
+                } else {
+                    (width.min(current_width), height.min(current_height))
+                };
+                Ok(Self::scale_to(img, target_width, target_height))
+            }
+            FitMode::Cover => Ok(Self::resize_cover(
+                img,
+                width,
+                height,
+                params.gravity,
+                params.upscale,
+            )),
+            FitMode::Contain => Ok(Self::resize_contain(
+                img,
+                width,
+                height,
+                params.gravity,
+                params.upscale,
+                &params.background_color,
+            )),
+        }
+    }
+
+    /// 旋转后的画布尺寸，任意角度时为旋转后的外接矩形
+    fn rotated_dimensions(width: u32, height: u32, angle: u16) -> (u32, u32) {
+        match angle % 360 {
+            0 | 180 => (width, height),
+            90 | 270 => (height, width),
+            angle => {
+                let (sin, cos) = (angle as f64).to_radians().sin_cos();
+                (
+                    (width as f64 * cos.abs() + height as f64 * sin.abs()).ceil() as u32,
+                    (width as f64 * sin.abs() + height as f64 * cos.abs()).ceil() as u32,
+                )
+            }
+        }
+    }
+
+    /// 不处理像素，估算应用裁剪、旋转和缩放后的输出尺寸（上限）
+    ///
+    /// 用于在逐帧转换动图之前检查输出的像素预算。
+    pub fn output_dimensions(width: u32, height: u32, params: &ImageTransformParams) -> (u32, u32) {
+        let (mut width, mut height) = (width, height);
+        if let Some(ref crop) = params.crop {
+            if crop.x < width && crop.y < height {
+                width = crop.width.min(width - crop.x);
+                height = crop.height.min(height - crop.y);
+            }
+        }
+        if let Some(angle) = params.rotate {
+            (width, height) = Self::rotated_dimensions(width, height, angle);
+        }
+
+        let ratio = match (params.width, params.height) {
+            (None, None) => return (width, height),
+            (Some(w), None) => w as f32 / width as f32,
+            (None, Some(h)) => h as f32 / height as f32,
+            // 只有 outside 模式的输出可能超出目标框
+            (Some(w), Some(h)) if params.fit == FitMode::Outside => {
+                (w as f32 / width as f32).max(h as f32 / height as f32)
+            }
+            (Some(w), Some(h)) => return (w, h),
+        };
+        let ratio = Self::limit_ratio(ratio, params.upscale);
+        (
+            Self::scale_dimension(width, ratio),
+            Self::scale_dimension(height, ratio),
+        )
+    }
+
+    /// 等比缩放覆盖目标框后按对齐方式裁剪
+    ///
+    /// 不允许放大且原图不足以覆盖目标框时，裁剪出保持目标宽高比的最大区域。
+    fn resize_cover(
+        img: DynamicImage,
+        width: u32,
+        height: u32,
+        gravity: Gravity,
+        upscale: bool,
+    ) -> DynamicImage {
+        let (current_width, current_height) = img.dimensions();
+        let ratio =
+            (width as f32 / current_width as f32).max(height as f32 / current_height as f32);
+        let ratio = Self::limit_ratio(ratio, upscale);
+        let scaled_width = Self::scale_dimension(current_width, ratio);
+        let scaled_height = Self::scale_dimension(current_height, ratio);
+
+        let (crop_width, crop_height) = if scaled_width >= width && scaled_height >= height {
+            (width, height)
+        } else {
+            let target_ratio = width as f32 / height as f32;
+            if scaled_width as f32 / scaled_height as f32 > target_ratio {
+                (
+                    ((scaled_height as f32 * target_ratio).round() as u32).clamp(1, scaled_width),
+                    scaled_height,
+                )
+            } else {
+                (
+                    scaled_width,
+                    ((scaled_width as f32 / target_ratio).round() as u32).clamp(1, scaled_height),
+                )
+            }
+        };
+
+        let scaled = Self::scale_to(img, scaled_width, scaled_height);
+        let (x, y) = match gravity {
+            Gravity::Smart => Self::entropy_offset(&scaled, crop_width, crop_height),
+            _ => Self::aligned_offset(
+                gravity,
+                scaled_width - crop_width,
+                scaled_height - crop_height,
+            ),
+        };
+
+        info!(
+            "cover裁剪: {}x{} -> {}x{}+{}+{}",
+            scaled_width, scaled_height, crop_width, crop_height, x, y
+        );
+        scaled.crop_imm(x, y, crop_width, crop_height)
+    }
+
+    /// 等比缩放到目标框内后按对齐方式留白补齐为目标尺寸
+    ///
+    /// 指定了背景色时使用该颜色，否则留白区域透明。
+    fn resize_contain(
+        img: DynamicImage,
+        width: u32,
+        height: u32,
+        gravity: Gravity,
+        upscale: bool,
+        background: &Option<BackgroundColor>,
+    ) -> DynamicImage {
+        let (current_width, current_height) = img.dimensions();
+        let ratio =
+            (width as f32 / current_width as f32).min(height as f32 / current_height as f32);
+        let ratio = Self::limit_ratio(ratio, upscale);
+        let scaled_width = Self::scale_dimension(current_width, ratio).min(width);
+        let scaled_height = Self::scale_dimension(current_height, ratio).min(height);
+
+        let scaled = Self::scale_to(img, scaled_width, scaled_height);
+        let fill = match background {
+            Some(color) => {
+                let (r, g, b) = color.rgb();
+                Rgba([r, g, b, 255])
+            }
+            None => Rgba([0, 0, 0, 0]),
+        };
+        let mut canvas = RgbaImage::from_pixel(width, height, fill);
+        let (x, y) = Self::aligned_offset(gravity, width - scaled_width, height - scaled_height);
+        image::imageops::overlay(&mut canvas, &scaled.to_rgba8(), x as i64, y as i64);
+
+        info!(
+            "contain留白: {}x{} 放置于 {}x{} 画布 (+{}+{})",
+            scaled_width, scaled_height, width, height, x, y
+        );
+        DynamicImage::ImageRgba8(canvas)
+    }
+
+    /// 不允许放大时把缩放比例限制在 1 以内
+    fn limit_ratio(ratio: f32, upscale: bool) -> f32 {
+        if upscale {
+            ratio
+        } else {
+            ratio.min(1.0)
+        }
+    }
+
+    /// 按比例计算缩放后的边长，至少为 1 像素
+    fn scale_dimension(value: u32, ratio: f32) -> u32 {
+        ((value as f32 * ratio).round() as u32).max(1)
+    }
+
+    /// 精确缩放到指定尺寸，尺寸不变时直接返回原图
+    fn scale_to(img: DynamicImage, width: u32, height: u32) -> DynamicImage {
+        let (current_width, current_height) = img.dimensions();
+        if width == current_width && height == current_height {
+            return img;
+        }
+
+        info!(
+            "高质量缩放: {}x{} -> {}x{}",
+            current_width, current_height, width, height
+        );
+        img.resize_exact(width, height, FilterType::Lanczos3)
+    }
+
+    /// 根据对齐方式计算偏移量
+    fn aligned_offset(gravity: Gravity, slack_x: u32, slack_y: u32) -> (u32, u32) {
+        let (align_x, align_y) = gravity.alignment();
+        (
+            (slack_x as f32 * align_x).round() as u32,
+            (slack_y as f32 * align_y).round() as u32,
+        )
+    }
+
+    /// 在可移动范围内选择信息熵最高的裁剪位置
+    ///
+    /// 候选位置按离中心的距离排序，熵相同时优先靠近中心的位置。
+    fn entropy_offset(img: &DynamicImage, crop_width: u32, crop_height: u32) -> (u32, u32) {
+        const STEPS: u32 = 24;
+
+        let (width, height) = img.dimensions();
+        let slack_x = width - crop_width;
+        let slack_y = height - crop_height;
+        if slack_x == 0 && slack_y == 0 {
+            return (0, 0);
+        }
+
+        let candidates = |slack: u32| -> Vec<u32> {
+            let steps = STEPS.min(slack);
+            let mut offsets: Vec<u32> = (0..=steps)
+                .map(|i| (slack as u64 * i as u64 / steps.max(1) as u64) as u32)
+                .collect();
+            offsets.dedup();
+            offsets.sort_by_key(|offset| (*offset as i64 * 2 - slack as i64).abs());
+            offsets
+        };
+
+        let luma = img.to_luma8();
+        // 大图按步长采样，控制计算量
+        let stride = ((crop_width as u64 * crop_height as u64 / 65_536) as f64)
+            .sqrt()
+            .max(1.0) as u32;
+
+        let mut best = (0, 0);
+        let mut best_entropy = f64::MIN;
+        for y in candidates(slack_y) {
+            for x in candidates(slack_x) {
+                let mut histogram = [0u32; 256];
+                let mut total = 0u32;
+                for py in (y..y + crop_height).step_by(stride as usize) {
+                    for px in (x..x + crop_width).step_by(stride as usize) {
+                        histogram[luma.get_pixel(px, py).0[0] as usize] += 1;
+                        total += 1;
+                    }
+                }
+
+                let entropy: f64 = histogram
+                    .iter()
+                    .filter(|&&count| count > 0)
+                    .map(|&count| {
+                        let p = count as f64 / total as f64;
+                        -p * p.log2()
+                    })
+                    .sum();
+
+                if entropy > best_entropy {
+                    best_entropy = entropy;
+                    best = (x, y);
+                }
+            }
+        }
+
+        info!(
+            "smart裁剪位置: +{}+{}, 信息熵: {:.3}",
+            best.0, best.1, best_entropy
+        );
+        best
+    }
+
+    /// 高质量图片缩放（等比缩放到框内）
     pub fn resize_image_hq(
         img: DynamicImage,
         max_width: Option<u32>,
         max_height: Option<u32>,
+        allow_upscale: bool,
     ) -> Result<DynamicImage, AppError> {
         let (current_width, current_height) = img.dimensions();
 
@@ -146,14 +550,14 @@ impl StaticImageTransform {
 
         // 防止放大小图：如果目标尺寸大于原始尺寸，保持原始尺寸
         let (final_width, final_height) =
-            if target_width > current_width || target_height > current_height {
+            if !allow_upscale && (target_width > current_width || target_height > current_height) {
                 warn!(
                     "目标尺寸大于原始尺寸，保持原始尺寸: {}x{}",
                     current_width, current_height
                 );
                 (current_width, current_height)
             } else {
-                (target_width, target_height)
+                (target_width.max(1), target_height.max(1))
             };
 
         // 如果尺寸没有变化，返回原图
@@ -184,13 +588,10 @@ impl StaticImageTransform {
 
         info!("移除透明通道，应用背景色");
 
-        // 确定背景色
-        let (bg_r, bg_g, bg_b) = match bg_color {
-            Some(BackgroundColor::White) => (255, 255, 255),
-            Some(BackgroundColor::Black) => (0, 0, 0),
-            Some(BackgroundColor::Custom(r, g, b)) => (*r, *g, *b),
-            None => (255, 255, 255), // 默认白色背景
-        };
+        // 确定背景色，默认白色背景
+        let (bg_r, bg_g, bg_b) = bg_color
+            .as_ref()
+            .map_or((255, 255, 255), BackgroundColor::rgb);
 
         let (width, height) = img.dimensions();
         let rgba_img = img.to_rgba8();
@@ -223,7 +624,7 @@ impl StaticImageTransform {
     }
 
     /// 使用专用编码器进行高级编码
-    pub async fn encode_with_specialized_encoder(
+    pub fn encode_with_specialized_encoder(
         img: DynamicImage,
         format: ImageFormat,
         params: &ImageTransformParams,
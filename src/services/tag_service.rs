@@ -0,0 +1,99 @@
+use sea_orm::DatabaseConnection;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use crate::models::{ImageInfo, TagCount};
+use crate::repositories::TagRepository;
+use crate::utils::AppError;
+
+/// 单张图片的最大标签数
+const MAX_TAGS_PER_IMAGE: usize = 32;
+
+/// 单个标签的最大长度（字符）
+const MAX_TAG_LENGTH: usize = 50;
+
+/// 图片标签业务逻辑
+pub struct TagService {
+    repo: TagRepository,
+}
+
+impl TagService {
+    pub fn new(connection: Arc<DatabaseConnection>) -> Self {
+        Self {
+            repo: TagRepository::new(connection),
+        }
+    }
+
+    /// 规范化标签：去除首尾空白、转为小写、去重并排序
+    ///
+    /// 逗号在查询参数中用作分隔符，不能出现在标签中。
+    pub fn normalize(tags: &[String]) -> Result<Vec<String>, AppError> {
+        let mut normalized = BTreeSet::new();
+        for tag in tags {
+            let tag = tag.trim().to_lowercase();
+            if tag.is_empty() {
+                continue;
+            }
+            if tag.contains(',') {
+                return Err(AppError::BadRequest(format!("标签不能包含逗号: {}", tag)));
+            }
+            if tag.chars().count() > MAX_TAG_LENGTH {
+                return Err(AppError::BadRequest(format!(
+                    "标签不能超过 {} 个字符: {}",
+                    MAX_TAG_LENGTH, tag
+                )));
+            }
+            normalized.insert(tag);
+        }
+
+        if normalized.len() > MAX_TAGS_PER_IMAGE {
+            return Err(AppError::BadRequest(format!(
+                "每张图片最多 {} 个标签",
+                MAX_TAGS_PER_IMAGE
+            )));
+        }
+        Ok(normalized.into_iter().collect())
+    }
+
+    /// 替换图片的全部标签，返回规范化后的标签
+    pub async fn set_tags(&self, hash: &str, tags: &[String]) -> Result<Vec<String>, AppError> {
+        let tags = Self::normalize(tags)?;
+        self.repo.replace(hash, &tags).await?;
+        Ok(tags)
+    }
+
+    /// 获取图片的标签
+    pub async fn get_tags(&self, hash: &str) -> Result<Vec<String>, AppError> {
+        self.repo.find_by_image(hash).await
+    }
+
+    /// 为图片列表填充标签
+    pub async fn attach_tags(&self, images: &mut [ImageInfo]) -> Result<(), AppError> {
+        let hashes: Vec<String> = images.iter().map(|image| image.hash.clone()).collect();
+        let mut tags = self.repo.find_by_images(&hashes).await?;
+        for image in images {
+            image.tags = tags.remove(&image.hash).unwrap_or_default();
+        }
+        Ok(())
+    }
+
+    /// 删除图片的全部标签
+    pub async fn delete_tags(&self, hash: &str) -> Result<(), AppError> {
+        self.repo.delete_by_image(hash).await?;
+        Ok(())
+    }
+
+    /// 统计标签的使用次数（所有者为空时统计全部图片）
+    pub async fn list_tags(&self, owner_token_id: Option<i32>) -> Result<Vec<TagCount>, AppError> {
+        Ok(self
+            .repo
+            .count_by_tag(owner_token_id)
+            .await?
+            .into_iter()
+            .map(|(tag, count)| TagCount {
+                tag,
+                count: count as u64,
+            })
+            .collect())
+    }
+}
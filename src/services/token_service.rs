@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use chrono::Utc;
@@ -6,11 +7,13 @@ use sea_orm::{ActiveValue::Set, DatabaseConnection};
 use sha2::{Digest, Sha256};
 use tracing::info;
 
-use crate::database::DatabasePool;
+use crate::app_state::AppState;
 use crate::entities::api_token;
-use crate::models::{ApiTokenInfo, CreateTokenPayload, CreateTokenResponse, TokenRole};
+use crate::models::{
+    ApiTokenInfo, CreateTokenPayload, CreateTokenResponse, QuotaDrift, QuotaReconcileReport, TokenRole,
+};
 use crate::repositories::{ImageRepository, TokenRepository};
-use crate::services::{CacheService, ImageService};
+use crate::services::{AlbumService, TrashService};
 use crate::utils::AppError;
 
 /// Token 业务逻辑
@@ -52,6 +55,8 @@ impl TokenService {
             role: Set(TokenRole::Admin.as_str().to_string()),
             max_upload_size: Set(None),
             used_upload_size: Set(0),
+            max_image_count: Set(None),
+            used_image_count: Set(0),
             expires_at: Set(None),
             is_active: Set(true),
             created_at: Set(now),
@@ -127,6 +132,8 @@ impl TokenService {
             role: Set(payload.role.as_str().to_string()),
             max_upload_size: Set(max_upload_size),
             used_upload_size: Set(0),
+            max_image_count: Set(payload.max_image_count.map(|count| count as i64)),
+            used_image_count: Set(0),
             expires_at: Set(payload.expires_at),
             is_active: Set(true),
             created_at: Set(now),
@@ -144,7 +151,7 @@ impl TokenService {
 
     pub async fn delete_token_with_data(
         &self,
-        pool: &DatabasePool,
+        app_state: &AppState,
         token_id: i32,
     ) -> Result<(), AppError> {
         let token = self
@@ -161,45 +168,148 @@ impl TokenService {
             ));
         }
 
-        let connection = pool.get_connection();
+        let connection = app_state.db_pool().get_connection();
         let image_repo = ImageRepository::new(connection.clone());
-        let cache_service = CacheService::new(connection.clone())?;
         let images = {
             use crate::repositories::ImageRepositoryTrait;
             image_repo.find_by_owner(token_id).await?
         };
-        let total = images.len();
+        let mut total = 0;
         let mut cleaned_cache = 0u64;
 
-        for image in images {
-            // 删除图片文件
-            ImageService::delete_image(pool, &image.hash).await?;
-            // 清理相关缓存
-            cleaned_cache += cache_service.remove_by_original_hash(&image.hash).await?;
+        // 令牌删除后无法再恢复其图片，回收站中的图片一并彻底删除
+        for image in &images {
+            cleaned_cache += TrashService::purge(app_state, image).await?;
+            total += 1;
         }
 
+        let albums = AlbumService::new(connection)
+            .delete_albums_by_owner(token_id)
+            .await?;
+
         self.repo.delete_by_id(token_id).await?;
         info!(
-            "删除Token {} 完成，移除{}张图片，清理{}个缓存",
+            "删除Token {} 完成，移除{}张图片、{}个相册，清理{}个缓存",
             token_id,
             total,
+            albums,
             cleaned_cache
         );
         Ok(())
     }
 
-    pub async fn reserve_storage(&self, token_id: i32, bytes: i64) -> Result<(), AppError> {
-        if bytes <= 0 {
+    /// 禁用令牌，已上传的图片保留；不能禁用最后一个可用的管理员令牌
+    pub async fn revoke_token(&self, token_id: i32) -> Result<ApiTokenInfo, AppError> {
+        let token = self
+            .repo
+            .find_by_id(token_id)
+            .await?
+            .ok_or_else(|| AppError::BadRequest("Token不存在".to_string()))?;
+
+        if token.is_active
+            && matches!(TokenRole::from(token.role.as_str()), TokenRole::Admin)
+            && self.repo.count_active_admins().await? <= 1
+        {
+            return Err(AppError::BadRequest(
+                "系统至少需要一个可用的管理员令牌".to_string(),
+            ));
+        }
+
+        let mut active: api_token::ActiveModel = token.into();
+        active.is_active = Set(false);
+        active.updated_at = Set(Utc::now());
+        let model = self.repo.update(active).await?;
+        info!("已禁用Token {}", token_id);
+        Ok(model.into())
+    }
+
+    /// 为令牌生成新的明文，旧的明文立即失效（用于找回遗失的管理员令牌）
+    pub async fn rotate_token(&self, token_id: i32) -> Result<CreateTokenResponse, AppError> {
+        let token = self
+            .repo
+            .find_by_id(token_id)
+            .await?
+            .ok_or_else(|| AppError::BadRequest("Token不存在".to_string()))?;
+
+        let plaintext = Self::generate_token();
+        let mut active: api_token::ActiveModel = token.into();
+        active.token_hash = Set(Self::hash_token(&plaintext));
+        active.updated_at = Set(Utc::now());
+        let model = self.repo.update(active).await?;
+        info!("已重新生成Token {} 的明文", token_id);
+
+        Ok(CreateTokenResponse {
+            token: model.into(),
+            plaintext,
+        })
+    }
+
+    /// 获取最早创建的可用管理员令牌
+    pub async fn first_admin(&self) -> Result<Option<ApiTokenInfo>, AppError> {
+        Ok(self.repo.find_first_admin().await?.map(ApiTokenInfo::from))
+    }
+
+    /// 占用令牌的上传配额（字节数和图片数量），超过任一上限时返回错误
+    pub async fn reserve_storage(&self, token_id: i32, bytes: i64, images: i64) -> Result<(), AppError> {
+        if bytes <= 0 && images <= 0 {
             return Ok(());
         }
-        self.repo.adjust_usage(token_id, bytes).await
+        self.repo.adjust_usage(token_id, bytes.max(0), images.max(0)).await
     }
 
-    pub async fn release_storage(&self, token_id: i32, bytes: i64) -> Result<(), AppError> {
-        if bytes <= 0 {
+    /// 退还令牌的上传配额（字节数和图片数量）
+    pub async fn release_storage(&self, token_id: i32, bytes: i64, images: i64) -> Result<(), AppError> {
+        if bytes <= 0 && images <= 0 {
             return Ok(());
         }
-        self.repo.adjust_usage(token_id, -bytes).await
+        self.repo.adjust_usage(token_id, -bytes.max(0), -images.max(0)).await
+    }
+
+    /// 按图片记录重新计算所有令牌的已用配额（包括回收站中的图片），dry_run 时只报告不修改
+    pub async fn reconcile_usage(
+        &self,
+        image_repo: &ImageRepository,
+        dry_run: bool,
+    ) -> Result<QuotaReconcileReport, AppError> {
+        use crate::repositories::ImageRepositoryTrait;
+
+        let usage: HashMap<i32, (i64, i64)> = image_repo
+            .usage_by_owner()
+            .await?
+            .into_iter()
+            .map(|(owner, size, count)| (owner, (size, count)))
+            .collect();
+
+        let tokens = self.repo.list_models().await?;
+        let mut drifted = Vec::new();
+        for token in &tokens {
+            let (actual_size, actual_count) = usage.get(&token.id).copied().unwrap_or((0, 0));
+            if token.used_upload_size == actual_size && token.used_image_count == actual_count {
+                continue;
+            }
+
+            if !dry_run {
+                self.repo.set_usage(token.id, actual_size, actual_count).await?;
+                info!(
+                    "修正Token {} 的已用配额: {}字节/{}张 -> {}字节/{}张",
+                    token.id, token.used_upload_size, token.used_image_count, actual_size, actual_count
+                );
+            }
+            drifted.push(QuotaDrift {
+                token_id: token.id,
+                name: token.name.clone(),
+                recorded_size: token.used_upload_size,
+                actual_size,
+                recorded_count: token.used_image_count,
+                actual_count,
+            });
+        }
+
+        Ok(QuotaReconcileReport {
+            dry_run,
+            tokens_checked: tokens.len(),
+            drifted,
+        })
     }
 
     pub async fn get_token(&self, token_id: i32) -> Result<ApiTokenInfo, AppError> {
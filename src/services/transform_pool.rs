@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tokio::sync::{oneshot, watch};
+use tracing::{error, info, warn};
+
+use crate::config::TransformConfig;
+use crate::services::metrics::Metrics;
+use crate::utils::AppError;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// 转换结果（数据和MIME类型）
+pub type TransformOutput = (Vec<u8>, String);
+
+/// 在相同请求之间共享的转换结果
+type SharedResult = Result<Arc<TransformOutput>, Arc<AppError>>;
+
+type Inflight = Arc<Mutex<HashMap<String, watch::Receiver<Option<SharedResult>>>>>;
+
+/// 图片转换工作线程池
+///
+/// 解码、缩放和编码在独立的工作线程上执行，不占用异步运行时的线程。
+/// 等待执行的任务数有上限，队列已满时直接返回 503，而不是让请求无限堆积；
+/// 同一缓存键的并发请求只计算一次，结果由所有等待的请求共享。
+pub struct TransformPool {
+    sender: SyncSender<Job>,
+    workers: usize,
+    queued: Arc<AtomicUsize>,
+    inflight: Inflight,
+}
+
+impl TransformPool {
+    /// 根据配置启动工作线程
+    pub fn from_config(config: &TransformConfig) -> Result<Self, AppError> {
+        let workers = if config.workers == 0 {
+            thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        } else {
+            config.workers
+        };
+
+        let (sender, receiver) = mpsc::sync_channel::<Job>(config.queue_size);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let queued = Arc::new(AtomicUsize::new(0));
+        for index in 0..workers {
+            let receiver = receiver.clone();
+            let queued = queued.clone();
+            thread::Builder::new()
+                .name(format!("rifs-transform-{}", index))
+                .spawn(move || Self::worker_loop(&receiver, &queued))
+                .map_err(|e| AppError::Internal(format!("启动图片转换工作线程失败: {}", e)))?;
+        }
+        Ok(Self {
+            sender,
+            workers,
+            queued,
+            inflight: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// 工作线程数
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// 在工作线程上执行任务，队列已满时立即返回 [`AppError::ServiceUnavailable`]
+    pub async fn execute<T, F>(&self, job: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    {
+        let (result_sender, result_receiver) = oneshot::channel();
+        let job: Job = Box::new(move || {
+            let _ = result_sender.send(job());
+        });
+
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        if let Err(e) = self.sender.try_send(job) {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err(match e {
+                TrySendError::Full(_) => {
+                    warn!("图片转换队列已满，拒绝请求");
+                    Metrics::get().observe_transform_rejected();
+                    AppError::ServiceUnavailable("图片转换繁忙，请稍后重试".to_string())
+                }
+                TrySendError::Disconnected(_) => {
+                    AppError::Internal("图片转换工作线程已停止".to_string())
+                }
+            });
+        }
+        Metrics::get().set_transform_queue_depth(self.queued.load(Ordering::SeqCst));
+
+        result_receiver
+            .await
+            .map_err(|_| AppError::Internal("图片转换任务异常退出".to_string()))?
+    }
+
+    /// 合并相同键的并发任务
+    ///
+    /// 第一个请求在后台任务中执行 `task`，之后到达的相同请求等待同一个结果；
+    /// 即使发起请求的客户端断开，计算也会完成，以便其他等待者拿到结果。
+    pub async fn deduplicate<F>(&self, key: &str, task: F) -> Result<TransformOutput, AppError>
+    where
+        F: Future<Output = Result<TransformOutput, AppError>> + Send + 'static,
+    {
+        let mut receiver = {
+            let mut inflight = self.inflight.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(receiver) = inflight.get(key) {
+                info!("合并进行中的相同转换: {}", key);
+                receiver.clone()
+            } else {
+                let (sender, receiver) = watch::channel(None);
+                inflight.insert(key.to_string(), receiver.clone());
+
+                let guard = InflightGuard {
+                    inflight: self.inflight.clone(),
+                    key: key.to_string(),
+                };
+                tokio::spawn(async move {
+                    let result = task.await.map(Arc::new).map_err(Arc::new);
+                    // 先移除再通知，之后到达的请求不会再拿到已完成的任务
+                    drop(guard);
+                    let _ = sender.send(Some(result));
+                });
+                receiver
+            }
+        };
+
+        let result = receiver
+            .wait_for(Option::is_some)
+            .await
+            .map_err(|_| AppError::Internal("图片转换任务异常退出".to_string()))?
+            .clone()
+            .expect("已等待到结果");
+        match result {
+            Ok(output) => Ok(output.as_ref().clone()),
+            Err(e) => Err(shared_error(&e)),
+        }
+    }
+
+    fn worker_loop(receiver: &Mutex<Receiver<Job>>, queued: &AtomicUsize) {
+        loop {
+            let job = match receiver.lock().unwrap_or_else(|e| e.into_inner()).recv() {
+                Ok(job) => job,
+                Err(_) => return,
+            };
+            let depth = queued.fetch_sub(1, Ordering::SeqCst) - 1;
+            Metrics::get().set_transform_queue_depth(depth);
+
+            if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                error!("图片转换任务发生panic");
+            }
+        }
+    }
+}
+
+/// 任务结束（包括panic）时移除进行中的记录
+struct InflightGuard {
+    inflight: Inflight,
+    key: String,
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.inflight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.key);
+    }
+}
+
+/// 为共享同一结果的其他请求复制错误
+fn shared_error(err: &AppError) -> AppError {
+    match err {
+        AppError::FileIo(e) => AppError::FileIo(std::io::Error::new(e.kind(), e.to_string())),
+        AppError::UnsupportedFileType => AppError::UnsupportedFileType,
+        AppError::FileTooLarge { max_size } => AppError::FileTooLarge {
+            max_size: *max_size,
+        },
+        AppError::FileNotFound => AppError::FileNotFound,
+        AppError::NotFound(msg) => AppError::NotFound(msg.clone()),
+        AppError::InvalidFile => AppError::InvalidFile,
+        AppError::Unauthorized(msg) => AppError::Unauthorized(msg.clone()),
+        AppError::Forbidden(msg) => AppError::Forbidden(msg.clone()),
+        AppError::Internal(msg) => AppError::Internal(msg.clone()),
+        AppError::BadRequest(msg) => AppError::BadRequest(msg.clone()),
+        AppError::TooManyRequests { retry_after } => AppError::TooManyRequests {
+            retry_after: *retry_after,
+        },
+        AppError::ServiceUnavailable(msg) => AppError::ServiceUnavailable(msg.clone()),
+    }
+}
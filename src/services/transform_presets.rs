@@ -0,0 +1,88 @@
+use std::collections::{BTreeMap, HashMap};
+use tracing::info;
+
+use super::{image_format_utils::ImageFormatUtils, ImageTransformService};
+use crate::models::ImageTransformParams;
+use crate::utils::AppError;
+
+/// 图片地址中引用预设的前缀，如 `/images/<hash>@preset:thumb`
+pub const PRESET_PREFIX: &str = "preset:";
+
+/// 命名转换预设
+///
+/// 启动时解析并校验配置中的 `[presets]`，请求时按名称查找，无需重复解析。
+#[derive(Debug, Clone, Default)]
+pub struct TransformPresets {
+    presets: HashMap<String, ImageTransformParams>,
+}
+
+impl TransformPresets {
+    /// 解析配置中的预设，任一预设无效时返回错误
+    pub fn from_config(presets: &BTreeMap<String, String>) -> Result<Self, AppError> {
+        let mut parsed = HashMap::with_capacity(presets.len());
+
+        for (name, params_str) in presets {
+            if !Self::is_valid_name(name) {
+                return Err(AppError::Internal(format!(
+                    "无效的预设名称: {}，只能包含字母、数字、- 和 _",
+                    name
+                )));
+            }
+
+            let params = ImageTransformParams::parse(params_str)
+                .map_err(|e| AppError::Internal(format!("预设 {} 解析失败: {}", name, e)))?;
+            ImageFormatUtils::validate_params(&params)
+                .map_err(|e| AppError::Internal(format!("预设 {} 参数无效: {}", name, e)))?;
+
+            info!(
+                "加载转换预设: {} -> {}",
+                name,
+                params.to_normalized_string()
+            );
+            if parsed.insert(name.to_lowercase(), params).is_some() {
+                return Err(AppError::Internal(format!("预设名称重复: {}", name)));
+            }
+        }
+
+        Ok(Self { presets: parsed })
+    }
+
+    /// 按名称获取预设（名称不区分大小写）
+    pub fn get(&self, name: &str) -> Option<&ImageTransformParams> {
+        self.presets.get(&name.to_lowercase())
+    }
+
+    /// 解析图片地址中 `@` 之后的转换参数
+    ///
+    /// `preset:名称` 引用预设；其他参数字符串在 `allow_custom` 为 false 时拒绝。
+    pub fn resolve(
+        &self,
+        params_str: &str,
+        allow_custom: bool,
+    ) -> Result<ImageTransformParams, AppError> {
+        if let Some(name) = params_str.strip_prefix(PRESET_PREFIX) {
+            return self
+                .get(name)
+                .cloned()
+                .ok_or_else(|| AppError::BadRequest(format!("转换预设不存在: {}", name)));
+        }
+
+        if !allow_custom {
+            return Err(AppError::Forbidden(
+                "仅允许使用预设转换参数，请使用 @preset:<名称>".to_string(),
+            ));
+        }
+
+        let params = ImageTransformParams::parse(params_str)
+            .map_err(|e| AppError::BadRequest(format!("转换参数解析失败: {}", e)))?;
+        ImageTransformService::validate_params(&params)?;
+        Ok(params)
+    }
+
+    fn is_valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+}
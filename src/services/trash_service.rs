@@ -0,0 +1,145 @@
+use chrono::{DateTime, Utc};
+use tracing::{info, warn};
+
+use crate::app_state::AppState;
+use crate::config::AppConfig;
+use crate::models::ImageInfo;
+use crate::repositories::{ImageRepository, ImageRepositoryTrait};
+use crate::services::{CacheService, ImageService};
+use crate::utils::AppError;
+
+/// 清理回收站时每批读取的数量
+const PURGE_BATCH_SIZE: u64 = 100;
+
+/// 回收站服务
+///
+/// 删除的图片只记录删除时间，记录、文件和占用的配额都保留，恢复后原样可用；
+/// 彻底删除时才删除记录、释放文件引用并退还所有者的配额。
+pub struct TrashService;
+
+impl TrashService {
+    /// 删除图片：启用回收站且未要求彻底删除时移入回收站，否则立即删除；返回清理的缓存数量
+    pub async fn delete(
+        app_state: &AppState,
+        image: &ImageInfo,
+        permanent: bool,
+    ) -> Result<u64, AppError> {
+        if permanent || !AppConfig::get().trash.enabled {
+            Self::purge(app_state, image).await
+        } else {
+            Self::trash(app_state, image).await
+        }
+    }
+
+    /// 把图片移入回收站，转换缓存随之清理
+    pub async fn trash(app_state: &AppState, image: &ImageInfo) -> Result<u64, AppError> {
+        let image_repo = ImageRepository::new(app_state.db_pool().get_connection());
+        if !image_repo
+            .update_deleted_at(&image.hash, Some(Utc::now()))
+            .await?
+        {
+            return Err(AppError::FileNotFound);
+        }
+
+        info!("图片已移入回收站: {}", image.hash);
+        Ok(Self::remove_caches(app_state, &image.hash).await)
+    }
+
+    /// 查询回收站中的图片
+    pub async fn get_trashed(
+        app_state: &AppState,
+        identifier: &str,
+    ) -> Result<Option<ImageInfo>, AppError> {
+        let image_repo = ImageRepository::new(app_state.db_pool().get_connection());
+        Ok(image_repo
+            .find_by_hash(identifier)
+            .await?
+            .filter(|image| image.deleted_at.is_some()))
+    }
+
+    /// 从回收站恢复图片
+    pub async fn restore(app_state: &AppState, image: &ImageInfo) -> Result<ImageInfo, AppError> {
+        let image_repo = ImageRepository::new(app_state.db_pool().get_connection());
+        if !image_repo.update_deleted_at(&image.hash, None).await? {
+            return Err(AppError::FileNotFound);
+        }
+
+        info!("图片已从回收站恢复: {}", image.hash);
+        Ok(ImageInfo {
+            deleted_at: None,
+            ..image.clone()
+        })
+    }
+
+    /// 彻底删除图片（无论是否在回收站中），退还所有者的配额；返回清理的缓存数量
+    pub async fn purge(app_state: &AppState, image: &ImageInfo) -> Result<u64, AppError> {
+        ImageService::delete_image(app_state.db_pool(), app_state.storage(), &image.hash).await?;
+
+        info!("图片已彻底删除: {}", image.hash);
+        Ok(Self::remove_caches(app_state, &image.hash).await)
+    }
+
+    /// 清空回收站（所有者为空时清空全部），返回彻底删除的图片数量
+    pub async fn empty(app_state: &AppState, owner_token_id: Option<i32>) -> Result<u64, AppError> {
+        Self::purge_trashed_before(app_state, Utc::now(), owner_token_id).await
+    }
+
+    /// 彻底删除超过保留期的图片，返回删除的数量
+    pub async fn purge_expired(app_state: &AppState) -> Result<u64, AppError> {
+        let retention = AppConfig::get().trash.retention.as_seconds();
+        let cutoff = Utc::now() - chrono::Duration::seconds(retention as i64);
+        let purged = Self::purge_trashed_before(app_state, cutoff, None).await?;
+        if purged > 0 {
+            info!("回收站清理完成: 彻底删除{}张过期图片", purged);
+        }
+        Ok(purged)
+    }
+
+    /// 分批彻底删除在给定时间之前移入回收站的图片
+    async fn purge_trashed_before(
+        app_state: &AppState,
+        before: DateTime<Utc>,
+        owner_token_id: Option<i32>,
+    ) -> Result<u64, AppError> {
+        let image_repo = ImageRepository::new(app_state.db_pool().get_connection());
+        let mut purged = 0;
+        loop {
+            let batch = image_repo
+                .find_trashed_before(before, owner_token_id, PURGE_BATCH_SIZE)
+                .await?;
+            if batch.is_empty() {
+                break;
+            }
+            for image in &batch {
+                Self::purge(app_state, image).await?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+
+    /// 清理图片的转换缓存，失败时只记录日志
+    async fn remove_caches(app_state: &AppState, hash: &str) -> u64 {
+        if !AppConfig::get().cache.enable_transform_cache {
+            return 0;
+        }
+
+        let connection = app_state.db_pool().get_connection();
+        let result = match CacheService::new(connection, app_state.cache_storage()) {
+            Ok(cache_service) => cache_service.remove_by_original_hash(hash).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(count) => {
+                if count > 0 {
+                    info!("删除图片{}的{}个相关缓存", hash, count);
+                }
+                count
+            }
+            Err(e) => {
+                warn!("删除图片缓存失败: {}", e);
+                0
+            }
+        }
+    }
+}
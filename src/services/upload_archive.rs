@@ -0,0 +1,209 @@
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{Read, Seek};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::config::AppConfig;
+use crate::storage::{StagedUpload, UploadStager};
+use crate::utils::AppError;
+
+/// 批量上传支持的压缩包格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// 根据文件头识别压缩包格式，不是压缩包时返回 None
+    pub fn detect(head: &[u8]) -> Option<Self> {
+        if head.starts_with(b"PK\x03\x04") {
+            Some(Self::Zip)
+        } else if head.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::TarGz)
+        } else if head.get(257..262) == Some(b"ustar".as_slice()) {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// 压缩包中的一个文件
+pub struct ArchiveEntry {
+    /// 文件在压缩包中的路径
+    pub name: String,
+    /// 暂存结果，单个文件出错时不影响其他文件
+    pub staged: Result<StagedUpload, AppError>,
+}
+
+/// 解包过程中从读取线程发往异步任务的消息
+type EntryMessage = Result<(String, Result<Vec<u8>, AppError>), AppError>;
+
+/// 解开压缩包并逐个暂存其中的文件
+///
+/// 压缩包在阻塞线程中读取，每个文件读完后交给异步任务写入暂存目录，
+/// 同一时刻最多只有一个文件的内容在内存中。目录、隐藏文件和 `__MACOSX` 元数据会被跳过。
+///
+/// 文件数超过 `max_entries` 时返回外层错误，整个批量上传失败；压缩包损坏或解压后总大小
+/// 超过批量上传上限时返回内层错误，只有这个压缩包失败。
+pub async fn extract_archive(
+    archive: &StagedUpload,
+    kind: ArchiveKind,
+    max_entries: usize,
+) -> Result<Result<Vec<ArchiveEntry>, AppError>, AppError> {
+    let path = archive.path().to_path_buf();
+    let (tx, mut rx) = mpsc::channel::<EntryMessage>(1);
+
+    let reader = tokio::task::spawn_blocking(move || {
+        let result = File::open(&path)
+            .map_err(AppError::from)
+            .and_then(|file| match kind {
+                ArchiveKind::Zip => read_zip(file, &tx),
+                ArchiveKind::Tar => read_tar(file, &tx),
+                ArchiveKind::TarGz => read_tar(GzDecoder::new(file), &tx),
+            });
+        if let Err(e) = result {
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+
+    let mut entries = Vec::new();
+    let mut failure = None;
+    while let Some(message) = rx.recv().await {
+        let (name, data) = match message {
+            Ok(entry) => entry,
+            Err(e) => {
+                failure = Some(e);
+                break;
+            }
+        };
+        // 提前返回时接收端被丢弃，读取线程随之停止
+        if entries.len() >= max_entries {
+            return Err(AppError::BadRequest(format!(
+                "批量上传最多允许 {} 个文件",
+                AppConfig::get().storage.max_batch_files
+            )));
+        }
+        let staged = match data {
+            Ok(data) => stage(&data).await,
+            Err(e) => Err(e),
+        };
+        entries.push(ArchiveEntry { name, staged });
+    }
+
+    reader
+        .await
+        .map_err(|e| AppError::Internal(format!("读取压缩包失败: {}", e)))?;
+
+    Ok(match failure {
+        Some(e) => Err(e),
+        None => Ok(entries),
+    })
+}
+
+/// 把解压出的文件内容写入暂存目录
+async fn stage(data: &[u8]) -> Result<StagedUpload, AppError> {
+    let mut stager = UploadStager::new().await?;
+    stager.write_chunk(data).await?;
+    stager.finish().await
+}
+
+/// 解压限制，跟踪解压后的总大小
+struct ExtractLimits {
+    max_total: u64,
+    max_file: u64,
+    total: u64,
+}
+
+impl ExtractLimits {
+    fn new() -> Self {
+        let config = AppConfig::get();
+        Self {
+            max_total: config.storage.max_batch_size.as_bytes(),
+            max_file: config.storage.max_file_size.as_bytes(),
+            total: 0,
+        }
+    }
+
+    /// 读取一个文件的内容，超过单文件大小限制时返回文件级错误
+    fn read_entry(
+        &mut self,
+        name: &str,
+        reader: impl Read,
+    ) -> Result<Result<Vec<u8>, AppError>, AppError> {
+        let mut data = Vec::new();
+        if let Err(e) = reader.take(self.max_file + 1).read_to_end(&mut data) {
+            warn!("读取压缩包中的文件失败: {} - {}", name, e);
+            return Ok(Err(AppError::InvalidFile));
+        }
+
+        self.total += data.len() as u64;
+        if self.total > self.max_total {
+            return Err(AppError::BadRequest(format!(
+                "压缩包解压后超过 {} 字节上限",
+                self.max_total
+            )));
+        }
+
+        if data.len() as u64 > self.max_file {
+            return Ok(Err(AppError::FileTooLarge {
+                max_size: self.max_file,
+            }));
+        }
+        Ok(Ok(data))
+    }
+}
+
+fn read_zip<R: Read + Seek>(reader: R, tx: &mpsc::Sender<EntryMessage>) -> Result<(), AppError> {
+    let mut archive = zip::ZipArchive::new(reader)
+        .map_err(|e| AppError::BadRequest(format!("无效的zip压缩包: {}", e)))?;
+    let mut limits = ExtractLimits::new();
+
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|e| AppError::BadRequest(format!("无效的zip压缩包: {}", e)))?;
+        let name = entry.name().to_string();
+        if !entry.is_file() || should_skip(&name) {
+            continue;
+        }
+
+        let data = limits.read_entry(&name, entry)?;
+        if tx.blocking_send(Ok((name, data))).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn read_tar<R: Read>(reader: R, tx: &mpsc::Sender<EntryMessage>) -> Result<(), AppError> {
+    let invalid = |e: std::io::Error| AppError::BadRequest(format!("无效的tar压缩包: {}", e));
+    let mut archive = tar::Archive::new(reader);
+    let mut limits = ExtractLimits::new();
+
+    for entry in archive.entries().map_err(invalid)? {
+        let entry = entry.map_err(invalid)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path().map_err(invalid)?.to_string_lossy().to_string();
+        if should_skip(&name) {
+            continue;
+        }
+
+        let data = limits.read_entry(&name, entry)?;
+        if tx.blocking_send(Ok((name, data))).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// 跳过隐藏文件和 macOS 生成的资源文件
+fn should_skip(name: &str) -> bool {
+    name.split('/')
+        .any(|part| (part.starts_with('.') && part != ".") || part == "__MACOSX")
+}
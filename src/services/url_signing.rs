@@ -0,0 +1,92 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::config::AuthConfig;
+use crate::utils::AppError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 签名URL的默认有效期（秒）
+pub const DEFAULT_SIGNED_URL_TTL: u64 = 3600;
+
+/// 图片URL签名
+///
+/// 签名覆盖图片哈希、转换参数字符串和过期时间（Unix 秒），任一部分被修改都会导致校验失败。
+pub struct UrlSigner {
+    key: Vec<u8>,
+}
+
+impl UrlSigner {
+    /// 使用指定密钥创建签名器
+    pub fn new(secret: &str) -> Self {
+        Self {
+            key: secret.as_bytes().to_vec(),
+        }
+    }
+
+    /// 从认证配置创建签名器
+    ///
+    /// 未配置密钥属于服务端配置问题，生成和校验签名时都返回服务器内部错误。
+    pub fn from_config(config: &AuthConfig) -> Result<Self, AppError> {
+        config
+            .url_signing_secret
+            .as_deref()
+            .map(str::trim)
+            .filter(|secret| !secret.is_empty())
+            .map(Self::new)
+            .ok_or_else(|| AppError::Internal("服务未配置URL签名密钥".to_string()))
+    }
+
+    /// 计算签名（URL安全的 Base64，无填充）
+    pub fn sign(&self, hash: &str, transform: &str, expires: i64) -> String {
+        URL_SAFE_NO_PAD.encode(self.mac(hash, transform, expires).finalize().into_bytes())
+    }
+
+    /// 校验签名和过期时间
+    pub fn verify(
+        &self,
+        hash: &str,
+        transform: &str,
+        expires: i64,
+        signature: &str,
+        now: i64,
+    ) -> Result<(), AppError> {
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| AppError::Forbidden("签名格式无效".to_string()))?;
+
+        // verify_slice 使用常量时间比较
+        self.mac(hash, transform, expires)
+            .verify_slice(&signature)
+            .map_err(|_| AppError::Forbidden("签名无效".to_string()))?;
+
+        if expires < now {
+            return Err(AppError::Forbidden("签名URL已过期".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// 构造带签名参数的图片路径
+    pub fn signed_path(&self, hash: &str, transform: &str, expires: i64) -> String {
+        let signature = self.sign(hash, transform, expires);
+        let identifier = if transform.is_empty() {
+            hash.to_string()
+        } else {
+            // 背景色参数中的 # 需要转义，否则会被当作URL片段
+            format!("{}@{}", hash, transform.replace('#', "%23"))
+        };
+
+        format!(
+            "/images/{}?expires={}&signature={}",
+            identifier, expires, signature
+        )
+    }
+
+    fn mac(&self, hash: &str, transform: &str, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC可以接受任意长度的密钥");
+        mac.update(format!("{}\n{}\n{}", hash, transform, expires).as_bytes());
+        mac
+    }
+}
@@ -0,0 +1,192 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use std::io::{ErrorKind, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+
+use super::{validate_key, ByteStream, ObjectMeta, StorageBackend};
+use crate::utils::AppError;
+
+/// 本地文件系统存储后端
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    /// 创建以指定目录为根的本地存储
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 将对象键解析为磁盘路径
+    fn resolve(&self, key: &str) -> Result<PathBuf, AppError> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+
+    /// 生成与目标文件同目录的隐藏临时文件路径
+    fn temp_path_for(path: &Path) -> PathBuf {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        path.with_file_name(format!(".{}.{:08x}.tmp", file_name, rand::random::<u32>()))
+    }
+
+    fn to_meta(key: String, metadata: &std::fs::Metadata) -> ObjectMeta {
+        ObjectMeta {
+            key,
+            size: metadata.len(),
+            last_modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), AppError> {
+        let path = self.resolve(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // 先写入临时文件再重命名，避免读取到写了一半的文件
+        let tmp_path = Self::temp_path_for(&path);
+        let mut file = fs::File::create(&tmp_path).await?;
+        file.write_all(data).await?;
+        file.sync_all().await?;
+        drop(file);
+
+        if let Err(e) = fs::rename(&tmp_path, &path).await {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    async fn put_file(&self, key: &str, source: &Path) -> Result<(), AppError> {
+        let path = self.resolve(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // 同一文件系统内直接重命名；跨文件系统时先复制到临时文件再重命名
+        if fs::rename(source, &path).await.is_ok() {
+            return Ok(());
+        }
+
+        let tmp_path = Self::temp_path_for(&path);
+        let result = async {
+            fs::copy(source, &tmp_path).await?;
+            fs::File::open(&tmp_path).await?.sync_all().await?;
+            fs::rename(&tmp_path, &path).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError> {
+        let path = self.resolve(key)?;
+        match fs::read(&path).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(AppError::FileNotFound),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn get_stream(&self, key: &str) -> Result<ByteStream, AppError> {
+        let path = self.resolve(key)?;
+        match fs::File::open(&path).await {
+            Ok(file) => Ok(ReaderStream::new(file).boxed()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(AppError::FileNotFound),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn get_range_stream(&self, key: &str, range: Range<u64>) -> Result<ByteStream, AppError> {
+        let path = self.resolve(key)?;
+        let mut file = match fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Err(AppError::FileNotFound),
+            Err(e) => return Err(e.into()),
+        };
+        file.seek(SeekFrom::Start(range.start)).await?;
+        let reader = file.take(range.end.saturating_sub(range.start));
+        Ok(ReaderStream::new(reader).boxed())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        let path = self.resolve(key)?;
+        match fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn stat(&self, key: &str) -> Result<Option<ObjectMeta>, AppError> {
+        let path = self.resolve(key)?;
+        match fs::metadata(&path).await {
+            Ok(metadata) if metadata.is_file() => {
+                Ok(Some(Self::to_meta(key.to_string(), &metadata)))
+            }
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, AppError> {
+        let mut objects = Vec::new();
+        let mut pending = vec![(self.root.clone(), String::new())];
+
+        while let Some((dir, dir_key)) = pending.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                // 跳过写入中的临时文件
+                if name.starts_with('.') {
+                    continue;
+                }
+                let key = if dir_key.is_empty() {
+                    name
+                } else {
+                    format!("{}/{}", dir_key, name)
+                };
+
+                let metadata = entry.metadata().await?;
+                if metadata.is_dir() {
+                    // 只下探可能包含匹配对象的目录
+                    if prefix.starts_with(&format!("{}/", key)) || key.starts_with(prefix) {
+                        pending.push((entry.path(), key));
+                    }
+                } else if metadata.is_file() && key.starts_with(prefix) {
+                    objects.push(Self::to_meta(key, &metadata));
+                }
+            }
+        }
+
+        objects.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(objects)
+    }
+}
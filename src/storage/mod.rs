@@ -0,0 +1,129 @@
+pub mod local;
+pub mod s3;
+pub mod staging;
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+pub use staging::{StagedUpload, UploadStager};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures_util::stream::BoxStream;
+use serde::Serialize;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::{AppConfig, StorageBackendKind};
+use crate::utils::AppError;
+
+/// 存储对象元数据
+#[derive(Debug, Clone, Serialize)]
+pub struct ObjectMeta {
+    /// 对象键（相对于存储根目录，使用 `/` 分隔）
+    pub key: String,
+    /// 对象大小（字节）
+    pub size: u64,
+    /// 最后修改时间
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// 对象内容字节流
+pub type ByteStream = BoxStream<'static, Result<Bytes, std::io::Error>>;
+
+/// 存储后端接口
+///
+/// 所有对象都通过相对键（如 `ab/cd/abcd...ef.png`）访问，
+/// 具体落在本地目录还是对象存储由实现决定。
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// 后端名称（用于日志）
+    fn name(&self) -> &'static str;
+
+    /// 写入对象，已存在时覆盖
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), AppError>;
+
+    /// 将本地文件写入为对象，已存在时覆盖
+    ///
+    /// 文件内容以流的方式写入，不会整体读入内存；源文件可能被移动，
+    /// 调用方在写入后不应再依赖它。
+    async fn put_file(&self, key: &str, path: &Path) -> Result<(), AppError>;
+
+    /// 读取对象内容，不存在时返回 `AppError::FileNotFound`
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError>;
+
+    /// 以流的方式读取对象内容，不存在时返回 `AppError::FileNotFound`
+    async fn get_stream(&self, key: &str) -> Result<ByteStream, AppError>;
+
+    /// 以流的方式读取对象的指定字节区间（`range.end` 不含），调用方需保证区间有效
+    async fn get_range_stream(&self, key: &str, range: Range<u64>) -> Result<ByteStream, AppError>;
+
+    /// 删除对象，对象不存在时视为成功
+    async fn delete(&self, key: &str) -> Result<(), AppError>;
+
+    /// 检查对象是否存在
+    async fn exists(&self, key: &str) -> Result<bool, AppError> {
+        Ok(self.stat(key).await?.is_some())
+    }
+
+    /// 获取对象元数据，不存在时返回 `None`
+    async fn stat(&self, key: &str) -> Result<Option<ObjectMeta>, AppError>;
+
+    /// 列出指定前缀下的所有对象
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, AppError>;
+}
+
+/// 存储区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageArea {
+    /// 原始上传图片
+    Uploads,
+    /// 转换结果缓存
+    Cache,
+}
+
+/// 根据配置创建指定区域的存储后端
+pub fn create_storage(
+    config: &AppConfig,
+    area: StorageArea,
+) -> Result<Arc<dyn StorageBackend>, AppError> {
+    match config.storage.backend {
+        StorageBackendKind::Local => {
+            let root = match area {
+                StorageArea::Uploads => config.storage.upload_dir.clone(),
+                StorageArea::Cache => config.cache.cache_dir.clone(),
+            };
+            Ok(Arc::new(LocalStorage::new(root)))
+        }
+        StorageBackendKind::S3 => {
+            let s3_config = config.storage.s3.as_ref().ok_or_else(|| {
+                AppError::Internal("存储后端为s3，但缺少 [storage.s3] 配置".to_string())
+            })?;
+            let namespace = match area {
+                StorageArea::Uploads => "images",
+                StorageArea::Cache => "cache",
+            };
+            Ok(Arc::new(S3Storage::new(s3_config, namespace)?))
+        }
+    }
+}
+
+/// 校验对象键，拒绝绝对路径和目录穿越
+pub fn validate_key(key: &str) -> Result<(), AppError> {
+    if key.is_empty()
+        || key.starts_with('/')
+        || key.contains('\\')
+        || key
+            .split('/')
+            .any(|segment| segment.is_empty() || segment == "." || segment == "..")
+    {
+        return Err(AppError::BadRequest(format!("无效的存储键: {}", key)));
+    }
+    Ok(())
+}
+
+/// 基于哈希生成分层存储键：`ab/cd/<name>`，避免单个目录文件过多
+pub fn sharded_key(hash: &str, name: &str) -> String {
+    format!("{}/{}/{}", &hash[0..2], &hash[2..4], name)
+}
@@ -0,0 +1,594 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, TryStreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode};
+use sha2::{Digest, Sha256};
+use std::ops::Range;
+use std::path::Path;
+use tokio_util::io::ReaderStream;
+use tracing::{debug, error};
+
+use super::{validate_key, ByteStream, ObjectMeta, StorageBackend};
+use crate::config::S3Config;
+use crate::utils::AppError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 空请求体的 SHA-256
+const EMPTY_PAYLOAD_SHA256: &str =
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// 流式上传时不对请求体签名
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// 请求体
+enum RequestBody {
+    Empty,
+    Bytes(Vec<u8>),
+    /// 流式请求体，需提前给出长度
+    Stream {
+        body: reqwest::Body,
+        length: u64,
+    },
+}
+
+/// S3 兼容对象存储后端（AWS S3、MinIO 等）
+///
+/// 使用 AWS Signature Version 4 对请求签名，不依赖官方 SDK。
+pub struct S3Storage {
+    client: reqwest::Client,
+    endpoint: reqwest::Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    path_style: bool,
+    /// 对象键前缀（不含首尾 `/`）
+    key_prefix: String,
+}
+
+impl S3Storage {
+    /// 创建 S3 存储后端，`namespace` 用于区分上传文件和缓存文件
+    pub fn new(config: &S3Config, namespace: &str) -> Result<Self, AppError> {
+        let endpoint = reqwest::Url::parse(&config.endpoint)
+            .map_err(|e| AppError::Internal(format!("无效的S3 endpoint: {}", e)))?;
+
+        if config.bucket.trim().is_empty() {
+            return Err(AppError::Internal("S3 bucket 不能为空".to_string()));
+        }
+
+        // 大文件的上传和下载都是流式传输，不设置整体超时，只限制连接和两次读取之间的等待时间
+        let timeout = std::time::Duration::from_secs(config.timeout.as_seconds());
+        let client = reqwest::Client::builder()
+            .connect_timeout(timeout)
+            .read_timeout(timeout)
+            .build()
+            .map_err(|e| AppError::Internal(format!("创建S3客户端失败: {}", e)))?;
+
+        let key_prefix = [config.prefix.trim_matches('/'), namespace.trim_matches('/')]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Ok(Self {
+            client,
+            endpoint,
+            bucket: config.bucket.clone(),
+            region: config.region.clone(),
+            access_key: config.access_key.clone(),
+            secret_key: config.secret_key.clone(),
+            path_style: config.path_style,
+            key_prefix,
+        })
+    }
+
+    /// 将对象键转换为存储桶中的完整键
+    fn full_key(&self, key: &str) -> String {
+        if self.key_prefix.is_empty() {
+            key.to_string()
+        } else if key.is_empty() {
+            format!("{}/", self.key_prefix)
+        } else {
+            format!("{}/{}", self.key_prefix, key)
+        }
+    }
+
+    /// 将存储桶中的完整键还原为对象键
+    fn strip_prefix<'a>(&self, full_key: &'a str) -> &'a str {
+        if self.key_prefix.is_empty() {
+            full_key
+        } else {
+            full_key
+                .strip_prefix(&self.key_prefix)
+                .map(|rest| rest.trim_start_matches('/'))
+                .unwrap_or(full_key)
+        }
+    }
+
+    /// 计算请求的主机名和规范化路径
+    fn host_and_path(&self, full_key: Option<&str>) -> (String, String) {
+        let host = self.endpoint.host_str().unwrap_or_default();
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        let base_path = self.endpoint.path().trim_end_matches('/');
+        let object_path = full_key.map(uri_encode_path).unwrap_or_default();
+
+        if self.path_style {
+            let path = match full_key {
+                Some(_) => format!("{}/{}/{}", base_path, self.bucket, object_path),
+                None => format!("{}/{}", base_path, self.bucket),
+            };
+            (host, path)
+        } else {
+            (
+                format!("{}.{}", self.bucket, host),
+                format!("{}/{}", base_path, object_path),
+            )
+        }
+    }
+
+    /// 发送签名请求
+    async fn send(
+        &self,
+        method: Method,
+        full_key: Option<&str>,
+        query: &[(&str, &str)],
+        body: RequestBody,
+    ) -> Result<reqwest::Response, AppError> {
+        self.send_with_headers(method, full_key, query, body, &[])
+            .await
+    }
+
+    /// 发送带附加请求头（不参与签名）的签名请求
+    async fn send_with_headers(
+        &self,
+        method: Method,
+        full_key: Option<&str>,
+        query: &[(&str, &str)],
+        body: RequestBody,
+        extra_headers: &[(&str, String)],
+    ) -> Result<reqwest::Response, AppError> {
+        let (host, path) = self.host_and_path(full_key);
+        let payload_hash = match &body {
+            RequestBody::Empty => EMPTY_PAYLOAD_SHA256.to_string(),
+            RequestBody::Bytes(data) => format!("{:x}", Sha256::digest(data)),
+            RequestBody::Stream { .. } => UNSIGNED_PAYLOAD.to_string(),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let headers = vec![
+            ("host".to_string(), host.clone()),
+            ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+            ("x-amz-date".to_string(), amz_date.clone()),
+        ];
+
+        let authorization = sign_request(
+            &SigningParams {
+                access_key: &self.access_key,
+                secret_key: &self.secret_key,
+                region: &self.region,
+                service: "s3",
+                amz_date: &amz_date,
+            },
+            method.as_str(),
+            &path,
+            query,
+            &headers,
+            &payload_hash,
+        );
+
+        let query_string = canonical_query_string(query);
+        let url = format!(
+            "{}://{}{}{}{}",
+            self.endpoint.scheme(),
+            host,
+            path,
+            if query_string.is_empty() { "" } else { "?" },
+            query_string
+        );
+
+        debug!("S3请求: {} {}", method, url);
+
+        let mut request = self
+            .client
+            .request(method, &url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header("authorization", authorization);
+        for (name, value) in extra_headers {
+            request = request.header(*name, value);
+        }
+        match body {
+            RequestBody::Empty => {}
+            RequestBody::Bytes(data) => request = request.body(data),
+            RequestBody::Stream { body, length } => {
+                request = request
+                    .header(reqwest::header::CONTENT_LENGTH, length)
+                    .body(body);
+            }
+        }
+
+        request.send().await.map_err(|e| {
+            error!("S3请求失败: {}", e);
+            AppError::Internal(format!("S3请求失败: {}", e))
+        })
+    }
+
+    /// 将非成功响应转换为错误
+    async fn error_from_response(operation: &str, response: reqwest::Response) -> AppError {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let code = extract_xml_value(&body, "Code").unwrap_or_default();
+        error!("S3{}失败: {} {}", operation, status, code);
+        AppError::Internal(format!("S3{}失败: {} {}", operation, status, code))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3Storage {
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), AppError> {
+        validate_key(key)?;
+        let full_key = self.full_key(key);
+        let response = self
+            .send(
+                Method::PUT,
+                Some(&full_key),
+                &[],
+                RequestBody::Bytes(data.to_vec()),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::error_from_response("上传", response).await)
+        }
+    }
+
+    async fn put_file(&self, key: &str, path: &Path) -> Result<(), AppError> {
+        validate_key(key)?;
+        let full_key = self.full_key(key);
+        let file = tokio::fs::File::open(path).await?;
+        let length = file.metadata().await?.len();
+        let body = RequestBody::Stream {
+            body: reqwest::Body::wrap_stream(ReaderStream::new(file)),
+            length,
+        };
+        let response = self.send(Method::PUT, Some(&full_key), &[], body).await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::error_from_response("上传", response).await)
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError> {
+        validate_key(key)?;
+        let full_key = self.full_key(key);
+        let response = self
+            .send(Method::GET, Some(&full_key), &[], RequestBody::Empty)
+            .await?;
+
+        match response.status() {
+            status if status.is_success() => response
+                .bytes()
+                .await
+                .map(|bytes| bytes.to_vec())
+                .map_err(|e| AppError::Internal(format!("读取S3对象失败: {}", e))),
+            StatusCode::NOT_FOUND => Err(AppError::FileNotFound),
+            _ => Err(Self::error_from_response("下载", response).await),
+        }
+    }
+
+    async fn get_stream(&self, key: &str) -> Result<ByteStream, AppError> {
+        validate_key(key)?;
+        let full_key = self.full_key(key);
+        let response = self
+            .send(Method::GET, Some(&full_key), &[], RequestBody::Empty)
+            .await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(response
+                .bytes_stream()
+                .map_err(std::io::Error::other)
+                .boxed()),
+            StatusCode::NOT_FOUND => Err(AppError::FileNotFound),
+            _ => Err(Self::error_from_response("下载", response).await),
+        }
+    }
+
+    async fn get_range_stream(&self, key: &str, range: Range<u64>) -> Result<ByteStream, AppError> {
+        validate_key(key)?;
+        if range.start >= range.end {
+            return Ok(futures_util::stream::empty().boxed());
+        }
+
+        let full_key = self.full_key(key);
+        let range_header = format!("bytes={}-{}", range.start, range.end - 1);
+        let response = self
+            .send_with_headers(
+                Method::GET,
+                Some(&full_key),
+                &[],
+                RequestBody::Empty,
+                &[("range", range_header)],
+            )
+            .await?;
+
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT => Ok(response
+                .bytes_stream()
+                .map_err(std::io::Error::other)
+                .boxed()),
+            // 服务端忽略了 Range 时自行截取
+            StatusCode::OK => {
+                let data = response
+                    .bytes()
+                    .await
+                    .map_err(|e| AppError::Internal(format!("读取S3对象失败: {}", e)))?;
+                let end = (range.end as usize).min(data.len());
+                let start = (range.start as usize).min(end);
+                let chunk = data.slice(start..end);
+                Ok(futures_util::stream::once(async move { Ok(chunk) }).boxed())
+            }
+            StatusCode::NOT_FOUND => Err(AppError::FileNotFound),
+            _ => Err(Self::error_from_response("下载", response).await),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        validate_key(key)?;
+        let full_key = self.full_key(key);
+        let response = self
+            .send(Method::DELETE, Some(&full_key), &[], RequestBody::Empty)
+            .await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND => Ok(()),
+            _ => Err(Self::error_from_response("删除", response).await),
+        }
+    }
+
+    async fn stat(&self, key: &str) -> Result<Option<ObjectMeta>, AppError> {
+        validate_key(key)?;
+        let full_key = self.full_key(key);
+        let response = self
+            .send(Method::HEAD, Some(&full_key), &[], RequestBody::Empty)
+            .await?;
+
+        match response.status() {
+            status if status.is_success() => {
+                let headers = response.headers();
+                let size = headers
+                    .get(reqwest::header::CONTENT_LENGTH)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .unwrap_or(0);
+                let last_modified = headers
+                    .get(reqwest::header::LAST_MODIFIED)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+                    .map(|dt| dt.with_timezone(&Utc));
+
+                Ok(Some(ObjectMeta {
+                    key: key.to_string(),
+                    size,
+                    last_modified,
+                }))
+            }
+            StatusCode::NOT_FOUND => Ok(None),
+            _ => Err(Self::error_from_response("查询", response).await),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, AppError> {
+        let full_prefix = self.full_key(prefix);
+        let mut objects = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", full_prefix.as_str())];
+            if let Some(ref token) = continuation_token {
+                query.push(("continuation-token", token.as_str()));
+            }
+
+            let response = self
+                .send(Method::GET, None, &query, RequestBody::Empty)
+                .await?;
+            if !response.status().is_success() {
+                return Err(Self::error_from_response("列举", response).await);
+            }
+
+            let body = response
+                .text()
+                .await
+                .map_err(|e| AppError::Internal(format!("读取S3列举结果失败: {}", e)))?;
+
+            for contents in extract_xml_blocks(&body, "Contents") {
+                let Some(full_key) = extract_xml_value(contents, "Key") else {
+                    continue;
+                };
+                let size = extract_xml_value(contents, "Size")
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .unwrap_or(0);
+                let last_modified = extract_xml_value(contents, "LastModified")
+                    .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+                    .map(|dt| dt.with_timezone(&Utc));
+
+                objects.push(ObjectMeta {
+                    key: self.strip_prefix(&full_key).to_string(),
+                    size,
+                    last_modified,
+                });
+            }
+
+            let truncated = extract_xml_value(&body, "IsTruncated").as_deref() == Some("true");
+            continuation_token = extract_xml_value(&body, "NextContinuationToken");
+            if !truncated || continuation_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(objects)
+    }
+}
+
+/// SigV4 签名参数
+pub struct SigningParams<'a> {
+    pub access_key: &'a str,
+    pub secret_key: &'a str,
+    pub region: &'a str,
+    pub service: &'a str,
+    /// 请求时间，格式为 `YYYYMMDDTHHMMSSZ`
+    pub amz_date: &'a str,
+}
+
+/// 计算 AWS Signature Version 4 的 Authorization 头
+///
+/// `path` 必须是已经 URI 编码的规范路径，`headers` 中的名称必须为小写。
+pub fn sign_request(
+    params: &SigningParams<'_>,
+    method: &str,
+    path: &str,
+    query: &[(&str, &str)],
+    headers: &[(String, String)],
+    payload_hash: &str,
+) -> String {
+    let mut sorted_headers: Vec<(String, String)> = headers
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+        .collect();
+    sorted_headers.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let canonical_headers: String = sorted_headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = sorted_headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        if path.is_empty() { "/" } else { path },
+        canonical_query_string(query),
+        canonical_headers,
+        signed_headers,
+        payload_hash
+    );
+
+    let date = &params.amz_date[..8];
+    let scope = format!("{}/{}/{}/aws4_request", date, params.region, params.service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+        params.amz_date,
+        scope,
+        Sha256::digest(canonical_request.as_bytes())
+    );
+
+    let k_date = hmac_sha256(
+        format!("AWS4{}", params.secret_key).as_bytes(),
+        date.as_bytes(),
+    );
+    let k_region = hmac_sha256(&k_date, params.region.as_bytes());
+    let k_service = hmac_sha256(&k_region, params.service.as_bytes());
+    let k_signing = hmac_sha256(&k_service, b"aws4_request");
+    let signature = hmac_sha256(&k_signing, string_to_sign.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        params.access_key, scope, signed_headers, signature
+    )
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC可以接受任意长度的密钥");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// 按 SigV4 规则进行 URI 编码
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn uri_encode_path(key: &str) -> String {
+    uri_encode(key, false)
+}
+
+/// 生成排序后的规范查询字符串
+fn canonical_query_string(query: &[(&str, &str)]) -> String {
+    let mut pairs: Vec<(String, String)> = query
+        .iter()
+        .map(|(name, value)| (uri_encode(name, true), uri_encode(value, true)))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// 提取 XML 中所有指定标签的内容块
+fn extract_xml_blocks<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut blocks = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find(&open) {
+        let after_open = &rest[start + open.len()..];
+        let Some(end) = after_open.find(&close) else {
+            break;
+        };
+        blocks.push(&after_open[..end]);
+        rest = &after_open[end + close.len()..];
+    }
+
+    blocks
+}
+
+/// 提取 XML 中第一个指定标签的文本值
+fn extract_xml_value(xml: &str, tag: &str) -> Option<String> {
+    extract_xml_blocks(xml, tag)
+        .first()
+        .map(|value| xml_unescape(value))
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,197 @@
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+use super::ByteStream;
+use crate::config::AppConfig;
+use crate::utils::{validate_file_size, AppError};
+
+/// 暂存目录名（位于上传目录下，以 `.` 开头以免被存储列举）
+const STAGING_DIR_NAME: &str = ".staging";
+
+/// 用于文件类型检测的文件头长度
+const HEAD_LEN: usize = 8192;
+
+/// 上传暂存写入器
+///
+/// 上传内容按块写入暂存目录中的临时文件，同时增量计算 SHA-256 并检查大小限制，
+/// 整个过程不会把文件完整读入内存。
+pub struct UploadStager {
+    file: fs::File,
+    staged: StagedUpload,
+}
+
+impl UploadStager {
+    /// 在暂存目录中创建新的临时文件
+    pub async fn new() -> Result<Self, AppError> {
+        let dir = staging_dir();
+        fs::create_dir_all(&dir).await?;
+
+        let path = staging_path(&dir);
+        let file = fs::File::create(&path).await?;
+
+        Ok(Self {
+            file,
+            staged: StagedUpload::empty(path),
+        })
+    }
+
+    /// 写入一块数据，超过最大文件大小时立即返回错误
+    pub async fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), AppError> {
+        validate_file_size(self.staged.size + chunk.len() as u64)?;
+        self.append(chunk).await
+    }
+
+    /// 把存储中读出的对象写入暂存文件，不受上传大小限制（存储校验修复已有文件时使用）
+    pub async fn stage_stream(mut stream: ByteStream) -> Result<StagedUpload, AppError> {
+        let mut stager = Self::new().await?;
+        while let Some(chunk) = stream.next().await {
+            stager.append(&chunk?).await?;
+        }
+        stager.finish().await
+    }
+
+    async fn append(&mut self, chunk: &[u8]) -> Result<(), AppError> {
+        self.file.write_all(chunk).await?;
+        self.staged.hasher.update(chunk);
+        if self.staged.head.len() < HEAD_LEN {
+            let take = (HEAD_LEN - self.staged.head.len()).min(chunk.len());
+            self.staged.head.extend_from_slice(&chunk[..take]);
+        }
+        self.staged.size += chunk.len() as u64;
+
+        Ok(())
+    }
+
+    /// 完成写入并落盘
+    pub async fn finish(mut self) -> Result<StagedUpload, AppError> {
+        self.file.flush().await?;
+        self.file.sync_all().await?;
+        Ok(self.staged)
+    }
+}
+
+/// 已暂存的上传文件
+///
+/// 被丢弃时自动删除临时文件（已被存储后端移走时忽略）。
+pub struct StagedUpload {
+    path: PathBuf,
+    size: u64,
+    hasher: Sha256,
+    head: Vec<u8>,
+}
+
+impl StagedUpload {
+    /// 临时文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 文件大小（字节）
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// 文件开头的若干字节，用于检测文件类型
+    pub fn head(&self) -> &[u8] {
+        &self.head
+    }
+
+    fn empty(path: PathBuf) -> Self {
+        Self {
+            path,
+            size: 0,
+            hasher: Sha256::new(),
+            head: Vec::new(),
+        }
+    }
+
+    /// 在阻塞线程中改写暂存文件，返回新的暂存文件
+    ///
+    /// `rewrite` 接收原文件内容，把新内容写入给定的 writer，新内容按块写入新的暂存文件并增量计算哈希，
+    /// 内存中只有原文件一份完整内容。返回 false 表示无需改写，保留原文件。
+    pub async fn rewrite<F>(self, rewrite: F) -> Result<StagedUpload, AppError>
+    where
+        F: FnOnce(Vec<u8>, &mut dyn Write) -> Result<bool, AppError> + Send + 'static,
+    {
+        let dir = staging_dir();
+        tokio::task::spawn_blocking(move || {
+            let data = std::fs::read(&self.path)?;
+            let path = staging_path(&dir);
+            let mut writer = StagedWriter {
+                file: BufWriter::new(std::fs::File::create(&path)?),
+                staged: StagedUpload::empty(path),
+            };
+            if !rewrite(data, &mut writer)? {
+                return Ok(self);
+            }
+
+            let file = writer.file.into_inner().map_err(|e| e.into_error())?;
+            file.sync_all()?;
+            Ok(writer.staged)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("改写暂存文件失败: {}", e)))?
+    }
+
+    /// 计算文件哈希，`salt` 会追加在文件内容之后参与计算
+    pub fn hash_with(&self, salt: &[u8]) -> String {
+        let mut hasher = self.hasher.clone();
+        hasher.update(salt);
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+impl Drop for StagedUpload {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("删除上传暂存文件失败: {:?} - {}", self.path, e);
+            }
+        }
+    }
+}
+
+/// 在阻塞线程中写入暂存文件，同时计算大小、哈希和文件头
+struct StagedWriter {
+    file: BufWriter<std::fs::File>,
+    staged: StagedUpload,
+}
+
+impl Write for StagedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.file.write(buf)?;
+        let chunk = &buf[..written];
+        self.staged.hasher.update(chunk);
+        if self.staged.head.len() < HEAD_LEN {
+            let take = (HEAD_LEN - self.staged.head.len()).min(chunk.len());
+            self.staged.head.extend_from_slice(&chunk[..take]);
+        }
+        self.staged.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// 在暂存目录中生成新的临时文件路径
+fn staging_path(dir: &Path) -> PathBuf {
+    dir.join(format!(
+        "{}-{:016x}.upload",
+        std::process::id(),
+        rand::random::<u64>()
+    ))
+}
+
+/// 上传暂存目录
+///
+/// 位于上传目录内，本地存储时可以直接重命名到最终位置。
+fn staging_dir() -> PathBuf {
+    Path::new(&AppConfig::get().storage.upload_dir).join(STAGING_DIR_NAME)
+}
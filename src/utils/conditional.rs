@@ -0,0 +1,69 @@
+use axum::http::{header, HeaderMap};
+use chrono::{DateTime, Utc};
+
+/// 生成强 ETag（带双引号）
+pub fn strong_etag(tag: &str) -> String {
+    format!("\"{}\"", tag)
+}
+
+/// 格式化为 HTTP 日期（IMF-fixdate）
+pub fn http_date(time: &DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// 解析 HTTP 日期
+pub fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value.trim())
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// 根据 `If-None-Match` / `If-Modified-Since` 判断客户端缓存是否仍然有效
+///
+/// 存在 `If-None-Match` 时忽略 `If-Modified-Since`（RFC 9110 §13.2.2）。
+pub fn is_not_modified(headers: &HeaderMap, etag: &str, last_modified: &DateTime<Utc>) -> bool {
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+        let Ok(value) = if_none_match.to_str() else {
+            return false;
+        };
+        return etag_list_matches(value, etag, true);
+    }
+
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_http_date)
+        .is_some_and(|since| last_modified.timestamp() <= since.timestamp())
+}
+
+/// 判断 `If-Range` 是否允许按 `Range` 返回部分内容
+///
+/// 不存在 `If-Range` 时返回 `true`；ETag 使用强比较，日期必须与 `Last-Modified` 完全一致。
+pub fn if_range_matches(headers: &HeaderMap, etag: &str, last_modified: &DateTime<Utc>) -> bool {
+    let Some(if_range) = headers.get(header::IF_RANGE) else {
+        return true;
+    };
+    let Ok(value) = if_range.to_str() else {
+        return false;
+    };
+    let value = value.trim();
+
+    if value.starts_with('"') || value.starts_with("W/") {
+        etag_list_matches(value, etag, false)
+    } else {
+        parse_http_date(value).is_some_and(|date| date.timestamp() == last_modified.timestamp())
+    }
+}
+
+/// 判断 ETag 列表中是否包含指定 ETag，`weak` 为真时使用弱比较
+fn etag_list_matches(list: &str, etag: &str, weak: bool) -> bool {
+    list.split(',').map(str::trim).any(|candidate| {
+        if candidate == "*" {
+            return true;
+        }
+        match candidate.strip_prefix("W/") {
+            Some(opaque) => weak && opaque == etag,
+            None => candidate == etag,
+        }
+    })
+}
@@ -1,4 +1,8 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 use thiserror::Error;
 
 use crate::models::ErrorResponse;
@@ -18,21 +22,40 @@ pub enum AppError {
     #[error("文件不存在")]
     FileNotFound,
 
+    #[error("资源不存在: {0}")]
+    NotFound(String),
+
     #[error("无效的文件")]
     InvalidFile,
 
     #[error("未经授权: {0}")]
     Unauthorized(String),
 
+    #[error("禁止访问: {0}")]
+    Forbidden(String),
+
     #[error("服务器内部错误: {0}")]
     Internal(String),
 
     #[error("请求格式错误: {0}")]
     BadRequest(String),
+
+    #[error("请求过于频繁，请在 {retry_after} 秒后重试")]
+    TooManyRequests { retry_after: u64 },
+
+    #[error("服务暂时不可用: {0}")]
+    ServiceUnavailable(String),
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
+        let retry_after = match self {
+            AppError::TooManyRequests { retry_after } => Some(retry_after),
+            // 过载是暂时的，提示客户端稍后重试
+            AppError::ServiceUnavailable(_) => Some(1),
+            _ => None,
+        };
+
         let (status, error_response) = match self {
             AppError::FileIo(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -66,6 +89,14 @@ impl IntoResponse for AppError {
                     code: Some(404),
                 },
             ),
+            AppError::NotFound(msg) => (
+                StatusCode::NOT_FOUND,
+                ErrorResponse {
+                    success: false,
+                    message: msg,
+                    code: Some(404),
+                },
+            ),
             AppError::InvalidFile => (
                 StatusCode::BAD_REQUEST,
                 ErrorResponse {
@@ -82,6 +113,14 @@ impl IntoResponse for AppError {
                     code: Some(401),
                 },
             ),
+            AppError::Forbidden(msg) => (
+                StatusCode::FORBIDDEN,
+                ErrorResponse {
+                    success: false,
+                    message: msg,
+                    code: Some(403),
+                },
+            ),
             AppError::Internal(msg) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ErrorResponse {
@@ -98,9 +137,31 @@ impl IntoResponse for AppError {
                     code: Some(400),
                 },
             ),
+            AppError::TooManyRequests { retry_after } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorResponse {
+                    success: false,
+                    message: format!("请求过于频繁，请在 {} 秒后重试", retry_after),
+                    code: Some(429),
+                },
+            ),
+            AppError::ServiceUnavailable(msg) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                ErrorResponse {
+                    success: false,
+                    message: msg,
+                    code: Some(503),
+                },
+            ),
         };
 
-        (status, Json(error_response)).into_response()
+        let mut response = (status, Json(error_response)).into_response();
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, retry_after.into());
+        }
+        response
     }
 }
 
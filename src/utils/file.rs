@@ -1,5 +1,3 @@
-use std::path::{Path, PathBuf};
-
 use crate::config::AppConfig;
 use crate::utils::error::AppError;
 
@@ -94,32 +92,3 @@ pub fn validate_file_size(size: u64) -> Result<(), AppError> {
         Ok(())
     }
 }
-
-/// 获取基础上传目录路径
-pub fn get_upload_dir() -> PathBuf {
-    let config = AppConfig::get();
-    config.upload_dir_path()
-}
-
-/// 确保上传目录存在
-pub async fn ensure_upload_dir() -> Result<(), AppError> {
-    let upload_dir = get_upload_dir();
-    if !upload_dir.exists() {
-        tokio::fs::create_dir_all(&upload_dir).await?;
-    }
-    Ok(())
-}
-
-/// 确保特定图片的目录结构存在
-pub async fn ensure_image_dir(relative_path: &Path) -> Result<(), AppError> {
-    let parent_dir = get_upload_dir().join(relative_path.parent().unwrap_or(Path::new("")));
-    if !parent_dir.exists() {
-        tokio::fs::create_dir_all(&parent_dir).await?;
-    }
-    Ok(())
-}
-
-/// 获取文件的完整路径
-pub fn get_file_path(relative_path: &Path) -> PathBuf {
-    get_upload_dir().join(relative_path)
-}
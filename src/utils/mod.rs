@@ -1,12 +1,12 @@
 pub mod byte_size;
+pub mod conditional;
 pub mod duration;
 pub mod error;
 pub mod file;
+pub mod range;
 
 pub use byte_size::ByteSize;
 pub use duration::Duration;
 pub use error::AppError;
-pub use file::{
-    detect_file_type, ensure_image_dir, ensure_upload_dir, get_extension_from_mime, get_file_path,
-    get_upload_dir, validate_file_size,
-};
+pub use file::{detect_file_type, get_extension_from_mime, validate_file_size};
+pub use range::{parse_range_header, RangeRequest};
@@ -0,0 +1,87 @@
+use std::ops::Range;
+
+/// 单个请求中允许的最大区间数，超过时忽略 `Range` 返回完整内容
+const MAX_RANGES: usize = 16;
+
+/// `Range` 请求头的解析结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// 返回完整内容（无 `Range`、格式无法识别或区间过多）
+    Full,
+    /// 返回部分内容，区间已排序合并，`end` 不含
+    Partial(Vec<Range<u64>>),
+    /// 没有可满足的区间，应返回 416
+    Unsatisfiable,
+}
+
+/// 解析 `Range: bytes=...` 请求头
+///
+/// 支持 `a-b`、`a-`、`-n` 三种形式及多区间；重叠或相邻的区间会被合并。
+pub fn parse_range_header(value: &str, size: u64) -> RangeRequest {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+
+    let specs: Vec<&str> = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect();
+    if specs.is_empty() || specs.len() > MAX_RANGES {
+        return RangeRequest::Full;
+    }
+
+    let mut ranges = Vec::with_capacity(specs.len());
+    for part in specs {
+        let Some((start, end)) = part.split_once('-') else {
+            return RangeRequest::Full;
+        };
+        let (start, end) = (start.trim(), end.trim());
+
+        let range = if start.is_empty() {
+            // 后缀区间：最后 n 个字节
+            let Ok(suffix) = end.parse::<u64>() else {
+                return RangeRequest::Full;
+            };
+            if suffix == 0 {
+                continue;
+            }
+            size.saturating_sub(suffix)..size
+        } else {
+            let Ok(start) = start.parse::<u64>() else {
+                return RangeRequest::Full;
+            };
+            let end = if end.is_empty() {
+                size
+            } else {
+                match end.parse::<u64>() {
+                    Ok(end) if end >= start => end.saturating_add(1).min(size),
+                    _ => return RangeRequest::Full,
+                }
+            };
+            if start >= size {
+                continue;
+            }
+            start..end
+        };
+
+        if !range.is_empty() {
+            ranges.push(range);
+        }
+    }
+
+    if ranges.is_empty() {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    RangeRequest::Partial(merged)
+}
@@ -0,0 +1,236 @@
+//! 相册与标签测试
+//! 使用启用认证的 config_auth_test 配置，覆盖相册的增删改查、图片排序、权限检查以及按标签组合查询
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use rifs::models::TokenRole;
+
+mod common;
+use common::{create_test_app, create_token, random_png, request, upload};
+
+/// 查询图片列表，返回哈希
+async fn query_hashes(app: &axum::Router, token: &str, query: &str) -> Vec<String> {
+    let uri = format!("/api/images/query?{}", query);
+    let (status, json) = request(app, Method::GET, &uri, Some(token), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    json["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["hash"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_album_lifecycle() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, owner) = create_token(&app_state, TokenRole::User).await;
+    let (_, other) = create_token(&app_state, TokenRole::User).await;
+
+    let images = [
+        upload(&app, Some(&owner), &random_png()).await,
+        upload(&app, Some(&owner), &random_png()).await,
+        upload(&app, Some(&owner), &random_png()).await,
+    ];
+
+    let (status, json) = request(
+        &app,
+        Method::POST,
+        "/api/albums",
+        Some(&owner),
+        Some(json!({ "name": " 旅行 ", "description": "夏天" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["name"], "旅行");
+    assert_eq!(json["data"]["image_count"], 0);
+    let album_id = json["data"]["id"].as_i64().unwrap();
+    let album_uri = format!("/api/albums/{}", album_id);
+    let images_uri = format!("{}/images", album_uri);
+
+    // 按添加顺序排列，重复添加的图片保持原位置
+    let (status, json) = request(
+        &app,
+        Method::POST,
+        &images_uri,
+        Some(&owner),
+        Some(json!({ "hashes": [&images[0], &images[1]] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["image_count"], 2);
+    let (status, json) = request(
+        &app,
+        Method::POST,
+        &images_uri,
+        Some(&owner),
+        Some(json!({ "hashes": [&images[2], &images[0]] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["image_count"], 3);
+    assert_eq!(json["data"]["cover"], images[0].as_str());
+
+    let album_query = format!("album_id={}", album_id);
+    assert_eq!(query_hashes(&app, &owner, &album_query).await, images);
+
+    // 列出的图片排在最前面，其余保持原有顺序
+    let (status, json) = request(
+        &app,
+        Method::PUT,
+        &images_uri,
+        Some(&owner),
+        Some(json!({ "hashes": [&images[2]] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["cover"], images[2].as_str());
+    assert_eq!(
+        query_hashes(&app, &owner, &album_query).await,
+        [images[2].clone(), images[0].clone(), images[1].clone()]
+    );
+    assert_eq!(
+        query_hashes(&app, &owner, &format!("{}&order_dir=desc", album_query)).await,
+        [images[1].clone(), images[0].clone(), images[2].clone()]
+    );
+
+    // 其他令牌无法访问，也不能添加不属于相册所有者的图片
+    let (status, _) = request(&app, Method::GET, &album_uri, Some(&other), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let foreign = upload(&app, Some(&other), &random_png()).await;
+    let (status, _) = request(
+        &app,
+        Method::POST,
+        &images_uri,
+        Some(&owner),
+        Some(json!({ "hashes": [&foreign] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 移出相册和删除图片都会更新相册
+    let uri = format!("{}/{}", images_uri, images[0]);
+    let (status, _) = request(&app, Method::DELETE, &uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/api/images/{}", images[1]);
+    let (status, _) = request(&app, Method::DELETE, &uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        query_hashes(&app, &owner, &album_query).await,
+        [images[2].clone()]
+    );
+
+    let (status, json) = request(
+        &app,
+        Method::PUT,
+        &album_uri,
+        Some(&owner),
+        Some(json!({ "name": "旅行 2024", "description": "" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["name"], "旅行 2024");
+    assert!(json["data"]["description"].is_null());
+    assert_eq!(json["data"]["image_count"], 1);
+
+    let (status, json) = request(&app, Method::GET, "/api/albums", Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"].as_array().unwrap().len(), 1);
+
+    // 删除相册不影响其中的图片
+    let (status, _) = request(&app, Method::DELETE, &album_uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = request(&app, Method::GET, &album_uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let uri = format!("/images/{}/info", images[2]);
+    let (status, _) = request(&app, Method::GET, &uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_tag_filters() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, token) = create_token(&app_state, TokenRole::User).await;
+    let (_, other) = create_token(&app_state, TokenRole::User).await;
+
+    let cat = upload(&app, Some(&token), &random_png()).await;
+    let dog = upload(&app, Some(&token), &random_png()).await;
+    let both = upload(&app, Some(&token), &random_png()).await;
+    let untagged = upload(&app, Some(&token), &random_png()).await;
+
+    for (hash, tags) in [
+        (&cat, json!(["Cat ", "2024"])),
+        (&dog, json!(["dog", "2024"])),
+        (&both, json!(["cat", "dog", "draft"])),
+    ] {
+        let uri = format!("/api/images/{}/tags", hash);
+        let (status, json) = request(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(&token),
+            Some(json!({ "tags": tags })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", json);
+    }
+
+    // 其他令牌不能修改标签，标签不能包含逗号
+    let uri = format!("/api/images/{}/tags", cat);
+    let body = json!({ "tags": ["mine"] });
+    let (status, _) = request(&app, Method::PUT, &uri, Some(&other), Some(body)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let body = json!({ "tags": ["a,b"] });
+    let (status, _) = request(&app, Method::PUT, &uri, Some(&token), Some(body)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let uri = format!("/images/{}/info", cat);
+    let (_, json) = request(&app, Method::GET, &uri, Some(&token), None).await;
+    assert_eq!(json["data"]["tags"], json!(["2024", "cat"]));
+
+    let sorted = |mut hashes: Vec<String>| {
+        hashes.sort();
+        hashes
+    };
+    let expected =
+        |hashes: &[&String]| sorted(hashes.iter().map(|hash| hash.to_string()).collect());
+
+    assert_eq!(
+        sorted(query_hashes(&app, &token, "tags=cat").await),
+        expected(&[&cat, &both])
+    );
+    assert_eq!(
+        sorted(query_hashes(&app, &token, "tags=cat,DOG").await),
+        expected(&[&both])
+    );
+    assert_eq!(
+        sorted(query_hashes(&app, &token, "any_tags=cat,dog&exclude_tags=draft").await),
+        expected(&[&cat, &dog])
+    );
+    assert_eq!(
+        sorted(query_hashes(&app, &token, "exclude_tags=cat,dog").await),
+        expected(&[&untagged])
+    );
+
+    // JSON 请求体中可以使用数组
+    let (status, json) = request(
+        &app,
+        Method::POST,
+        "/api/images/query",
+        Some(&token),
+        Some(json!({ "tags": ["2024"], "any_tags": ["dog"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["total"], 1);
+    assert_eq!(json["data"]["items"][0]["hash"], dog.as_str());
+    assert_eq!(json["data"]["items"][0]["tags"], json!(["2024", "dog"]));
+
+    let (status, json) = request(&app, Method::GET, "/api/tags", Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"][0], json!({ "tag": "2024", "count": 2 }));
+    assert_eq!(json["data"].as_array().unwrap().len(), 4);
+    let (_, json) = request(&app, Method::GET, "/api/tags", Some(&other), None).await;
+    assert_eq!(json["data"], json!([]));
+}
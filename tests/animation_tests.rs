@@ -0,0 +1,189 @@
+//! 动图转换测试
+//! 覆盖逐帧缩放、GIF 与 WebP 动图互转（保留帧时长和循环次数）、单帧提取参数以及输出像素预算
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use image::{GenericImageView, ImageFormat, Rgba};
+use std::io::Cursor;
+use tower::ServiceExt;
+
+use rifs::models::ImageTransformParams;
+use rifs::services::ImageTransformService;
+use rifs::utils::AppError;
+
+mod common;
+use common::{create_test_app, upload};
+
+const COLORS: [[u8; 4]; 3] = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+
+/// 构造 20x10 的三帧 GIF，时长依次为 100 / 200 / 300 毫秒，共播放 3 次
+///
+/// 第二帧只覆盖左半部分，用于验证按画布合成帧。
+fn animated_gif() -> Vec<u8> {
+    let mut output = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut output, 20, 10, &[]).unwrap();
+        encoder.set_repeat(gif::Repeat::Finite(2)).unwrap();
+
+        for (index, color) in COLORS.iter().enumerate() {
+            let width = if index == 1 { 10 } else { 20 };
+            let mut pixels: Vec<u8> = color.repeat(width as usize * 10);
+            let mut frame = gif::Frame::from_rgba(width, 10, &mut pixels);
+            frame.delay = (index as u16 + 1) * 10;
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+    output
+}
+
+async fn transform(data: &[u8], mime: &str, params: &str) -> (Vec<u8>, String) {
+    let params = ImageTransformParams::parse(params).unwrap();
+    ImageTransformService::transform_image_blocking(data, mime, &params).unwrap()
+}
+
+/// 读取 GIF 的尺寸、每帧时长（10毫秒单位）和循环设置
+fn read_gif(data: &[u8]) -> ((u16, u16), Vec<u16>, gif::Repeat) {
+    let mut reader = gif::DecodeOptions::new()
+        .read_info(Cursor::new(data))
+        .unwrap();
+    let mut delays = Vec::new();
+    while let Some(frame) = reader.read_next_frame().unwrap() {
+        delays.push(frame.delay);
+    }
+    ((reader.width(), reader.height()), delays, reader.repeat())
+}
+
+fn assert_color(actual: Rgba<u8>, expected: [u8; 4]) {
+    let close = actual
+        .0
+        .iter()
+        .zip(expected)
+        .all(|(a, e)| (*a as i16 - e as i16).abs() <= 8);
+    assert!(close, "颜色不匹配: {:?} != {:?}", actual.0, expected);
+}
+
+#[tokio::test]
+async fn test_resize_keeps_every_gif_frame() {
+    let (output, mime) = transform(&animated_gif(), "image/gif", "w10").await;
+    assert_eq!(mime, "image/gif");
+
+    let (size, delays, repeat) = read_gif(&output);
+    assert_eq!(size, (10, 5));
+    assert_eq!(delays, vec![10, 20, 30]);
+    assert_eq!(repeat, gif::Repeat::Finite(2));
+}
+
+#[tokio::test]
+async fn test_gif_to_webp_and_back() {
+    let (webp_data, mime) = transform(&animated_gif(), "image/gif", "webp").await;
+    assert_eq!(mime, "image/webp");
+
+    let decoded = webp::AnimDecoder::new(&webp_data).decode().unwrap();
+    assert_eq!(decoded.len(), 3);
+    assert_eq!(decoded.loop_count, 3);
+    let frames: Vec<_> = (&decoded).into_iter().collect();
+    let end_times: Vec<i32> = frames.iter().map(|frame| frame.get_time_ms()).collect();
+    assert_eq!(end_times, vec![100, 300, 600]);
+
+    // 第二帧左半部分为新内容，右半部分沿用第一帧
+    assert!(frames[1].get_layout().is_alpha());
+    let second = image::RgbaImage::from_raw(20, 10, frames[1].get_image().to_vec()).unwrap();
+    assert_eq!(second.dimensions(), (20, 10));
+    assert_color(*second.get_pixel(2, 5), COLORS[1]);
+    assert_color(*second.get_pixel(17, 5), COLORS[0]);
+
+    let (gif_data, mime) = transform(&webp_data, "image/webp", "w10_gif").await;
+    assert_eq!(mime, "image/gif");
+    let (size, delays, repeat) = read_gif(&gif_data);
+    assert_eq!(size, (10, 5));
+    assert_eq!(delays, vec![10, 20, 30]);
+    assert_eq!(repeat, gif::Repeat::Finite(2));
+}
+
+#[tokio::test]
+async fn test_frame_token_selects_single_frame() {
+    let params = ImageTransformParams::parse("frame1_w10_png").unwrap();
+    assert_eq!(params.frame, Some(1));
+    assert_eq!(params.to_normalized_string(), "w10_frame1_png");
+    assert!(ImageTransformParams::parse("framex").is_err());
+
+    let gif_data = animated_gif();
+    let (output, mime) = transform(&gif_data, "image/gif", "frame1_png").await;
+    assert_eq!(mime, "image/png");
+    let img = image::load_from_memory_with_format(&output, ImageFormat::Png).unwrap();
+    assert_eq!(img.dimensions(), (20, 10));
+    assert_color(img.get_pixel(2, 5), COLORS[1]);
+    assert_color(img.get_pixel(17, 5), COLORS[0]);
+
+    // 未指定格式时输出单帧的原格式
+    let (output, mime) = transform(&gif_data, "image/gif", "frame2").await;
+    assert_eq!(mime, "image/gif");
+    let (_, delays, _) = read_gif(&output);
+    assert_eq!(delays.len(), 1);
+
+    // 超出范围的帧索引
+    let params = ImageTransformParams::parse("frame3").unwrap();
+    assert!(
+        ImageTransformService::transform_image_blocking(&gif_data, "image/gif", &params).is_err()
+    );
+
+    // 静态图片只有第0帧
+    let mut png = Vec::new();
+    image::RgbImage::new(4, 4)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .unwrap();
+    assert!(ImageTransformService::transform_image_blocking(&png, "image/png", &params).is_err());
+    let (_, mime) = transform(&png, "image/png", "frame0").await;
+    assert_eq!(mime, "image/png");
+}
+
+#[test]
+fn test_animation_output_pixel_budget() {
+    // 三帧放大到 8192x4096，输出像素总数超出预算，在逐帧转换前拒绝
+    let params = ImageTransformParams::parse("w8192_up").unwrap();
+    let result =
+        ImageTransformService::transform_image_blocking(&animated_gif(), "image/gif", &params);
+    assert!(matches!(result, Err(AppError::BadRequest(ref msg)) if msg.starts_with("动图过大")));
+
+    // 提取单帧时不受动图预算限制
+    let params = ImageTransformParams::parse("frame0_w2048_up_png").unwrap();
+    let (output, mime) =
+        ImageTransformService::transform_image_blocking(&animated_gif(), "image/gif", &params)
+            .unwrap();
+    assert_eq!(mime, "image/png");
+    assert_eq!(
+        image::load_from_memory(&output).unwrap().dimensions(),
+        (2048, 1024)
+    );
+}
+
+#[tokio::test]
+async fn test_gif_first_frame_header() {
+    let app = create_test_app("config_test").await.0;
+    let hash = upload(&app, None, &animated_gif()).await;
+
+    for (params, first_frame) in [
+        ("png", true),
+        ("w10_jpeg", true),
+        ("frame0", true),
+        ("frame1_png", false),
+        ("w10", false),
+        ("webp", false),
+        ("gif", false),
+    ] {
+        let request = Request::builder()
+            .uri(format!("/images/{}@{}", hash, params))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", params);
+        assert_eq!(
+            response.headers().contains_key("x-gif-first-frame"),
+            first_frame,
+            "{}",
+            params
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! 审计日志测试
+//! 使用启用认证的 config_auth_test 配置，覆盖令牌管理、图片删除、缓存清空和认证失败的记录，
+//! 以及认证失败的合并记录、过期事件清理、审计日志的查询和导出
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, Method, Request, StatusCode},
+};
+use chrono::Utc;
+use sea_orm::ActiveValue::Set;
+use serde_json::Value;
+use std::net::SocketAddr;
+
+use rifs::entities::audit_event;
+use rifs::models::TokenRole;
+use rifs::repositories::AuditRepository;
+use rifs::services::AuditService;
+
+mod common;
+use common::{
+    build_request, create_test_app, create_token, random_png, send_bytes, upload_request,
+};
+
+const CLIENT_ADDR: &str = "203.0.113.7:40000";
+const OTHER_CLIENT_ADDR: &str = "198.51.100.9:40000";
+
+/// 发送请求，附带指定的客户端连接地址
+async fn send_from(
+    app: &axum::Router,
+    client_addr: &str,
+    mut request: Request<Body>,
+) -> (StatusCode, Vec<u8>) {
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(client_addr.parse::<SocketAddr>().unwrap()));
+    send_bytes(app, request).await
+}
+
+/// 发送请求，附带默认的客户端连接地址
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Vec<u8>) {
+    send_from(app, CLIENT_ADDR, request).await
+}
+
+async fn request(
+    app: &axum::Router,
+    method: Method,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let (status, body) = send(app, build_request(method, uri, token, body)).await;
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn upload(app: &axum::Router, token: &str) -> String {
+    let request = upload_request(
+        "/upload",
+        Some(token),
+        "file",
+        &[("audit.png", &random_png())],
+    );
+    let (status, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    json["data"]["hash"].as_str().unwrap().to_string()
+}
+
+/// 查询审计事件
+async fn audit_events(app: &axum::Router, admin: &str, filter: &str) -> Vec<Value> {
+    let uri = format!("/api/audit?limit=100&{}", filter);
+    let (status, json) = request(app, Method::GET, &uri, Some(admin), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    json["data"]["items"].as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn test_audit_token_and_auth_events() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (admin_id, admin) = create_token(&app_state, TokenRole::Admin).await;
+    let (user_id, user) = create_token(&app_state, TokenRole::User).await;
+
+    // 认证失败记录客户端IP和原因
+    let (status, _) = request(&app, Method::GET, "/api/tokens/list", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // 同一客户端在时间窗口内的重复失败只计数，其他客户端单独记录
+    for _ in 0..3 {
+        let (status, _) = request(&app, Method::GET, "/api/tokens/list", Some("bogus"), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let bogus = build_request(Method::GET, "/api/tokens/list", Some("bogus"), None);
+    let (status, _) = send_from(&app, OTHER_CLIENT_ADDR, bogus).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let failures = audit_events(&app, &admin, "action=auth_failure").await;
+    assert_eq!(failures.len(), 2);
+    assert!(failures
+        .iter()
+        .all(|event| event["actor_token_id"].is_null() && event["outcome"] == "failure"));
+    assert_eq!(failures[0]["client_ip"], "198.51.100.9");
+    assert_eq!(failures[1]["client_ip"], "203.0.113.7");
+    assert_eq!(failures[1]["detail"], "缺少认证token");
+
+    // 时间窗口结束后的下一次失败附带合并的次数
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let (status, _) = request(&app, Method::GET, "/api/tokens/list", Some("bogus"), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let failures = audit_events(&app, &admin, "action=auth_failure").await;
+    assert_eq!(failures.len(), 3);
+    assert_eq!(failures[0]["client_ip"], "203.0.113.7");
+    assert!(failures[0]["detail"]
+        .as_str()
+        .unwrap()
+        .ends_with("（此前另有3次认证失败未单独记录）"));
+
+    // 公开图片访问不需要令牌，不记录认证失败
+    let hash = upload(&app, &user).await;
+    let (status, _) = request(&app, Method::GET, &format!("/images/{}", hash), None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        audit_events(&app, &admin, "action=auth_failure")
+            .await
+            .len(),
+        3
+    );
+
+    // 创建和删除令牌
+    let (status, json) = request(
+        &app,
+        Method::POST,
+        "/api/tokens/create",
+        Some(&admin),
+        Some(serde_json::json!({ "name": "temp", "role": "user" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let created_id = json["token"]["id"].as_i64().unwrap();
+    let uri = format!("/api/tokens/{}", created_id);
+    let (status, _) = request(&app, Method::DELETE, &uri, Some(&admin), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // 普通用户尝试管理令牌时记录失败
+    let (status, _) = request(
+        &app,
+        Method::POST,
+        "/api/tokens/create",
+        Some(&user),
+        Some(serde_json::json!({ "name": "sneaky", "role": "admin" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let created = audit_events(&app, &admin, "action=token_create").await;
+    assert_eq!(created.len(), 2);
+    let denied = &created[0];
+    assert_eq!(denied["actor_token_id"], user_id);
+    assert_eq!(denied["outcome"], "failure");
+    assert!(denied["target"].is_null());
+    let granted = &created[1];
+    assert_eq!(granted["actor_token_id"], admin_id);
+    assert_eq!(granted["outcome"], "success");
+    assert_eq!(granted["target"], created_id.to_string());
+
+    let deleted = audit_events(&app, &admin, "action=token_delete").await;
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0]["target"], created_id.to_string());
+    assert_eq!(deleted[0]["outcome"], "success");
+
+    // 按操作令牌和结果过滤
+    let by_user = audit_events(&app, &admin, &format!("actor_token_id={}", user_id)).await;
+    assert_eq!(by_user.len(), 1);
+    assert!(
+        audit_events(&app, &admin, "action=token_create&outcome=success")
+            .await
+            .iter()
+            .all(|event| event["actor_token_id"] == admin_id)
+    );
+}
+
+#[tokio::test]
+async fn test_audit_destructive_actions_and_export() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (admin_id, admin) = create_token(&app_state, TokenRole::Admin).await;
+    let (user_id, user) = create_token(&app_state, TokenRole::User).await;
+
+    // 移入回收站、彻底删除、直接删除和越权删除
+    let trashed = upload(&app, &user).await;
+    let permanent = upload(&app, &user).await;
+    let foreign = upload(&app, &admin).await;
+    for uri in [
+        format!("/api/images/{}", trashed),
+        format!("/api/trash/{}", trashed),
+        format!("/api/images/{}?permanent=true", permanent),
+    ] {
+        let (status, _) = request(&app, Method::DELETE, &uri, Some(&user), None).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+    }
+    let uri = format!("/api/images/{}", foreign);
+    let (status, _) = request(&app, Method::DELETE, &uri, Some(&user), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let deletes = audit_events(&app, &admin, "action=image_delete").await;
+    assert_eq!(deletes.len(), 2);
+    assert_eq!(deletes[0]["target"], foreign.as_str());
+    assert_eq!(deletes[0]["outcome"], "failure");
+    assert_eq!(deletes[1]["target"], trashed.as_str());
+    assert_eq!(deletes[1]["outcome"], "success");
+    let purges = audit_events(&app, &admin, "action=image_purge").await;
+    let purged: Vec<&str> = purges
+        .iter()
+        .map(|event| event["target"].as_str().unwrap())
+        .collect();
+    assert_eq!(purged, [permanent.as_str(), trashed.as_str()]);
+    assert!(purges
+        .iter()
+        .all(|event| event["actor_token_id"] == user_id));
+
+    // 清空缓存
+    let (status, _) = request(&app, Method::DELETE, "/api/cache/clear", Some(&admin), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let clears = audit_events(&app, &admin, "action=cache_clear").await;
+    assert_eq!(clears.len(), 1);
+    assert_eq!(clears[0]["actor_token_id"], admin_id);
+
+    // 分页
+    let (_, json) = request(&app, Method::GET, "/api/audit?limit=2", Some(&admin), None).await;
+    let total = json["data"]["total"].as_u64().unwrap();
+    assert_eq!(total, 5);
+    assert_eq!(json["data"]["items"].as_array().unwrap().len(), 2);
+    let (_, json) = request(
+        &app,
+        Method::GET,
+        "/api/audit?limit=2&offset=4",
+        Some(&admin),
+        None,
+    )
+    .await;
+    assert_eq!(json["data"]["items"].as_array().unwrap().len(), 1);
+    assert_eq!(json["data"]["items"][0]["target"], trashed.as_str());
+
+    // 只有管理员可以查询和导出
+    for uri in ["/api/audit", "/api/audit/export"] {
+        let (status, _) = request(&app, Method::GET, uri, Some(&user), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    // 导出为 JSON Lines，每行一个事件
+    let request = Request::builder()
+        .uri("/api/audit/export?outcome=success")
+        .header(header::AUTHORIZATION, format!("Bearer {}", admin))
+        .body(Body::empty())
+        .unwrap();
+    let (status, body) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    let lines: Vec<Value> = String::from_utf8(body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines.iter().all(|event| event["outcome"] == "success"));
+    assert_eq!(lines.last().unwrap()["action"], "image_delete");
+}
+
+#[tokio::test]
+async fn test_audit_retention() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, admin) = create_token(&app_state, TokenRole::Admin).await;
+    let connection = app_state.db_pool().get_connection();
+
+    // 超过保留期（默认 90 天）的事件被清理
+    for days in [100, 10] {
+        AuditRepository::new(connection.clone())
+            .insert(audit_event::ActiveModel {
+                action: Set("cache_clear".to_string()),
+                outcome: Set("success".to_string()),
+                created_at: Set(Utc::now() - chrono::Duration::days(days)),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+    }
+
+    let audit_service = AuditService::new(connection);
+    assert_eq!(audit_service.purge_expired().await.unwrap(), 1);
+    assert_eq!(audit_service.purge_expired().await.unwrap(), 0);
+
+    let events = audit_events(&app, &admin, "action=cache_clear").await;
+    assert_eq!(events.len(), 1);
+}
+
+#[tokio::test]
+async fn test_audit_export_spans_batches() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, admin) = create_token(&app_state, TokenRole::Admin).await;
+
+    // 超过单批数量的事件分多批查询后按顺序输出
+    let repo = AuditRepository::new(app_state.db_pool().get_connection());
+    for minutes in 0..1201 {
+        repo.insert(audit_event::ActiveModel {
+            action: Set("cache_clear".to_string()),
+            target: Set(Some(minutes.to_string())),
+            outcome: Set("success".to_string()),
+            created_at: Set(Utc::now() - chrono::Duration::minutes(minutes)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    }
+
+    let request = build_request(Method::GET, "/api/audit/export", Some(&admin), None);
+    let (status, body) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    let targets: Vec<String> = String::from_utf8(body)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let event: Value = serde_json::from_str(line).unwrap();
+            event["target"].as_str().unwrap().to_string()
+        })
+        .collect();
+    let expected: Vec<String> = (0..1201).map(|minutes| minutes.to_string()).collect();
+    assert_eq!(targets, expected);
+}
@@ -0,0 +1,279 @@
+//! 批量上传测试
+//! 使用启用认证的 config_auth_test 配置，覆盖多文件上传、压缩包解析、逐文件错误和整批配额检查
+
+use axum::http::{Method, StatusCode};
+use serde_json::Value;
+use std::io::Write;
+
+use rifs::app_state::AppState;
+use rifs::models::{CreateTokenPayload, TokenRole};
+use rifs::services::upload_archive::ArchiveKind;
+use rifs::services::TokenService;
+
+mod common;
+use common::{
+    create_test_app, create_token_with, random_png, request, send, token_payload, upload_request,
+};
+
+/// 创建普通用户令牌，返回令牌ID和明文
+async fn create_user_token(app_state: &AppState, max_upload_size: Option<u64>) -> (i32, String) {
+    let payload = CreateTokenPayload {
+        max_upload_size,
+        ..token_payload(TokenRole::User)
+    };
+    create_token_with(app_state, payload).await
+}
+
+async fn upload_batch(
+    app: &axum::Router,
+    token: &str,
+    files: &[(&str, &[u8])],
+) -> (StatusCode, Value) {
+    send(
+        app,
+        upload_request("/upload/batch", Some(token), "files", files),
+    )
+    .await
+}
+
+fn filenames(json: &Value) -> Vec<String> {
+    json["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["filename"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_detect_archive_kind() {
+    assert_eq!(
+        ArchiveKind::detect(b"PK\x03\x04rest"),
+        Some(ArchiveKind::Zip)
+    );
+    assert_eq!(
+        ArchiveKind::detect(&[0x1f, 0x8b, 0x08]),
+        Some(ArchiveKind::TarGz)
+    );
+
+    let mut tar_header = vec![0u8; 512];
+    tar_header[257..262].copy_from_slice(b"ustar");
+    assert_eq!(ArchiveKind::detect(&tar_header), Some(ArchiveKind::Tar));
+
+    assert_eq!(ArchiveKind::detect(&random_png()), None);
+    assert_eq!(ArchiveKind::detect(b""), None);
+}
+
+#[tokio::test]
+async fn test_batch_upload_reports_each_file() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, token) = create_user_token(&app_state, None).await;
+
+    let first = random_png();
+    let second = random_png();
+    let (status, json) = upload_batch(
+        &app,
+        &token,
+        &[
+            ("a.png", &first),
+            ("notes.txt", b"not an image"),
+            ("b.png", &second),
+            ("empty.png", b""),
+        ],
+    )
+    .await;
+
+    // 部分文件失败不影响整个请求
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["success"], false);
+    assert_eq!(json["total"], 4);
+    assert_eq!(json["succeeded"], 2);
+    assert_eq!(json["failed"], 2);
+    assert_eq!(
+        filenames(&json),
+        ["a.png", "notes.txt", "b.png", "empty.png"]
+    );
+
+    let results = json["results"].as_array().unwrap();
+    assert_eq!(results[0]["success"], true);
+    assert_eq!(results[0]["data"]["original_filename"], "a.png");
+    assert_eq!(results[1]["success"], false);
+    assert!(results[1]["data"].is_null());
+    assert_eq!(results[2]["success"], true);
+    assert_eq!(results[3]["success"], false);
+
+    // 保存成功的图片可以正常访问
+    let hash = results[2]["data"]["hash"].as_str().unwrap();
+    let uri = format!("/images/{}", hash);
+    let (status, _) = request(&app, Method::GET, &uri, None, None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_batch_upload_archives() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, token) = create_user_token(&app_state, None).await;
+
+    // zip：跳过目录和 macOS 资源文件
+    let mut zip_bytes = Vec::new();
+    {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(&mut zip_bytes));
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("album/", options).unwrap();
+        writer.start_file("album/one.png", options).unwrap();
+        writer.write_all(&random_png()).unwrap();
+        writer
+            .start_file("__MACOSX/album/._one.png", options)
+            .unwrap();
+        writer.write_all(b"resource fork").unwrap();
+        writer.start_file("album/readme.txt", options).unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.finish().unwrap();
+    }
+
+    // tar.gz：同样跳过隐藏文件
+    let mut tar_builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::default(),
+    ));
+    for (path, data) in [
+        ("two.png", random_png()),
+        ("./.DS_Store", b"finder".to_vec()),
+        ("./three.png", random_png()),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar_builder
+            .append_data(&mut header, path, data.as_slice())
+            .unwrap();
+    }
+    let tar_gz_bytes = tar_builder.into_inner().unwrap().finish().unwrap();
+
+    let loose = random_png();
+    let (status, json) = upload_batch(
+        &app,
+        &token,
+        &[
+            ("album.zip", &zip_bytes),
+            ("loose.png", &loose),
+            ("more.tar.gz", &tar_gz_bytes),
+        ],
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        filenames(&json),
+        [
+            "album/one.png",
+            "album/readme.txt",
+            "loose.png",
+            "two.png",
+            "three.png"
+        ]
+    );
+    assert_eq!(json["succeeded"], 4);
+    assert_eq!(json["failed"], 1);
+    assert_eq!(json["results"][1]["success"], false);
+    // 原始文件名只保留路径中的文件名部分
+    assert_eq!(json["results"][0]["data"]["original_filename"], "one.png");
+}
+
+/// 生成包含指定数量 PNG 文件的 zip 压缩包
+fn zip_of_pngs(count: usize) -> Vec<u8> {
+    let mut zip_bytes = Vec::new();
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(&mut zip_bytes));
+    for index in 0..count {
+        writer
+            .start_file(
+                format!("{}.png", index),
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        writer.write_all(&random_png()).unwrap();
+    }
+    writer.finish().unwrap();
+    zip_bytes
+}
+
+#[tokio::test]
+async fn test_batch_upload_broken_archive() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, token) = create_user_token(&app_state, None).await;
+
+    // 损坏的压缩包只记为该压缩包失败，其他文件照常保存
+    let zip_bytes = zip_of_pngs(2);
+    let truncated = &zip_bytes[..zip_bytes.len() / 2];
+    let png = random_png();
+    let (status, json) = upload_batch(
+        &app,
+        &token,
+        &[("first.png", &png), ("broken.zip", truncated)],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(filenames(&json), ["first.png", "broken.zip"]);
+    assert_eq!(json["results"][0]["success"], true);
+    assert_eq!(json["results"][1]["success"], false);
+    assert!(json["results"][1]["message"]
+        .as_str()
+        .unwrap()
+        .contains("无效的zip压缩包"));
+    assert_eq!(json["succeeded"], 1);
+    assert_eq!(json["failed"], 1);
+
+    // 压缩包中的文件超过批量上传的文件数上限时整批失败
+    let (status, json) = upload_batch(
+        &app,
+        &token,
+        &[("first.png", &png), ("many.zip", &zip_of_pngs(100))],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["message"], "批量上传最多允许 100 个文件");
+}
+
+#[tokio::test]
+async fn test_batch_upload_quota() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let first = random_png();
+    let second = random_png();
+    let total = (first.len() + second.len()) as u64;
+
+    // 配额不足以容纳整批时全部拒绝，不会保存其中一部分
+    let (token_id, token) = create_user_token(&app_state, Some(total - 1)).await;
+    let (status, json) = upload_batch(&app, &token, &[("a.png", &first), ("b.png", &second)]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["message"], "已超过上传配额");
+
+    let service = TokenService::new(app_state.db_pool().get_connection());
+    assert_eq!(
+        service.get_token(token_id).await.unwrap().used_upload_size,
+        0
+    );
+
+    // 失败和重复的文件占用的配额会被退还
+    let (token_id, token) = create_user_token(&app_state, Some(total + 100)).await;
+    let (status, json) = upload_batch(
+        &app,
+        &token,
+        &[
+            ("a.png", &first),
+            ("bad.png", b"not an image"),
+            ("again.png", &first),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["succeeded"], 2);
+    assert_eq!(
+        json["results"][0]["data"]["hash"],
+        json["results"][2]["data"]["hash"]
+    );
+    assert_eq!(
+        service.get_token(token_id).await.unwrap().used_upload_size,
+        first.len() as i64
+    );
+}
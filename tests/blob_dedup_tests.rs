@@ -0,0 +1,137 @@
+//! 文件内容去重测试
+//! 不同令牌上传相同文件时共享同一份存储，但图片记录、配额和删除仍然按令牌独立
+
+use axum::http::{Method, StatusCode};
+use sha2::{Digest, Sha256};
+
+use rifs::models::TokenRole;
+use rifs::repositories::BlobRepository;
+use rifs::services::TokenService;
+use rifs::storage::sharded_key;
+
+mod common;
+use common::{create_test_app, create_token, random_png, request, upload};
+
+#[tokio::test]
+async fn test_identical_uploads_share_storage() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (first_id, first_token) = create_token(&app_state, TokenRole::User).await;
+    let (second_id, second_token) = create_token(&app_state, TokenRole::User).await;
+
+    let png = random_png();
+    let content_hash = format!("{:x}", Sha256::digest(&png));
+    let blob_key = sharded_key(&content_hash, &format!("{}.png", content_hash));
+    let blob_repo = BlobRepository::new(app_state.db_pool().get_connection());
+
+    // 图片记录按令牌区分，文件内容只保存一份
+    let first_hash = upload(&app, Some(&first_token), &png).await;
+    let second_hash = upload(&app, Some(&second_token), &png).await;
+    assert_ne!(first_hash, second_hash);
+
+    let blob = blob_repo
+        .find_by_hash(&content_hash)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(blob.ref_count, 2);
+    assert_eq!(blob.size, png.len() as i64);
+    assert!(app_state.storage().exists(&blob_key).await.unwrap());
+
+    // 配额仍然分别计算
+    let service = TokenService::new(app_state.db_pool().get_connection());
+    for token_id in [first_id, second_id] {
+        assert_eq!(
+            service.get_token(token_id).await.unwrap().used_upload_size,
+            png.len() as i64
+        );
+    }
+
+    // 不能删除其他令牌的图片
+    let uri = format!("/api/images/{}", second_hash);
+    assert_eq!(
+        request(&app, Method::DELETE, &uri, Some(&first_token), None)
+            .await
+            .0,
+        StatusCode::UNAUTHORIZED
+    );
+
+    // 彻底删除其中一张后，另一张仍然可以访问
+    let uri = format!("/api/images/{}?permanent=true", first_hash);
+    assert_eq!(
+        request(&app, Method::DELETE, &uri, Some(&first_token), None)
+            .await
+            .0,
+        StatusCode::OK
+    );
+    let blob = blob_repo
+        .find_by_hash(&content_hash)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(blob.ref_count, 1);
+    assert!(app_state.storage().exists(&blob_key).await.unwrap());
+    let uri = format!("/images/{}", second_hash);
+    assert_eq!(
+        request(&app, Method::GET, &uri, Some(&second_token), None)
+            .await
+            .0,
+        StatusCode::OK
+    );
+
+    // 最后一个引用删除后文件随之删除
+    let uri = format!("/api/images/{}?permanent=true", second_hash);
+    assert_eq!(
+        request(&app, Method::DELETE, &uri, Some(&second_token), None)
+            .await
+            .0,
+        StatusCode::OK
+    );
+    assert!(blob_repo
+        .find_by_hash(&content_hash)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(!app_state.storage().exists(&blob_key).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_release_waits_for_file_removal() {
+    let (_, app_state) = create_test_app("config_auth_test").await;
+    let blob_repo = BlobRepository::new(app_state.db_pool().get_connection());
+    let content_hash = format!("{:x}", Sha256::digest(random_png()));
+    assert_eq!(
+        blob_repo
+            .acquire(&content_hash, 1, "image/png", "png")
+            .await
+            .unwrap(),
+        1
+    );
+
+    // 删除文件期间上传相同内容，需等待删除完成后重新创建记录，由上传方重新写入文件
+    let (removing_tx, removing_rx) = tokio::sync::oneshot::channel();
+    let connection = app_state.db_pool().get_connection();
+    let hash = content_hash.clone();
+    let acquirer = tokio::spawn(async move {
+        removing_rx.await.unwrap();
+        BlobRepository::new(connection)
+            .acquire(&hash, 1, "image/png", "png")
+            .await
+    });
+    let released = blob_repo
+        .release(&content_hash, || async {
+            removing_tx.send(()).unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            Ok(())
+        })
+        .await
+        .unwrap();
+    assert!(released);
+    assert_eq!(acquirer.await.unwrap().unwrap(), 1);
+
+    let blob = blob_repo
+        .find_by_hash(&content_hash)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(blob.ref_count, 1);
+}
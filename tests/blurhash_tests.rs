@@ -0,0 +1,164 @@
+//! BlurHash 占位图测试
+//! 覆盖编码格式、上传时保存和返回的占位字符串以及 `blurhash` 转换输出
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use image::{DynamicImage, ImageFormat, RgbImage};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use rifs::services::blurhash::BlurHash;
+
+mod common;
+use common::{create_test_app, upload_file};
+
+const BASE83_CHARS: &str =
+    "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+fn decode83(value: &str) -> u32 {
+    value
+        .chars()
+        .fold(0, |acc, c| acc * 83 + BASE83_CHARS.find(c).unwrap() as u32)
+}
+
+/// 左侧三分之二为红色、右侧为随机深浅蓝色的图片
+fn red_and_blue(width: u32, height: u32) -> RgbImage {
+    let blue: u8 = rand::random::<u8>() | 0x80;
+    RgbImage::from_fn(width, height, |x, _| {
+        if x < width * 2 / 3 {
+            image::Rgb([255, 0, 0])
+        } else {
+            image::Rgb([0, 0, blue])
+        }
+    })
+}
+
+fn encode_png(img: RgbImage) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(img)
+        .write_to(&mut std::io::Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+async fn upload(app: &axum::Router, data: &[u8]) -> Value {
+    let (status, json) = upload_file(app, None, "photo.png", data).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    json["data"].clone()
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, HeaderSnapshot, Vec<u8>) {
+    let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = HeaderSnapshot {
+        content_type: response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|value| value.to_str().unwrap().to_string()),
+        etag: response
+            .headers()
+            .get(header::ETAG)
+            .map(|value| value.to_str().unwrap().to_string()),
+    };
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, headers, body.to_vec())
+}
+
+struct HeaderSnapshot {
+    content_type: Option<String>,
+    etag: Option<String>,
+}
+
+#[test]
+fn test_blurhash_encoding() {
+    // 纯色图片的直流分量即原色，横向图片使用 4x3 个分量
+    let red = DynamicImage::ImageRgb8(RgbImage::from_pixel(16, 8, image::Rgb([255, 0, 0])));
+    let hash = BlurHash::encode(&red);
+    assert_eq!(hash.len(), 4 + 2 * 4 * 3);
+    assert_eq!(&hash[..1], "L");
+    assert_eq!(decode83(&hash[2..6]), 0xff0000);
+
+    // 纵向图片使用 3x4 个分量
+    let portrait = DynamicImage::ImageRgb8(red_and_blue(8, 16));
+    let hash = BlurHash::encode(&portrait);
+    assert_eq!(hash.len(), 28);
+    assert_eq!(&hash[..1], "T");
+    assert!(hash.chars().all(|c| BASE83_CHARS.contains(c)));
+
+    // 内容不同的图片得到不同的占位字符串，同一图片结果稳定
+    let landscape = DynamicImage::ImageRgb8(red_and_blue(64, 32));
+    assert_eq!(BlurHash::encode(&landscape), BlurHash::encode(&landscape));
+    assert_ne!(BlurHash::encode(&landscape), BlurHash::encode(&red));
+}
+
+#[tokio::test]
+async fn test_upload_returns_blurhash() {
+    let app = create_test_app("config_test").await.0;
+    let img = red_and_blue(37, 23);
+    let expected = BlurHash::encode(&DynamicImage::ImageRgb8(img.clone()));
+
+    let data = upload(&app, &encode_png(img)).await;
+    let hash = data["hash"].as_str().unwrap().to_string();
+    assert_eq!(data["blurhash"], expected.as_str());
+
+    let (status, _, body) = get(&app, &format!("/images/{}/info", hash)).await;
+    assert_eq!(status, StatusCode::OK);
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["blurhash"], expected.as_str());
+
+    // 查询结果同样包含占位字符串
+    let (status, _, body) = get(
+        &app,
+        "/api/images/query?min_width=37&max_width=37&min_height=23&max_height=23&limit=100",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let item = json["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["hash"] == hash.as_str())
+        .cloned()
+        .unwrap();
+    assert_eq!(item["blurhash"], expected.as_str());
+}
+
+#[tokio::test]
+async fn test_blurhash_transform_output() {
+    let app = create_test_app("config_test").await.0;
+    let data = upload(&app, &encode_png(red_and_blue(60, 30))).await;
+    let hash = data["hash"].as_str().unwrap();
+
+    // 单独使用时返回上传时保存的值
+    for params in ["blurhash", "bh"] {
+        let (status, headers, body) = get(&app, &format!("/images/{}@{}", hash, params)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            headers.content_type.as_deref(),
+            Some("text/plain; charset=utf-8")
+        );
+        assert_eq!(String::from_utf8(body).unwrap(), data["blurhash"]);
+    }
+
+    // 与其他转换参数组合时按转换结果计算：只裁出红色部分得到纯色占位
+    let (status, headers, body) = get(&app, &format!("/images/{}@crop20x20_bh", hash)).await;
+    assert_eq!(status, StatusCode::OK);
+    let cropped = String::from_utf8(body).unwrap();
+    assert_ne!(cropped, data["blurhash"].as_str().unwrap());
+    assert_eq!(decode83(&cropped[2..6]), 0xff0000);
+
+    // 带 ETag 重新验证时返回 304
+    let request = Request::builder()
+        .uri(format!("/images/{}@crop20x20_bh", hash))
+        .header(header::IF_NONE_MATCH, headers.etag.unwrap())
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+}
@@ -0,0 +1,364 @@
+//! 管理命令行测试
+//! 覆盖参数解析、令牌的创建/禁用/重新生成、目录导入和存储校验命令
+
+use std::path::{Path, PathBuf};
+
+use rifs::app_state::AppState;
+use rifs::cli::commands::{execute, import_directory};
+use rifs::cli::{Cli, Command};
+use rifs::models::{CreateTokenPayload, ScrubIssueKind, ScrubOptions, ScrubReport, TokenRole};
+use rifs::services::{ScrubService, TokenService};
+use rifs::utils::{AppError, ByteSize, Duration};
+
+async fn create_app_state() -> AppState {
+    if let Err(err) = rifs::config::AppConfig::init(Some("config_test")) {
+        if !matches!(err, AppError::Internal(ref msg) if msg == "配置已被初始化") {
+            panic!("Failed to initialize config: {}", err);
+        }
+    }
+
+    AppState::new().await.expect("Failed to create app state")
+}
+
+fn parse(args: &str) -> Result<Cli, AppError> {
+    Cli::parse(args.split_whitespace())
+}
+
+fn random_png() -> Vec<u8> {
+    let mut png_bytes = Vec::new();
+    image::RgbImage::from_fn(12, 10, |x, y| {
+        image::Rgb([rand::random(), x as u8 * 20, y as u8 * 20])
+    })
+    .write_to(
+        &mut std::io::Cursor::new(&mut png_bytes),
+        image::ImageFormat::Png,
+    )
+    .unwrap();
+    png_bytes
+}
+
+/// 创建包含图片、非图片文件、隐藏文件和子目录的临时目录
+fn create_import_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rifs-import-{:016x}", rand::random::<u64>()));
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+    std::fs::write(dir.join("a.png"), random_png()).unwrap();
+    std::fs::write(dir.join("b.png"), random_png()).unwrap();
+    std::fs::write(dir.join("notes.txt"), b"not an image").unwrap();
+    std::fs::write(dir.join(".hidden.png"), random_png()).unwrap();
+    std::fs::write(dir.join("nested").join("c.png"), random_png()).unwrap();
+    dir
+}
+
+fn file_names(paths: &[(PathBuf, String)]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|(path, _)| path.file_name().unwrap().to_string_lossy().to_string())
+        .collect()
+}
+
+#[test]
+fn test_parse_commands() {
+    assert_eq!(parse("").unwrap().command, Command::Serve);
+    assert_eq!(parse("--help").unwrap().command, Command::Help);
+
+    let cli = parse("-c config_test stats").unwrap();
+    assert_eq!(cli.config.as_deref(), Some("config_test"));
+    assert_eq!(cli.command, Command::Stats);
+
+    // 选项可以放在子命令之后，也可以使用 --name=value 形式
+    let cli = parse(
+        "token create uploader --role admin --max-upload-size=1GB --expires 30d --config=prod",
+    )
+    .unwrap();
+    assert_eq!(cli.config.as_deref(), Some("prod"));
+    assert_eq!(
+        cli.command,
+        Command::TokenCreate {
+            name: "uploader".to_string(),
+            role: TokenRole::Admin,
+            max_upload_size: Some(ByteSize::gb(1)),
+            max_images: None,
+            expires: Some(Duration::days(30)),
+        }
+    );
+    assert_eq!(
+        parse("import ./photos --recursive --owner 3")
+            .unwrap()
+            .command,
+        Command::Import {
+            dir: PathBuf::from("./photos"),
+            owner: Some(3),
+            private: false,
+            recursive: true,
+        }
+    );
+    assert_eq!(
+        parse("verify --checksum").unwrap().command,
+        Command::Verify {
+            checksum: true,
+            repair: false
+        }
+    );
+    assert_eq!(
+        parse("verify --repair").unwrap().command,
+        Command::Verify {
+            checksum: false,
+            repair: true
+        }
+    );
+    assert_eq!(parse("cache purge").unwrap().command, Command::CachePurge);
+    assert_eq!(
+        parse("token rotate 1").unwrap().command,
+        Command::TokenRotate { id: 1 }
+    );
+    assert_eq!(
+        parse("token create limited --max-images 50")
+            .unwrap()
+            .command,
+        Command::TokenCreate {
+            name: "limited".to_string(),
+            role: TokenRole::User,
+            max_upload_size: None,
+            max_images: Some(50),
+            expires: None,
+        }
+    );
+    assert_eq!(
+        parse("token reconcile --dry-run").unwrap().command,
+        Command::TokenReconcile { dry_run: true }
+    );
+    assert_eq!(
+        parse("token reconcile").unwrap().command,
+        Command::TokenReconcile { dry_run: false }
+    );
+
+    // 参数错误
+    for args in [
+        "bogus",
+        "token",
+        "token revoke",
+        "token revoke abc",
+        "token create name --role root",
+        "token create name --max-upload-size lots",
+        "token create name --max-images -1",
+        "token reconcile 1",
+        "stats --checksum",
+        "stats extra",
+        "verify --checksum=yes",
+        "import",
+        "--config",
+    ] {
+        assert!(
+            matches!(parse(args), Err(AppError::BadRequest(_))),
+            "应当拒绝: {}",
+            args
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_token_commands() {
+    let app_state = create_app_state().await;
+    let service = TokenService::new(app_state.db_pool().get_connection());
+    let admin = service.first_admin().await.unwrap().unwrap();
+    assert_eq!(admin.role, TokenRole::Admin);
+
+    // 唯一可用的管理员令牌不能被禁用
+    assert!(service.revoke_token(admin.id).await.is_err());
+
+    execute(
+        &app_state,
+        Command::TokenCreate {
+            name: "cli-user".to_string(),
+            role: TokenRole::User,
+            max_upload_size: Some(ByteSize::mb(5)),
+            max_images: Some(100),
+            expires: Some(Duration::days(1)),
+        },
+    )
+    .await
+    .unwrap();
+    let created = service
+        .list_tokens()
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|token| token.name == "cli-user")
+        .unwrap();
+    assert_eq!(created.max_upload_size, Some(5 * 1024 * 1024));
+    assert_eq!(created.max_image_count, Some(100));
+    assert!(created.expires_at.is_some());
+
+    // 重新生成明文后旧令牌失效
+    let user = service
+        .create_token(CreateTokenPayload {
+            name: "rotated".to_string(),
+            role: TokenRole::User,
+            max_upload_size: None,
+            max_image_count: None,
+            expires_at: None,
+        })
+        .await
+        .unwrap();
+    let rotated = service.rotate_token(user.token.id).await.unwrap();
+    assert_ne!(rotated.plaintext, user.plaintext);
+    assert!(service
+        .find_by_token_hash(&user.plaintext)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        service
+            .find_by_token_hash(&rotated.plaintext)
+            .await
+            .unwrap()
+            .unwrap()
+            .id,
+        user.token.id
+    );
+
+    // 禁用后令牌保留但无法认证
+    execute(&app_state, Command::TokenRevoke { id: user.token.id })
+        .await
+        .unwrap();
+    assert!(!service.get_token(user.token.id).await.unwrap().is_active);
+    assert!(matches!(
+        service.verify_plain_token(&rotated.plaintext).await,
+        Err(AppError::Unauthorized(_))
+    ));
+    assert!(execute(&app_state, Command::TokenRevoke { id: 99999 })
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_import_and_verify() {
+    let app_state = create_app_state().await;
+    let dir = create_import_dir();
+
+    // 不递归时只导入顶层文件，非图片文件单独失败，隐藏文件跳过
+    let report = import_directory(&app_state, &dir, None, false, false)
+        .await
+        .unwrap();
+    assert_eq!(file_names(&report.imported), ["a.png", "b.png"]);
+    assert_eq!(file_names(&report.failed), ["notes.txt"]);
+
+    let report = import_directory(&app_state, &dir, None, true, true)
+        .await
+        .unwrap();
+    assert_eq!(file_names(&report.imported), ["a.png", "b.png", "c.png"]);
+    let nested_hash = report.imported[2].1.clone();
+
+    let service = TokenService::new(app_state.db_pool().get_connection());
+    let admin = service.first_admin().await.unwrap().unwrap();
+    let image = rifs::services::ImageService::get_image_info(app_state.db_pool(), &nested_hash)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(image.owner_token_id, Some(admin.id));
+    assert!(image.is_private);
+    assert_eq!(image.original_filename.as_deref(), Some("c.png"));
+
+    assert!(
+        import_directory(&app_state, &dir.join("a.png"), None, false, false)
+            .await
+            .is_err()
+    );
+    assert!(
+        import_directory(&app_state, &dir, Some(99999), false, false)
+            .await
+            .is_err()
+    );
+
+    let report = ScrubService::scrub(&app_state, options(true))
+        .await
+        .unwrap();
+    assert_eq!(report.images_checked, 3);
+    assert_eq!(record_issues(&report), []);
+
+    // 内容被改写（大小不变）只有校验内容时才能发现
+    let hashes: Vec<String> = report_hashes(&dir, &app_state).await;
+    let tampered = rifs::services::ImageService::get_image_info(app_state.db_pool(), &hashes[0])
+        .await
+        .unwrap()
+        .unwrap();
+    let mut data = app_state
+        .storage()
+        .get(&tampered.storage_key())
+        .await
+        .unwrap();
+    let last = data.len() - 1;
+    data[last] ^= 0xff;
+    app_state
+        .storage()
+        .put(&tampered.storage_key(), &data)
+        .await
+        .unwrap();
+    let report = ScrubService::scrub(&app_state, options(false))
+        .await
+        .unwrap();
+    assert_eq!(record_issues(&report), []);
+    let report = ScrubService::scrub(&app_state, options(true))
+        .await
+        .unwrap();
+    assert_eq!(
+        record_issues(&report),
+        [(tampered.hash.clone(), ScrubIssueKind::ChecksumMismatch)]
+    );
+
+    // 文件丢失
+    let missing = rifs::services::ImageService::get_image_info(app_state.db_pool(), &hashes[1])
+        .await
+        .unwrap()
+        .unwrap();
+    app_state
+        .storage()
+        .delete(&missing.storage_key())
+        .await
+        .unwrap();
+    let report = ScrubService::scrub(&app_state, options(false))
+        .await
+        .unwrap();
+    assert_eq!(
+        record_issues(&report),
+        [(missing.hash.clone(), ScrubIssueKind::MissingFile)]
+    );
+    assert!(execute(
+        &app_state,
+        Command::Verify {
+            checksum: false,
+            repair: false
+        }
+    )
+    .await
+    .is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn options(checksum: bool) -> ScrubOptions {
+    ScrubOptions {
+        checksum,
+        repair: false,
+    }
+}
+
+/// 与图片记录相关的问题（上传目录与其他测试共用，不检查孤立文件）
+fn record_issues(report: &ScrubReport) -> Vec<(String, ScrubIssueKind)> {
+    report
+        .issues
+        .iter()
+        .filter_map(|issue| Some((issue.record.clone()?, issue.kind)))
+        .collect()
+}
+
+/// 重新导入顶层图片得到它们的hash（已存在的图片直接返回原记录）
+async fn report_hashes(dir: &Path, app_state: &AppState) -> Vec<String> {
+    import_directory(app_state, dir, None, false, false)
+        .await
+        .unwrap()
+        .imported
+        .into_iter()
+        .map(|(_, hash)| hash)
+        .collect()
+}
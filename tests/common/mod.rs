@@ -0,0 +1,179 @@
+//! 集成测试共用的测试应用、令牌、图片和请求工具
+//!
+//! 每个测试文件只用到其中一部分，未使用的函数不视为警告。
+#![allow(dead_code)]
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use rifs::app_state::AppState;
+use rifs::models::{CreateTokenPayload, TokenRole};
+use rifs::routes::create_routes;
+use rifs::services::TokenService;
+use rifs::utils::AppError;
+
+/// multipart 请求体的分隔符
+pub const BOUNDARY: &str = "----RifsTestBoundary";
+
+/// 使用指定配置创建测试应用
+///
+/// 配置在同一个测试进程中只初始化一次，重复初始化的错误忽略。
+pub async fn create_test_app(config: &str) -> (axum::Router, AppState) {
+    if let Err(err) = rifs::config::AppConfig::init(Some(config)) {
+        if !matches!(err, AppError::Internal(ref msg) if msg == "配置已被初始化") {
+            panic!("Failed to initialize config: {}", err);
+        }
+    }
+
+    let app_state = AppState::new().await.expect("Failed to create app state");
+    let app = create_routes(app_state.clone(), app_state.config());
+    (app, app_state)
+}
+
+/// 不限制配额、永不过期的令牌参数
+pub fn token_payload(role: TokenRole) -> CreateTokenPayload {
+    CreateTokenPayload {
+        name: "test".to_string(),
+        role,
+        max_upload_size: None,
+        max_image_count: None,
+        expires_at: None,
+    }
+}
+
+/// 按参数创建令牌，返回令牌ID和明文
+pub async fn create_token_with(app_state: &AppState, payload: CreateTokenPayload) -> (i32, String) {
+    let created = TokenService::new(app_state.db_pool().get_connection())
+        .create_token(payload)
+        .await
+        .unwrap();
+    (created.token.id, created.plaintext)
+}
+
+/// 创建指定角色的令牌，返回令牌ID和明文
+pub async fn create_token(app_state: &AppState, role: TokenRole) -> (i32, String) {
+    create_token_with(app_state, token_payload(role)).await
+}
+
+/// 生成内容随机的 16x16 PNG 图片，每次调用的哈希都不同
+pub fn random_png() -> Vec<u8> {
+    let mut png_bytes = Vec::new();
+    image::RgbImage::from_fn(16, 16, |x, _| image::Rgb([rand::random(), x as u8, 0]))
+        .write_to(
+            &mut std::io::Cursor::new(&mut png_bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+    png_bytes
+}
+
+/// 构造 multipart 请求体，所有文件使用同一个字段名
+pub fn multipart_body(field: &str, files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut form_data = Vec::new();
+    for (filename, data) in files {
+        form_data.extend_from_slice(format!("--{}\r\n", BOUNDARY).as_bytes());
+        form_data.extend_from_slice(
+            format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n",
+                field, filename
+            )
+            .as_bytes(),
+        );
+        form_data.extend_from_slice(b"Content-Type: application/octet-stream\r\n\r\n");
+        form_data.extend_from_slice(data);
+        form_data.extend_from_slice(b"\r\n");
+    }
+    form_data.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+    form_data
+}
+
+/// 构造上传请求，字段名为 files 时可以一次上传多个文件
+pub fn upload_request(
+    uri: &str,
+    token: Option<&str>,
+    field: &str,
+    files: &[(&str, &[u8])],
+) -> Request<Body> {
+    let mut builder = Request::builder().method(Method::POST).uri(uri).header(
+        header::CONTENT_TYPE,
+        format!("multipart/form-data; boundary={}", BOUNDARY),
+    );
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    builder
+        .body(Body::from(multipart_body(field, files)))
+        .unwrap()
+}
+
+/// 上传单个文件，返回状态码和响应体
+pub async fn upload_file(
+    app: &axum::Router,
+    token: Option<&str>,
+    filename: &str,
+    data: &[u8],
+) -> (StatusCode, Value) {
+    send(
+        app,
+        upload_request("/upload", token, "file", &[(filename, data)]),
+    )
+    .await
+}
+
+/// 上传单张图片并返回哈希
+pub async fn upload(app: &axum::Router, token: Option<&str>, data: &[u8]) -> String {
+    let (status, json) = upload_file(app, token, "image.png", data).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    json["data"]["hash"].as_str().unwrap().to_string()
+}
+
+/// 构造请求，可选附带令牌和 JSON 请求体
+pub fn build_request(
+    method: Method,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> Request<Body> {
+    let mut builder = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    match body {
+        Some(body) => builder
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => builder.body(Body::empty()),
+    }
+    .unwrap()
+}
+
+/// 发送请求，返回状态码和原始响应体
+pub async fn send_bytes(app: &axum::Router, request: Request<Body>) -> (StatusCode, Vec<u8>) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, body.to_vec())
+}
+
+/// 发送请求，返回状态码和 JSON 响应体（无法解析时为 Null）
+pub async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let (status, body) = send_bytes(app, request).await;
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// 发送请求并解析 JSON 响应体
+pub async fn request(
+    app: &axum::Router,
+    method: Method,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    send(app, build_request(method, uri, token, body)).await
+}
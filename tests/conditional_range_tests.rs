@@ -0,0 +1,260 @@
+//! 条件请求与区间请求测试
+//! 覆盖 ETag / Last-Modified 协商以及单区间、多区间的 Range 响应
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, Request, StatusCode},
+};
+use tower::ServiceExt;
+
+use rifs::utils::conditional::{http_date, if_range_matches, is_not_modified, parse_http_date};
+use rifs::utils::{parse_range_header, RangeRequest};
+
+mod common;
+use common::{create_test_app, upload};
+
+/// 上传一张随机颜色的 PNG，返回 (哈希, 文件内容)
+async fn upload_random_png(app: &axum::Router) -> (String, Vec<u8>) {
+    let mut png_bytes = Vec::new();
+    let image = image::RgbImage::from_fn(16, 16, |x, y| {
+        image::Rgb([rand::random(), (x * 16) as u8, (y * 16) as u8])
+    });
+    image::DynamicImage::ImageRgb8(image)
+        .write_to(
+            &mut std::io::Cursor::new(&mut png_bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+    let hash = upload(app, None, &png_bytes).await;
+    (hash, png_bytes)
+}
+
+/// 以 (起始, 结束) 列表构造期望的区间结果
+fn partial(ranges: &[(u64, u64)]) -> RangeRequest {
+    RangeRequest::Partial(ranges.iter().map(|&(start, end)| start..end).collect())
+}
+
+#[test]
+fn test_parse_range_header() {
+    assert_eq!(parse_range_header("bytes=0-9", 100), partial(&[(0, 10)]));
+    assert_eq!(parse_range_header("bytes=90-", 100), partial(&[(90, 100)]));
+    assert_eq!(parse_range_header("bytes=-10", 100), partial(&[(90, 100)]));
+    // 结束位置超出文件大小时截断
+    assert_eq!(
+        parse_range_header("bytes=95-200", 100),
+        partial(&[(95, 100)])
+    );
+    // 重叠和相邻区间合并并排序
+    assert_eq!(
+        parse_range_header("bytes=50-59, 0-4, 5-9, 55-70", 100),
+        partial(&[(0, 10), (50, 71)])
+    );
+    assert_eq!(
+        parse_range_header("bytes=100-", 100),
+        RangeRequest::Unsatisfiable
+    );
+    // 无法识别的格式忽略 Range
+    assert_eq!(parse_range_header("items=0-9", 100), RangeRequest::Full);
+    assert_eq!(parse_range_header("bytes=9-0", 100), RangeRequest::Full);
+    assert_eq!(parse_range_header("bytes=abc", 100), RangeRequest::Full);
+}
+
+#[test]
+fn test_conditional_headers() {
+    let last_modified = parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+    assert_eq!(http_date(&last_modified), "Wed, 21 Oct 2015 07:28:00 GMT");
+
+    let etag = "\"abc@w100\"";
+    let mut headers = HeaderMap::new();
+    assert!(!is_not_modified(&headers, etag, &last_modified));
+
+    headers.insert(
+        header::IF_NONE_MATCH,
+        "\"other\", W/\"abc@w100\"".parse().unwrap(),
+    );
+    assert!(is_not_modified(&headers, etag, &last_modified));
+
+    // If-None-Match 不匹配时忽略 If-Modified-Since
+    headers.insert(header::IF_NONE_MATCH, "\"other\"".parse().unwrap());
+    headers.insert(
+        header::IF_MODIFIED_SINCE,
+        "Thu, 22 Oct 2015 07:28:00 GMT".parse().unwrap(),
+    );
+    assert!(!is_not_modified(&headers, etag, &last_modified));
+
+    headers.remove(header::IF_NONE_MATCH);
+    assert!(is_not_modified(&headers, etag, &last_modified));
+    headers.insert(
+        header::IF_MODIFIED_SINCE,
+        "Tue, 20 Oct 2015 07:28:00 GMT".parse().unwrap(),
+    );
+    assert!(!is_not_modified(&headers, etag, &last_modified));
+
+    // If-Range 使用强比较
+    let mut headers = HeaderMap::new();
+    assert!(if_range_matches(&headers, etag, &last_modified));
+    headers.insert(header::IF_RANGE, "W/\"abc@w100\"".parse().unwrap());
+    assert!(!if_range_matches(&headers, etag, &last_modified));
+    headers.insert(header::IF_RANGE, "\"abc@w100\"".parse().unwrap());
+    assert!(if_range_matches(&headers, etag, &last_modified));
+    headers.insert(
+        header::IF_RANGE,
+        "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+    );
+    assert!(if_range_matches(&headers, etag, &last_modified));
+}
+
+#[tokio::test]
+async fn test_image_etag_and_not_modified() {
+    let app = create_test_app("config_test").await.0;
+    let (hash, _) = upload_random_png(&app).await;
+
+    let request = Request::builder()
+        .uri(format!("/images/{}", hash))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let last_modified = response.headers()[header::LAST_MODIFIED]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(etag, format!("\"{}\"", hash));
+    assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+
+    let request = Request::builder()
+        .uri(format!("/images/{}", hash))
+        .header(header::IF_NONE_MATCH, &etag)
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::ETAG], etag.as_str());
+
+    let request = Request::builder()
+        .uri(format!("/images/{}", hash))
+        .header(header::IF_MODIFIED_SINCE, &last_modified)
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    // 转换结果的 ETag 包含规范化的转换参数
+    let request = Request::builder()
+        .uri(format!("/images/{}@png_w8", hash))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::ETAG],
+        format!("\"{}@w8_png\"", hash).as_str()
+    );
+
+    let request = Request::builder()
+        .uri(format!("/images/{}@w8_png", hash))
+        .header(header::IF_NONE_MATCH, format!("\"{}@w8_png\"", hash))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+}
+
+#[tokio::test]
+async fn test_image_byte_ranges() {
+    let app = create_test_app("config_test").await.0;
+    let (hash, png_bytes) = upload_random_png(&app).await;
+    let total = png_bytes.len();
+
+    // 单区间
+    let request = Request::builder()
+        .uri(format!("/images/{}", hash))
+        .header(header::RANGE, "bytes=0-9")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        response.headers()[header::CONTENT_RANGE],
+        format!("bytes 0-9/{}", total).as_str()
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(body.as_ref(), &png_bytes[0..10]);
+
+    // 多区间
+    let request = Request::builder()
+        .uri(format!("/images/{}", hash))
+        .header(header::RANGE, "bytes=0-3,-4")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    let content_type = response.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let boundary = content_type
+        .strip_prefix("multipart/byteranges; boundary=")
+        .unwrap()
+        .to_string();
+    let content_length: usize = response.headers()[header::CONTENT_LENGTH]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(body.len(), content_length);
+
+    let mut expected = Vec::new();
+    for (start, end) in [(0, 4), (total - 4, total)] {
+        expected.extend_from_slice(
+            format!(
+                "\r\n--{}\r\nContent-Type: image/png\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                boundary,
+                start,
+                end - 1,
+                total
+            )
+            .as_bytes(),
+        );
+        expected.extend_from_slice(&png_bytes[start..end]);
+    }
+    expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    assert_eq!(body.as_ref(), expected.as_slice());
+
+    // 无法满足的区间
+    let request = Request::builder()
+        .uri(format!("/images/{}", hash))
+        .header(header::RANGE, format!("bytes={}-", total))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(
+        response.headers()[header::CONTENT_RANGE],
+        format!("bytes */{}", total).as_str()
+    );
+
+    // If-Range 不匹配时返回完整内容
+    let request = Request::builder()
+        .uri(format!("/images/{}", hash))
+        .header(header::RANGE, "bytes=0-9")
+        .header(header::IF_RANGE, "\"stale\"")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(body.len(), total);
+}
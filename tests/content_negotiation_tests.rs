@@ -0,0 +1,158 @@
+//! 输出格式协商测试
+//! 覆盖 Accept 请求头解析、`auto` 参数以及协商结果对 ETag 和 Vary 的影响
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use tower::ServiceExt;
+
+use rifs::models::ImageTransformParams;
+use rifs::services::image_format_utils::ImageFormatUtils;
+
+mod common;
+use common::{create_test_app, upload};
+
+const CHROME_ACCEPT: &str = "image/avif,image/webp,image/apng,image/svg+xml,image/*,*/*;q=0.8";
+
+async fn upload_png(app: &axum::Router) -> String {
+    let mut png_bytes = Vec::new();
+    image::RgbImage::from_fn(32, 16, |x, _| image::Rgb([rand::random(), x as u8, 0]))
+        .write_to(
+            &mut std::io::Cursor::new(&mut png_bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+    upload(app, None, &png_bytes).await
+}
+
+fn get(uri: &str, accept: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder().method(Method::GET).uri(uri);
+    if let Some(accept) = accept {
+        builder = builder.header(header::ACCEPT, accept);
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+#[test]
+fn test_negotiate_format() {
+    let params = ImageTransformParams::default();
+    let negotiate =
+        |accept: &str, mime: &str| ImageFormatUtils::negotiate_format(accept, mime, &params);
+
+    assert_eq!(negotiate(CHROME_ACCEPT, "image/jpeg"), Some("avif"));
+    assert_eq!(negotiate("image/webp,*/*", "image/png"), Some("webp"));
+    assert_eq!(negotiate("IMAGE/WEBP", "image/png"), Some("webp"));
+    // q=0 表示明确拒绝
+    assert_eq!(
+        negotiate("image/avif;q=0, image/webp", "image/png"),
+        Some("webp")
+    );
+    // 按 q 值选择权重最高的格式
+    assert_eq!(
+        negotiate("image/avif;q=0.1, image/webp", "image/png"),
+        Some("webp")
+    );
+    assert_eq!(
+        negotiate("image/webp;q=0.5, image/avif;q=0.9", "image/jpeg"),
+        Some("avif")
+    );
+    assert_eq!(
+        negotiate("image/avif; Q=0.4, image/webp;q=0.6", "image/jpeg"),
+        Some("webp")
+    );
+    // 权重相同时优先 AVIF
+    assert_eq!(
+        negotiate("image/webp;q=0.8, image/avif;q=0.8", "image/png"),
+        Some("avif")
+    );
+    // 全部拒绝或原格式权重更高时保持原样
+    assert_eq!(
+        negotiate("image/avif;q=0, image/webp;q=0", "image/png"),
+        None
+    );
+    assert_eq!(negotiate("image/png, image/webp;q=0.5", "image/png"), None);
+    // 通配符不代表支持新格式
+    assert_eq!(negotiate("image/*,*/*;q=0.8", "image/jpeg"), None);
+    assert_eq!(negotiate("", "image/jpeg"), None);
+
+    // 动图只协商为 WebP；已经是目标格式时保持原样
+    assert_eq!(negotiate(CHROME_ACCEPT, "image/gif"), Some("webp"));
+    assert_eq!(negotiate(CHROME_ACCEPT, "image/webp"), None);
+    assert_eq!(negotiate(CHROME_ACCEPT, "image/x-icon"), None);
+
+    // AVIF 不支持质量参数
+    let params = ImageTransformParams::parse("q80").unwrap();
+    assert_eq!(
+        ImageFormatUtils::negotiate_format(CHROME_ACCEPT, "image/png", &params),
+        Some("webp")
+    );
+}
+
+#[test]
+fn test_parse_auto_token() {
+    let params = ImageTransformParams::parse("w300_auto").unwrap();
+    assert!(params.auto_format);
+    assert!(params.format.is_none());
+    assert_eq!(params.to_normalized_string(), "w300_auto");
+
+    // 明确指定格式时 auto 不生效
+    let params = ImageTransformParams::parse("auto_w300_png").unwrap();
+    assert_eq!(params.to_normalized_string(), "w300_png");
+}
+
+#[tokio::test]
+async fn test_auto_format_follows_accept_header() {
+    let app = create_test_app("config_test").await.0;
+    let hash = upload_png(&app).await;
+    let uri = format!("/images/{}@w8_auto", hash);
+
+    let mut etags = Vec::new();
+    for (accept, expected_mime) in [
+        (Some(CHROME_ACCEPT), "image/avif"),
+        (Some("image/webp,*/*"), "image/webp"),
+        (None, "image/png"),
+    ] {
+        let response = app.clone().oneshot(get(&uri, accept)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], expected_mime);
+        assert_eq!(response.headers()[header::VARY], "Accept");
+        etags.push(response.headers()[header::ETAG].clone());
+    }
+    etags.dedup();
+    assert_eq!(etags.len(), 3);
+
+    // 协商结果与显式指定格式共享 ETag 和转换缓存
+    let response = app
+        .clone()
+        .oneshot(get(&format!("/images/{}@w8_webp", hash), None))
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[header::ETAG], etags[1]);
+    assert!(!response
+        .headers()
+        .get_all(header::VARY)
+        .iter()
+        .any(|value| value == "Accept"));
+
+    // 客户端缓存重新验证同样按协商后的 ETag 判断
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(&uri)
+        .header(header::ACCEPT, "image/webp")
+        .header(header::IF_NONE_MATCH, etags[1].clone())
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::VARY], "Accept");
+
+    // 只有 auto 参数且客户端不支持新格式时返回原图
+    let response = app
+        .oneshot(get(&format!("/images/{}@auto", hash), Some("image/png")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-transform-applied"], "false");
+}
@@ -80,6 +80,68 @@ async fn test_upload_endpoint_functionality() {
     assert!(body_str.contains("success"));
 }
 
+#[tokio::test]
+async fn test_upload_then_download_original() {
+    let app = create_test_app().await;
+
+    // 每次生成不同的图片内容，避免与其他测试去重
+    let mut png_bytes = Vec::new();
+    let image = image::RgbImage::from_pixel(3, 2, image::Rgb([rand::random(), 0x20, 0x30]));
+    image::DynamicImage::ImageRgb8(image)
+        .write_to(
+            &mut std::io::Cursor::new(&mut png_bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+    let boundary = "----RifsStreamingBoundary";
+    let mut form_data = Vec::new();
+    form_data.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+    form_data.extend_from_slice(
+        b"Content-Disposition: form-data; name=\"file\"; filename=\"stream.png\"\r\n",
+    );
+    form_data.extend_from_slice(b"Content-Type: image/png\r\n\r\n");
+    form_data.extend_from_slice(&png_bytes);
+    form_data.extend_from_slice(b"\r\n");
+    form_data.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/upload")
+        .header(
+            "content-type",
+            format!("multipart/form-data; boundary={}", boundary),
+        )
+        .body(Body::from(form_data))
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let hash = json["data"]["hash"].as_str().unwrap().to_string();
+    assert_eq!(json["data"]["size"].as_u64().unwrap(), png_bytes.len() as u64);
+
+    // 原图以流的方式返回，内容与上传一致
+    let request = Request::builder()
+        .uri(format!("/images/{}", hash))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-length"],
+        png_bytes.len().to_string().as_str()
+    );
+    assert_eq!(response.headers()["content-type"], "image/png");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(body.as_ref(), png_bytes.as_slice());
+}
+
 #[tokio::test]
 async fn test_images_query_endpoint() {
     let app = create_test_app().await;
@@ -0,0 +1,218 @@
+//! 图片属性测试
+//! 覆盖帧数统计、透明度与调色板提取、上传后返回的属性以及按尺寸和方向过滤查询
+
+use axum::http::{Method, StatusCode};
+use image::{DynamicImage, ImageFormat, RgbImage, RgbaImage};
+use serde_json::Value;
+
+use rifs::models::{ImageTransformParams, TokenRole};
+use rifs::services::image_format_utils::ImageFormatUtils;
+use rifs::services::image_properties::ImageProperties;
+use rifs::services::ImageTransformService;
+
+mod common;
+use common::{create_test_app, create_token, request, upload_file};
+
+/// 三帧 GIF 动图
+fn animated_gif() -> Vec<u8> {
+    let mut output = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut output, 8, 8, &[]).unwrap();
+        for color in [[255u8, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]] {
+            let mut pixels: Vec<u8> = color.repeat(64);
+            let frame = gif::Frame::from_rgba(8, 8, &mut pixels);
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+    output
+}
+
+/// 左边红色、右边蓝色的图片，右侧约三分之一宽度
+fn two_colors(width: u32, height: u32) -> RgbImage {
+    RgbImage::from_fn(width, height, |x, _| {
+        if x < width * 2 / 3 {
+            image::Rgb([255, 0, 0])
+        } else {
+            image::Rgb([0, 0, 255])
+        }
+    })
+}
+
+fn encode(img: &DynamicImage, format: ImageFormat) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut bytes), format)
+        .unwrap();
+    bytes
+}
+
+/// 生成内容随机、指定尺寸的PNG图片
+fn random_png(width: u32, height: u32) -> Vec<u8> {
+    let img = RgbImage::from_fn(width, height, |x, _| {
+        image::Rgb([rand::random(), x as u8, 0])
+    });
+    encode(&DynamicImage::ImageRgb8(img), ImageFormat::Png)
+}
+
+async fn upload(app: &axum::Router, token: &str, data: &[u8]) -> Value {
+    let (status, json) = upload_file(app, Some(token), "image", data).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    json["data"].clone()
+}
+
+async fn query_hashes(app: &axum::Router, token: &str, query: &str) -> Vec<String> {
+    let uri = format!("/api/images/query?{}", query);
+    let (status, json) = request(app, Method::GET, &uri, Some(token), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    json["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["hash"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_frame_count() {
+    let gif_data = animated_gif();
+    assert_eq!(ImageFormatUtils::frame_count("image/gif", &gif_data), 3);
+
+    let params = ImageTransformParams::parse("webp").unwrap();
+    let (webp_data, mime) =
+        ImageTransformService::transform_image_blocking(&gif_data, "image/gif", &params).unwrap();
+    assert_eq!(mime, "image/webp");
+    assert_eq!(ImageFormatUtils::frame_count("image/webp", &webp_data), 3);
+
+    // 静态图片和无法识别的内容都按单帧处理
+    let png = random_png(4, 4);
+    assert_eq!(ImageFormatUtils::frame_count("image/png", &png), 1);
+    assert_eq!(ImageFormatUtils::frame_count("image/gif", b"broken"), 1);
+}
+
+#[test]
+fn test_analyze_properties() {
+    let opaque = DynamicImage::ImageRgb8(two_colors(90, 30));
+    let properties = ImageProperties::analyze(&opaque, &[], "image/png");
+    assert_eq!((properties.width, properties.height), (90, 30));
+    assert_eq!(properties.frame_count, 1);
+    assert!(!properties.has_alpha);
+    assert_eq!(properties.color_type, "rgb8");
+    assert_eq!(properties.palette, ["#ff0000", "#0000ff"]);
+
+    // 有 alpha 通道但完全不透明时不算透明
+    let black = image::Rgba([0, 0, 0, 255]);
+    let solid = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, black));
+    let properties = ImageProperties::analyze(&solid, &[], "image/png");
+    assert_eq!(properties.color_type, "rgba8");
+    assert!(!properties.has_alpha);
+
+    // 透明像素不参与主色调统计
+    let transparent = DynamicImage::ImageRgba8(RgbaImage::from_fn(20, 20, |x, _| {
+        if x < 15 {
+            image::Rgba([255, 255, 255, 0])
+        } else {
+            image::Rgba([0, 128, 0, 255])
+        }
+    }));
+    let properties = ImageProperties::analyze(&transparent, &[], "image/png");
+    assert!(properties.has_alpha);
+    assert_eq!(
+        properties.palette.first().map(String::as_str),
+        Some("#008000")
+    );
+}
+
+#[tokio::test]
+async fn test_upload_stores_properties() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, token) = create_token(&app_state, TokenRole::User).await;
+
+    let data = upload(
+        &app,
+        &token,
+        &encode(
+            &DynamicImage::ImageRgb8(two_colors(120, 60)),
+            ImageFormat::Png,
+        ),
+    )
+    .await;
+    assert_eq!(data["width"], 120);
+    assert_eq!(data["height"], 60);
+    assert_eq!(data["frame_count"], 1);
+    assert_eq!(data["has_alpha"], false);
+    assert_eq!(data["color_type"], "rgb8");
+    assert_eq!(data["dominant_color"], "#ff0000");
+    assert_eq!(data["palette"][1], "#0000ff");
+
+    // 图片信息接口返回同样从数据库读取的属性
+    let uri = format!("/images/{}/info", data["hash"].as_str().unwrap());
+    let (status, json) = request(&app, Method::GET, &uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["width"], 120);
+    assert_eq!(json["data"]["dominant_color"], "#ff0000");
+    assert_eq!(json["data"]["palette"], data["palette"]);
+
+    let data = upload(&app, &token, &animated_gif()).await;
+    assert_eq!(data["frame_count"], 3);
+    assert_eq!(
+        (data["width"].as_u64(), data["height"].as_u64()),
+        (Some(8), Some(8))
+    );
+}
+
+#[tokio::test]
+async fn test_query_by_dimensions() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, token) = create_token(&app_state, TokenRole::User).await;
+
+    let landscape = upload(&app, &token, &random_png(300, 200)).await["hash"].clone();
+    let portrait = upload(&app, &token, &random_png(100, 200)).await["hash"].clone();
+    let square = upload(&app, &token, &random_png(150, 150)).await["hash"].clone();
+    let [landscape, portrait, square] =
+        [landscape, portrait, square].map(|hash| hash.as_str().unwrap().to_string());
+
+    assert_eq!(
+        query_hashes(&app, &token, "orientation=portrait").await,
+        [portrait.as_str()]
+    );
+    assert_eq!(
+        query_hashes(&app, &token, "orientation=square").await,
+        [square.as_str()]
+    );
+    assert_eq!(
+        query_hashes(&app, &token, "min_width=120&max_height=180").await,
+        [square.as_str()]
+    );
+    assert_eq!(
+        query_hashes(&app, &token, "order_by=width&order_dir=desc").await,
+        [landscape.as_str(), square.as_str(), portrait.as_str()]
+    );
+    assert_eq!(
+        query_hashes(
+            &app,
+            &token,
+            "max_width=4294967295&order_by=width&order_dir=desc"
+        )
+        .await,
+        [landscape.as_str(), square.as_str(), portrait.as_str()]
+    );
+    assert_eq!(
+        query_hashes(&app, &token, "orientation=landscape&min_height=200").await,
+        [landscape]
+    );
+    assert!(query_hashes(&app, &token, "min_width=1000")
+        .await
+        .is_empty());
+
+    // 超出 i32 范围的尺寸参数不会回绕成负数
+    assert!(query_hashes(&app, &token, "min_width=4294967295")
+        .await
+        .is_empty());
+    assert!(query_hashes(&app, &token, "min_height=2147483648")
+        .await
+        .is_empty());
+
+    // 无效的方向参数
+    let uri = "/api/images/query?orientation=diagonal";
+    let (status, _) = request(&app, Method::GET, uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,371 @@
+//! 图片元数据测试
+//! 覆盖上传元数据策略（全部移除 / 仅移除GPS）、暂存文件改写、strip 转换参数以及 ICC 配置文件保留
+
+use bytes::Bytes;
+use exif::experimental::Writer as ExifWriter;
+use exif::{Field, In, Tag, Value};
+use image::{DynamicImage, GenericImageView, ImageFormat, Rgb, RgbImage};
+use img_parts::jpeg::{markers, Jpeg, JpegSegment};
+use img_parts::png::{Png, PngChunk};
+use img_parts::{ImageEXIF, ImageICC};
+use sha2::{Digest, Sha256};
+
+use rifs::config::MetadataPolicy;
+use rifs::models::ImageTransformParams;
+use rifs::services::image_metadata::ImageMetadata;
+use rifs::services::static_image_transform::StaticImageTransform;
+use rifs::services::ImageTransformService;
+use rifs::storage::UploadStager;
+
+const XMP_PREFIX: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// 构造包含相机型号和 GPS 纬度的 EXIF（TIFF 格式）
+fn exif_with_gps() -> Vec<u8> {
+    let make = Field {
+        tag: Tag::Make,
+        ifd_num: In::PRIMARY,
+        value: Value::Ascii(vec![b"RifsCam".to_vec()]),
+    };
+    let latitude_ref = Field {
+        tag: Tag::GPSLatitudeRef,
+        ifd_num: In::PRIMARY,
+        value: Value::Ascii(vec![b"N".to_vec()]),
+    };
+
+    let mut writer = ExifWriter::new();
+    writer.push_field(&make);
+    writer.push_field(&latitude_ref);
+    let mut output = std::io::Cursor::new(Vec::new());
+    writer.write(&mut output, false).unwrap();
+    output.into_inner()
+}
+
+/// 伪造一个 RGB 颜色空间的 ICC 配置文件（只需要文件头中的颜色空间字段）
+fn fake_icc_profile() -> Vec<u8> {
+    let mut profile = vec![0u8; 132];
+    profile[0..4].copy_from_slice(&132u32.to_be_bytes());
+    profile[12..16].copy_from_slice(b"mntr");
+    profile[16..20].copy_from_slice(b"RGB ");
+    profile[36..40].copy_from_slice(b"acsp");
+    profile
+}
+
+fn encode(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+    let mut data = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, Rgb([90, 120, 150])))
+        .write_to(&mut std::io::Cursor::new(&mut data), format)
+        .unwrap();
+    data
+}
+
+/// 构造带 EXIF、XMP、注释、ICC 以及 EOI 之后附加数据的 JPEG
+fn jpeg_with_metadata() -> Vec<u8> {
+    let mut jpeg = Jpeg::from_bytes(Bytes::from(encode(32, 16, ImageFormat::Jpeg))).unwrap();
+    jpeg.set_exif(Some(Bytes::from(exif_with_gps())));
+    jpeg.set_icc_profile(Some(Bytes::from(fake_icc_profile())));
+
+    let mut xmp = XMP_PREFIX.to_vec();
+    xmp.extend_from_slice(
+        b"<x:xmpmeta><rdf:Description exif:GPSLatitude=\"48,51.0N\"/></x:xmpmeta>",
+    );
+    jpeg.segments_mut().insert(
+        1,
+        JpegSegment::new_with_contents(markers::APP1, Bytes::from(xmp)),
+    );
+    jpeg.segments_mut().insert(
+        1,
+        JpegSegment::new_with_contents(markers::COM, Bytes::from_static(b"taken at home")),
+    );
+
+    let mut data = jpeg.encoder().bytes().to_vec();
+    data.extend_from_slice(b"TRAILER-WITH-PRIVATE-DATA");
+    data
+}
+
+/// 构造以传感器方向存储、EXIF Orientation 为 6（需顺时针旋转90度）的 32x16 JPEG
+fn rotated_jpeg() -> Vec<u8> {
+    let orientation = Field {
+        tag: Tag::Orientation,
+        ifd_num: In::PRIMARY,
+        value: Value::Short(vec![6]),
+    };
+    let make = Field {
+        tag: Tag::Make,
+        ifd_num: In::PRIMARY,
+        value: Value::Ascii(vec![b"RifsCam".to_vec()]),
+    };
+
+    let mut writer = ExifWriter::new();
+    writer.push_field(&orientation);
+    writer.push_field(&make);
+    let mut exif = std::io::Cursor::new(Vec::new());
+    writer.write(&mut exif, false).unwrap();
+
+    let mut jpeg = Jpeg::from_bytes(Bytes::from(encode(32, 16, ImageFormat::Jpeg))).unwrap();
+    jpeg.set_exif(Some(Bytes::from(exif.into_inner())));
+    jpeg.encoder().bytes().to_vec()
+}
+
+fn has_segment(jpeg: &Jpeg, marker: u8, prefix: &[u8]) -> bool {
+    jpeg.segments()
+        .iter()
+        .any(|segment| segment.marker() == marker && segment.contents().starts_with(prefix))
+}
+
+fn read_exif(tiff: &[u8]) -> exif::Exif {
+    exif::Reader::new().read_raw(tiff.to_vec()).unwrap()
+}
+
+#[test]
+fn test_strip_all_jpeg_metadata() {
+    let data = jpeg_with_metadata();
+    let stripped = ImageMetadata::strip(&data, "image/jpeg", MetadataPolicy::Strip)
+        .unwrap()
+        .unwrap();
+
+    let jpeg = Jpeg::from_bytes(Bytes::from(stripped.clone())).unwrap();
+    assert!(jpeg.exif().is_none());
+    assert!(!has_segment(&jpeg, markers::APP1, b""));
+    assert!(!has_segment(&jpeg, markers::COM, b""));
+    // ICC 配置文件保留
+    assert_eq!(jpeg.icc_profile().unwrap().as_ref(), fake_icc_profile());
+    // EOI 之后的附加数据被移除
+    assert!(stripped.ends_with(&[0xFF, 0xD9]));
+
+    // 像素数据不受影响
+    let original = image::load_from_memory(&data).unwrap();
+    let decoded = image::load_from_memory(&stripped).unwrap();
+    assert_eq!(decoded.dimensions(), (32, 16));
+    assert_eq!(decoded.to_rgb8(), original.to_rgb8());
+
+    // 再次处理时无需修改
+    assert!(
+        ImageMetadata::strip(&stripped, "image/jpeg", MetadataPolicy::Strip)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_strip_keeps_jpeg_orientation() {
+    let data = rotated_jpeg();
+    assert_eq!(
+        StaticImageTransform::load_image_with_color_info(&data)
+            .unwrap()
+            .dimensions(),
+        (16, 32)
+    );
+
+    let stripped = ImageMetadata::strip(&data, "image/jpeg", MetadataPolicy::Strip)
+        .unwrap()
+        .unwrap();
+    let jpeg = Jpeg::from_bytes(Bytes::from(stripped.clone())).unwrap();
+    let exif = read_exif(&jpeg.exif().unwrap());
+    assert!(exif.get_field(Tag::Make, In::PRIMARY).is_none());
+    assert_eq!(
+        exif.get_field(Tag::Orientation, In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0)),
+        Some(6)
+    );
+
+    // 移除元数据后仍按原方向显示
+    assert_eq!(
+        StaticImageTransform::load_image_with_color_info(&stripped)
+            .unwrap()
+            .dimensions(),
+        (16, 32)
+    );
+
+    // strip 转换参数同样保留方向信息
+    let params = ImageTransformParams::parse("strip").unwrap();
+    let (output, _) =
+        ImageTransformService::transform_image_blocking(&data, "image/jpeg", &params).unwrap();
+    assert_eq!(output, stripped);
+    assert_eq!(
+        StaticImageTransform::load_image_with_color_info(&output)
+            .unwrap()
+            .dimensions(),
+        (16, 32)
+    );
+}
+
+#[test]
+fn test_strip_gps_keeps_other_jpeg_metadata() {
+    let data = jpeg_with_metadata();
+    let stripped = ImageMetadata::strip(&data, "image/jpeg", MetadataPolicy::StripGps)
+        .unwrap()
+        .unwrap();
+
+    let jpeg = Jpeg::from_bytes(Bytes::from(stripped)).unwrap();
+    let exif = read_exif(&jpeg.exif().unwrap());
+    assert!(exif.get_field(Tag::Make, In::PRIMARY).is_some());
+    assert!(exif
+        .fields()
+        .all(|field| field.tag.context() != exif::Context::Gps));
+
+    // 含 GPS 的 XMP 整体移除，注释和 ICC 保留
+    assert!(!has_segment(&jpeg, markers::APP1, XMP_PREFIX));
+    assert!(has_segment(&jpeg, markers::COM, b"taken at home"));
+    assert!(jpeg.icc_profile().is_some());
+}
+
+#[test]
+fn test_strip_png_metadata() {
+    let mut png = Png::from_bytes(Bytes::from(encode(8, 8, ImageFormat::Png))).unwrap();
+    png.set_icc_profile(Some(Bytes::from(fake_icc_profile())));
+    png.set_exif(Some(Bytes::from(exif_with_gps())));
+    let position = png.chunks().len() - 1;
+    png.chunks_mut().insert(
+        position,
+        PngChunk::new(*b"tEXt", Bytes::from_static(b"Comment\0taken at home")),
+    );
+    let data = png.encoder().bytes().to_vec();
+
+    let stripped = ImageMetadata::strip(&data, "image/png", MetadataPolicy::StripGps)
+        .unwrap()
+        .unwrap();
+    let png = Png::from_bytes(Bytes::from(stripped)).unwrap();
+    let exif = read_exif(&png.exif().unwrap());
+    assert!(exif.get_field(Tag::GPSLatitudeRef, In::PRIMARY).is_none());
+    assert!(exif.get_field(Tag::Make, In::PRIMARY).is_some());
+    assert!(png.chunk_by_type(*b"tEXt").is_some());
+
+    let stripped = ImageMetadata::strip(&data, "image/png", MetadataPolicy::Strip)
+        .unwrap()
+        .unwrap();
+    let png = Png::from_bytes(Bytes::from(stripped.clone())).unwrap();
+    assert!(png.exif().is_none());
+    assert!(png.chunk_by_type(*b"tEXt").is_none());
+    assert!(png.chunk_by_type(*b"iCCP").is_some());
+    assert_eq!(
+        image::load_from_memory(&stripped).unwrap().dimensions(),
+        (8, 8)
+    );
+}
+
+#[test]
+fn test_keep_and_unsupported_formats_are_untouched() {
+    let data = jpeg_with_metadata();
+    assert!(
+        ImageMetadata::strip(&data, "image/jpeg", MetadataPolicy::Keep)
+            .unwrap()
+            .is_none()
+    );
+
+    let gif = encode(4, 4, ImageFormat::Gif);
+    assert!(
+        ImageMetadata::strip(&gif, "image/gif", MetadataPolicy::Strip)
+            .unwrap()
+            .is_none()
+    );
+
+    // 声明为 JPEG 但无法解析时拒绝
+    assert!(
+        ImageMetadata::strip(b"\xFF\xD8\xFF\xE1\x00", "image/jpeg", MetadataPolicy::Strip).is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_strip_transform_token() {
+    let params = ImageTransformParams::parse("strip_w10").unwrap();
+    assert!(params.strip_metadata);
+    assert_eq!(params.to_normalized_string(), "w10_strip");
+
+    // 仅 strip 时不重新编码，只移除元数据
+    let params = ImageTransformParams::parse("strip").unwrap();
+    assert!(params.needs_transform());
+    assert!(!params.needs_reencode());
+
+    let data = jpeg_with_metadata();
+    let (output, mime) =
+        ImageTransformService::transform_image_blocking(&data, "image/jpeg", &params).unwrap();
+    assert_eq!(mime, "image/jpeg");
+    assert_eq!(
+        output,
+        ImageMetadata::strip(&data, "image/jpeg", MetadataPolicy::Strip)
+            .unwrap()
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_reencoded_output_keeps_icc_profile() {
+    let data = jpeg_with_metadata();
+
+    for (token, mime) in [
+        ("png", "image/png"),
+        ("jpeg", "image/jpeg"),
+        ("webp", "image/webp"),
+    ] {
+        let params = ImageTransformParams::parse(&format!("w16_{}", token)).unwrap();
+        let (output, output_mime) =
+            ImageTransformService::transform_image_blocking(&data, "image/jpeg", &params).unwrap();
+        assert_eq!(output_mime, mime);
+        assert_eq!(
+            ImageMetadata::rgb_icc_profile(&output),
+            Some(fake_icc_profile()),
+            "{} 输出缺少 ICC 配置文件",
+            token
+        );
+        // 重新编码的输出不携带 EXIF
+        assert!(exif::Reader::new()
+            .read_from_container(&mut std::io::Cursor::new(&output))
+            .is_err());
+        assert_eq!(
+            image::load_from_memory(&output).unwrap().dimensions(),
+            (16, 8)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_strip_staged_upload() {
+    // 暂存目录位于配置的上传目录中
+    let _ = rifs::config::AppConfig::init(Some("config_test"));
+
+    let data = jpeg_with_metadata();
+    let mut stager = UploadStager::new().await.unwrap();
+    stager.write_chunk(&data).await.unwrap();
+    let staged = stager.finish().await.unwrap();
+    let original_path = staged.path().to_path_buf();
+
+    // 改写结果写入新的暂存文件，大小和哈希按改写后的内容计算
+    let staged = staged
+        .rewrite(|data, output| {
+            ImageMetadata::strip_to(
+                Bytes::from(data),
+                "image/jpeg",
+                MetadataPolicy::Strip,
+                output,
+            )
+        })
+        .await
+        .unwrap();
+    let expected = ImageMetadata::strip(&data, "image/jpeg", MetadataPolicy::Strip)
+        .unwrap()
+        .unwrap();
+    assert_ne!(staged.path(), original_path);
+    assert!(!original_path.exists());
+    assert_eq!(std::fs::read(staged.path()).unwrap(), expected);
+    assert_eq!(staged.size(), expected.len() as u64);
+    assert_eq!(
+        staged.hash_with(&[]),
+        format!("{:x}", Sha256::digest(&expected))
+    );
+
+    // 没有需要移除的内容时保留原文件
+    let path = staged.path().to_path_buf();
+    let staged = staged
+        .rewrite(|data, output| {
+            ImageMetadata::strip_to(
+                Bytes::from(data),
+                "image/jpeg",
+                MetadataPolicy::Strip,
+                output,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(staged.path(), path);
+    assert_eq!(staged.size(), expected.len() as u64);
+}
@@ -0,0 +1,122 @@
+//! Prometheus 指标接口测试
+//! 覆盖请求按路由模板统计、上传字节数、转换耗时、转换缓存命中以及状态类指标
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use tower::ServiceExt;
+
+mod common;
+use common::{create_test_app, upload};
+
+fn get(uri: &str) -> Request<Body> {
+    Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// 查找指定指标行的值
+fn metric_value(text: &str, series: &str) -> Option<f64> {
+    text.lines()
+        .find_map(|line| line.strip_prefix(series)?.trim().parse().ok())
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    let app = create_test_app("config_test").await.0;
+
+    let mut png_bytes = Vec::new();
+    image::RgbImage::from_fn(32, 16, |x, _| image::Rgb([rand::random(), x as u8, 0]))
+        .write_to(
+            &mut std::io::Cursor::new(&mut png_bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+    let hash = upload(&app, None, &png_bytes).await;
+
+    // 第一次转换未命中缓存，第二次命中
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(get(&format!("/images/{}@w8_webp", hash)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let response = app.clone().oneshot(get("/not-found")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app.oneshot(get("/metrics")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+
+    // 按路由模板统计，不包含图片哈希
+    assert_eq!(
+        metric_value(
+            &text,
+            r#"rifs_http_requests_total{method="GET",route="/images/{filename}",status="200"}"#
+        ),
+        Some(2.0)
+    );
+    assert_eq!(
+        metric_value(
+            &text,
+            r#"rifs_http_request_duration_seconds_count{method="POST",route="/upload",status="200"}"#
+        ),
+        Some(1.0)
+    );
+    assert_eq!(
+        metric_value(
+            &text,
+            r#"rifs_http_requests_total{method="GET",route="unmatched",status="404"}"#
+        ),
+        Some(1.0)
+    );
+    assert!(!text.contains(&hash));
+
+    assert_eq!(metric_value(&text, "rifs_uploads_total"), Some(1.0));
+    assert_eq!(
+        metric_value(&text, "rifs_upload_bytes_total"),
+        Some(png_bytes.len() as f64)
+    );
+    assert_eq!(
+        metric_value(
+            &text,
+            r#"rifs_transform_duration_seconds_count{source="png",target="webp"}"#
+        ),
+        Some(1.0)
+    );
+    assert_eq!(
+        metric_value(
+            &text,
+            r#"rifs_transform_cache_requests_total{result="miss"}"#
+        ),
+        Some(1.0)
+    );
+    assert_eq!(
+        metric_value(
+            &text,
+            r#"rifs_transform_cache_requests_total{result="hit"}"#
+        ),
+        Some(1.0)
+    );
+
+    // 状态类指标在抓取时刷新
+    assert!(metric_value(&text, "rifs_transform_cache_entries").unwrap() >= 1.0);
+    assert!(metric_value(&text, "rifs_transform_cache_size_bytes").unwrap() > 0.0);
+    assert_eq!(
+        metric_value(&text, r#"rifs_db_pool_connections{state="max"}"#),
+        Some(20.0)
+    );
+}
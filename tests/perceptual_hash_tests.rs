@@ -0,0 +1,156 @@
+//! 感知哈希与相似图片测试
+//! 覆盖 dHash 对重新编码和缩放的稳定性、相似图片查询以及上传时的相似图片策略
+
+use axum::http::{Method, StatusCode};
+use image::{DynamicImage, ImageFormat, RgbImage};
+use serde_json::Value;
+
+use rifs::services::perceptual_hash::PerceptualHash;
+use rifs::services::static_image_transform::StaticImageTransform;
+
+mod common;
+use common::{create_test_app, request, send, upload_request};
+
+/// 生成带随机色块的图片，不同种子的图片在结构上明显不同
+fn pattern_image(width: u32, height: u32) -> DynamicImage {
+    let blocks: Vec<u8> = (0..64).map(|_| rand::random()).collect();
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+        let value = blocks[((y * 8 / height) * 8 + x * 8 / width) as usize];
+        image::Rgb([value, value / 2, 255 - value])
+    }))
+}
+
+fn encode(img: &DynamicImage, format: ImageFormat) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut bytes), format)
+        .unwrap();
+    bytes
+}
+
+/// 解码图片文件并计算感知哈希，无法解码时返回 None
+fn dhash(data: &[u8]) -> Option<u64> {
+    StaticImageTransform::load_image_with_color_info(data)
+        .ok()
+        .map(|img| PerceptualHash::from_image(&img))
+}
+
+async fn upload(
+    app: &axum::Router,
+    query: &str,
+    data: &[u8],
+    filename: &str,
+) -> (StatusCode, Value) {
+    let uri = format!("/upload{}", query);
+    send(app, upload_request(&uri, None, "file", &[(filename, data)])).await
+}
+
+#[test]
+fn test_dhash_is_stable_across_encodings() {
+    let original = pattern_image(256, 192);
+    let hash = dhash(&encode(&original, ImageFormat::Png)).unwrap();
+
+    // 缩小并转为 JPEG 后的副本仍然相似
+    let resized = original.resize_exact(128, 96, image::imageops::FilterType::Triangle);
+    let copy = dhash(&encode(&resized, ImageFormat::Jpeg)).unwrap();
+    assert!(PerceptualHash::distance(hash, copy) <= 5);
+
+    // 结构不同的图片距离较大
+    let other = dhash(&encode(&pattern_image(256, 192), ImageFormat::Png)).unwrap();
+    assert!(PerceptualHash::distance(hash, other) > 10);
+
+    assert_eq!(PerceptualHash::distance(hash, hash), 0);
+    assert_eq!(
+        PerceptualHash::parse(&PerceptualHash::to_hex(hash)),
+        Some(hash)
+    );
+    assert_eq!(PerceptualHash::to_hex(1), "0000000000000001");
+    assert!(dhash(b"not an image").is_none());
+}
+
+#[tokio::test]
+async fn test_find_similar_images() {
+    let app = create_test_app("config_test").await.0;
+    let original = pattern_image(256, 192);
+    let resized = original.resize_exact(160, 120, image::imageops::FilterType::Triangle);
+
+    let (status, json) = upload(&app, "", &encode(&original, ImageFormat::Png), "a.png").await;
+    assert_eq!(status, StatusCode::OK);
+    let hash = json["data"]["hash"].as_str().unwrap().to_string();
+    assert_eq!(json["data"]["phash"].as_str().unwrap().len(), 16);
+
+    // 默认策略照常保存相似图片
+    let (status, json) = upload(&app, "", &encode(&resized, ImageFormat::Jpeg), "b.jpg").await;
+    assert_eq!(status, StatusCode::OK);
+    let copy_hash = json["data"]["hash"].as_str().unwrap().to_string();
+    assert_ne!(copy_hash, hash);
+
+    let (status, _) = upload(
+        &app,
+        "",
+        &encode(&pattern_image(256, 192), ImageFormat::Png),
+        "c.png",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, json) = request(
+        &app,
+        Method::GET,
+        &format!("/api/images/{}/similar", hash),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let results = json["data"].as_array().unwrap();
+    assert!(results
+        .iter()
+        .any(|item| item["hash"] == copy_hash.as_str()));
+    assert!(results.iter().all(|item| item["hash"] != hash.as_str()));
+    assert!(results
+        .iter()
+        .all(|item| item["distance"].as_u64().unwrap() <= 5));
+
+    let (status, _) = request(
+        &app,
+        Method::GET,
+        "/api/images/not-exist/similar",
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_near_duplicate_upload_policy() {
+    let app = create_test_app("config_test").await.0;
+    let original = pattern_image(256, 192);
+    let resized = original.resize_exact(200, 150, image::imageops::FilterType::Triangle);
+    let copy = encode(&resized, ImageFormat::Jpeg);
+
+    let (status, json) = upload(&app, "", &encode(&original, ImageFormat::Png), "a.png").await;
+    assert_eq!(status, StatusCode::OK);
+    let hash = json["data"]["hash"].as_str().unwrap().to_string();
+
+    // reject：拒绝相似图片
+    let (status, json) = upload(&app, "?near_duplicate=reject", &copy, "copy.jpg").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["message"].as_str().unwrap().contains(&hash));
+
+    // link：直接返回已有图片
+    let (status, json) = upload(&app, "?near_duplicate=link", &copy, "copy.jpg").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["hash"], hash.as_str());
+
+    // 不相似的图片不受影响
+    let (status, json) = upload(
+        &app,
+        "?near_duplicate=reject",
+        &encode(&pattern_image(256, 192), ImageFormat::Png),
+        "other.png",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(json["data"]["hash"], hash.as_str());
+}
@@ -0,0 +1,111 @@
+//! 转换预设测试
+//! 覆盖预设解析校验、名称查找、仅预设模式以及通过 `@preset:名称` 访问图片
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use std::collections::BTreeMap;
+use tower::ServiceExt;
+
+use rifs::services::transform_presets::TransformPresets;
+use rifs::utils::AppError;
+
+mod common;
+use common::{create_test_app, upload};
+
+fn load_presets(entries: &[(&str, &str)]) -> Result<TransformPresets, AppError> {
+    let map: BTreeMap<String, String> = entries
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    TransformPresets::from_config(&map)
+}
+
+#[test]
+fn test_presets_are_validated() {
+    let presets =
+        load_presets(&[("thumb", "w300_h300_cover_webp_q80"), ("Large", "w1200")]).unwrap();
+    assert_eq!(
+        presets.get("thumb").unwrap().to_normalized_string(),
+        "w300_h300_cover_webp_q80"
+    );
+    assert_eq!(presets.get("large").unwrap().width, Some(1200));
+    assert!(presets.get("missing").is_none());
+
+    // 无效的参数、名称或重复名称拒绝加载
+    assert!(load_presets(&[("huge", "w10000")]).is_err());
+    assert!(load_presets(&[("bad", "r9x")]).is_err());
+    assert!(load_presets(&[("a b", "w100")]).is_err());
+    assert!(load_presets(&[("Thumb", "w100"), ("thumb", "w200")]).is_err());
+}
+
+#[test]
+fn test_resolve_presets_only() {
+    let presets = load_presets(&[("thumb", "w300_webp")]).unwrap();
+
+    let params = presets.resolve("preset:thumb", false).unwrap();
+    assert_eq!(params.to_normalized_string(), "w300_webp");
+    assert!(matches!(
+        presets.resolve("preset:missing", true),
+        Err(AppError::BadRequest(_))
+    ));
+
+    // 仅预设模式下拒绝自定义参数
+    assert!(matches!(
+        presets.resolve("w300_webp", false),
+        Err(AppError::Forbidden(_))
+    ));
+    assert_eq!(presets.resolve("w300_webp", true).unwrap().width, Some(300));
+    assert!(presets.resolve("w0", true).is_err());
+}
+
+#[tokio::test]
+async fn test_get_image_with_preset() {
+    let app = create_test_app("config_test").await.0;
+
+    let mut png_bytes = Vec::new();
+    image::RgbImage::from_fn(32, 16, |x, _| image::Rgb([rand::random(), x as u8, 0]))
+        .write_to(
+            &mut std::io::Cursor::new(&mut png_bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+    let hash = upload(&app, None, &png_bytes).await;
+
+    let get = |uri: String| {
+        Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(get(format!("/images/{}@preset:thumb", hash)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[header::ETAG].clone();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let img = image::load_from_memory_with_format(&body, image::ImageFormat::Png).unwrap();
+    assert_eq!((img.width(), img.height()), (8, 4));
+
+    // 预设与等价的自定义参数共享 ETag 和缓存
+    let response = app
+        .clone()
+        .oneshot(get(format!("/images/{}@w8_png", hash)))
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[header::ETAG], etag);
+
+    let response = app
+        .oneshot(get(format!("/images/{}@preset:missing", hash)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,243 @@
+//! 上传配额测试
+//! 使用启用认证的 config_auth_test 配置，覆盖删除时退还配额、图片数量配额以及按图片记录重新计算配额
+
+use axum::http::{Method, StatusCode};
+use serde_json::Value;
+
+use rifs::app_state::AppState;
+use rifs::models::{ApiTokenInfo, CreateTokenPayload, TokenRole};
+use rifs::repositories::{ImageRepository, ImageRepositoryTrait, TokenRepository};
+use rifs::services::TokenService;
+
+mod common;
+use common::{
+    create_test_app, create_token_with, random_png, request, send, token_payload, upload,
+    upload_file, upload_request,
+};
+
+/// 创建令牌，返回令牌ID和明文
+async fn create_token(
+    app_state: &AppState,
+    role: TokenRole,
+    max_image_count: Option<u64>,
+) -> (i32, String) {
+    let payload = CreateTokenPayload {
+        max_upload_size: Some(10 * 1024 * 1024),
+        max_image_count,
+        ..token_payload(role)
+    };
+    create_token_with(app_state, payload).await
+}
+
+async fn get_token(app_state: &AppState, token_id: i32) -> ApiTokenInfo {
+    TokenService::new(app_state.db_pool().get_connection())
+        .get_token(token_id)
+        .await
+        .unwrap()
+}
+
+/// 一次上传多个文件
+async fn upload_batch(app: &axum::Router, token: &str, files: &[&[u8]]) -> (StatusCode, Value) {
+    let files: Vec<(&str, &[u8])> = files.iter().map(|data| ("quota.png", *data)).collect();
+    send(
+        app,
+        upload_request("/upload/batch", Some(token), "files", &files),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_delete_releases_quota() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (owner_id, owner) = create_token(&app_state, TokenRole::User, None).await;
+
+    let first = upload(&app, Some(&owner), &random_png()).await;
+    let second = upload(&app, Some(&owner), &random_png()).await;
+    let token = get_token(&app_state, owner_id).await;
+    assert_eq!(token.used_image_count, 2);
+    assert!(token.used_upload_size > 0);
+
+    // 移入回收站时仍然占用配额，彻底删除后退还
+    let uri = format!("/api/images/{}", first);
+    let (status, _) = request(&app, Method::DELETE, &uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(get_token(&app_state, owner_id).await.used_image_count, 2);
+    let purge_uri = format!("/api/trash/{}", first);
+    let (status, _) = request(&app, Method::DELETE, &purge_uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(get_token(&app_state, owner_id).await.used_image_count, 1);
+
+    // 直接删除同样退还配额，全部删除后归零
+    let uri = format!("/api/images/{}?permanent=true", second);
+    let (status, _) = request(&app, Method::DELETE, &uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let token = get_token(&app_state, owner_id).await;
+    assert_eq!(token.used_upload_size, 0);
+    assert_eq!(token.used_image_count, 0);
+}
+
+#[tokio::test]
+async fn test_image_count_quota() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (owner_id, owner) = create_token(&app_state, TokenRole::User, Some(3)).await;
+    assert_eq!(
+        get_token(&app_state, owner_id).await.max_image_count,
+        Some(3)
+    );
+
+    let first = upload(&app, Some(&owner), &random_png()).await;
+
+    // 超过数量配额时整批拒绝，不占用配额
+    let (png_a, png_b, png_c) = (random_png(), random_png(), random_png());
+    let (status, json) = upload_batch(&app, &owner, &[&png_a, &png_b, &png_c]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", json);
+    assert_eq!(get_token(&app_state, owner_id).await.used_image_count, 1);
+
+    // 重复文件不占用配额，未使用的部分在批量上传结束后退还
+    let (status, json) = upload_batch(&app, &owner, &[&png_a, &png_a]).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(get_token(&app_state, owner_id).await.used_image_count, 2);
+
+    upload(&app, Some(&owner), &png_b).await;
+    let (status, json) = upload_file(&app, Some(&owner), "quota.png", &png_c).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["message"], "已超过图片数量配额");
+
+    // 删除后可以继续上传
+    let uri = format!("/api/images/{}?permanent=true", first);
+    let (status, _) = request(&app, Method::DELETE, &uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    upload(&app, Some(&owner), &png_c).await;
+    assert_eq!(get_token(&app_state, owner_id).await.used_image_count, 3);
+}
+
+#[tokio::test]
+async fn test_reconcile_usage() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (admin_id, admin) = create_token(&app_state, TokenRole::Admin, None).await;
+    let (owner_id, owner) = create_token(&app_state, TokenRole::User, None).await;
+
+    let hash = upload(&app, Some(&owner), &random_png()).await;
+    let trashed = upload(&app, Some(&owner), &random_png()).await;
+    request(
+        &app,
+        Method::DELETE,
+        &format!("/api/images/{}", trashed),
+        Some(&owner),
+        None,
+    )
+    .await;
+    let expected = get_token(&app_state, owner_id).await;
+    let image = ImageRepository::new(app_state.db_pool().get_connection())
+        .find_by_hash(&hash)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(expected.used_upload_size > image.size as i64);
+    assert_eq!(expected.used_image_count, 2);
+
+    // 人为制造偏差
+    let token_repo = TokenRepository::new(app_state.db_pool().get_connection());
+    token_repo.set_usage(owner_id, 12345, 7).await.unwrap();
+    token_repo.set_usage(admin_id, 1, 1).await.unwrap();
+
+    // 仅管理员可以调用
+    let (status, _) = request(
+        &app,
+        Method::POST,
+        "/api/tokens/reconcile",
+        Some(&owner),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // dry_run 只报告不修改
+    let (status, json) = request(
+        &app,
+        Method::POST,
+        "/api/tokens/reconcile?dry_run=true",
+        Some(&admin),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(json["data"]["dry_run"], true);
+    let drift = json["data"]["drifted"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|drift| drift["token_id"] == owner_id)
+        .unwrap()
+        .clone();
+    assert_eq!(drift["recorded_size"], 12345);
+    assert_eq!(drift["actual_size"], expected.used_upload_size);
+    assert_eq!(drift["recorded_count"], 7);
+    assert_eq!(drift["actual_count"], 2);
+    assert_eq!(get_token(&app_state, owner_id).await.used_image_count, 7);
+
+    // 回收站中的图片仍然计入配额
+    let (status, json) = request(
+        &app,
+        Method::POST,
+        "/api/tokens/reconcile",
+        Some(&admin),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(json["data"]["dry_run"], false);
+    let token = get_token(&app_state, owner_id).await;
+    assert_eq!(token.used_upload_size, expected.used_upload_size);
+    assert_eq!(token.used_image_count, 2);
+    let token = get_token(&app_state, admin_id).await;
+    assert_eq!(token.used_upload_size, 0);
+    assert_eq!(token.used_image_count, 0);
+
+    // 修正后再次检查没有偏差
+    let (_, json) = request(
+        &app,
+        Method::POST,
+        "/api/tokens/reconcile?dry_run=true",
+        Some(&admin),
+        None,
+    )
+    .await;
+    assert!(!json["data"]["drifted"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|drift| drift["token_id"] == owner_id || drift["token_id"] == admin_id));
+}
+
+#[tokio::test]
+async fn test_reconcile_keeps_existing_usage() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, admin) = create_token(&app_state, TokenRole::Admin, None).await;
+    let (owner_id, owner) = create_token(&app_state, TokenRole::User, None).await;
+
+    upload(&app, Some(&owner), &random_png()).await;
+    upload(&app, Some(&owner), &random_png()).await;
+    let before = get_token(&app_state, owner_id).await;
+    assert!(before.used_upload_size > 0);
+
+    // 配额与图片记录一致时不视为偏差，也不会被改写
+    let (status, json) = request(
+        &app,
+        Method::POST,
+        "/api/tokens/reconcile",
+        Some(&admin),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert!(!json["data"]["drifted"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|drift| drift["token_id"] == owner_id));
+
+    let after = get_token(&app_state, owner_id).await;
+    assert_eq!(after.used_upload_size, before.used_upload_size);
+    assert_eq!(after.used_image_count, 2);
+}
@@ -0,0 +1,213 @@
+//! 请求频率限制测试
+//! 使用 config_rate_limit_test 配置（启用认证、信任 X-Forwarded-For、极小的限额），
+//! 覆盖令牌桶计算、按客户端IP和按令牌分别计数、转换只在缓存未命中时计数以及 429 响应
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use rifs::config::{RateLimitConfig, RateLimitRule};
+use rifs::models::TokenRole;
+use rifs::services::rate_limiter::{RateLimitCategory, RateLimitKey, RateLimiter};
+use rifs::utils::{AppError, Duration};
+
+mod common;
+use common::{create_test_app, create_token, multipart_body, random_png, BOUNDARY};
+
+/// 发送请求，返回状态码、Retry-After 和响应体
+async fn send(
+    app: &axum::Router,
+    request: axum::http::request::Builder,
+    ip: &str,
+    token: Option<&str>,
+    body: Body,
+) -> (StatusCode, Option<u64>, Value) {
+    // 左侧伪造的地址会被忽略
+    let mut request = request.header("x-forwarded-for", format!("10.0.0.1, {}", ip));
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(header::RETRY_AFTER)
+        .map(|value| value.to_str().unwrap().parse().unwrap());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        retry_after,
+        serde_json::from_slice(&body).unwrap_or(Value::Null),
+    )
+}
+
+async fn upload(app: &axum::Router, ip: &str, token: &str) -> (StatusCode, Option<u64>, Value) {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/upload")
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={}", BOUNDARY),
+        );
+    let body = multipart_body("file", &[("photo.png", &random_png())]);
+    send(app, request, ip, Some(token), Body::from(body)).await
+}
+
+async fn get(app: &axum::Router, uri: &str, ip: &str, token: Option<&str>) -> StatusCode {
+    send(app, Request::builder().uri(uri), ip, token, Body::empty())
+        .await
+        .0
+}
+
+fn rule(requests: u32, seconds: u64, burst: Option<u32>) -> RateLimitRule {
+    RateLimitRule {
+        requests,
+        period: Duration::seconds(seconds),
+        burst,
+    }
+}
+
+#[test]
+fn test_token_bucket() {
+    let config = RateLimitConfig {
+        enabled: true,
+        trust_forwarded_for: false,
+        upload: rule(1, 60, Some(3)),
+        transform: rule(10, 1, None),
+        api: rule(2, 3600, None),
+    };
+    let limiter = RateLimiter::from_config(&config).unwrap();
+    let client = RateLimitKey::Ip("192.0.2.1".parse().unwrap());
+
+    // 桶容量由 burst 决定，耗尽后按补充速度计算等待时间
+    for _ in 0..3 {
+        limiter.check(RateLimitCategory::Upload, &client).unwrap();
+    }
+    match limiter.check(RateLimitCategory::Upload, &client) {
+        Err(AppError::TooManyRequests { retry_after }) => assert_eq!(retry_after, 60),
+        other => panic!("应当被限制: {:?}", other),
+    }
+
+    // 不同类别和不同计数对象互不影响
+    limiter.check(RateLimitCategory::Api, &client).unwrap();
+    limiter
+        .check(RateLimitCategory::Upload, &RateLimitKey::Token(1))
+        .unwrap();
+
+    // 补充速度快的规则在等待后恢复
+    for _ in 0..10 {
+        limiter
+            .check(RateLimitCategory::Transform, &client)
+            .unwrap();
+    }
+    assert!(limiter
+        .check(RateLimitCategory::Transform, &client)
+        .is_err());
+    std::thread::sleep(std::time::Duration::from_millis(250));
+    limiter
+        .check(RateLimitCategory::Transform, &client)
+        .unwrap();
+
+    // 未启用时不限制
+    let disabled = RateLimiter::from_config(&RateLimitConfig::default()).unwrap();
+    for _ in 0..1000 {
+        disabled
+            .check(RateLimitCategory::Upload, &RateLimitKey::Unknown)
+            .unwrap();
+    }
+
+    // 无效的规则
+    let invalid = RateLimitConfig {
+        api: rule(0, 60, None),
+        ..config
+    };
+    assert!(RateLimiter::from_config(&invalid).is_err());
+}
+
+#[tokio::test]
+async fn test_upload_rate_limit_per_token() {
+    let (app, app_state) = create_test_app("config_rate_limit_test").await;
+    let (_, first) = create_token(&app_state, TokenRole::User).await;
+    let (_, second) = create_token(&app_state, TokenRole::User).await;
+
+    // 已认证的请求按令牌计数，同一IP下的其他令牌不受影响
+    for _ in 0..2 {
+        let (status, _, json) = upload(&app, "198.51.100.1", &first).await;
+        assert_eq!(status, StatusCode::OK, "{}", json);
+    }
+    let (status, retry_after, json) = upload(&app, "198.51.100.2", &first).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(json["code"], 429);
+    assert!(retry_after.unwrap() > 0);
+
+    let (status, _, _) = upload(&app, "198.51.100.1", &second).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_api_rate_limit_per_ip() {
+    let (app, _) = create_test_app("config_rate_limit_test").await;
+
+    // 未认证的请求按客户端IP计数，被拒绝的请求同样计入
+    for _ in 0..3 {
+        assert_eq!(
+            get(&app, "/api/stats", "203.0.113.1", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+    assert_eq!(
+        get(&app, "/api/stats", "203.0.113.1", None).await,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    assert_eq!(
+        get(&app, "/api/stats", "203.0.113.2", None).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    // 不在限制范围内的路径不受影响
+    assert_eq!(
+        get(&app, "/health", "203.0.113.1", None).await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn test_transform_rate_limit_counts_cache_misses() {
+    let (app, app_state) = create_test_app("config_rate_limit_test").await;
+    let (_, token) = create_token(&app_state, TokenRole::User).await;
+    let (status, _, json) = upload(&app, "192.0.2.10", &token).await;
+    assert_eq!(status, StatusCode::OK);
+    let hash = json["data"]["hash"].as_str().unwrap().to_string();
+
+    let ip = "192.0.2.20";
+    let uri = |params: &str| format!("/images/{}@{}", hash, params);
+    assert_eq!(get(&app, &uri("w8"), ip, None).await, StatusCode::OK);
+    // 命中缓存和访问原图不计数
+    for _ in 0..3 {
+        assert_eq!(get(&app, &uri("w8"), ip, None).await, StatusCode::OK);
+        assert_eq!(
+            get(&app, &format!("/images/{}", hash), ip, None).await,
+            StatusCode::OK
+        );
+    }
+    assert_eq!(get(&app, &uri("w9"), ip, None).await, StatusCode::OK);
+    assert_eq!(
+        get(&app, &uri("w10"), ip, None).await,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+
+    // 其他客户端不受影响
+    assert_eq!(
+        get(&app, &uri("w10"), "192.0.2.21", None).await,
+        StatusCode::OK
+    );
+}
@@ -0,0 +1,173 @@
+//! 远程URL上传测试
+//! 使用本机模拟服务器覆盖正常抓取、重定向限制、超时、大小限制以及内网地址拦截
+
+use axum::{
+    extract::Path,
+    http::{header, Method, StatusCode},
+    response::{IntoResponse, Redirect},
+    routing::get,
+    Router,
+};
+use serde_json::{json, Value};
+use std::net::IpAddr;
+
+use rifs::services::remote_fetch::{is_public_ip, IpNetwork};
+
+mod common;
+use common::{create_test_app, random_png, request};
+
+/// 启动模拟的远程图片服务器，返回基础地址
+///
+/// 每次请求返回内容不同的图片，避免重复文件直接返回已有记录
+async fn start_remote_server() -> String {
+    let router = Router::new()
+        .route(
+            "/photos/cat.png",
+            get(|| async { ([(header::CONTENT_TYPE, "image/png")], random_png()) }),
+        )
+        .route("/text", get(|| async { "plain text" }))
+        .route("/missing", get(|| async { StatusCode::NOT_FOUND }))
+        .route(
+            "/large",
+            get(|| async { vec![0u8; 11 * 1024 * 1024].into_response() }),
+        )
+        .route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                "too late"
+            }),
+        )
+        .route(
+            "/redirect/{count}",
+            get(|Path(count): Path<u32>| async move {
+                if count == 0 {
+                    Redirect::temporary("/photos/cat.png")
+                } else {
+                    Redirect::temporary(&format!("/redirect/{}", count - 1))
+                }
+            }),
+        )
+        .route(
+            "/internal",
+            get(|| async { Redirect::temporary("http://10.0.0.1/secret.png") }),
+        );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+async fn upload_url(app: &axum::Router, body: Value) -> (StatusCode, Value) {
+    request(app, Method::POST, "/upload/url", None, Some(body)).await
+}
+
+#[test]
+fn test_address_classification() {
+    for ip in [
+        "127.0.0.1",
+        "10.1.2.3",
+        "172.16.0.1",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "255.255.255.255",
+        "::1",
+        "::",
+        "fd00::1",
+        "fe80::1",
+        "::ffff:127.0.0.1",
+        "64:ff9b::a00:1",
+    ] {
+        assert!(!is_public_ip(ip.parse().unwrap()), "{} 应视为内网地址", ip);
+    }
+    for ip in [
+        "8.8.8.8",
+        "1.1.1.1",
+        "2606:4700:4700::1111",
+        "64:ff9b::808:808",
+    ] {
+        assert!(is_public_ip(ip.parse().unwrap()), "{} 应视为公网地址", ip);
+    }
+
+    let network = IpNetwork::parse("10.1.0.0/16").unwrap();
+    assert!(network.contains("10.1.255.1".parse::<IpAddr>().unwrap()));
+    assert!(!network.contains("10.2.0.1".parse::<IpAddr>().unwrap()));
+    assert!(network.contains("::ffff:10.1.0.1".parse::<IpAddr>().unwrap()));
+    assert!(IpNetwork::parse("::1")
+        .unwrap()
+        .contains("::1".parse::<IpAddr>().unwrap()));
+    assert!(IpNetwork::parse("0.0.0.0/0")
+        .unwrap()
+        .contains("8.8.8.8".parse::<IpAddr>().unwrap()));
+    assert!(IpNetwork::parse("10.0.0.0/33").is_err());
+    assert!(IpNetwork::parse("example.com").is_err());
+}
+
+#[tokio::test]
+async fn test_upload_from_url() {
+    let app = create_test_app("config_test").await.0;
+    let base = start_remote_server().await;
+
+    let (status, json) =
+        upload_url(&app, json!({ "url": format!("{}/photos/cat.png", base) })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["original_filename"], "cat.png");
+    assert_eq!(json["data"]["mime_type"], "image/png");
+
+    // 跟随不超过上限的重定向，文件名取自最终地址
+    let (status, json) = upload_url(
+        &app,
+        json!({ "url": format!("{}/redirect/1", base), "filename": "named.png" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["original_filename"], "named.png");
+
+    // 上传的图片可以正常访问
+    let hash = json["data"]["hash"].as_str().unwrap();
+    let uri = format!("/images/{}", hash);
+    let (status, _) = request(&app, Method::GET, &uri, None, None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_upload_from_url_rejections() {
+    let app = create_test_app("config_test").await.0;
+    let base = start_remote_server().await;
+
+    for path in [
+        // 非图片内容
+        "/text",
+        // 远程返回错误状态码
+        "/missing",
+        // 超过 max_file_size
+        "/large",
+        // 超过 fetch.timeout
+        "/slow",
+        // 超过 fetch.max_redirects
+        "/redirect/3",
+        // 重定向到内网地址
+        "/internal",
+    ] {
+        let (status, json) = upload_url(&app, json!({ "url": format!("{}{}", base, path) })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", path, json);
+        assert_eq!(json["success"], false);
+    }
+
+    // 不在允许列表中的内网地址和非 HTTP 协议直接拒绝
+    for url in [
+        "http://10.0.0.1/a.png",
+        "http://[::1]/a.png",
+        "http://169.254.169.254/latest/meta-data",
+        "file:///etc/passwd",
+        "not a url",
+    ] {
+        let (status, _) = upload_url(&app, json!({ "url": url })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", url);
+    }
+}
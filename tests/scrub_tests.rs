@@ -0,0 +1,336 @@
+//! 存储校验测试
+//! 覆盖缺失文件、大小不一致、内容校验失败、孤立文件的检查与修复，以及管理接口
+//! 使用 config_scrub_test 配置（独立的上传和缓存目录，孤立文件不设宽限期）
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tower::ServiceExt;
+
+use rifs::app_state::AppState;
+use rifs::models::{ImageInfo, ScrubAction, ScrubIssue, ScrubIssueKind, ScrubOptions};
+use rifs::services::scrub_service::QUARANTINE_PREFIX;
+use rifs::services::{CacheService, ImageService, ScrubService, TokenService};
+use rifs::storage::sharded_key;
+
+mod common;
+use common::{create_test_app, upload};
+
+fn random_png() -> Vec<u8> {
+    let mut png_bytes = Vec::new();
+    image::RgbImage::from_fn(24, 16, |x, y| {
+        image::Rgb([rand::random(), x as u8 * 10, y as u8 * 10])
+    })
+    .write_to(
+        &mut std::io::Cursor::new(&mut png_bytes),
+        image::ImageFormat::Png,
+    )
+    .unwrap();
+    png_bytes
+}
+
+async fn upload_png(app: &axum::Router) -> String {
+    upload(app, None, &random_png()).await
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str) -> axum::response::Response {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+async fn body_json(response: axum::response::Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+async fn image_info(app_state: &AppState, hash: &str) -> Option<ImageInfo> {
+    ImageService::get_image_info(app_state.db_pool(), hash)
+        .await
+        .unwrap()
+}
+
+/// 只保留本测试制造的问题，按存储键排序
+fn issues_for(issues: &[ScrubIssue], keys: &[&str]) -> Vec<(String, ScrubIssueKind)> {
+    let mut found: Vec<_> = issues
+        .iter()
+        .filter(|issue| keys.contains(&issue.storage_key.as_str()))
+        .map(|issue| (issue.storage_key.clone(), issue.kind))
+        .collect();
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    found
+}
+
+fn action_for(issues: &[ScrubIssue], key: &str) -> Option<ScrubAction> {
+    let issue = issues
+        .iter()
+        .find(|issue| issue.storage_key == key)
+        .unwrap_or_else(|| panic!("没有找到 {} 的问题", key));
+    assert_eq!(issue.repair_error, None, "{}", key);
+    issue.action
+}
+
+fn sorted(mut expected: Vec<(String, ScrubIssueKind)>) -> Vec<(String, ScrubIssueKind)> {
+    expected.sort_by(|a, b| a.0.cmp(&b.0));
+    expected
+}
+
+#[tokio::test]
+async fn test_scrub_detects_and_repairs_problems() {
+    let (app, app_state) = create_test_app("config_scrub_test").await;
+    let storage = app_state.storage();
+    let cache_storage = app_state.cache_storage();
+
+    let healthy = upload_png(&app).await;
+    let missing = upload_png(&app).await;
+    let tampered = upload_png(&app).await;
+    let truncated = upload_png(&app).await;
+    let healthy_key = image_info(&app_state, &healthy)
+        .await
+        .unwrap()
+        .storage_key();
+    let missing_key = image_info(&app_state, &missing)
+        .await
+        .unwrap()
+        .storage_key();
+    let tampered_key = image_info(&app_state, &tampered)
+        .await
+        .unwrap()
+        .storage_key();
+    let truncated_key = image_info(&app_state, &truncated)
+        .await
+        .unwrap()
+        .storage_key();
+
+    // 文件丢失、内容被改写（大小不变）、文件被截断
+    storage.delete(&missing_key).await.unwrap();
+    let mut data = storage.get(&tampered_key).await.unwrap();
+    let last = data.len() - 1;
+    data[last] ^= 0xff;
+    storage.put(&tampered_key, &data).await.unwrap();
+    let data = storage.get(&truncated_key).await.unwrap();
+    storage
+        .put(&truncated_key, &data[..data.len() - 10])
+        .await
+        .unwrap();
+
+    // 孤立文件：内容完好的图片，以及内容与文件名不一致的文件
+    let orphan = random_png();
+    let orphan_hash = format!("{:x}", Sha256::digest(&orphan));
+    let orphan_key = sharded_key(&orphan_hash, &format!("{}.png", orphan_hash));
+    storage.put(&orphan_key, &orphan).await.unwrap();
+    let bad_hash = format!("{:064x}", rand::random::<u128>());
+    let bad_orphan_key = sharded_key(&bad_hash, &format!("{}.png", bad_hash));
+    storage.put(&bad_orphan_key, &random_png()).await.unwrap();
+
+    // 缓存：一条记录的文件丢失，另有一个没有记录的缓存文件
+    for transform in ["w8", "w4"] {
+        let response = send(
+            &app,
+            Method::GET,
+            &format!("/images/{}@{}", healthy, transform),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let cache_service =
+        CacheService::new(app_state.db_pool().get_connection(), cache_storage.clone()).unwrap();
+    let cache_entries: Vec<_> = cache_service
+        .list_all()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|entry| entry.original_hash == healthy)
+        .collect();
+    assert_eq!(cache_entries.len(), 2);
+    let cached_key = cache_service.storage_key_of(&cache_entries[0]).to_string();
+    let missing_cache_key = cache_service.storage_key_of(&cache_entries[1]).to_string();
+    cache_storage.delete(&missing_cache_key).await.unwrap();
+    let orphan_cache_key = format!("zz/zz/{:032x}.png", rand::random::<u128>());
+    cache_storage
+        .put(&orphan_cache_key, b"orphan cache")
+        .await
+        .unwrap();
+
+    let keys = [
+        healthy_key.as_str(),
+        missing_key.as_str(),
+        tampered_key.as_str(),
+        truncated_key.as_str(),
+        orphan_key.as_str(),
+        bad_orphan_key.as_str(),
+        cached_key.as_str(),
+        missing_cache_key.as_str(),
+        orphan_cache_key.as_str(),
+    ];
+    let expected_without_checksum = vec![
+        (missing_key.clone(), ScrubIssueKind::MissingFile),
+        (truncated_key.clone(), ScrubIssueKind::SizeMismatch),
+        (orphan_key.clone(), ScrubIssueKind::OrphanFile),
+        (bad_orphan_key.clone(), ScrubIssueKind::OrphanFile),
+        (missing_cache_key.clone(), ScrubIssueKind::MissingFile),
+        (orphan_cache_key.clone(), ScrubIssueKind::OrphanFile),
+    ];
+
+    // 只检查时不修改任何数据
+    let report = ScrubService::scrub(&app_state, ScrubOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(report.images_checked, 4);
+    assert_eq!(report.cache_checked, 2);
+    assert_eq!(
+        issues_for(&report.issues, &keys),
+        sorted(expected_without_checksum.clone())
+    );
+    assert_eq!(report.repaired(), 0);
+    let missing_issue = report
+        .issues
+        .iter()
+        .find(|issue| issue.storage_key == missing_key)
+        .unwrap();
+    assert_eq!(missing_issue.record.as_deref(), Some(missing.as_str()));
+    assert!(image_info(&app_state, &missing).await.is_some());
+    assert!(storage.exists(&orphan_key).await.unwrap());
+
+    // 校验内容时才能发现大小不变的改写
+    let report = ScrubService::scrub(
+        &app_state,
+        ScrubOptions {
+            checksum: true,
+            repair: false,
+        },
+    )
+    .await
+    .unwrap();
+    let mut expected = expected_without_checksum.clone();
+    expected.push((tampered_key.clone(), ScrubIssueKind::ChecksumMismatch));
+    assert_eq!(issues_for(&report.issues, &keys), sorted(expected.clone()));
+
+    // 修复
+    let options = ScrubOptions {
+        checksum: true,
+        repair: true,
+    };
+    let report = app_state
+        .scrub_job()
+        .run(&app_state, options)
+        .await
+        .unwrap();
+    assert_eq!(issues_for(&report.issues, &keys), sorted(expected));
+    assert_eq!(
+        action_for(&report.issues, &missing_key),
+        Some(ScrubAction::DeletedRecord)
+    );
+    assert_eq!(
+        action_for(&report.issues, &tampered_key),
+        Some(ScrubAction::QuarantinedFile)
+    );
+    assert_eq!(
+        action_for(&report.issues, &truncated_key),
+        Some(ScrubAction::QuarantinedFile)
+    );
+    assert_eq!(
+        action_for(&report.issues, &orphan_key),
+        Some(ScrubAction::AdoptedFile)
+    );
+    assert_eq!(
+        action_for(&report.issues, &bad_orphan_key),
+        Some(ScrubAction::QuarantinedFile)
+    );
+    assert_eq!(
+        action_for(&report.issues, &missing_cache_key),
+        Some(ScrubAction::DeletedRecord)
+    );
+    assert_eq!(
+        action_for(&report.issues, &orphan_cache_key),
+        Some(ScrubAction::DeletedFile)
+    );
+
+    // 有问题的记录被删除，损坏的文件移入隔离区
+    assert!(image_info(&app_state, &healthy).await.is_some());
+    for hash in [&missing, &tampered, &truncated] {
+        assert!(image_info(&app_state, hash).await.is_none());
+    }
+    for key in [&tampered_key, &truncated_key, &bad_orphan_key] {
+        assert!(!storage.exists(key).await.unwrap());
+        assert!(storage
+            .exists(&format!("{}{}", QUARANTINE_PREFIX, key))
+            .await
+            .unwrap());
+    }
+    assert!(!cache_storage.exists(&orphan_cache_key).await.unwrap());
+    assert!(cache_storage.exists(&cached_key).await.unwrap());
+
+    // 孤立的图片文件归入管理员令牌，作为私有图片
+    let admin = TokenService::new(app_state.db_pool().get_connection())
+        .first_admin()
+        .await
+        .unwrap()
+        .unwrap();
+    let mut hasher = Sha256::new();
+    hasher.update(&orphan);
+    hasher.update(admin.id.to_le_bytes());
+    let adopted = image_info(&app_state, &format!("{:x}", hasher.finalize()))
+        .await
+        .unwrap();
+    assert_eq!(adopted.owner_token_id, Some(admin.id));
+    assert_eq!(adopted.storage_key(), orphan_key);
+    assert!(adopted.is_private);
+    assert_eq!(adopted.size, orphan.len() as u64);
+    assert_eq!(adopted.width, Some(24));
+    let response = send(&app, Method::GET, &format!("/images/{}", adopted.hash)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 修复后再次校验没有问题，隔离区的文件不再被视为孤立文件
+    let report = ScrubService::scrub(&app_state, options).await.unwrap();
+    assert_eq!(issues_for(&report.issues, &keys), Vec::new());
+    let quarantined = format!("{}{}", QUARANTINE_PREFIX, tampered_key);
+    assert!(!report
+        .issues
+        .iter()
+        .any(|issue| issue.storage_key == quarantined));
+
+    let status = app_state.scrub_job().status();
+    assert!(!status.running);
+    assert!(status.last_report.unwrap().repaired() >= 7);
+}
+
+#[tokio::test]
+async fn test_scrub_endpoint() {
+    let (app, _app_state) = create_test_app("config_scrub_test").await;
+
+    let response = send(&app, Method::GET, "/api/system/scrub").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    assert_eq!(json["data"]["running"], false);
+    assert!(json["data"]["last_report"].is_null());
+
+    let response = send(&app, Method::POST, "/api/system/scrub?checksum=true").await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+    // 后台执行，轮询直到完成
+    let mut report = Value::Null;
+    for _ in 0..100 {
+        let json = body_json(send(&app, Method::GET, "/api/system/scrub").await).await;
+        if json["data"]["running"] == false && !json["data"]["last_report"].is_null() {
+            report = json["data"]["last_report"].clone();
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert_eq!(report["options"]["checksum"], true);
+    assert_eq!(report["options"]["repair"], false);
+    assert_eq!(report["images_checked"], 0);
+    assert!(report["issues"].is_array());
+
+    let response = send(&app, Method::POST, "/api/system/scrub?repair=maybe").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
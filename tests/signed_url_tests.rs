@@ -0,0 +1,269 @@
+//! 私有图片与签名URL测试
+//! 使用启用认证的 config_auth_test 配置，覆盖签名校验、私有图片访问控制、签名URL生成和可见性切换
+
+use axum::http::{header, Method, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use rifs::config::AuthConfig;
+use rifs::models::TokenRole;
+use rifs::services::url_signing::UrlSigner;
+use rifs::utils::AppError;
+
+mod common;
+use common::{build_request, create_test_app, create_token, send, upload_request};
+
+async fn json_body(response: axum::response::Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// 以指定令牌上传私有图片，返回上传结果
+async fn upload_private(app: &axum::Router, token: &str) -> Value {
+    let mut png_bytes = Vec::new();
+    image::RgbImage::from_fn(32, 16, |x, _| image::Rgb([rand::random(), x as u8, 0]))
+        .write_to(
+            &mut std::io::Cursor::new(&mut png_bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+    let request = upload_request(
+        "/upload?private=true",
+        Some(token),
+        "file",
+        &[("private.png", &png_bytes)],
+    );
+    let (status, json) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    json["data"].clone()
+}
+
+#[test]
+fn test_url_signer_verify() {
+    let signer = UrlSigner::new("secret");
+    let signature = signer.sign("abc", "w100_webp", 2000);
+
+    assert!(signer
+        .verify("abc", "w100_webp", 2000, &signature, 1000)
+        .is_ok());
+
+    // 任一签名字段被篡改都会失败
+    let tampered = [
+        signer.verify("abd", "w100_webp", 2000, &signature, 1000),
+        signer.verify("abc", "w200_webp", 2000, &signature, 1000),
+        signer.verify("abc", "w100_webp", 3000, &signature, 1000),
+        UrlSigner::new("other").verify("abc", "w100_webp", 2000, &signature, 1000),
+        signer.verify("abc", "w100_webp", 2000, "not base64!", 1000),
+    ];
+    for result in tampered {
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+    }
+
+    // 过期
+    assert!(matches!(
+        signer.verify("abc", "w100_webp", 2000, &signature, 2001),
+        Err(AppError::Forbidden(msg)) if msg == "签名URL已过期"
+    ));
+}
+
+#[test]
+fn test_url_signer_requires_secret() {
+    // 未配置或只有空白的密钥是服务端配置问题，生成和校验签名共用同一个错误
+    for secret in [None, Some("   ".to_string())] {
+        let config = AuthConfig {
+            url_signing_secret: secret,
+            ..AuthConfig::default()
+        };
+        assert!(matches!(
+            UrlSigner::from_config(&config),
+            Err(AppError::Internal(msg)) if msg == "服务未配置URL签名密钥"
+        ));
+    }
+
+    let config = AuthConfig {
+        url_signing_secret: Some("secret".to_string()),
+        ..AuthConfig::default()
+    };
+    assert!(UrlSigner::from_config(&config).is_ok());
+}
+
+#[test]
+fn test_signed_path_escapes_transform() {
+    let signer = UrlSigner::new("secret");
+    assert_eq!(
+        signer.signed_path("abc", "", 10),
+        format!(
+            "/images/abc?expires=10&signature={}",
+            signer.sign("abc", "", 10)
+        )
+    );
+
+    let path = signer.signed_path("abc", "w100_b#ffffff", 10);
+    assert!(path.starts_with("/images/abc@w100_b%23ffffff?expires=10&signature="));
+    assert!(path.ends_with(&signer.sign("abc", "w100_b#ffffff", 10)));
+}
+
+#[tokio::test]
+async fn test_private_image_access() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, owner) = create_token(&app_state, TokenRole::User).await;
+    let (_, other) = create_token(&app_state, TokenRole::User).await;
+
+    let image = upload_private(&app, &owner).await;
+    assert_eq!(image["is_private"], true);
+    let hash = image["hash"].as_str().unwrap().to_string();
+    let image_uri = format!("/images/{}", hash);
+
+    // 匿名访问和非所有者访问均被拒绝
+    let response = app
+        .clone()
+        .oneshot(build_request(Method::GET, &image_uri, None, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app
+        .clone()
+        .oneshot(build_request(Method::GET, &image_uri, Some(&other), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .clone()
+        .oneshot(build_request(
+            Method::GET,
+            &format!("/images/{}/info", hash),
+            None,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // 所有者可以直接访问，且不允许共享缓存
+    let response = app
+        .clone()
+        .oneshot(build_request(Method::GET, &image_uri, Some(&owner), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[header::CACHE_CONTROL]
+        .to_str()
+        .unwrap()
+        .starts_with("private"));
+
+    // 切换为公开后匿名可访问
+    let visibility_uri = format!("/api/images/{}/visibility", hash);
+    let response = app
+        .clone()
+        .oneshot(build_request(
+            Method::POST,
+            &visibility_uri,
+            Some(&other),
+            Some(serde_json::json!({ "is_private": false })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .clone()
+        .oneshot(build_request(
+            Method::POST,
+            &visibility_uri,
+            Some(&owner),
+            Some(serde_json::json!({ "is_private": false })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .oneshot(build_request(Method::GET, &image_uri, None, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[header::CACHE_CONTROL]
+        .to_str()
+        .unwrap()
+        .starts_with("public"));
+}
+
+#[tokio::test]
+async fn test_sign_image_url() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (_, owner) = create_token(&app_state, TokenRole::User).await;
+    let (_, other) = create_token(&app_state, TokenRole::User).await;
+
+    let hash = upload_private(&app, &owner).await["hash"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let sign_uri = format!("/api/images/{}/sign", hash);
+    let sign_body = serde_json::json!({ "transform": "w8_png", "expires_in": 60 });
+
+    // 只有所有者可以生成签名URL
+    let response = app
+        .clone()
+        .oneshot(build_request(
+            Method::POST,
+            &sign_uri,
+            Some(&other),
+            Some(sign_body.clone()),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // 有效期不能超过配置的上限
+    let response = app
+        .clone()
+        .oneshot(build_request(
+            Method::POST,
+            &sign_uri,
+            Some(&owner),
+            Some(serde_json::json!({ "expires_in": 7200 })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(build_request(
+            Method::POST,
+            &sign_uri,
+            Some(&owner),
+            Some(sign_body),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let url = json_body(response).await["data"]["url"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(url.starts_with(&format!("/images/{}@w8_png?expires=", hash)));
+
+    // 签名URL无需认证即可访问私有图片
+    let response = app
+        .clone()
+        .oneshot(build_request(Method::GET, &url, None, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let img = image::load_from_memory_with_format(&body, image::ImageFormat::Png).unwrap();
+    assert_eq!((img.width(), img.height()), (8, 4));
+
+    // 修改转换参数后签名失效
+    let tampered = url.replace("@w8_png", "@w16_png");
+    let response = app
+        .oneshot(build_request(Method::GET, &tampered, None, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,196 @@
+//! 图片转换工作线程池测试
+//! 覆盖队列上限、并发相同转换的合并，以及线程池占满时图片转换请求返回 503
+//! 使用 config_transform_pool_test 配置（单个工作线程、长度为1的队列）
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::task::Poll;
+use tokio::sync::oneshot;
+use tower::ServiceExt;
+
+use rifs::config::TransformConfig;
+use rifs::services::transform_pool::{TransformOutput, TransformPool};
+use rifs::utils::AppError;
+
+mod common;
+use common::{create_test_app, upload};
+
+/// 阻塞工作线程直到放行的任务，返回任务、开始执行的通知和放行的发送端
+fn blocking_job() -> (
+    impl FnOnce() -> Result<(), AppError> + Send + 'static,
+    mpsc::Receiver<()>,
+    mpsc::Sender<()>,
+) {
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let job = move || {
+        started_tx.send(()).unwrap();
+        release_rx.recv().unwrap();
+        Ok(())
+    };
+    (job, started_rx, release_tx)
+}
+
+async fn wait_started(started: mpsc::Receiver<()>) {
+    tokio::task::spawn_blocking(move || started.recv().unwrap())
+        .await
+        .unwrap();
+}
+
+/// 轮询一次，使任务进入队列
+async fn enqueue<F: Future + Unpin>(future: &mut F) {
+    std::future::poll_fn(|cx| {
+        assert!(Pin::new(&mut *future).poll(cx).is_pending());
+        Poll::Ready(())
+    })
+    .await
+}
+
+async fn upload_png(app: &axum::Router) -> String {
+    let mut png_bytes = Vec::new();
+    image::RgbImage::from_fn(48, 32, |x, y| {
+        image::Rgb([rand::random(), x as u8 * 5, y as u8 * 7])
+    })
+    .write_to(
+        &mut std::io::Cursor::new(&mut png_bytes),
+        image::ImageFormat::Png,
+    )
+    .unwrap();
+
+    upload(app, None, &png_bytes).await
+}
+
+async fn get(app: &axum::Router, uri: &str) -> axum::response::Response {
+    let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_pool_rejects_when_queue_full() {
+    let pool = Arc::new(
+        TransformPool::from_config(&TransformConfig {
+            workers: 1,
+            queue_size: 1,
+            ..TransformConfig::default()
+        })
+        .unwrap(),
+    );
+    assert_eq!(pool.workers(), 1);
+
+    let (job, started, release) = blocking_job();
+    let running_pool = pool.clone();
+    let running = tokio::spawn(async move { running_pool.execute(job).await });
+    wait_started(started).await;
+
+    // 唯一的工作线程忙碌时，下一个任务进入队列，队列满后立即拒绝
+    let mut queued = Box::pin(pool.execute(|| Ok(2)));
+    enqueue(&mut queued).await;
+    match pool.execute(|| Ok(3)).await {
+        Err(AppError::ServiceUnavailable(_)) => {}
+        other => panic!("队列已满时应当拒绝: {:?}", other),
+    }
+
+    release.send(()).unwrap();
+    running.await.unwrap().unwrap();
+    assert_eq!(queued.await.unwrap(), 2);
+
+    // 线程空闲后恢复接收任务，任务的错误原样返回
+    assert_eq!(pool.execute(|| Ok(4)).await.unwrap(), 4);
+    let result: Result<(), AppError> = pool
+        .execute(|| Err(AppError::BadRequest("无效参数".to_string())))
+        .await;
+    assert!(matches!(result, Err(AppError::BadRequest(ref msg)) if msg == "无效参数"));
+}
+
+#[tokio::test]
+async fn test_deduplicate_concurrent_identical_tasks() {
+    let pool = TransformPool::from_config(&TransformConfig::default()).unwrap();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let task = |release: Option<oneshot::Receiver<()>>| {
+        let runs = runs.clone();
+        async move {
+            runs.fetch_add(1, Ordering::SeqCst);
+            if let Some(release) = release {
+                release.await.unwrap();
+            }
+            Ok::<TransformOutput, AppError>((vec![1, 2, 3], "image/png".to_string()))
+        }
+    };
+    let expected = (vec![1, 2, 3], "image/png".to_string());
+
+    // 第一个请求执行期间到达的相同请求等待同一个结果，不同的键分别执行
+    let (release_tx, release_rx) = oneshot::channel();
+    let (first, second, other, _) = tokio::join!(
+        pool.deduplicate("same", task(Some(release_rx))),
+        pool.deduplicate("same", task(None)),
+        pool.deduplicate("other", task(None)),
+        async { release_tx.send(()).unwrap() },
+    );
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+    assert_eq!(first.unwrap(), expected);
+    assert_eq!(second.unwrap(), expected);
+    assert_eq!(other.unwrap(), expected);
+
+    // 完成后不再合并
+    pool.deduplicate("same", task(None)).await.unwrap();
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+    // 错误同样返回给所有等待者
+    let (release_tx, release_rx) = oneshot::channel::<()>();
+    let failing = async move {
+        release_rx.await.unwrap();
+        Err(AppError::BadRequest("不支持的参数".to_string()))
+    };
+    let (first, second, _) = tokio::join!(
+        pool.deduplicate("failing", failing),
+        pool.deduplicate("failing", task(None)),
+        async { release_tx.send(()).unwrap() },
+    );
+    assert!(matches!(first, Err(AppError::BadRequest(_))));
+    assert!(matches!(second, Err(AppError::BadRequest(ref msg)) if msg == "不支持的参数"));
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_transform_request_returns_503_when_pool_busy() {
+    let (app, app_state) = create_test_app("config_transform_pool_test").await;
+    let hash = upload_png(&app).await;
+    let pool = app_state.transform_pool();
+    assert_eq!(pool.workers(), 1);
+
+    // 占用唯一的工作线程并填满队列
+    let (job, started, release) = blocking_job();
+    let running_state = app_state.clone();
+    let running = tokio::spawn(async move { running_state.transform_pool().execute(job).await });
+    wait_started(started).await;
+    let mut queued = Box::pin(pool.execute(|| Ok(())));
+    enqueue(&mut queued).await;
+
+    let response = get(&app, &format!("/images/{}@w16", hash)).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "1");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], 503);
+
+    // 原图不经过线程池，不受影响
+    let response = get(&app, &format!("/images/{}", hash)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 线程池空闲后同一转换正常返回
+    release.send(()).unwrap();
+    running.await.unwrap().unwrap();
+    queued.await.unwrap();
+    let response = get(&app, &format!("/images/{}@w16", hash)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+}
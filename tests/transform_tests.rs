@@ -0,0 +1,266 @@
+//! 图片转换测试
+//! 覆盖转换参数解析以及裁剪、适配模式和对齐方式
+
+use image::{DynamicImage, GenericImageView, ImageFormat, Rgb, RgbImage};
+
+use rifs::models::{CropRect, FitMode, Gravity, ImageTransformParams};
+use rifs::services::ImageTransformService;
+
+/// 生成指定尺寸的 PNG，`pixel` 决定每个像素的颜色
+fn png_from_fn(width: u32, height: u32, pixel: impl Fn(u32, u32) -> Rgb<u8>) -> Vec<u8> {
+    let mut data = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, pixel))
+        .write_to(&mut std::io::Cursor::new(&mut data), ImageFormat::Png)
+        .unwrap();
+    data
+}
+
+async fn transform(data: &[u8], params: &str) -> DynamicImage {
+    let params = ImageTransformParams::parse(params).unwrap();
+    ImageTransformService::validate_params(&params).unwrap();
+    let (output, _) =
+        ImageTransformService::transform_image_blocking(data, "image/png", &params).unwrap();
+    image::load_from_memory(&output).unwrap()
+}
+
+#[test]
+fn test_parse_fit_gravity_and_crop() {
+    let params =
+        ImageTransformParams::parse("w200_h200_cover_gsmart_crop300x200+10+20_up_png").unwrap();
+    assert_eq!(params.fit, FitMode::Cover);
+    assert_eq!(params.gravity, Gravity::Smart);
+    assert_eq!(
+        params.crop,
+        Some(CropRect {
+            x: 10,
+            y: 20,
+            width: 300,
+            height: 200
+        })
+    );
+    assert!(params.upscale);
+    assert_eq!(
+        params.to_normalized_string(),
+        "w200_h200_cover_gsmart_crop300x200+10+20_up_png"
+    );
+
+    // 参数顺序不影响规范化结果，别名会被统一
+    let params = ImageTransformParams::parse("gentropy_png_cover_h200_w200").unwrap();
+    assert_eq!(params.to_normalized_string(), "w200_h200_cover_gsmart_png");
+
+    // 默认值不出现在规范化字符串中
+    let params = ImageTransformParams::parse("w100_inside_gc").unwrap();
+    assert_eq!(params.to_normalized_string(), "w100");
+
+    // 仅裁剪也需要转换
+    let params = ImageTransformParams::parse("crop10x10").unwrap();
+    assert!(params.needs_transform());
+    assert_eq!(params.to_normalized_string(), "crop10x10+0+0");
+
+    assert!(ImageTransformParams::parse("crop10x").is_err());
+    assert!(ImageTransformParams::parse("crop10x10+1+2+3").is_err());
+}
+
+#[tokio::test]
+async fn test_fit_modes_dimensions() {
+    let data = png_from_fn(400, 200, |x, _| Rgb([(x % 256) as u8, 0, 0]));
+
+    assert_eq!(transform(&data, "w100_h100").await.dimensions(), (100, 50));
+    assert_eq!(
+        transform(&data, "w100_h100_inside").await.dimensions(),
+        (100, 50)
+    );
+    assert_eq!(
+        transform(&data, "w100_h100_outside").await.dimensions(),
+        (200, 100)
+    );
+    assert_eq!(
+        transform(&data, "w100_h100_cover").await.dimensions(),
+        (100, 100)
+    );
+    assert_eq!(
+        transform(&data, "w100_h100_contain").await.dimensions(),
+        (100, 100)
+    );
+    assert_eq!(
+        transform(&data, "w100_h100_fill").await.dimensions(),
+        (100, 100)
+    );
+}
+
+#[tokio::test]
+async fn test_upscale_is_opt_in() {
+    let data = png_from_fn(40, 20, |_, _| Rgb([10, 20, 30]));
+
+    // 默认不放大
+    assert_eq!(transform(&data, "w80_png").await.dimensions(), (40, 20));
+    assert_eq!(transform(&data, "w80_up_png").await.dimensions(), (80, 40));
+
+    // cover 不放大时裁剪出保持目标宽高比的最大区域
+    assert_eq!(
+        transform(&data, "w100_h100_cover").await.dimensions(),
+        (20, 20)
+    );
+    assert_eq!(
+        transform(&data, "w100_h100_cover_up").await.dimensions(),
+        (100, 100)
+    );
+
+    // contain 始终输出目标尺寸，不放大时原图居中留白
+    let padded = transform(&data, "w100_h100_contain_na#ff0000_png").await;
+    assert_eq!(padded.dimensions(), (100, 100));
+    assert_eq!(padded.to_rgba8().get_pixel(0, 0).0, [255, 0, 0, 255]);
+    assert_eq!(padded.to_rgba8().get_pixel(50, 50).0, [10, 20, 30, 255]);
+}
+
+#[tokio::test]
+async fn test_cover_gravity() {
+    // 左半边红色，右半边蓝色
+    let data = png_from_fn(200, 100, |x, _| {
+        if x < 100 {
+            Rgb([255, 0, 0])
+        } else {
+            Rgb([0, 0, 255])
+        }
+    });
+
+    let west = transform(&data, "w100_h100_cover_gw").await.to_rgb8();
+    assert_eq!(west.get_pixel(99, 50).0, [255, 0, 0]);
+
+    let east = transform(&data, "w100_h100_cover_geast").await.to_rgb8();
+    assert_eq!(east.get_pixel(0, 50).0, [0, 0, 255]);
+}
+
+#[tokio::test]
+async fn test_smart_gravity_prefers_detail() {
+    // 左侧纯色，右侧为噪点纹理
+    let data = png_from_fn(300, 100, |x, y| {
+        if x < 200 {
+            Rgb([128, 128, 128])
+        } else {
+            let v = ((x * 7919 + y * 104_729) % 251) as u8;
+            Rgb([v, v.wrapping_mul(3), v.wrapping_mul(7)])
+        }
+    });
+
+    let smart = transform(&data, "w100_h100_cover_gsmart").await.to_rgb8();
+    // 选中的区域应位于右侧纹理部分，而不是中间的纯色区域
+    let distinct: std::collections::HashSet<[u8; 3]> =
+        smart.pixels().map(|pixel| pixel.0).collect();
+    assert!(distinct.len() > 50);
+}
+
+#[tokio::test]
+async fn test_crop_rectangle() {
+    let data = png_from_fn(100, 100, |x, y| {
+        if x >= 50 && y >= 50 {
+            Rgb([0, 255, 0])
+        } else {
+            Rgb([0, 0, 0])
+        }
+    });
+
+    let cropped = transform(&data, "crop40x30+55+60_png").await;
+    assert_eq!(cropped.dimensions(), (40, 30));
+    assert!(cropped
+        .to_rgb8()
+        .pixels()
+        .all(|pixel| pixel.0 == [0, 255, 0]));
+
+    // 超出图片部分自动截断
+    let cropped = transform(&data, "crop80x80+60+60_png").await;
+    assert_eq!(cropped.dimensions(), (40, 40));
+
+    // 先裁剪再缩放
+    let cropped = transform(&data, "crop50x50+50+50_w10_png").await;
+    assert_eq!(cropped.dimensions(), (10, 10));
+
+    let params = ImageTransformParams::parse("crop10x10+200+0_png").unwrap();
+    assert!(ImageTransformService::transform_image_blocking(&data, "image/png", &params).is_err());
+}
+
+/// 在 JPEG 的 SOI 之后插入只包含 Orientation 的 EXIF 段
+fn jpeg_with_orientation(jpeg: &[u8], orientation: u16) -> Vec<u8> {
+    let mut tiff = Vec::new();
+    tiff.extend_from_slice(b"II*\0");
+    tiff.extend_from_slice(&8u32.to_le_bytes());
+    tiff.extend_from_slice(&1u16.to_le_bytes());
+    // Orientation 标签: 0x0112, SHORT, 数量1
+    tiff.extend_from_slice(&0x0112u16.to_le_bytes());
+    tiff.extend_from_slice(&3u16.to_le_bytes());
+    tiff.extend_from_slice(&1u32.to_le_bytes());
+    tiff.extend_from_slice(&orientation.to_le_bytes());
+    tiff.extend_from_slice(&[0, 0]);
+    tiff.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut segment = b"Exif\0\0".to_vec();
+    segment.extend_from_slice(&tiff);
+
+    let mut output = jpeg[..2].to_vec();
+    output.extend_from_slice(&[0xFF, 0xE1]);
+    output.extend_from_slice(&((segment.len() + 2) as u16).to_be_bytes());
+    output.extend_from_slice(&segment);
+    output.extend_from_slice(&jpeg[2..]);
+    output
+}
+
+#[test]
+fn test_parse_rotate_and_flip() {
+    let params = ImageTransformParams::parse("fv_r-90_fh_png").unwrap();
+    assert_eq!(params.rotate, Some(270));
+    assert!(params.flip_horizontal);
+    assert!(params.flip_vertical);
+    assert_eq!(params.to_normalized_string(), "r270_fh_fv_png");
+
+    let params = ImageTransformParams::parse("r360").unwrap();
+    assert_eq!(params.rotate, None);
+    assert!(!params.needs_transform());
+
+    assert!(ImageTransformParams::parse("rabc").is_err());
+}
+
+#[tokio::test]
+async fn test_rotate_and_flip() {
+    // 左上角红色，其余黑色
+    let data = png_from_fn(40, 20, |x, y| {
+        if x < 10 && y < 10 {
+            Rgb([255, 0, 0])
+        } else {
+            Rgb([0, 0, 0])
+        }
+    });
+
+    // 顺时针旋转90度后，红色块位于右上角
+    let rotated = transform(&data, "r90_png").await;
+    assert_eq!(rotated.dimensions(), (20, 40));
+    assert_eq!(rotated.to_rgb8().get_pixel(15, 5).0, [255, 0, 0]);
+
+    let flipped = transform(&data, "fh_png").await.to_rgb8();
+    assert_eq!(flipped.get_pixel(35, 5).0, [255, 0, 0]);
+
+    let flipped = transform(&data, "fv_png").await.to_rgb8();
+    assert_eq!(flipped.get_pixel(5, 15).0, [255, 0, 0]);
+
+    // 任意角度旋转扩展画布，空白区域使用背景色
+    let rotated = transform(&data, "r45_na#00ff00_png").await;
+    assert_eq!(rotated.dimensions(), (43, 43));
+    assert_eq!(rotated.to_rgb8().get_pixel(0, 0).0, [0, 255, 0]);
+}
+
+#[tokio::test]
+async fn test_exif_orientation_is_applied() {
+    let mut jpeg = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(40, 20, Rgb([200, 200, 200])))
+        .write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+        .unwrap();
+
+    // Orientation=6 表示需要顺时针旋转90度
+    let data = jpeg_with_orientation(&jpeg, 6);
+    let params = ImageTransformParams::parse("png").unwrap();
+    let (output, _) =
+        ImageTransformService::transform_image_blocking(&data, "image/jpeg", &params).unwrap();
+    assert_eq!(
+        image::load_from_memory(&output).unwrap().dimensions(),
+        (20, 40)
+    );
+}
@@ -0,0 +1,219 @@
+//! 回收站测试
+//! 使用启用认证的 config_auth_test 配置（默认启用回收站），覆盖删除、恢复、彻底删除、
+//! 清空回收站、过期清理以及回收站对查询和配额的影响
+
+use axum::http::{Method, StatusCode};
+use chrono::{Duration, Utc};
+
+use rifs::app_state::AppState;
+use rifs::models::{CreateTokenPayload, TokenRole};
+use rifs::repositories::{ImageRepository, ImageRepositoryTrait};
+use rifs::services::{TokenService, TrashService};
+
+mod common;
+use common::{create_test_app, create_token_with, random_png, request, token_payload, upload};
+
+/// 创建普通用户令牌，返回令牌ID和明文
+async fn create_user_token(app_state: &AppState) -> (i32, String) {
+    let payload = CreateTokenPayload {
+        max_upload_size: Some(10 * 1024 * 1024),
+        ..token_payload(TokenRole::User)
+    };
+    create_token_with(app_state, payload).await
+}
+
+/// 查询列表接口返回的哈希
+async fn list_hashes(app: &axum::Router, token: &str, uri: &str) -> Vec<String> {
+    let (status, json) = request(app, Method::GET, uri, Some(token), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    json["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["hash"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn used_upload_size(app_state: &AppState, token_id: i32) -> i64 {
+    TokenService::new(app_state.db_pool().get_connection())
+        .get_token(token_id)
+        .await
+        .unwrap()
+        .used_upload_size
+}
+
+#[tokio::test]
+async fn test_trash_and_restore() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (owner_id, owner) = create_user_token(&app_state).await;
+    let (_, other) = create_user_token(&app_state).await;
+
+    let png = random_png();
+    let kept = upload(&app, Some(&owner), &random_png()).await;
+    let deleted = upload(&app, Some(&owner), &png).await;
+    let used = used_upload_size(&app_state, owner_id).await;
+
+    // 删除后移入回收站，图片不再可以访问，也不出现在查询和统计中
+    let uri = format!("/api/images/{}", deleted);
+    let (status, json) = request(&app, Method::DELETE, &uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["trashed"], true);
+
+    let image_uri = format!("/images/{}", deleted);
+    let (status, _) = request(&app, Method::GET, &image_uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = request(&app, Method::DELETE, &uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        list_hashes(&app, &owner, "/api/images/query").await,
+        [kept.as_str()]
+    );
+    let (_, json) = request(&app, Method::GET, "/api/stats", Some(&owner), None).await;
+    assert_eq!(json["data"]["total_count"], 1);
+
+    // 回收站中只有自己删除的图片，配额在彻底删除前仍然占用
+    assert_eq!(
+        list_hashes(&app, &owner, "/api/trash").await,
+        [deleted.as_str()]
+    );
+    assert!(list_hashes(&app, &other, "/api/trash").await.is_empty());
+    assert_eq!(used_upload_size(&app_state, owner_id).await, used);
+
+    // 只有所有者可以恢复
+    let restore_uri = format!("/api/trash/{}/restore", deleted);
+    let (status, _) = request(&app, Method::POST, &restore_uri, Some(&other), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, json) = request(&app, Method::POST, &restore_uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json["data"]["deleted_at"].is_null());
+    let (status, _) = request(&app, Method::POST, &restore_uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = request(&app, Method::GET, &image_uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(list_hashes(&app, &owner, "/api/trash").await.is_empty());
+    assert_eq!(used_upload_size(&app_state, owner_id).await, used);
+
+    // 重新上传回收站中的相同图片时直接恢复
+    let (status, _) = request(&app, Method::DELETE, &uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(upload(&app, Some(&owner), &png).await, deleted);
+    assert!(list_hashes(&app, &owner, "/api/trash").await.is_empty());
+    assert_eq!(used_upload_size(&app_state, owner_id).await, used);
+}
+
+#[tokio::test]
+async fn test_purge_releases_quota() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (owner_id, owner) = create_user_token(&app_state).await;
+    let (other_id, other) = create_user_token(&app_state).await;
+
+    // 彻底删除回收站中的图片后退还配额，文件随之删除
+    let hash = upload(&app, Some(&owner), &random_png()).await;
+    let image_repo = ImageRepository::new(app_state.db_pool().get_connection());
+    let image = image_repo.find_by_hash(&hash).await.unwrap().unwrap();
+    let uri = format!("/api/images/{}", hash);
+    request(&app, Method::DELETE, &uri, Some(&owner), None).await;
+    assert!(app_state
+        .storage()
+        .exists(&image.storage_key())
+        .await
+        .unwrap());
+
+    let purge_uri = format!("/api/trash/{}", hash);
+    let (status, _) = request(&app, Method::DELETE, &purge_uri, Some(&other), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = request(&app, Method::DELETE, &purge_uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(image_repo.find_by_hash(&hash).await.unwrap().is_none());
+    assert!(!app_state
+        .storage()
+        .exists(&image.storage_key())
+        .await
+        .unwrap());
+    assert_eq!(used_upload_size(&app_state, owner_id).await, 0);
+    let (status, _) = request(&app, Method::DELETE, &purge_uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 跳过回收站直接删除
+    let hash = upload(&app, Some(&owner), &random_png()).await;
+    let uri = format!("/api/images/{}?permanent=true", hash);
+    let (status, json) = request(&app, Method::DELETE, &uri, Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["trashed"], false);
+    assert!(image_repo.find_by_hash(&hash).await.unwrap().is_none());
+    assert_eq!(used_upload_size(&app_state, owner_id).await, 0);
+
+    // 清空回收站只影响自己的图片
+    for _ in 0..2 {
+        let hash = upload(&app, Some(&owner), &random_png()).await;
+        request(
+            &app,
+            Method::DELETE,
+            &format!("/api/images/{}", hash),
+            Some(&owner),
+            None,
+        )
+        .await;
+    }
+    let foreign = upload(&app, Some(&other), &random_png()).await;
+    request(
+        &app,
+        Method::DELETE,
+        &format!("/api/images/{}", foreign),
+        Some(&other),
+        None,
+    )
+    .await;
+
+    let (status, json) = request(&app, Method::DELETE, "/api/trash", Some(&owner), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["purged"], 2);
+    assert_eq!(used_upload_size(&app_state, owner_id).await, 0);
+    assert_eq!(
+        list_hashes(&app, &other, "/api/trash").await,
+        [foreign.as_str()]
+    );
+    assert!(used_upload_size(&app_state, other_id).await > 0);
+}
+
+#[tokio::test]
+async fn test_purge_expired_and_token_deletion() {
+    let (app, app_state) = create_test_app("config_auth_test").await;
+    let (owner_id, owner) = create_user_token(&app_state).await;
+    let image_repo = ImageRepository::new(app_state.db_pool().get_connection());
+
+    // 只清理超过保留期的图片
+    let fresh = upload(&app, Some(&owner), &random_png()).await;
+    let expired = upload(&app, Some(&owner), &random_png()).await;
+    for hash in [&fresh, &expired] {
+        request(
+            &app,
+            Method::DELETE,
+            &format!("/api/images/{}", hash),
+            Some(&owner),
+            None,
+        )
+        .await;
+    }
+    image_repo
+        .update_deleted_at(&expired, Some(Utc::now() - Duration::days(31)))
+        .await
+        .unwrap();
+
+    assert!(TrashService::purge_expired(&app_state).await.unwrap() >= 1);
+    assert!(image_repo.find_by_hash(&expired).await.unwrap().is_none());
+    assert!(TrashService::get_trashed(&app_state, &fresh)
+        .await
+        .unwrap()
+        .is_some());
+
+    // 删除令牌时其图片和回收站中的图片都彻底删除，不会留下无法恢复的记录
+    let live = upload(&app, Some(&owner), &random_png()).await;
+    TokenService::new(app_state.db_pool().get_connection())
+        .delete_token_with_data(&app_state, owner_id)
+        .await
+        .unwrap();
+    assert!(image_repo.find_by_hash(&fresh).await.unwrap().is_none());
+    assert!(image_repo.find_by_hash(&live).await.unwrap().is_none());
+}